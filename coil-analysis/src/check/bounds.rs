@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+
+use coil_error::{msg, Error, Span};
+use coil_parser::{Expr, ExprKind, Generics, Statement, TypeExpr};
+
+use super::{Body, Checker};
+use crate::{Bound, DefId, DefKind, Ty, UNSATISFIED_BOUND};
+
+/// How many impls deep a bound may depend on before it is taken not to hold.
+const MAX_DEPTH: usize = 32;
+
+/// A bound that must hold where a generic item is used.
+pub(super) struct Obligation {
+    /// The bound as the item declares it.
+    bound: Bound,
+    /// What the generics of the item stand for where it is used.
+    subst: HashMap<DefId, Ty>,
+    span: Span,
+    /// The item declaring the bound.
+    origin: DefId,
+}
+
+impl<'a> Checker<'a> {
+    /// The bounds `item` puts on its generics, including the supertraits a trait puts on
+    /// `Self`.
+    pub(super) fn bounds(&mut self, item: &'a Expr) -> Vec<Bound> {
+        if let Some(bounds) = self.typing.bounds.get(&item.id) {
+            return bounds.clone();
+        }
+        let Some(generics) = generics_of(item) else {
+            return vec![];
+        };
+        // An item may refer to itself in its bounds.
+        self.typing.bounds.insert(item.id, vec![]);
+        let params = self.generics(item);
+        let pending = std::mem::take(&mut self.pending);
+        let bounds = self.outside_body(|this| {
+            let mut bounds = vec![];
+            for (param, &def) in generics.params.iter().zip(&params) {
+                for bound in &param.bounds {
+                    bounds.extend(this.bound(Ty::Param(def), bound));
+                }
+            }
+            for predicate in &generics.where_clause {
+                let ty = this.lower(&predicate.ty);
+                for bound in &predicate.bounds {
+                    bounds.extend(this.bound(ty.clone(), bound));
+                }
+            }
+            if let ExprKind::Statement(Statement::Trait { supertraits, .. }) = &item.kind {
+                if let Some(self_param) = this.self_param(item) {
+                    for supertrait in supertraits {
+                        bounds.extend(this.bound(Ty::Param(self_param), supertrait));
+                    }
+                }
+            }
+            bounds
+        });
+        self.pending = pending;
+        self.typing.bounds.insert(item.id, bounds.clone());
+        bounds
+    }
+
+    fn bound(&mut self, ty: Ty, bound: &TypeExpr) -> Option<Bound> {
+        let (trait_, args) = self.trait_ref(bound)?;
+        Some(Bound { ty, trait_, args })
+    }
+
+    /// The bounds of `item` together with those of its trait or impl, for a function.
+    fn bounds_with_container(&mut self, item: &'a Expr) -> Vec<Bound> {
+        let mut bounds = self.bounds(item);
+        if let Some(&container) = self.containers.get(&item.id) {
+            bounds.extend(self.bounds(container));
+            bounds.extend(self.trait_self_bound(container));
+        }
+        bounds
+    }
+
+    /// The bounds that hold inside `item`, with everything their supertraits imply.
+    pub(super) fn environment(&mut self, item: &'a Expr) -> Vec<Bound> {
+        let mut bounds = self.bounds_with_container(item);
+        let mut i = 0;
+        while i < bounds.len() && bounds.len() < MAX_DEPTH * MAX_DEPTH {
+            for implied in self.supertraits(&bounds[i].clone()) {
+                if !bounds.contains(&implied) {
+                    bounds.push(implied);
+                }
+            }
+            i += 1;
+        }
+        bounds
+    }
+
+    /// `Self: Trait<A>` inside the trait `item`, whose generics stand for themselves.
+    fn trait_self_bound(&mut self, item: &Expr) -> Option<Bound> {
+        let ExprKind::Statement(Statement::Trait { .. }) = &item.kind else {
+            return None;
+        };
+        let trait_ = *self.res.declarations.get(&item.id)?;
+        let info = self.trait_info(trait_)?;
+        Some(Bound {
+            ty: Ty::Param(info.self_param),
+            trait_,
+            args: info
+                .generics
+                .iter()
+                .map(|&param| Ty::Param(param))
+                .collect(),
+        })
+    }
+
+    /// The bounds on `Self` of the trait of `bound`, for the type `bound` is on.
+    pub(super) fn supertraits(&mut self, bound: &Bound) -> Vec<Bound> {
+        let (Some(info), Some(item)) = (self.trait_info(bound.trait_), self.item(bound.trait_))
+        else {
+            return vec![];
+        };
+        let mut subst: HashMap<_, _> = info
+            .generics
+            .iter()
+            .copied()
+            .zip(bound.args.clone())
+            .collect();
+        subst.insert(info.self_param, bound.ty.clone());
+        self.bounds(item)
+            .into_iter()
+            .filter(|supertrait| supertrait.ty == Ty::Param(info.self_param))
+            .map(|supertrait| supertrait.substitute(&subst))
+            .collect()
+    }
+
+    /// Requires the bounds of the item `def` to hold where it is used at `span`, with
+    /// `subst` saying what its generics stand for. The bounds of the impl or trait of a
+    /// function must hold too.
+    pub(super) fn require_bounds(&mut self, def: DefId, subst: &HashMap<DefId, Ty>, span: Span) {
+        let Some(item) = self.item(def) else {
+            return;
+        };
+        for bound in self.bounds_with_container(item) {
+            self.require(Obligation {
+                bound,
+                subst: subst.clone(),
+                span,
+                origin: def,
+            });
+        }
+    }
+
+    /// Requires the bounds of the struct, enum or union `ty` is an instance of.
+    pub(super) fn require_adt_bounds(&mut self, ty: &Ty, span: Span) {
+        if let Ty::Adt { def, args } = ty {
+            let Some(item) = self.item(*def) else {
+                return;
+            };
+            let subst = self.generics(item).into_iter().zip(args.clone()).collect();
+            self.require_bounds(*def, &subst, span);
+        }
+    }
+
+    /// Requires `obligation` to hold at the end of the body, or once the item whose
+    /// signature or fields are being lowered is.
+    fn require(&mut self, obligation: Obligation) {
+        match self.bodies.last_mut() {
+            Some(body) => body.obligations.push(obligation),
+            None => self.pending.push(obligation),
+        }
+    }
+
+    /// Requires the supertraits of the trait an impl implements to hold for its target.
+    pub(super) fn require_supertraits(
+        &mut self,
+        trait_: DefId,
+        args: &[Ty],
+        target: &Ty,
+        span: Span,
+    ) {
+        let (Some(info), Some(item)) = (self.trait_info(trait_), self.item(trait_)) else {
+            return;
+        };
+        let mut subst: HashMap<_, _> = info.generics.iter().copied().zip(args.to_vec()).collect();
+        subst.insert(info.self_param, target.clone());
+        for bound in self.bounds(item) {
+            if bound.ty == Ty::Param(info.self_param) {
+                self.require(Obligation {
+                    bound,
+                    subst: subst.clone(),
+                    span,
+                    origin: trait_,
+                });
+            }
+        }
+    }
+
+    /// Checks the obligations of the innermost body.
+    pub(super) fn solve_obligations(&mut self) {
+        for obligation in std::mem::take(&mut self.body().obligations) {
+            self.solve(obligation);
+        }
+    }
+
+    /// Checks the obligations of the types written in the signature, fields or target of
+    /// `item`, under its bounds.
+    pub(super) fn solve_pending(&mut self, item: &'a Expr) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let pending = std::mem::take(&mut self.pending);
+        let environment = self.environment(item);
+        self.bodies.push(Body {
+            def: self.res.declarations.get(&item.id).copied(),
+            environment,
+            ..Body::default()
+        });
+        for obligation in pending {
+            self.solve(obligation);
+        }
+        self.bodies.pop();
+    }
+
+    fn solve(&mut self, obligation: Obligation) {
+        let bound = obligation.bound.substitute(&obligation.subst);
+        let ty = self.infer().resolve(&bound.ty);
+        // A type that isn't known is reported by itself.
+        if matches!(ty, Ty::Var(_)) || self.holds(&bound, 0) {
+            return;
+        }
+        let trait_ = Ty::Adt {
+            def: bound.trait_,
+            args: bound.args.clone(),
+        };
+        let message = msg!(
+            "analysis-unsatisfied-bound",
+            name = self.display(&trait_),
+            ty = self.display(&ty)
+        );
+        let note = msg!(
+            "analysis-note-required-by",
+            bound = obligation.bound.display(self.res),
+            name = self.res.def(obligation.origin).name
+        );
+        let mut error =
+            Error::at(UNSATISFIED_BOUND, &message, self.map, obligation.span).with_note(&note);
+        if let (Ty::Param(param), Some(def)) = (&ty, self.body().def) {
+            if self.res.def(*param).kind == DefKind::GenericParam {
+                let bound = Bound { ty, ..bound };
+                let note = msg!(
+                    "analysis-note-add-bound",
+                    bound = bound.display(self.res),
+                    name = self.res.def(def).name
+                );
+                error = error.with_note(&note);
+            }
+        }
+        self.typing.errors.push(error);
+    }
+
+    /// Whether `bound` holds, because the body is bound by it or an impl provides it.
+    pub(super) fn holds(&mut self, bound: &Bound, depth: usize) -> bool {
+        let ty = self.infer().shallow(&bound.ty);
+        if matches!(ty, Ty::Error | Ty::Never) {
+            return true;
+        }
+        for known in self.body().environment.clone() {
+            if known.trait_ == bound.trait_
+                && self.infer().probe(|infer| {
+                    infer.unify(&known.ty, &ty) && infer.unify_all(&known.args, &bound.args)
+                })
+            {
+                return true;
+            }
+        }
+        depth < MAX_DEPTH
+            && self
+                .select_impl(&ty, bound.trait_, &bound.args, depth)
+                .is_some()
+    }
+
+    /// The impl of `trait_` with `args` for `ty` whose own bounds hold, with what its
+    /// generics stand for.
+    pub(super) fn select_impl(
+        &mut self,
+        ty: &Ty,
+        trait_: DefId,
+        args: &[Ty],
+        depth: usize,
+    ) -> Option<(usize, HashMap<DefId, Ty>)> {
+        for i in 0..self.typing.impls.len() {
+            let imp = self.typing.impls[i].clone();
+            let Some((implemented, impl_args)) = &imp.trait_ else {
+                continue;
+            };
+            if *implemented != trait_ {
+                continue;
+            }
+            let saved = self.infer().clone();
+            let (subst, _) = self.instantiate(&imp.generics);
+            let target = imp.target.substitute(&subst);
+            let impl_args: Vec<_> = impl_args.iter().map(|ty| ty.substitute(&subst)).collect();
+            let item = self.items[&imp.node];
+            let found = self.infer().unify(&target, ty)
+                && self.infer().unify_all(&impl_args, args)
+                && self
+                    .bounds(item)
+                    .iter()
+                    .all(|bound| self.holds(&bound.substitute(&subst), depth + 1));
+            if found {
+                return Some((i, subst));
+            }
+            *self.infer() = saved;
+        }
+        None
+    }
+
+    /// The `Self` of the trait or impl `item`.
+    pub(super) fn self_param(&self, item: &Expr) -> Option<DefId> {
+        let index = self
+            .res
+            .defs
+            .iter()
+            .position(|def| def.kind == DefKind::SelfType && def.node == Some(item.id))?;
+        Some(DefId(index as u32))
+    }
+}
+
+fn generics_of(item: &Expr) -> Option<&Generics> {
+    let ExprKind::Statement(statement) = &item.kind else {
+        return None;
+    };
+    match statement {
+        Statement::Fn { generics, .. }
+        | Statement::Struct { generics, .. }
+        | Statement::Enum { generics, .. }
+        | Statement::Union { generics, .. }
+        | Statement::Trait { generics, .. }
+        | Statement::Impl { generics, .. }
+        | Statement::TypeAlias { generics, .. } => Some(generics),
+        _ => None,
+    }
+}
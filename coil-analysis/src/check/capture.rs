@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+
+use coil_error::Span;
+use coil_parser::{
+    walk, ArgName, BinaryOperator as B, Expr, ExprKind, Ident, NodeId, Statement,
+    UnaryOperator as U, Visitor,
+};
+
+use super::{Checker, Typing};
+use crate::{Capture, CaptureMode, Closure, DefId, DefKind, Definition, Primitive, Resolution, Ty};
+
+impl Checker<'_> {
+    /// Finds the variables each closure uses from the function around it, and whether it
+    /// holds them by reference or by value.
+    pub(super) fn captures(&mut self) {
+        let mut items: Vec<_> = self.items.values().copied().collect();
+        items.sort_by_key(|item| item.id);
+        let mut closures = HashMap::new();
+        for item in items {
+            let body = match &item.kind {
+                ExprKind::Statement(Statement::Fn {
+                    body: Some(body), ..
+                }) => body,
+                ExprKind::Statement(Statement::Static {
+                    value: Some(value), ..
+                }) => value,
+                _ => continue,
+            };
+            let mut captures = Captures {
+                res: self.res,
+                typing: &self.typing,
+                open: vec![],
+                using: Use::Value,
+                closures: HashMap::new(),
+            };
+            captures.visit_expr(body);
+            closures.extend(captures.closures);
+        }
+        self.typing.closures = closures;
+    }
+}
+
+/// How the expression being visited is used.
+#[derive(Clone, Copy)]
+enum Use {
+    /// Its value is taken, which moves it unless its type is copied.
+    Value,
+    /// As a place, held in the given way.
+    Place(CaptureMode),
+}
+
+struct Captures<'a> {
+    res: &'a Resolution,
+    typing: &'a Typing,
+    /// The closures around the walk, innermost last, with what each captures so far.
+    open: Vec<(Span, Vec<(DefId, CaptureMode)>)>,
+    using: Use,
+    closures: HashMap<NodeId, Closure>,
+}
+
+impl Captures<'_> {
+    /// Visits `expr`, which is used as `using`.
+    fn visit(&mut self, expr: &Expr, using: Use) {
+        self.using = using;
+        self.visit_expr(expr);
+    }
+
+    /// The way a use of `expr` as `using` holds it.
+    fn mode(&self, expr: &Expr, using: Use) -> CaptureMode {
+        match using {
+            Use::Place(mode) => mode,
+            Use::Value => match self.typing.exprs.get(&expr.id) {
+                Some(ty) if !is_copied(ty) => CaptureMode::Move,
+                _ => CaptureMode::Ref,
+            },
+        }
+    }
+
+    /// Records the use of the variable `expr` names in every closure around it that it is
+    /// declared outside of.
+    fn capture(&mut self, expr: &Expr, mode: CaptureMode) {
+        let Some(&def) = self.res.resolved(expr.id).first() else {
+            return;
+        };
+        let definition = self.res.def(def);
+        if !matches!(definition.kind, DefKind::Local { .. } | DefKind::Param) {
+            return;
+        }
+        for (span, captures) in &mut self.open {
+            if contains(*span, definition.span) {
+                continue;
+            }
+            match captures.iter_mut().find(|(other, _)| *other == def) {
+                Some((_, held)) => *held = mode.max(*held),
+                None => captures.push((def, mode)),
+            }
+        }
+    }
+
+    /// `callee(args)`, where the receiver of a method is held the way the method takes
+    /// `self`.
+    fn call(&mut self, call: &Expr, callee: &Expr, args: &[(Option<Box<str>>, Expr)]) {
+        let path = match &callee.kind {
+            ExprKind::Generic { path, .. } => path,
+            _ => callee,
+        };
+        let sig = self
+            .typing
+            .calls
+            .get(&call.id)
+            .and_then(|(def, _)| self.typing.signatures.get(def))
+            .filter(|sig| sig.has_self());
+        match (&path.kind, sig) {
+            (ExprKind::Binary { left, .. }, Some(sig)) => {
+                let using = match (&sig.params[0].1, self.typing.exprs.get(&left.id)) {
+                    (Ty::Reference { mutable: true, .. }, _) => Use::Place(CaptureMode::MutRef),
+                    (Ty::Reference { .. }, _) | (_, Some(Ty::Reference { .. })) => {
+                        Use::Place(CaptureMode::Ref)
+                    }
+                    _ => Use::Value,
+                };
+                self.visit(left, using);
+            }
+            // Calling a closure only reads it.
+            _ => self.visit(callee, Use::Place(CaptureMode::Ref)),
+        }
+        for (_, arg) in args {
+            self.visit(arg, Use::Value);
+        }
+    }
+
+    fn closure(&mut self, expr: &Expr, body: &Expr) {
+        self.open.push((expr.span, vec![]));
+        self.visit(body, Use::Value);
+        let (_, captures) = self.open.pop().unwrap();
+        let captures = captures
+            .into_iter()
+            .map(|(def, mode)| Capture {
+                def,
+                mode,
+                ty: self.typing.bindings.get(&def).cloned().unwrap_or(Ty::Error),
+            })
+            .collect();
+        let names = self.res.params.get(&expr.id).cloned().unwrap_or_default();
+        let (params, returns) = match self.typing.exprs.get(&expr.id) {
+            Some(Ty::Fn { params, returns }) => (params.clone(), (**returns).clone()),
+            _ => (vec![], Ty::Error),
+        };
+        let params = names
+            .iter()
+            .zip(params)
+            .map(|(&def, ty)| {
+                let Definition { name, span, .. } = self.res.def(def);
+                let name = Ident {
+                    text: name.clone(),
+                    span: *span,
+                };
+                (ArgName::Unnamed(name), ty)
+            })
+            .collect();
+        let closure = Closure {
+            captures,
+            params,
+            returns,
+        };
+        self.closures.insert(expr.id, closure);
+    }
+}
+
+impl Visitor<'_> for Captures<'_> {
+    fn visit_expr(&mut self, expr: &Expr) {
+        let using = std::mem::replace(&mut self.using, Use::Value);
+        match &expr.kind {
+            ExprKind::Identifier(_) => self.capture(expr, self.mode(expr, using)),
+            // A field is held the way the struct it is in is.
+            ExprKind::Binary {
+                op: B::Dot, left, ..
+            } => self.visit(left, Use::Place(self.mode(expr, using))),
+            ExprKind::Binary { op, left, right } if is_assignment(*op) => {
+                self.visit(left, Use::Place(CaptureMode::MutRef));
+                self.visit(right, Use::Value);
+            }
+            // Comparisons take their operands by reference.
+            ExprKind::Binary {
+                op: B::Eq | B::NotEq | B::Greater | B::GreaterEq | B::Lesser | B::LesserEq,
+                left,
+                right,
+            } => {
+                self.visit(left, Use::Place(CaptureMode::Ref));
+                self.visit(right, Use::Place(CaptureMode::Ref));
+            }
+            ExprKind::Unary {
+                op: U::Reference | U::DoubleReference,
+                expr,
+            } => self.visit(expr, Use::Place(CaptureMode::Ref)),
+            ExprKind::Unary {
+                op: U::MutReference,
+                expr,
+            } => self.visit(expr, Use::Place(CaptureMode::MutRef)),
+            // Going through a reference only needs the reference, held uniquely to change
+            // what it points to.
+            ExprKind::Unary {
+                op: U::Dereference,
+                expr: inner,
+            } => {
+                let mode = match self.mode(expr, using) {
+                    CaptureMode::MutRef => CaptureMode::MutRef,
+                    _ => CaptureMode::Ref,
+                };
+                self.visit(inner, Use::Place(mode));
+            }
+            ExprKind::Index { expr: base, index } => {
+                self.visit(base, Use::Place(self.mode(expr, using)));
+                self.visit(index, Use::Value);
+            }
+            ExprKind::Call { callee, args } => self.call(expr, callee, args),
+            ExprKind::Closure { body, .. } => self.closure(expr, body),
+            _ => walk::expr(self, expr),
+        }
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Let { .. }
+            | Statement::Return(_)
+            | Statement::Break { .. }
+            | Statement::Continue { .. }
+            | Statement::Fallthrough => walk::statement(self, statement),
+            // Items in a body are walked on their own.
+            _ => {}
+        }
+    }
+}
+
+fn is_assignment(op: B) -> bool {
+    matches!(
+        op,
+        B::Assign
+            | B::AddAssign
+            | B::SubAssign
+            | B::MulAssign
+            | B::DivAssign
+            | B::ModAssign
+            | B::AndAssign
+            | B::OrAssign
+            | B::BitAndAssign
+            | B::BitOrAssign
+            | B::BitXorAssign
+            | B::BitShiftLeftAssign
+            | B::BitShiftRightAssign
+    )
+}
+
+/// Whether a value of type `ty` is copied rather than moved when it is used: the
+/// primitives other than `Str`, `&` references, pointers, and what is made only of those.
+fn is_copied(ty: &Ty) -> bool {
+    match ty {
+        Ty::Primitive(primitive) => *primitive != Primitive::Str,
+        Ty::Reference { mutable, .. } => !mutable,
+        Ty::Pointer { .. } | Ty::Never | Ty::Error | Ty::IntVar(_) | Ty::FloatVar(_) => true,
+        Ty::Array { ty, .. } | Ty::Optional(ty) | Ty::Range(ty) => is_copied(ty),
+        Ty::Tuple(tys) => tys.iter().all(is_copied),
+        Ty::Result { ok, error } => is_copied(ok) && is_copied(error),
+        Ty::Adt { .. } | Ty::Slice(_) | Ty::Fn { .. } | Ty::Param(_) | Ty::Var(_) => false,
+    }
+}
+
+fn contains(outer: Span, inner: Span) -> bool {
+    outer.file == inner.file && outer.start <= inner.start && inner.end <= outer.end
+}
@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+
+use coil_error::{msg, Error, Span};
+use coil_parser::{Expr, ExprKind, ModuleId, NodeId, Statement};
+
+use super::{Body, Checker};
+use crate::{
+    DefId, FnSig, Impl, Ty, BUILTIN_IMPL, CONFLICTING_IMPLS, DUPLICATE_DEFINITION, FOREIGN_IMPL,
+    MISSING_TRAIT_ITEMS, NOT_A_TRAIT_ITEM, ORPHAN_IMPL, TRAIT_ITEM_MISMATCH,
+};
+
+impl Checker<'_> {
+    /// Checks that impls don't overlap, that each is in a module allowed to write it, that
+    /// trait impls implement their trait, and that inherent impls don't declare a function
+    /// twice.
+    pub(super) fn coherence(&mut self) {
+        for index in 0..self.typing.impls.len() {
+            let imp = self.typing.impls[index].clone();
+            self.orphan(&imp);
+            match &imp.trait_ {
+                Some((trait_, args)) => {
+                    self.overlap(index, *trait_);
+                    self.trait_items(&imp, *trait_, args);
+                }
+                None => self.duplicate_functions(index),
+            }
+        }
+    }
+
+    /// The trait and target of the impl at `node`, as written.
+    fn impl_span(&self, node: NodeId) -> Span {
+        let ExprKind::Statement(Statement::Impl { trait_, target, .. }) = &self.items[&node].kind
+        else {
+            unreachable!("impls are collected from impl items");
+        };
+        match trait_ {
+            Some(trait_) => trait_.span.to(target.span),
+            None => target.span,
+        }
+    }
+
+    /// Reports the impls of `trait_` before the impl `index` that could apply to the same
+    /// types.
+    fn overlap(&mut self, index: usize, trait_: DefId) {
+        let imp = self.typing.impls[index].clone();
+        let Some((_, args)) = &imp.trait_ else {
+            return;
+        };
+        if imp.target == Ty::Error {
+            return;
+        }
+        for other in 0..index {
+            let other = self.typing.impls[other].clone();
+            let Some((other_trait, other_args)) = &other.trait_ else {
+                continue;
+            };
+            if *other_trait != trait_ || other.target == Ty::Error {
+                continue;
+            }
+            self.bodies.push(Body::default());
+            let (subst, _) = self.instantiate(&imp.generics);
+            let (other_subst, _) = self.instantiate(&other.generics);
+            let target = imp.target.substitute(&subst);
+            let args: Vec<_> = args.iter().map(|ty| ty.substitute(&subst)).collect();
+            let other_args: Vec<_> = other_args
+                .iter()
+                .map(|ty| ty.substitute(&other_subst))
+                .collect();
+            let overlaps = self
+                .infer()
+                .unify(&target, &other.target.substitute(&other_subst))
+                && self.infer().unify_all(&args, &other_args);
+            let ty = self.display(&target);
+            self.bodies.pop();
+            if overlaps {
+                let trait_ = Ty::Adt {
+                    def: trait_,
+                    args: args.clone(),
+                };
+                let message = msg!(
+                    "analysis-conflicting-impls",
+                    name = self.display(&trait_),
+                    ty = ty
+                );
+                let first = self.impl_span(other.node);
+                let note = msg!(
+                    "analysis-note-other-impl",
+                    line = self.map.get(first.file).location(first.start).line,
+                    module = self.program.module_path(self.modules[&other.node])
+                );
+                let span = self.impl_span(imp.node);
+                let error = Error::at(CONFLICTING_IMPLS, &message, self.map, span).with_note(&note);
+                self.typing.errors.push(error);
+                return;
+            }
+        }
+    }
+
+    /// Reports the functions of the inherent impl `index` that an inherent impl before it,
+    /// for types that overlap, declares with the same name and labels.
+    fn duplicate_functions(&mut self, index: usize) {
+        let imp = self.typing.impls[index].clone();
+        if imp.target == Ty::Error {
+            return;
+        }
+        let mut names: Vec<_> = imp.items.keys().cloned().collect();
+        names.sort();
+        let mut reported = vec![];
+        for other in 0..index {
+            let other = self.typing.impls[other].clone();
+            if other.trait_.is_some() || other.target == Ty::Error {
+                continue;
+            }
+            self.bodies.push(Body::default());
+            let (subst, _) = self.instantiate(&imp.generics);
+            let (other_subst, _) = self.instantiate(&other.generics);
+            let overlaps = self.infer().unify(
+                &imp.target.substitute(&subst),
+                &other.target.substitute(&other_subst),
+            );
+            self.bodies.pop();
+            if !overlaps {
+                continue;
+            }
+            for name in &names {
+                let Some(firsts) = other.items.get(name) else {
+                    continue;
+                };
+                for &def in &imp.items[name] {
+                    let sig = self.signature(def);
+                    let labels = sig.labels();
+                    let first = firsts
+                        .iter()
+                        .copied()
+                        .find(|&first| self.signature(first).match_labels(0, &labels).is_ok());
+                    if let Some(first) = first.filter(|_| !reported.contains(&def)) {
+                        reported.push(def);
+                        self.duplicate_function(first, def);
+                    }
+                }
+            }
+        }
+    }
+
+    fn duplicate_function(&mut self, first: DefId, def: DefId) {
+        let first = self.res.def(first).span;
+        let line = self.map.get(first.file).location(first.start).line;
+        let def = self.res.def(def);
+        let message = msg!("analysis-duplicate-definition", name = def.name);
+        let note = msg!("analysis-note-first-definition", line = line);
+        let error = Error::at(DUPLICATE_DEFINITION, &message, self.map, def.span).with_note(&note);
+        self.typing.errors.push(error);
+    }
+
+    /// Reports an impl outside the modules that declare its trait or the type it is for.
+    /// Functions can only be added to a type of the program, in its own module.
+    fn orphan(&mut self, imp: &Impl) {
+        let module = self.modules[&imp.node];
+        let owner = |this: &Self, def: DefId| {
+            let node = this.res.def(def).node?;
+            this.modules.get(&node).copied()
+        };
+        let mut owners = vec![];
+        if let Some((trait_, _)) = &imp.trait_ {
+            owners.extend(owner(self, *trait_));
+        }
+        if let Ty::Adt { def, .. } = &imp.target {
+            owners.extend(owner(self, *def));
+        }
+        if owners
+            .iter()
+            .any(|&owner| self.program.is_within(module, owner))
+        {
+            return;
+        }
+        let ty = self.display(&imp.target);
+        let (code, message) = match &imp.trait_ {
+            Some((trait_, args)) => {
+                let trait_ = Ty::Adt {
+                    def: *trait_,
+                    args: args.clone(),
+                };
+                let name = self.display(&trait_);
+                let message = msg!("analysis-orphan-impl", name = name, ty = ty);
+                (ORPHAN_IMPL, message)
+            }
+            None if owners.is_empty() => (BUILTIN_IMPL, msg!("analysis-builtin-impl", ty = ty)),
+            None => (FOREIGN_IMPL, msg!("analysis-foreign-impl", ty = ty)),
+        };
+        let mut error = Error::at(code, &message, self.map, self.impl_span(imp.node));
+        if imp.trait_.is_none() && owners.is_empty() {
+            error = error.with_note(&msg!("analysis-note-use-trait", ty = ty));
+        }
+        for owner in owners {
+            let module = self.program.module_path(owner);
+            error = error.with_note(&msg!("analysis-note-move-impl", module = module));
+        }
+        self.typing.errors.push(error);
+    }
+
+    /// Checks that the impl `imp` of `trait_` implements every function of the trait
+    /// without a body, with the same labels and types, and nothing else.
+    fn trait_items(&mut self, imp: &Impl, trait_: DefId, args: &[Ty]) {
+        let Some(info) = self.trait_info(trait_) else {
+            return;
+        };
+        let mut subst: HashMap<_, _> = info.generics.iter().copied().zip(args.to_vec()).collect();
+        subst.insert(info.self_param, imp.target.clone());
+        let trait_name = self.res.def(trait_).name.clone();
+        let mut missing = vec![];
+        let mut unmatched: Vec<DefId> = imp.items.values().flatten().copied().collect();
+        unmatched.sort();
+        let mut required: Vec<DefId> = info.items.values().flatten().copied().collect();
+        required.sort();
+        for def in required {
+            let expected = self.signature(def);
+            let name = self.res.def(def).name.clone();
+            let labels = expected.labels();
+            let same_name: Vec<_> = unmatched
+                .iter()
+                .copied()
+                .filter(|&found| self.res.def(found).name == name)
+                .collect();
+            let found = same_name
+                .iter()
+                .copied()
+                .find(|&found| self.signature(found).match_labels(0, &labels).is_ok());
+            // A function with the same name but other labels is taken to be the one meant
+            // when it is the only one left.
+            let found = found.or(match (&*same_name, info.items[&name].len()) {
+                ([found], 1) => Some(*found),
+                _ => None,
+            });
+            let Some(found) = found else {
+                if !self.has_body(def) {
+                    missing.push(format!("'{}'", self.labelled_name(&name, &expected)));
+                }
+                continue;
+            };
+            unmatched.retain(|&other| other != found);
+            let actual = self.signature(found);
+            let mut fn_subst = subst.clone();
+            fn_subst.extend(
+                expected
+                    .generics
+                    .iter()
+                    .copied()
+                    .zip(actual.generics.iter().map(|&param| Ty::Param(param))),
+            );
+            let expected = FnSig {
+                generics: expected.generics.clone(),
+                params: expected
+                    .params
+                    .iter()
+                    .map(|(arg, ty)| (arg.clone(), ty.substitute(&fn_subst)))
+                    .collect(),
+                returns: expected.returns.substitute(&fn_subst),
+            };
+            let matches = expected.generics.len() == actual.generics.len()
+                && actual.match_labels(0, &expected.labels()).is_ok()
+                && expected
+                    .params
+                    .iter()
+                    .zip(&actual.params)
+                    .all(|((_, x), (_, y))| x == y)
+                && expected.returns == actual.returns;
+            if !matches {
+                let message = msg!(
+                    "analysis-trait-item-mismatch",
+                    name = self.labelled_name(&name, &actual),
+                    trait_ = trait_name
+                );
+                let note = msg!(
+                    "analysis-note-trait-declares",
+                    signature = self.signature_text(&name, &expected)
+                );
+                let span = self.res.def(found).span;
+                let error =
+                    Error::at(TRAIT_ITEM_MISMATCH, &message, self.map, span).with_note(&note);
+                self.typing.errors.push(error);
+            }
+        }
+        if !missing.is_empty() {
+            let message = msg!(
+                "analysis-missing-trait-items",
+                names = missing.join(", "),
+                trait_ = trait_name
+            );
+            self.error(MISSING_TRAIT_ITEMS, &message, self.impl_span(imp.node));
+        }
+        for def in unmatched {
+            let name = self.res.def(def).name.clone();
+            let sig = self.signature(def);
+            let message = msg!(
+                "analysis-not-a-trait-item",
+                name = self.labelled_name(&name, &sig),
+                trait_ = trait_name
+            );
+            self.error(NOT_A_TRAIT_ITEM, &message, self.res.def(def).span);
+        }
+    }
+
+    fn has_body(&self, def: DefId) -> bool {
+        matches!(
+            self.item(def).map(|item| &item.kind),
+            Some(ExprKind::Statement(Statement::Fn { body: Some(_), .. }))
+        )
+    }
+
+    /// A function named with its labels, as in `eq(_:)`, leaving out `self`.
+    pub(super) fn labelled_name(&self, name: &str, sig: &FnSig) -> String {
+        let labels: String = sig
+            .params
+            .iter()
+            .skip(usize::from(sig.has_self()))
+            .map(|(arg, _)| format!("{}:", arg.label().unwrap_or("_")))
+            .collect();
+        format!("{name}({labels})")
+    }
+
+    /// A function written out with its labels and types, as in `fn eq(self: Int, _: Int)
+    /// -> Bool`.
+    fn signature_text(&self, name: &str, sig: &FnSig) -> String {
+        let params: Vec<_> = sig
+            .params
+            .iter()
+            .map(|(arg, ty)| format!("{}: {}", arg.label().unwrap_or("_"), self.display(ty)))
+            .collect();
+        let generics: Vec<_> = sig
+            .generics
+            .iter()
+            .map(|&param| self.res.def(param).name.to_string())
+            .collect();
+        let generics = match generics.is_empty() {
+            true => String::new(),
+            false => format!("<{}>", generics.join(", ")),
+        };
+        format!(
+            "fn {name}{generics}({}) -> {}",
+            params.join(", "),
+            self.display(&sig.returns)
+        )
+    }
+
+    /// Records the module `items` are declared in, and that of the items of their
+    /// modules.
+    pub(super) fn module_items(&mut self, id: ModuleId, items: &[Expr]) {
+        for item in items {
+            self.modules.insert(item.id, id);
+            if let ExprKind::Statement(Statement::Module { children, .. }) = &item.kind {
+                let child = self
+                    .res
+                    .modules
+                    .iter()
+                    .position(|&def| self.res.def(def).node == Some(item.id));
+                if let Some(child) = child {
+                    self.module_items(ModuleId(child as u32), children);
+                }
+            }
+        }
+    }
+}
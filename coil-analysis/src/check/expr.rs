@@ -0,0 +1,1221 @@
+use std::collections::HashMap;
+
+use coil_error::{msg, Error, Span};
+use coil_lexer::Literal;
+use coil_parser::{
+    BinaryOperator as B, ClosureParam, Expr, ExprKind, LabelMismatch, MatchArm, Statement,
+    TypeExpr, UnaryOperator as U,
+};
+
+use super::Checker;
+use crate::{
+    suggest, AdtKind, DefId, DefKind, Primitive, Ty, EXPECTED_VALUE, INVALID_OPERAND,
+    LITERAL_OUT_OF_RANGE, MISORDERED_LABEL, MISSING_LABEL, NOT_CALLABLE, RETURN_OUTSIDE_FN,
+    UNEXPECTED_LABEL, UNKNOWN_FIELD, UNKNOWN_METHOD, WRONG_ARGUMENT_COUNT, WRONG_LABEL,
+};
+
+const BOOL: Ty = Ty::Primitive(Primitive::Bool);
+
+impl Checker<'_> {
+    /// Checks that `expr` has the type `expected`, reporting it if not.
+    pub(super) fn check(&mut self, expr: &Expr, expected: &Ty) -> Ty {
+        let found = self.expr(expr, Some(expected));
+        if !self.coerce(&found, expected) {
+            self.mismatch(expr.span, expected, &found);
+        }
+        expected.clone()
+    }
+
+    /// Like [`check`](Self::check), except that any value fits where `()` is expected and
+    /// is dropped, as in the last statement of a function returning nothing.
+    fn check_or_drop(&mut self, expr: &Expr, expected: &Ty) -> Ty {
+        if self.infer().shallow(expected).is_unit() {
+            match self.expr(expr, Some(expected)) {
+                Ty::Never => Ty::Never,
+                _ => Ty::unit(),
+            }
+        } else {
+            self.check(expr, expected)
+        }
+    }
+
+    /// Whether a value of type `found` can be used where `expected` is. Besides values of
+    /// the same type, a `T` fits where a `T?` or `T!E` is expected, an array where a slice
+    /// is, and a mutable reference where a shared one is.
+    pub(super) fn coerce(&mut self, found: &Ty, expected: &Ty) -> bool {
+        let (found, expected) = (self.infer().shallow(found), self.infer().shallow(expected));
+        if self.infer().probe(|infer| infer.unify(&found, &expected)) {
+            return true;
+        }
+        match (&found, &expected) {
+            (_, Ty::Optional(ty) | Ty::Result { ok: ty, .. }) => self.coerce(&found, ty),
+            (Ty::Array { ty: found, .. }, Ty::Slice(expected)) => {
+                self.infer().unify(found, expected)
+            }
+            (
+                Ty::Reference {
+                    mutable: found_mutable,
+                    ty: found,
+                },
+                Ty::Reference {
+                    mutable: expected_mutable,
+                    ty: expected,
+                },
+            ) if *found_mutable || !expected_mutable => {
+                match (self.infer().shallow(found), self.infer().shallow(expected)) {
+                    (Ty::Array { ty: found, .. }, Ty::Slice(expected)) => {
+                        self.infer().unify(&found, &expected)
+                    }
+                    (found, expected) => {
+                        found_mutable != expected_mutable && self.infer().unify(&found, &expected)
+                    }
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// The type of `expr`. `expected` is what the context wants, which guides inference
+    /// but is checked by the caller.
+    pub(super) fn expr(&mut self, expr: &Expr, expected: Option<&Ty>) -> Ty {
+        let expected = expected.map(|ty| self.infer().shallow(ty));
+        let ty = self.expr_kind(expr, expected.as_ref());
+        self.body().exprs.insert(expr.id, ty.clone());
+        ty
+    }
+
+    fn expr_kind(&mut self, expr: &Expr, expected: Option<&Ty>) -> Ty {
+        match &expr.kind {
+            ExprKind::Statement(statement) => self.statement(expr, statement),
+            ExprKind::Literal(literal, text) => {
+                self.literal(expr.span, literal, text, expected, false)
+            }
+            ExprKind::Bool(_) => BOOL,
+            ExprKind::Identifier(_) => self.value(expr.id, expr.span, None),
+            ExprKind::Generic { path, args } => {
+                let node = match &path.kind {
+                    ExprKind::Binary {
+                        op: B::Dot, right, ..
+                    } => right.id,
+                    _ => path.id,
+                };
+                self.value(node, expr.span, Some(args))
+            }
+            ExprKind::Binary {
+                op: B::Dot,
+                left,
+                right,
+            } => self.dot(left, right),
+            ExprKind::Binary { op, left, right } => self.binary(*op, left, right, expected),
+            ExprKind::Unary { op, expr: operand } => self.unary(expr.span, *op, operand, expected),
+            ExprKind::Call { callee, args } => self.call(expr, callee, args),
+            ExprKind::Index { expr: base, index } => self.index(base, index),
+            ExprKind::Tuple(items) => match expected {
+                Some(Ty::Tuple(types)) if types.len() == items.len() => {
+                    for (item, ty) in items.iter().zip(types) {
+                        self.check(item, ty);
+                    }
+                    Ty::Tuple(types.clone())
+                }
+                _ => Ty::Tuple(items.iter().map(|item| self.expr(item, None)).collect()),
+            },
+            ExprKind::Array(items) => {
+                let element = match expected {
+                    Some(Ty::Array { ty, .. } | Ty::Slice(ty)) => Some((**ty).clone()),
+                    _ => None,
+                };
+                let element = match (element, items.first()) {
+                    (Some(ty), _) => ty,
+                    (None, Some(first)) => self.expr(first, None),
+                    (None, None) => self.infer().var(),
+                };
+                for item in items {
+                    self.check(item, &element);
+                }
+                Ty::Array {
+                    ty: Box::new(element),
+                    len: Some(items.len() as u64),
+                }
+            }
+            ExprKind::StructLiteral { path, fields } => self.struct_literal(path, fields, expected),
+            ExprKind::Block(statements) => self.block(statements, expected),
+            ExprKind::If {
+                condition,
+                then,
+                otherwise,
+                ..
+            } => {
+                self.check(condition, &BOOL);
+                let Some(otherwise) = otherwise else {
+                    self.check_or_drop(then, &Ty::unit());
+                    return Ty::unit();
+                };
+                if let Some(expected) = expected {
+                    self.check_or_drop(then, expected);
+                    self.check_or_drop(otherwise, expected);
+                    return expected.clone();
+                }
+                let then_ty = self.expr(then, None);
+                if self.infer().shallow(&then_ty) == Ty::Never {
+                    return self.expr(otherwise, None);
+                }
+                let otherwise_ty = self.expr(otherwise, None);
+                if !self.coerce(&otherwise_ty, &then_ty) {
+                    self.mismatch(tail_span(otherwise), &then_ty, &otherwise_ty);
+                }
+                then_ty
+            }
+            ExprKind::While {
+                condition, body, ..
+            }
+            | ExprKind::DoWhile {
+                condition, body, ..
+            } => {
+                self.check(condition, &BOOL);
+                self.check_or_drop(body, &Ty::unit());
+                Ty::unit()
+            }
+            ExprKind::For { iterable, body, .. } => {
+                let ty = self.expr(iterable, None);
+                let element = self.element(iterable.span, &ty);
+                if let Some(&def) = self.res.declarations.get(&expr.id) {
+                    self.body().bindings.insert(def, element);
+                }
+                self.check_or_drop(body, &Ty::unit());
+                Ty::unit()
+            }
+            ExprKind::Match { scrutinee, arms } => self.match_(scrutinee, arms, expected),
+            ExprKind::Closure {
+                params,
+                returns,
+                body,
+            } => self.closure(expr, params, returns.as_deref(), body, expected),
+            ExprKind::Error => Ty::Error,
+        }
+    }
+
+    fn statement(&mut self, expr: &Expr, statement: &Statement) -> Ty {
+        match statement {
+            Statement::Let { ty, value, .. } => {
+                let declared = ty.as_ref().map(|ty| self.lower(ty));
+                let ty = match (declared, value) {
+                    (Some(ty), Some(value)) => self.check(value, &ty),
+                    (Some(ty), None) => ty,
+                    (None, Some(value)) => self.expr(value, None),
+                    (None, None) => self.infer().var(),
+                };
+                if let Some(&def) = self.res.declarations.get(&expr.id) {
+                    self.body().bindings.insert(def, ty);
+                    self.body().lets.push(def);
+                }
+                Ty::unit()
+            }
+            Statement::Return(value) => {
+                let Some(returns) = self.body().returns.last().cloned() else {
+                    self.error(
+                        RETURN_OUTSIDE_FN,
+                        &msg!("analysis-return-outside-fn"),
+                        expr.span,
+                    );
+                    if let Some(value) = value {
+                        self.expr(value, None);
+                    }
+                    return Ty::Never;
+                };
+                match value {
+                    Some(value) => {
+                        self.check(value, &returns);
+                    }
+                    // A function returning `T?` returns no value this way.
+                    None if matches!(self.infer().shallow(&returns), Ty::Optional(_)) => {}
+                    None => {
+                        if !self.coerce(&Ty::unit(), &returns) {
+                            self.mismatch(expr.span, &returns, &Ty::unit());
+                        }
+                    }
+                }
+                Ty::Never
+            }
+            Statement::Break { value, .. } => {
+                if let Some(value) = value {
+                    self.expr(value, None);
+                }
+                Ty::Never
+            }
+            Statement::Continue { .. } | Statement::Fallthrough => Ty::Never,
+            _ => {
+                self.item_bodies(expr);
+                Ty::unit()
+            }
+        }
+    }
+
+    fn block(&mut self, statements: &[Expr], expected: Option<&Ty>) -> Ty {
+        let Some((last, statements)) = statements.split_last() else {
+            return Ty::unit();
+        };
+        for statement in statements {
+            self.expr(statement, Some(&Ty::unit()));
+        }
+        if let ExprKind::Statement(_) = last.kind {
+            return match self.expr(last, None) {
+                Ty::Never => Ty::Never,
+                _ => Ty::unit(),
+            };
+        }
+        match expected {
+            Some(expected) => self.check_or_drop(last, expected),
+            None => self.expr(last, None),
+        }
+    }
+
+    pub(super) fn literal(
+        &mut self,
+        span: Span,
+        literal: &Literal,
+        text: &str,
+        expected: Option<&Ty>,
+        negative: bool,
+    ) -> Ty {
+        // A suffix fixes the type, whatever the context expects.
+        let expected = match literal.suffix().and_then(Primitive::from_suffix) {
+            Some(primitive) => Some(Ty::Primitive(primitive)),
+            None => expected.cloned(),
+        };
+        match (literal, expected.as_ref()) {
+            (Literal::String, _) => Ty::Primitive(Primitive::Str),
+            (Literal::Char, _) => Ty::Primitive(Primitive::Char),
+            (Literal::Integer { radix, .. }, Some(Ty::Primitive(primitive)))
+                if primitive.is_integer() =>
+            {
+                self.integer_range(span, text, *radix, *primitive, negative);
+                Ty::Primitive(*primitive)
+            }
+            (Literal::Integer { .. }, Some(ty @ Ty::IntVar(_))) => ty.clone(),
+            (Literal::Integer { .. }, _) => self.infer().int_var(),
+            (Literal::Float { .. }, Some(ty)) if ty.is_float() => ty.clone(),
+            (Literal::Float { .. }, _) => self.infer().float_var(),
+        }
+    }
+
+    /// Reports an integer literal that doesn't fit in `primitive`.
+    fn integer_range(
+        &mut self,
+        span: Span,
+        text: &str,
+        radix: usize,
+        primitive: Primitive,
+        negative: bool,
+    ) {
+        let bits = primitive.bits().unwrap();
+        let (min, max) = match primitive.is_signed() {
+            true => (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1),
+            false => (0, (1i128 << bits) - 1),
+        };
+        let value = i128::from_str_radix(text, radix as u32).ok();
+        let fits = value.is_some_and(|value| match negative {
+            true => -value >= min,
+            false => value <= max,
+        });
+        if !fits {
+            let ty = primitive.name();
+            let message = msg!("analysis-literal-out-of-range", ty = ty);
+            let note = msg!("analysis-note-range", ty = ty, min = min, max = max);
+            let error = Error::at(LITERAL_OUT_OF_RANGE, &message, self.map, span).with_note(&note);
+            self.typing.errors.push(error);
+        }
+    }
+
+    /// The type of the value named at `node`, with the type arguments `explicit` for a
+    /// generic function.
+    fn value(
+        &mut self,
+        node: coil_parser::NodeId,
+        span: Span,
+        explicit: Option<&[TypeExpr]>,
+    ) -> Ty {
+        let Some(&def) = self.res.resolved(node).first() else {
+            return Ty::Error;
+        };
+        let name = self.res.def(def).name.clone();
+        match self.res.def(def).kind {
+            DefKind::Local { .. } | DefKind::Param => {
+                self.body().bindings.get(&def).cloned().unwrap_or(Ty::Error)
+            }
+            DefKind::Fn => {
+                let sig = self.signature(def);
+                let subst = self.instantiate_with(&sig.generics, explicit, &name, span);
+                self.require_bounds(def, &subst, span);
+                self.body().calls.insert(node, (def, subst.clone()));
+                Ty::Fn {
+                    params: sig
+                        .params
+                        .iter()
+                        .map(|(_, ty)| ty.substitute(&subst))
+                        .collect(),
+                    returns: Box::new(sig.returns.substitute(&subst)),
+                }
+            }
+            DefKind::Static => self.static_type(def),
+            DefKind::Variant { .. } | DefKind::Struct => match self.instantiate_variant(def) {
+                Some((ty, variant)) if variant.tuple || variant.fields.is_empty() => {
+                    self.require_adt_bounds(&ty, span);
+                    match variant.tuple {
+                        true => Ty::Fn {
+                            params: variant.fields.into_iter().map(|(_, ty)| ty).collect(),
+                            returns: Box::new(ty),
+                        },
+                        false => ty,
+                    }
+                }
+                _ => self.not_a_value(&name, span),
+            },
+            _ => self.not_a_value(&name, span),
+        }
+    }
+
+    fn not_a_value(&mut self, name: &str, span: Span) -> Ty {
+        let message = msg!("analysis-not-a-value", name = name);
+        self.error(EXPECTED_VALUE, &message, span);
+        Ty::Error
+    }
+
+    /// Fresh variables for `generics`, unified with the type arguments `explicit` if
+    /// they are written.
+    fn instantiate_with(
+        &mut self,
+        generics: &[DefId],
+        explicit: Option<&[TypeExpr]>,
+        name: &str,
+        span: Span,
+    ) -> HashMap<DefId, Ty> {
+        let (subst, vars) = self.instantiate(generics);
+        if let Some(explicit) = explicit {
+            let args: Vec<_> = explicit.iter().map(|arg| self.lower(arg)).collect();
+            if self.type_args(name, generics.len(), &args, span) {
+                for (var, arg) in vars.iter().zip(&args) {
+                    self.infer().unify(var, arg);
+                }
+            }
+        }
+        subst
+    }
+
+    /// The type the path `expr` names, if it names one, as in `Point.new()`.
+    fn type_path(&mut self, expr: &Expr) -> Option<Ty> {
+        let (path, args) = match &expr.kind {
+            ExprKind::Generic { path, args } => (&**path, &args[..]),
+            _ => (expr, &[][..]),
+        };
+        if !matches!(
+            path.kind,
+            ExprKind::Identifier(_) | ExprKind::Binary { op: B::Dot, .. }
+        ) {
+            return None;
+        }
+        let def = self.path_def(path)?;
+        let kind = self.res.def(def).kind;
+        if !kind.is_type() || kind == DefKind::Trait {
+            return None;
+        }
+        let args = args.iter().map(|arg| self.lower(arg)).collect();
+        Some(self.lower_path(def, args, expr.span))
+    }
+
+    /// `left.right`: a path to a value, a function of a type, or a field.
+    fn dot(&mut self, left: &Expr, right: &Expr) -> Ty {
+        if !self.res.resolved(right.id).is_empty() {
+            return self.value(right.id, right.span, None);
+        }
+        if let (ExprKind::Identifier(name), Some(ty)) = (&right.kind, self.type_path(left)) {
+            let Some((def, subst)) = self.find_function(&ty, name, false, None) else {
+                self.unknown_function(&ty, name, right.span);
+                return Ty::Error;
+            };
+            let sig = self.signature(def);
+            let (own, _) = self.instantiate(&sig.generics);
+            let subst: HashMap<_, _> = subst.into_iter().chain(own).collect();
+            self.require_bounds(def, &subst, right.span);
+            self.body().calls.insert(right.id, (def, subst.clone()));
+            return Ty::Fn {
+                params: sig
+                    .params
+                    .iter()
+                    .map(|(_, ty)| ty.substitute(&subst))
+                    .collect(),
+                returns: Box::new(sig.returns.substitute(&subst)),
+            };
+        }
+        let base = self.expr(left, None);
+        self.field(&base, right)
+    }
+
+    /// The type of the field `right` of a value of type `base`.
+    fn field(&mut self, base: &Ty, right: &Expr) -> Ty {
+        let name = match &right.kind {
+            ExprKind::Identifier(name) | ExprKind::Literal(Literal::Integer { .. }, name) => name,
+            _ => return Ty::Error,
+        };
+        let ty = self.autoderef(base);
+        let fields: Vec<(Box<str>, Ty)> = match &ty {
+            Ty::Error => return Ty::Error,
+            Ty::Tuple(types) => types
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| (i.to_string().into(), ty.clone()))
+                .collect(),
+            Ty::Adt { def, args } => {
+                let adt = self.adt(*def);
+                match adt.kind {
+                    AdtKind::Enum => vec![],
+                    _ => {
+                        let subst = adt.generics.iter().copied().zip(args.clone()).collect();
+                        adt.variants[0]
+                            .fields
+                            .iter()
+                            .map(|(name, ty)| (name.clone(), ty.substitute(&subst)))
+                            .collect()
+                    }
+                }
+            }
+            _ => vec![],
+        };
+        if let Some((_, ty)) = fields.iter().find(|(field, _)| field == name) {
+            return ty.clone();
+        }
+        let message = msg!(
+            "analysis-unknown-field",
+            name = name,
+            ty = self.display(&ty)
+        );
+        let error = Error::at(UNKNOWN_FIELD, &message, self.map, right.span);
+        let names: Vec<_> = fields.into_iter().map(|(name, _)| name).collect();
+        self.typing.errors.push(suggest(error, name, &names));
+        Ty::Error
+    }
+
+    /// `ty` with the references around it taken off.
+    fn autoderef(&mut self, ty: &Ty) -> Ty {
+        let mut ty = self.infer().shallow(ty);
+        while let Ty::Reference { ty: inner, .. } = ty {
+            ty = self.infer().shallow(&inner);
+        }
+        ty
+    }
+
+    fn unknown_function(&mut self, ty: &Ty, name: &str, span: Span) {
+        let message = msg!(
+            "analysis-unknown-function",
+            name = name,
+            ty = self.display(ty)
+        );
+        self.error(UNKNOWN_METHOD, &message, span);
+    }
+
+    /// Finds the function `name` among the impls for `ty` and the traits they implement,
+    /// with what the generics of the impl stand for. A method takes `self` and is called
+    /// on a value; otherwise the function is called on the type. `labels` picks among
+    /// overloads.
+    pub(super) fn find_function(
+        &mut self,
+        ty: &Ty,
+        name: &str,
+        method: bool,
+        labels: Option<&[Option<&str>]>,
+    ) -> Option<(DefId, HashMap<DefId, Ty>)> {
+        if let Ty::Param(param) = self.infer().shallow(ty) {
+            // A generic parameter, or the `Self` of a trait, has the functions of the
+            // traits it is bound by.
+            for bound in self.body().environment.clone() {
+                if bound.ty != Ty::Param(param) {
+                    continue;
+                }
+                let Some(info) = self.trait_info(bound.trait_) else {
+                    continue;
+                };
+                let candidates = info.items.get(name).cloned().unwrap_or_default();
+                let Some(def) = self.pick_overload(&candidates, method, labels) else {
+                    continue;
+                };
+                let mut subst: HashMap<_, _> =
+                    info.generics.iter().copied().zip(bound.args).collect();
+                subst.insert(info.self_param, ty.clone());
+                return Some((def, subst));
+            }
+            return None;
+        }
+        for i in 0..self.typing.impls.len() {
+            let imp = self.typing.impls[i].clone();
+            let mut implied = vec![];
+            let candidates = match (imp.items.get(name), &imp.trait_) {
+                (Some(defs), _) => defs.clone(),
+                (None, Some((trait_, args))) => {
+                    let Some(info) = self.trait_info(*trait_) else {
+                        continue;
+                    };
+                    implied.push((info.self_param, imp.target.clone()));
+                    implied.extend(info.generics.iter().copied().zip(args.iter().cloned()));
+                    info.items.get(name).cloned().unwrap_or_default()
+                }
+                (None, None) => continue,
+            };
+            let Some(def) = self.pick_overload(&candidates, method, labels) else {
+                continue;
+            };
+            let (mut subst, _) = self.instantiate(&imp.generics);
+            let target = imp.target.substitute(&subst);
+            if !self.infer().probe(|infer| infer.unify(&target, ty)) {
+                continue;
+            }
+            for (param, ty) in implied {
+                let ty = ty.substitute(&subst);
+                subst.insert(param, ty);
+            }
+            return Some((def, subst));
+        }
+        None
+    }
+
+    /// The function among `candidates` that is a method if `method` is set, preferring the
+    /// one whose labels are `labels`.
+    fn pick_overload(
+        &mut self,
+        candidates: &[DefId],
+        method: bool,
+        labels: Option<&[Option<&str>]>,
+    ) -> Option<DefId> {
+        let candidates: Vec<_> = candidates
+            .iter()
+            .copied()
+            .filter(|&def| self.signature(def).has_self() == method)
+            .collect();
+        match labels {
+            Some(labels) => self.overload(&candidates, usize::from(method), labels),
+            None => candidates.first().copied(),
+        }
+    }
+
+    /// The function among `candidates` a call with `labels` for the parameters after
+    /// `skip` is meant for: one that accepts them, else one taking as many arguments,
+    /// else the first. [`call_fn`](Self::call_fn) reports the labels that don't fit.
+    fn overload(
+        &mut self,
+        candidates: &[DefId],
+        skip: usize,
+        labels: &[Option<&str>],
+    ) -> Option<DefId> {
+        let accepted = candidates
+            .iter()
+            .copied()
+            .find(|&def| self.signature(def).match_labels(skip, labels).is_ok());
+        accepted
+            .or_else(|| {
+                candidates
+                    .iter()
+                    .copied()
+                    .find(|&def| self.signature(def).params.len() == skip + labels.len())
+            })
+            .or(candidates.first().copied())
+    }
+
+    fn call(&mut self, expr: &Expr, callee: &Expr, args: &[(Option<Box<str>>, Expr)]) -> Ty {
+        let labels: Vec<_> = args.iter().map(|(label, _)| label.as_deref()).collect();
+        let (path, explicit) = match &callee.kind {
+            ExprKind::Generic { path, args } => (&**path, Some(&args[..])),
+            _ => (callee, None),
+        };
+        let name_node = match &path.kind {
+            ExprKind::Binary {
+                op: B::Dot, right, ..
+            } => right,
+            _ => path,
+        };
+        let defs = self.res.resolved(name_node.id);
+        if !defs.is_empty()
+            && defs
+                .iter()
+                .all(|&def| self.res.def(def).kind == DefKind::Fn)
+        {
+            let def = self.overload(defs, 0, &labels).unwrap_or(defs[0]);
+            return self.call_fn(expr, def, None, HashMap::new(), explicit, args);
+        }
+        if let (
+            ExprKind::Binary {
+                op: B::Dot,
+                left,
+                right,
+            },
+            true,
+        ) = (&path.kind, defs.is_empty())
+        {
+            if let ExprKind::Identifier(name) = &right.kind {
+                return self.method_call(expr, left, right.span, name, explicit, args);
+            }
+        }
+        let callee_ty = self.expr(callee, None);
+        match self.infer().shallow(&callee_ty) {
+            Ty::Fn { params, returns } => {
+                if params.len() != args.len() {
+                    self.argument_count(expr.span, params.len(), args, None);
+                } else {
+                    for ((_, arg), param) in args.iter().zip(&params) {
+                        self.check(arg, param);
+                    }
+                }
+                *returns
+            }
+            ty => {
+                if ty != Ty::Error {
+                    let message = msg!("analysis-not-callable", ty = self.display(&ty));
+                    self.error(NOT_CALLABLE, &message, callee.span);
+                }
+                for (_, arg) in args {
+                    self.expr(arg, None);
+                }
+                Ty::Error
+            }
+        }
+    }
+
+    /// `left.name(args)`, where `left` is a value or names a type.
+    fn method_call(
+        &mut self,
+        expr: &Expr,
+        left: &Expr,
+        span: Span,
+        name: &str,
+        explicit: Option<&[TypeExpr]>,
+        args: &[(Option<Box<str>>, Expr)],
+    ) -> Ty {
+        let labels: Vec<_> = args.iter().map(|(label, _)| label.as_deref()).collect();
+        if let Some(ty) = self.type_path(left) {
+            if let Some((def, subst)) = self.find_function(&ty, name, false, Some(&labels)) {
+                return self.call_fn(expr, def, None, subst, explicit, args);
+            }
+            if ty != Ty::Error {
+                self.unknown_function(&ty, name, span);
+            }
+        } else {
+            let receiver = self.expr(left, None);
+            let mut ty = self.infer().shallow(&receiver);
+            loop {
+                if ty == Ty::Error {
+                    break;
+                }
+                if let Some((def, subst)) = self.find_function(&ty, name, true, Some(&labels)) {
+                    let receiver = Some((left, receiver));
+                    return self.call_fn(expr, def, receiver, subst, explicit, args);
+                }
+                match ty {
+                    Ty::Reference { ty: inner, .. } => ty = self.infer().shallow(&inner),
+                    _ => {
+                        let message = msg!(
+                            "analysis-unknown-method",
+                            name = name,
+                            ty = self.display(&receiver)
+                        );
+                        self.error(UNKNOWN_METHOD, &message, span);
+                        break;
+                    }
+                }
+            }
+        }
+        for (_, arg) in args {
+            self.expr(arg, None);
+        }
+        Ty::Error
+    }
+
+    /// A call of the function `def`, on `receiver` for a method. `subst` says what the
+    /// generics of its impl stand for.
+    fn call_fn(
+        &mut self,
+        expr: &Expr,
+        def: DefId,
+        receiver: Option<(&Expr, Ty)>,
+        mut subst: HashMap<DefId, Ty>,
+        explicit: Option<&[TypeExpr]>,
+        args: &[(Option<Box<str>>, Expr)],
+    ) -> Ty {
+        let sig = self.signature(def);
+        let name = self.res.def(def).name.clone();
+        subst.extend(self.instantiate_with(&sig.generics, explicit, &name, expr.span));
+        let mut params = &sig.params[..];
+        if let Some((receiver, ty)) = receiver {
+            let expected = params[0].1.substitute(&subst);
+            self.receiver(receiver, &ty, &expected);
+            params = &params[1..];
+        }
+        let labels: Vec<_> = args.iter().map(|(label, _)| label.as_deref()).collect();
+        let declared = msg!(
+            "analysis-note-declared-as",
+            name = name,
+            signature = self.labelled_name(&name, &sig)
+        );
+        match sig.match_labels(sig.params.len() - params.len(), &labels) {
+            Err(LabelMismatch::Count { expected, .. }) => {
+                self.argument_count(expr.span, expected, args, Some(&declared));
+            }
+            result => {
+                if let Err(mismatch) = result {
+                    self.label_mismatch(&name, mismatch, expr.span, &declared);
+                }
+                for ((_, arg), (_, param)) in args.iter().zip(params) {
+                    self.check(arg, &param.substitute(&subst));
+                }
+            }
+        }
+        self.require_bounds(def, &subst, expr.span);
+        self.body().calls.insert(expr.id, (def, subst.clone()));
+        sig.returns.substitute(&subst)
+    }
+
+    /// Checks the receiver of a method, which is borrowed or dereferenced as needed.
+    fn receiver(&mut self, expr: &Expr, found: &Ty, expected: &Ty) {
+        if self.infer().probe(|infer| infer.unify(found, expected)) {
+            return;
+        }
+        if let Ty::Reference { ty, .. } = self.infer().shallow(expected) {
+            if self.infer().probe(|infer| infer.unify(found, &ty)) {
+                return;
+            }
+        }
+        if let Ty::Reference { ty, .. } = self.infer().shallow(found) {
+            if self.infer().probe(|infer| infer.unify(&ty, expected)) {
+                return;
+            }
+        }
+        self.mismatch(expr.span, expected, found);
+    }
+
+    /// Reports a call with the wrong number of arguments, noting how the function is
+    /// declared if it is named, and checks the arguments on their own.
+    fn argument_count(
+        &mut self,
+        span: Span,
+        expected: usize,
+        args: &[(Option<Box<str>>, Expr)],
+        declared: Option<&str>,
+    ) {
+        let message = msg!(
+            "analysis-argument-count",
+            expected = expected,
+            found = args.len()
+        );
+        let mut error = Error::at(WRONG_ARGUMENT_COUNT, &message, self.map, span);
+        if let Some(declared) = declared {
+            error = error.with_note(declared);
+        }
+        self.typing.errors.push(error);
+        for (_, arg) in args {
+            self.expr(arg, None);
+        }
+    }
+
+    /// Reports the labels of a call to `name` that don't fit its parameters.
+    fn label_mismatch(&mut self, name: &str, mismatch: LabelMismatch, span: Span, declared: &str) {
+        let (code, message) = match mismatch {
+            LabelMismatch::Count { .. } => unreachable!("reported as an argument count"),
+            LabelMismatch::Missing { expected, .. } => (
+                MISSING_LABEL,
+                msg!("analysis-missing-label", name = name, label = expected),
+            ),
+            LabelMismatch::Unexpected { index, found } => (
+                UNEXPECTED_LABEL,
+                msg!(
+                    "analysis-unexpected-label",
+                    name = name,
+                    position = index + 1,
+                    label = found
+                ),
+            ),
+            LabelMismatch::Misordered { found, .. } => (
+                MISORDERED_LABEL,
+                msg!("analysis-misordered-label", name = name, label = found),
+            ),
+            LabelMismatch::Wrong {
+                found, expected, ..
+            } => (
+                WRONG_LABEL,
+                msg!("analysis-wrong-label", expected = expected, label = found),
+            ),
+        };
+        let error = Error::at(code, &message, self.map, span).with_note(declared);
+        self.typing.errors.push(error);
+    }
+
+    /// Reports an operator applied to a type it doesn't work on, unless the type is
+    /// unknown, and gives the type of the result.
+    fn operand(&mut self, op: &str, ty: &Ty, span: Span, ok: impl Fn(&Ty) -> bool) -> Ty {
+        let ty = self.infer().shallow(ty);
+        match ty {
+            Ty::Error | Ty::Never => Ty::Error,
+            Ty::Var(_) => ty,
+            _ if ok(&ty) => ty,
+            _ => {
+                let message = msg!("analysis-invalid-operand", op = op, ty = self.display(&ty));
+                self.error(INVALID_OPERAND, &message, span);
+                Ty::Error
+            }
+        }
+    }
+
+    fn unary(&mut self, span: Span, op: U, operand: &Expr, expected: Option<&Ty>) -> Ty {
+        let symbol = op.as_str();
+        match op {
+            U::Not => {
+                let ty = self.expr(operand, expected);
+                self.operand(symbol, &ty, span, |ty| *ty == BOOL || ty.is_integer())
+            }
+            U::BitNot => {
+                let ty = self.expr(operand, expected);
+                self.operand(symbol, &ty, span, Ty::is_integer)
+            }
+            U::Negative | U::Positive => {
+                let suffix = match &operand.kind {
+                    ExprKind::Literal(literal, _) => literal.suffix(),
+                    _ => None,
+                };
+                let literal_ty = match (suffix, expected) {
+                    (Some(suffix), _) => Primitive::from_suffix(suffix),
+                    (None, Some(Ty::Primitive(primitive))) => Some(*primitive),
+                    _ => None,
+                };
+                let ty = match (&operand.kind, op) {
+                    (ExprKind::Literal(literal @ Literal::Integer { .. }, text), U::Negative)
+                        if literal_ty.is_some_and(Primitive::is_signed) =>
+                    {
+                        let ty = self.literal(operand.span, literal, text, expected, true);
+                        self.body().exprs.insert(operand.id, ty.clone());
+                        ty
+                    }
+                    _ => self.expr(operand, expected),
+                };
+                let signed = |ty: &Ty| match ty {
+                    Ty::Primitive(primitive) => primitive.is_signed() || ty.is_float(),
+                    ty => ty.is_numeric(),
+                };
+                match op {
+                    U::Negative => self.operand(symbol, &ty, span, signed),
+                    _ => self.operand(symbol, &ty, span, Ty::is_numeric),
+                }
+            }
+            U::Reference | U::MutReference => {
+                let inner = match expected {
+                    Some(Ty::Reference { ty, .. }) => Some(&**ty),
+                    _ => None,
+                };
+                Ty::Reference {
+                    mutable: op == U::MutReference,
+                    ty: Box::new(self.expr(operand, inner)),
+                }
+            }
+            U::DoubleReference => Ty::Reference {
+                mutable: false,
+                ty: Box::new(Ty::Reference {
+                    mutable: false,
+                    ty: Box::new(self.expr(operand, None)),
+                }),
+            },
+            U::Dereference => {
+                let ty = self.expr(operand, None);
+                match self.infer().shallow(&ty) {
+                    Ty::Reference { ty, .. } | Ty::Pointer { ty, .. } => *ty,
+                    Ty::Error | Ty::Var(_) => Ty::Error,
+                    ty => {
+                        let message = msg!("analysis-not-dereferenceable", ty = self.display(&ty));
+                        self.error(INVALID_OPERAND, &message, span);
+                        Ty::Error
+                    }
+                }
+            }
+            U::Try => {
+                let ty = self.expr(operand, None);
+                let returns = self.body().returns.last().cloned();
+                let returns = returns.map(|returns| self.infer().shallow(&returns));
+                if !matches!(
+                    returns,
+                    Some(Ty::Optional(_) | Ty::Result { .. } | Ty::Error | Ty::Var(_))
+                ) {
+                    self.error(INVALID_OPERAND, &msg!("analysis-try-return"), span);
+                }
+                match self.operand(symbol, &ty, span, |ty| {
+                    matches!(ty, Ty::Optional(_) | Ty::Result { .. })
+                }) {
+                    Ty::Optional(ty) | Ty::Result { ok: ty, .. } => *ty,
+                    _ => Ty::Error,
+                }
+            }
+        }
+    }
+
+    fn binary(&mut self, op: B, left: &Expr, right: &Expr, expected: Option<&Ty>) -> Ty {
+        let symbol = op.as_str();
+        let span = left.span.to(right.span);
+        let integer = |ty: &Ty| ty.is_integer();
+        let logical = |ty: &Ty| *ty == BOOL || ty.is_integer();
+        match op {
+            B::Dot => unreachable!("paths and fields are checked by `dot`"),
+            B::Comma => {
+                self.expr(left, None);
+                self.expr(right, expected)
+            }
+            B::Add | B::Sub | B::Mul | B::Div | B::Mod => {
+                let ty = self.expr(left, expected);
+                self.same_operands(symbol, &ty, right, span, Ty::is_numeric)
+            }
+            B::BitAnd | B::BitOr | B::BitXor => {
+                let ty = self.expr(left, expected);
+                self.same_operands(symbol, &ty, right, span, logical)
+            }
+            B::BitShiftLeft | B::BitShiftRight => {
+                let ty = self.expr(left, expected);
+                let ty = self.operand(symbol, &ty, span, integer);
+                let amount = self.expr(right, None);
+                self.operand(symbol, &amount, right.span, integer);
+                ty
+            }
+            B::Eq | B::NotEq => {
+                let ty = self.expr(left, None);
+                self.check(right, &ty);
+                BOOL
+            }
+            B::Greater | B::GreaterEq | B::Lesser | B::LesserEq => {
+                let ty = self.expr(left, None);
+                self.same_operands(symbol, &ty, right, span, |ty| {
+                    ty.is_numeric() || matches!(ty, Ty::Primitive(Primitive::Char | Primitive::Str))
+                });
+                BOOL
+            }
+            B::And | B::Or => {
+                self.check(left, &BOOL);
+                self.check(right, &BOOL);
+                BOOL
+            }
+            B::Assign => {
+                let ty = self.expr(left, None);
+                self.check(right, &ty);
+                Ty::unit()
+            }
+            B::AddAssign | B::SubAssign | B::MulAssign | B::DivAssign | B::ModAssign => {
+                let ty = self.expr(left, None);
+                self.same_operands(symbol, &ty, right, span, Ty::is_numeric);
+                Ty::unit()
+            }
+            B::BitAndAssign | B::BitOrAssign | B::BitXorAssign => {
+                let ty = self.expr(left, None);
+                self.same_operands(symbol, &ty, right, span, logical);
+                Ty::unit()
+            }
+            B::BitShiftLeftAssign | B::BitShiftRightAssign => {
+                let ty = self.expr(left, None);
+                self.operand(symbol, &ty, span, integer);
+                let amount = self.expr(right, None);
+                self.operand(symbol, &amount, right.span, integer);
+                Ty::unit()
+            }
+            B::AndAssign | B::OrAssign => {
+                self.check(left, &BOOL);
+                self.check(right, &BOOL);
+                Ty::unit()
+            }
+            B::Range => {
+                let element = match expected {
+                    Some(Ty::Range(ty)) => Some(&**ty),
+                    _ => None,
+                };
+                let ty = self.expr(left, element);
+                let ty = self.same_operands(symbol, &ty, right, span, |ty| {
+                    ty.is_integer() || *ty == Ty::Primitive(Primitive::Char)
+                });
+                Ty::Range(Box::new(ty))
+            }
+        }
+    }
+
+    /// Checks an operator whose operands must be of the same type `ty`, which `ok`
+    /// accepts, and gives that type.
+    fn same_operands(
+        &mut self,
+        op: &str,
+        ty: &Ty,
+        right: &Expr,
+        span: Span,
+        ok: impl Fn(&Ty) -> bool,
+    ) -> Ty {
+        let ty = self.operand(op, ty, span, ok);
+        match ty {
+            Ty::Error => {
+                self.expr(right, None);
+                Ty::Error
+            }
+            ty => self.check(right, &ty),
+        }
+    }
+
+    fn index(&mut self, base: &Expr, index: &Expr) -> Ty {
+        let base_ty = self.expr(base, None);
+        let index_ty = self.expr(index, None);
+        let index_ty = self.infer().shallow(&index_ty);
+        let range = matches!(index_ty, Ty::Range(_));
+        let integer = |ty: &Ty| ty.is_integer();
+        match &index_ty {
+            Ty::Range(ty) => self.operand("[]", ty, index.span, integer),
+            ty => self.operand("[]", ty, index.span, integer),
+        };
+        let element = match self.autoderef(&base_ty) {
+            Ty::Array { ty, .. } | Ty::Slice(ty) => *ty,
+            Ty::Error | Ty::Var(_) => return Ty::Error,
+            ty => {
+                let message = msg!("analysis-not-indexable", ty = self.display(&ty));
+                self.error(INVALID_OPERAND, &message, base.span);
+                return Ty::Error;
+            }
+        };
+        match range {
+            true => Ty::Slice(Box::new(element)),
+            false => element,
+        }
+    }
+
+    /// The type of the elements of a value of type `ty` in a `for` loop.
+    fn element(&mut self, span: Span, ty: &Ty) -> Ty {
+        match self.infer().shallow(ty) {
+            Ty::Range(ty) | Ty::Array { ty, .. } | Ty::Slice(ty) => *ty,
+            Ty::Reference { mutable, ty } => match self.infer().shallow(&ty) {
+                Ty::Array { ty, .. } | Ty::Slice(ty) => Ty::Reference { mutable, ty },
+                _ => self.not_iterable(span, &Ty::Reference { mutable, ty }),
+            },
+            Ty::Error | Ty::Var(_) => Ty::Error,
+            ty => self.not_iterable(span, &ty),
+        }
+    }
+
+    fn not_iterable(&mut self, span: Span, ty: &Ty) -> Ty {
+        let message = msg!("analysis-not-iterable", ty = self.display(ty));
+        self.error(INVALID_OPERAND, &message, span);
+        Ty::Error
+    }
+
+    fn struct_literal(
+        &mut self,
+        path: &Expr,
+        fields: &[(Box<str>, Expr)],
+        expected: Option<&Ty>,
+    ) -> Ty {
+        let Some((ty, variant)) = self.struct_path(path) else {
+            for (_, value) in fields {
+                self.expr(value, None);
+            }
+            return Ty::Error;
+        };
+        if let Some(expected) = expected {
+            self.infer().probe(|infer| infer.unify(&ty, expected));
+        }
+        self.require_adt_bounds(&ty, path.span);
+        for (name, value) in fields {
+            match variant.fields.iter().find(|(field, _)| field == name) {
+                Some((_, field)) => {
+                    self.check(value, field);
+                }
+                None => {
+                    self.unknown_field(&ty, &variant.fields, name, value.span);
+                    self.expr(value, None);
+                }
+            }
+        }
+        let union = matches!(&ty, Ty::Adt { def, .. } if self.adt(*def).kind == AdtKind::Union);
+        if !union {
+            let named: Vec<_> = fields.iter().map(|(name, _)| &**name).collect();
+            self.missing_fields(&ty, &variant.fields, &named, path.span);
+        }
+        ty
+    }
+
+    fn match_(&mut self, scrutinee: &Expr, arms: &[MatchArm], expected: Option<&Ty>) -> Ty {
+        let scrutinee = self.expr(scrutinee, None);
+        let mut result = expected.cloned();
+        for arm in arms {
+            self.pattern(&arm.pattern, &scrutinee);
+            if let Some(guard) = &arm.guard {
+                self.check(guard, &BOOL);
+            }
+            match &result {
+                Some(ty) => {
+                    let ty = ty.clone();
+                    self.check_or_drop(&arm.body, &ty);
+                }
+                None => {
+                    let ty = self.expr(&arm.body, None);
+                    if self.infer().shallow(&ty) != Ty::Never {
+                        result = Some(ty);
+                    }
+                }
+            }
+        }
+        result.unwrap_or(Ty::Never)
+    }
+
+    fn closure(
+        &mut self,
+        expr: &Expr,
+        params: &[ClosureParam],
+        returns: Option<&TypeExpr>,
+        body: &Expr,
+        expected: Option<&Ty>,
+    ) -> Ty {
+        let (expected_params, expected_returns) = match expected {
+            Some(Ty::Fn {
+                params: types,
+                returns,
+            }) if types.len() == params.len() => (Some(types), Some(&**returns)),
+            _ => (None, None),
+        };
+        let defs = self.res.params.get(&expr.id).cloned().unwrap_or_default();
+        let mut types = vec![];
+        for (i, param) in params.iter().enumerate() {
+            let ty = match (&param.ty, expected_params) {
+                (Some(ty), _) => self.lower(ty),
+                (None, Some(types)) => types[i].clone(),
+                (None, None) => self.infer().var(),
+            };
+            if let Some(&def) = defs.get(i) {
+                self.body().bindings.insert(def, ty.clone());
+            }
+            types.push(ty);
+        }
+        let returns = match (returns, expected_returns) {
+            (Some(ty), _) => self.lower(ty),
+            (None, Some(ty)) => ty.clone(),
+            (None, None) => self.infer().var(),
+        };
+        self.body().returns.push(returns.clone());
+        self.check_or_drop(body, &returns);
+        self.body().returns.pop();
+        Ty::Fn {
+            params: types,
+            returns: Box::new(returns),
+        }
+    }
+
+    pub(super) fn unknown_field(
+        &mut self,
+        ty: &Ty,
+        fields: &[(Box<str>, Ty)],
+        name: &str,
+        span: Span,
+    ) {
+        let message = msg!("analysis-unknown-field", name = name, ty = self.display(ty));
+        let error = Error::at(UNKNOWN_FIELD, &message, self.map, span);
+        let names: Vec<_> = fields.iter().map(|(name, _)| name.clone()).collect();
+        self.typing.errors.push(suggest(error, name, &names));
+    }
+}
+
+/// Where the value of `expr` comes from: the last statement of a block.
+fn tail_span(expr: &Expr) -> Span {
+    match &expr.kind {
+        ExprKind::Block(statements) => statements.last().map_or(expr.span, tail_span),
+        _ => expr.span,
+    }
+}
@@ -0,0 +1,603 @@
+use std::collections::{HashMap, HashSet};
+
+use coil_error::{msg, Error, SourceMap};
+use coil_parser::{
+    walk, BinaryOperator as B, Expr, ExprKind, NodeId, Statement, UnaryOperator as U, Visitor,
+};
+
+use super::{Checker, Typing};
+use crate::{
+    DefId, DefKind, Resolution, Ty, IMMUTABLE_BINDING, MUTATION_THROUGH_REFERENCE,
+    UNINITIALISED_USE,
+};
+
+impl Checker<'_> {
+    /// Checks every body for assignments to bindings that aren't `mut`, mutation through
+    /// `&` references, and uses of variables that might not have a value yet.
+    pub(super) fn flow(&mut self) {
+        let mut items: Vec<_> = self.items.values().copied().collect();
+        items.sort_by_key(|item| item.id);
+        let mut errors = vec![];
+        for item in items {
+            let body = match &item.kind {
+                ExprKind::Statement(Statement::Fn {
+                    body: Some(body), ..
+                }) => body,
+                ExprKind::Statement(Statement::Static {
+                    value: Some(value), ..
+                }) => value,
+                _ => continue,
+            };
+            let mut flow = Flow {
+                res: self.res,
+                map: self.map,
+                typing: &self.typing,
+                items: &self.items,
+                state: Some(State::default()),
+                unassigned: HashSet::new(),
+                loop_bindings: HashSet::new(),
+                loops: vec![],
+                fallthrough: None,
+                quiet: 0,
+                errors: vec![],
+            };
+            flow.visit_expr(body);
+            errors.extend(flow.errors);
+        }
+        self.typing.errors.extend(errors);
+    }
+}
+
+/// The variables declared without a value that have been assigned one at some point of a
+/// body.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct State {
+    /// Those assigned on every path to the point.
+    assigned: HashSet<DefId>,
+    /// Those assigned on some path to the point.
+    maybe: HashSet<DefId>,
+}
+
+/// Where the paths from two points meet, either of which may be unreachable.
+fn join(a: Option<State>, b: Option<State>) -> Option<State> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(State {
+            assigned: a.assigned.intersection(&b.assigned).copied().collect(),
+            maybe: a.maybe.union(&b.maybe).copied().collect(),
+        }),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+/// The states a loop is left and restarted with by `break` and `continue`.
+struct Loop<'e> {
+    label: Option<&'e str>,
+    breaks: Option<State>,
+    continues: Option<State>,
+}
+
+/// Whether a place can be changed.
+enum Place<'e> {
+    Mutable,
+    /// A variable or static not declared `mut`.
+    Immutable(DefId),
+    /// A place behind the `&` reference `base`.
+    Shared(&'e Expr),
+}
+
+/// How a place is changed.
+#[derive(Clone, Copy)]
+enum Change {
+    Assign,
+    Borrow,
+}
+
+struct Flow<'a> {
+    res: &'a Resolution,
+    map: &'a SourceMap,
+    typing: &'a Typing,
+    items: &'a HashMap<NodeId, &'a Expr>,
+    /// What is assigned where the walk is, or `None` where it can't be reached.
+    state: Option<State>,
+    /// The variables declared without a value.
+    unassigned: HashSet<DefId>,
+    /// The variables of `for` loops.
+    loop_bindings: HashSet<DefId>,
+    loops: Vec<Loop<'a>>,
+    /// The state a `fallthrough` carries into the next arm of a `match`.
+    fallthrough: Option<State>,
+    /// Above zero while a loop is walked to find the state it starts with, when errors
+    /// aren't reported yet.
+    quiet: usize,
+    errors: Vec<Error>,
+}
+
+impl<'a> Flow<'a> {
+    fn report(&mut self, error: Error) {
+        if self.quiet == 0 {
+            self.errors.push(error);
+        }
+    }
+
+    fn statement(&mut self, expr: &'a Expr, statement: &'a Statement) {
+        match statement {
+            Statement::Let { value, .. } => {
+                let Some(&def) = self.res.declarations.get(&expr.id) else {
+                    return;
+                };
+                match value {
+                    Some(value) => self.visit_expr(value),
+                    None => {
+                        self.unassigned.insert(def);
+                        if let Some(state) = &mut self.state {
+                            state.assigned.remove(&def);
+                            state.maybe.remove(&def);
+                        }
+                    }
+                }
+            }
+            Statement::Return(value) => {
+                if let Some(value) = value {
+                    self.visit_expr(value);
+                }
+                self.state = None;
+            }
+            Statement::Break { label, value } => {
+                if let Some(value) = value {
+                    self.visit_expr(value);
+                }
+                let state = self.state.take();
+                if let Some(target) = self.target(label.as_deref()) {
+                    target.breaks = join(target.breaks.take(), state);
+                }
+            }
+            Statement::Continue { label } => {
+                let state = self.state.take();
+                if let Some(target) = self.target(label.as_deref()) {
+                    target.continues = join(target.continues.take(), state);
+                }
+            }
+            Statement::Fallthrough => {
+                self.fallthrough = join(self.fallthrough.take(), self.state.take());
+            }
+            // Items in a body are walked on their own.
+            _ => {}
+        }
+    }
+
+    /// The loop `break` or `continue` with `label` goes to.
+    fn target(&mut self, label: Option<&str>) -> Option<&mut Loop<'a>> {
+        match label {
+            Some(label) => self
+                .loops
+                .iter_mut()
+                .rev()
+                .find(|target| target.label == Some(label)),
+            None => self.loops.last_mut(),
+        }
+    }
+
+    /// Walks a loop, one run of which `iteration` walks, giving the state at which the
+    /// loop can end other than by `break`. The state a run starts with is found first,
+    /// from the state before the loop and at the end of every run, without reporting
+    /// anything.
+    fn looping(
+        &mut self,
+        label: Option<&'a str>,
+        iteration: &mut dyn FnMut(&mut Self) -> Option<State>,
+    ) {
+        let before = self.state.clone();
+        let mut start = before.clone();
+        self.quiet += 1;
+        loop {
+            self.state = start.clone();
+            self.run(label, iteration);
+            let next = join(before.clone(), self.state.take());
+            if next == start {
+                break;
+            }
+            start = next;
+        }
+        self.quiet -= 1;
+        self.state = start;
+        let (exit, breaks) = self.run(label, iteration);
+        self.state = join(exit, breaks);
+    }
+
+    /// Walks one run of a loop, leaving the state it restarts with. Gives the state at
+    /// which the loop can end and the states of its `break`s.
+    fn run(
+        &mut self,
+        label: Option<&'a str>,
+        iteration: &mut dyn FnMut(&mut Self) -> Option<State>,
+    ) -> (Option<State>, Option<State>) {
+        self.loops.push(Loop {
+            label,
+            breaks: None,
+            continues: None,
+        });
+        let exit = iteration(self);
+        let target = self.loops.pop().unwrap();
+        self.state = join(self.state.take(), target.continues);
+        (exit, target.breaks)
+    }
+
+    /// The variable `expr` names, if it is one declared without a value.
+    fn unassigned(&self, expr: &Expr) -> Option<DefId> {
+        let &def = self.res.resolved(expr.id).first()?;
+        self.unassigned.contains(&def).then_some(def)
+    }
+
+    fn use_variable(&mut self, expr: &Expr) {
+        let (Some(def), Some(state)) = (self.unassigned(expr), &self.state) else {
+            return;
+        };
+        if state.assigned.contains(&def) {
+            return;
+        }
+        let name = &self.res.def(def).name;
+        let message = match state.maybe.contains(&def) {
+            true => msg!("analysis-possibly-uninitialised", name = name),
+            false => msg!("analysis-uninitialised", name = name),
+        };
+        self.report(Error::at(UNINITIALISED_USE, &message, self.map, expr.span));
+    }
+
+    /// `target = value`, where `target` names a variable.
+    fn assign(&mut self, target: &'a Expr) {
+        let Some(def) = self.unassigned(target) else {
+            self.change(target, Change::Assign);
+            return;
+        };
+        let Some(state) = &mut self.state else {
+            return;
+        };
+        let first = !state.maybe.contains(&def);
+        state.assigned.insert(def);
+        state.maybe.insert(def);
+        // A variable that isn't `mut` can still be given its first value.
+        if !first && self.res.def(def).kind == (DefKind::Local { mutable: false }) {
+            let name = &self.res.def(def).name;
+            let message = msg!("analysis-assign-twice", name = name);
+            let error = Error::at(IMMUTABLE_BINDING, &message, self.map, target.span);
+            let error = self.suggest_mut(error, def);
+            self.report(error);
+        }
+    }
+
+    /// Checks that the place `expr` can be changed.
+    fn change(&mut self, expr: &'a Expr, change: Change) {
+        if self.state.is_none() {
+            return;
+        }
+        match self.place(expr) {
+            Place::Mutable => {}
+            Place::Immutable(def) => {
+                let name = &self.res.def(def).name;
+                let message = match change {
+                    Change::Assign => msg!("analysis-assign-immutable", name = name),
+                    Change::Borrow => msg!("analysis-borrow-immutable", name = name),
+                };
+                let error = Error::at(IMMUTABLE_BINDING, &message, self.map, expr.span);
+                let error = self.suggest_mut(error, def);
+                self.report(error);
+            }
+            Place::Shared(base) => {
+                let place = self.map.text(expr.span);
+                let message = match change {
+                    Change::Assign => msg!("analysis-assign-through-reference", place = place),
+                    Change::Borrow => msg!("analysis-borrow-through-reference", place = place),
+                };
+                let mut error =
+                    Error::at(MUTATION_THROUGH_REFERENCE, &message, self.map, expr.span);
+                if let ExprKind::Identifier(name) = &base.kind {
+                    // A reference made in the `let` of the variable is best fixed there.
+                    if let Some(place) = self.borrowed(base) {
+                        let borrow = format!("&mut {}", self.map.text(place.span));
+                        let note =
+                            msg!("analysis-note-borrow-mutable", name = name, borrow = borrow);
+                        error = error.with_note(&note);
+                    } else if let Some(ty) = self.typing.exprs.get(&base.id) {
+                        let ty = mutable_reference(ty).display(self.res).to_string();
+                        let note = msg!("analysis-note-mutable-reference", name = name, ty = ty);
+                        error = error.with_note(&note);
+                    }
+                }
+                self.report(error);
+            }
+        }
+    }
+
+    /// The place borrowed with `&` in the `let` that declares the variable `expr` names.
+    fn borrowed(&self, expr: &Expr) -> Option<&'a Expr> {
+        let &def = self.res.resolved(expr.id).first()?;
+        let item = self.items.get(&self.res.def(def).node?)?;
+        let ExprKind::Statement(Statement::Let {
+            value: Some(value), ..
+        }) = &item.kind
+        else {
+            return None;
+        };
+        match &value.kind {
+            ExprKind::Unary {
+                op: U::Reference,
+                expr,
+            } => Some(expr),
+            _ => None,
+        }
+    }
+
+    /// Whether the place `expr` can be changed. Values that aren't places, like the
+    /// result of a call, can be.
+    fn place(&self, expr: &'a Expr) -> Place<'a> {
+        match &expr.kind {
+            ExprKind::Identifier(_) => self.variable(expr.id),
+            ExprKind::Binary {
+                op: B::Dot,
+                left,
+                right,
+            } => match self.res.resolved(right.id) {
+                [] => self.inside(left),
+                _ => self.variable(right.id),
+            },
+            ExprKind::Index { expr, .. } => self.inside(expr),
+            ExprKind::Unary {
+                op: U::Dereference,
+                expr,
+            } => match self.typing.exprs.get(&expr.id) {
+                Some(Ty::Reference { mutable: false, .. }) => Place::Shared(expr),
+                _ => Place::Mutable,
+            },
+            _ => Place::Mutable,
+        }
+    }
+
+    /// Whether a field or element of `base`, which references are taken off, can be
+    /// changed.
+    fn inside(&self, base: &'a Expr) -> Place<'a> {
+        match self.typing.exprs.get(&base.id) {
+            Some(ty @ Ty::Reference { .. }) => match all_mutable(ty) {
+                true => Place::Mutable,
+                false => Place::Shared(base),
+            },
+            _ => self.place(base),
+        }
+    }
+
+    /// Whether the variable or static named at `node` can be changed.
+    fn variable(&self, node: NodeId) -> Place<'a> {
+        let Some(&def) = self.res.resolved(node).first() else {
+            return Place::Mutable;
+        };
+        let mutable = match self.res.def(def).kind {
+            DefKind::Local { mutable } => mutable,
+            DefKind::Param => false,
+            DefKind::Static => {
+                let item = self
+                    .res
+                    .def(def)
+                    .node
+                    .and_then(|node| self.items.get(&node));
+                !matches!(
+                    item.map(|item| &item.kind),
+                    Some(ExprKind::Statement(Statement::Static {
+                        mutable: false,
+                        ..
+                    }))
+                )
+            }
+            _ => true,
+        };
+        match mutable {
+            true => Place::Mutable,
+            false => Place::Immutable(def),
+        }
+    }
+
+    /// The receiver of the method call `call`, which is borrowed mutably when the method
+    /// takes `&mut Self` and the receiver isn't a reference already.
+    fn receiver(&mut self, call: &Expr, callee: &'a Expr) {
+        let callee = match &callee.kind {
+            ExprKind::Generic { path, .. } => path,
+            _ => callee,
+        };
+        let ExprKind::Binary {
+            op: B::Dot, left, ..
+        } = &callee.kind
+        else {
+            return;
+        };
+        let (Some((def, _)), Some(receiver)) = (
+            self.typing.calls.get(&call.id),
+            self.typing.exprs.get(&left.id),
+        ) else {
+            return;
+        };
+        let Some(sig) = self.typing.signatures.get(def) else {
+            return;
+        };
+        if sig.has_self()
+            && matches!(sig.params[0].1, Ty::Reference { mutable: true, .. })
+            && !matches!(receiver, Ty::Reference { .. })
+        {
+            self.change(left, Change::Borrow);
+        }
+    }
+
+    /// Adds a note on how to make `def` mutable.
+    fn suggest_mut(&self, error: Error, def: DefId) -> Error {
+        let definition = self.res.def(def);
+        let name = &definition.name;
+        let item = definition.node.and_then(|node| self.items.get(&node));
+        let declaration = match (definition.kind, item.map(|item| &item.kind)) {
+            (DefKind::Static, _) => format!("static mut {name}"),
+            (DefKind::Local { .. }, Some(ExprKind::Statement(Statement::Let { .. }))) => {
+                format!("let mut {name}")
+            }
+            (DefKind::Local { .. }, _) if !self.loop_bindings.contains(&def) => {
+                format!("mut {name}")
+            }
+            _ => return error.with_note(&msg!("analysis-note-mutable-copy", name = name)),
+        };
+        error.with_note(&msg!("analysis-note-add-mut", declaration = declaration))
+    }
+}
+
+impl<'a> Visitor<'a> for Flow<'a> {
+    fn visit_expr(&mut self, expr: &'a Expr) {
+        match &expr.kind {
+            ExprKind::Statement(statement) => self.statement(expr, statement),
+            ExprKind::Binary { op, left, right } => match op {
+                B::Dot => self.visit_expr(left),
+                B::Assign => {
+                    self.visit_expr(right);
+                    match &left.kind {
+                        ExprKind::Identifier(_) => self.assign(left),
+                        _ => {
+                            self.visit_expr(left);
+                            self.change(left, Change::Assign);
+                        }
+                    }
+                }
+                B::AddAssign
+                | B::SubAssign
+                | B::MulAssign
+                | B::DivAssign
+                | B::ModAssign
+                | B::AndAssign
+                | B::OrAssign
+                | B::BitAndAssign
+                | B::BitOrAssign
+                | B::BitXorAssign
+                | B::BitShiftLeftAssign
+                | B::BitShiftRightAssign => {
+                    self.visit_expr(left);
+                    self.visit_expr(right);
+                    self.change(left, Change::Assign);
+                }
+                B::And | B::Or => {
+                    self.visit_expr(left);
+                    let skipped = self.state.clone();
+                    self.visit_expr(right);
+                    self.state = join(skipped, self.state.take());
+                }
+                _ => walk::expr(self, expr),
+            },
+            ExprKind::Unary { op, expr: operand } => {
+                self.visit_expr(operand);
+                if *op == U::MutReference {
+                    self.change(operand, Change::Borrow);
+                }
+            }
+            ExprKind::Call { callee, args } => {
+                self.visit_expr(callee);
+                self.receiver(expr, callee);
+                for (_, arg) in args {
+                    self.visit_expr(arg);
+                }
+            }
+            ExprKind::If {
+                condition,
+                then,
+                otherwise,
+                ..
+            } => {
+                self.visit_expr(condition);
+                let skipped = self.state.clone();
+                self.visit_expr(then);
+                let then = std::mem::replace(&mut self.state, skipped);
+                if let Some(otherwise) = otherwise {
+                    self.visit_expr(otherwise);
+                }
+                self.state = join(then, self.state.take());
+            }
+            ExprKind::While {
+                label,
+                condition,
+                body,
+            } => self.looping(label.as_deref(), &mut |this| {
+                this.visit_expr(condition);
+                let exit = this.state.clone();
+                this.visit_expr(body);
+                exit
+            }),
+            ExprKind::DoWhile {
+                label,
+                body,
+                condition,
+            } => self.looping(label.as_deref(), &mut |this| {
+                this.visit_expr(body);
+                let continues = this.loops.last_mut().unwrap().continues.take();
+                this.state = join(this.state.take(), continues);
+                this.visit_expr(condition);
+                this.state.clone()
+            }),
+            ExprKind::For {
+                label,
+                iterable,
+                body,
+                ..
+            } => {
+                self.visit_expr(iterable);
+                if let Some(&def) = self.res.declarations.get(&expr.id) {
+                    self.loop_bindings.insert(def);
+                }
+                self.looping(label.as_deref(), &mut |this| {
+                    let exit = this.state.clone();
+                    this.visit_expr(body);
+                    exit
+                });
+            }
+            ExprKind::Match { scrutinee, arms } => {
+                self.visit_expr(scrutinee);
+                let start = self.state.clone();
+                let outer = self.fallthrough.take();
+                let mut exit = None;
+                for arm in arms {
+                    self.state = start.clone();
+                    if let Some(guard) = &arm.guard {
+                        self.visit_expr(guard);
+                    }
+                    self.state = join(self.state.take(), self.fallthrough.take());
+                    self.visit_expr(&arm.body);
+                    exit = join(exit, self.state.take());
+                }
+                self.fallthrough = outer;
+                self.state = if arms.is_empty() { start } else { exit };
+            }
+            ExprKind::Closure { body, .. } => {
+                // A closure may run any number of times, or never, so what it assigns
+                // doesn't count outside it.
+                let state = self.state.clone();
+                let loops = std::mem::take(&mut self.loops);
+                let fallthrough = self.fallthrough.take();
+                self.visit_expr(body);
+                self.state = state;
+                self.loops = loops;
+                self.fallthrough = fallthrough;
+            }
+            ExprKind::Identifier(_) => self.use_variable(expr),
+            _ => walk::expr(self, expr),
+        }
+    }
+}
+
+/// Whether every reference of `ty` is mutable, down to what they point to.
+fn all_mutable(ty: &Ty) -> bool {
+    match ty {
+        Ty::Reference { mutable, ty } => *mutable && all_mutable(ty),
+        _ => true,
+    }
+}
+
+/// `ty` with every reference around it made mutable.
+fn mutable_reference(ty: &Ty) -> Ty {
+    match ty {
+        Ty::Reference { ty, .. } => Ty::Reference {
+            mutable: true,
+            ty: Box::new(mutable_reference(ty)),
+        },
+        ty => ty.clone(),
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{Primitive, Ty};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VarKind {
+    Any,
+    Integer,
+    Float,
+}
+
+/// The type variables of a body and what they were unified with.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Infer {
+    vars: Vec<(VarKind, Option<Ty>)>,
+}
+
+impl Infer {
+    fn new_var(&mut self, kind: VarKind) -> u32 {
+        self.vars.push((kind, None));
+        self.vars.len() as u32 - 1
+    }
+
+    pub(super) fn var(&mut self) -> Ty {
+        Ty::Var(self.new_var(VarKind::Any))
+    }
+
+    pub(super) fn int_var(&mut self) -> Ty {
+        Ty::IntVar(self.new_var(VarKind::Integer))
+    }
+
+    pub(super) fn float_var(&mut self) -> Ty {
+        Ty::FloatVar(self.new_var(VarKind::Float))
+    }
+
+    /// `ty`, or what it was unified with if it is a variable.
+    pub(super) fn shallow(&self, ty: &Ty) -> Ty {
+        match ty {
+            Ty::Var(var) | Ty::IntVar(var) | Ty::FloatVar(var) => {
+                match &self.vars[*var as usize].1 {
+                    Some(ty) => self.shallow(ty),
+                    None => ty.clone(),
+                }
+            }
+            ty => ty.clone(),
+        }
+    }
+
+    /// `ty` with every variable replaced by what it was unified with.
+    pub(super) fn resolve(&self, ty: &Ty) -> Ty {
+        ty.map(&mut |ty| match &ty {
+            Ty::Var(_) | Ty::IntVar(_) | Ty::FloatVar(_) => {
+                let shallow = self.shallow(&ty);
+                if shallow == ty {
+                    ty
+                } else {
+                    self.resolve(&shallow)
+                }
+            }
+            _ => ty,
+        })
+    }
+
+    /// Makes the integer and float variables that nothing decided `Int` and `Float`.
+    pub(super) fn default_literals(&mut self) {
+        for i in 0..self.vars.len() {
+            let default = match self.vars[i] {
+                (VarKind::Integer, None) => Primitive::I64,
+                (VarKind::Float, None) => Primitive::F64,
+                _ => continue,
+            };
+            self.vars[i].1 = Some(Ty::Primitive(default));
+        }
+    }
+
+    /// Runs `f`, undoing what it unified if it returns `false`.
+    pub(super) fn probe(&mut self, f: impl FnOnce(&mut Self) -> bool) -> bool {
+        let saved = self.vars.clone();
+        let ok = f(self);
+        if !ok {
+            self.vars = saved;
+        }
+        ok
+    }
+
+    /// Makes `a` and `b` the same type if they can be, binding variables as needed.
+    pub(super) fn unify(&mut self, a: &Ty, b: &Ty) -> bool {
+        let (a, b) = (self.shallow(a), self.shallow(b));
+        match (&a, &b) {
+            (Ty::Error, _) | (_, Ty::Error) | (Ty::Never, _) | (_, Ty::Never) => true,
+            _ if a == b => true,
+            (Ty::Var(var), ty) | (ty, Ty::Var(var)) => self.bind(*var, ty),
+            (Ty::IntVar(var), ty) | (ty, Ty::IntVar(var)) if ty.is_integer() => self.bind(*var, ty),
+            (Ty::FloatVar(var), ty) | (ty, Ty::FloatVar(var)) if ty.is_float() => {
+                self.bind(*var, ty)
+            }
+            (Ty::Adt { def: a, args: x }, Ty::Adt { def: b, args: y }) => {
+                a == b && self.unify_all(x, y)
+            }
+            (Ty::Reference { mutable: m, ty: x }, Ty::Reference { mutable: n, ty: y })
+            | (Ty::Pointer { mutable: m, ty: x }, Ty::Pointer { mutable: n, ty: y }) => {
+                m == n && self.unify(x, y)
+            }
+            (Ty::Array { ty: x, len: m }, Ty::Array { ty: y, len: n }) => {
+                (m.is_none() || n.is_none() || m == n) && self.unify(x, y)
+            }
+            (Ty::Slice(x), Ty::Slice(y))
+            | (Ty::Optional(x), Ty::Optional(y))
+            | (Ty::Range(x), Ty::Range(y)) => self.unify(x, y),
+            (Ty::Tuple(x), Ty::Tuple(y)) => self.unify_all(x, y),
+            (
+                Ty::Fn {
+                    params: x,
+                    returns: r,
+                },
+                Ty::Fn {
+                    params: y,
+                    returns: s,
+                },
+            ) => self.unify_all(x, y) && self.unify(r, s),
+            (Ty::Result { ok: x, error: e }, Ty::Result { ok: y, error: f }) => {
+                self.unify(x, y) && self.unify(e, f)
+            }
+            _ => false,
+        }
+    }
+
+    pub(super) fn unify_all(&mut self, a: &[Ty], b: &[Ty]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| self.unify(a, b))
+    }
+
+    fn bind(&mut self, var: u32, ty: &Ty) -> bool {
+        let mut occurs = false;
+        self.resolve(ty).walk(&mut |ty| {
+            occurs |= matches!(ty, Ty::Var(v) | Ty::IntVar(v) | Ty::FloatVar(v) if *v == var)
+        });
+        if occurs {
+            return false;
+        }
+        self.vars[var as usize].1 = Some(ty.clone());
+        true
+    }
+}
@@ -0,0 +1,723 @@
+//! Type checking of a resolved [`Program`].
+//!
+//! Every function body is checked on its own, with local type inference: each expression
+//! is checked against the type its context expects when there is one, so that `let x: U8
+//! = 5` makes `5` a `U8`, and its type is inferred otherwise, with variables for what is
+//! not known yet. Integer and float literals that nothing decides the type of are `Int`
+//! and `Float`. Signatures, fields and statics have to be written out in full.
+//!
+//! Generic items are checked once, against the bounds on their generics, and every use
+//! of one must satisfy those bounds. Once the whole program checks, the generic functions
+//! are instantiated for the types each use gives them, for codegen.
+//!
+//! No two impls of a trait may apply to the same type, and an impl must be written in
+//! the module declaring its trait or type, or one nested in it.
+//!
+//! Each closure holds the variables it uses from the function around it by `&` reference
+//! if it only reads them, by `&mut` reference if it changes them, and by value if it
+//! uses the value of one whose type isn't copied. It is lowered to a function taking an
+//! environment with those captures before its own parameters.
+//!
+//! Once a body checks, each path through it is followed to find assignments to bindings
+//! that aren't `mut`, changes through `&` references, and variables used before every
+//! path to the use gives them a value.
+
+mod bounds;
+mod capture;
+mod coherence;
+mod expr;
+mod flow;
+mod infer;
+mod mono;
+mod pattern;
+
+use std::collections::HashMap;
+
+use coil_error::{msg, Error, ErrorCode, SourceMap, Span};
+use coil_lexer::Literal;
+use coil_parser::{
+    walk, Expr, ExprKind, Fields, ModuleId, NodeId, Program, Statement, TypeExpr, TypeKind, Visitor,
+};
+
+use crate::{
+    Adt, AdtKind, AdtVariant, Bound, Closure, DefId, DefKind, FnSig, Impl, Instance, Resolution,
+    Ty, TypeInstance, CANNOT_INFER, EXPECTED_TYPE, RECURSIVE_ALIAS, TYPE_MISMATCH,
+    WRONG_ARGUMENT_COUNT,
+};
+use bounds::Obligation;
+use infer::Infer;
+
+/// The result of [`check`].
+#[derive(Debug, Clone, Default)]
+pub struct Typing {
+    /// The type of every expression checked.
+    pub exprs: HashMap<NodeId, Ty>,
+    /// The type of every variable and parameter.
+    pub bindings: HashMap<DefId, Ty>,
+    pub signatures: HashMap<DefId, FnSig>,
+    pub adts: HashMap<DefId, Adt>,
+    pub statics: HashMap<DefId, Ty>,
+    pub traits: HashMap<DefId, Trait>,
+    pub impls: Vec<Impl>,
+    /// The bounds each generic item puts on its generics, by the node of the item.
+    pub bounds: HashMap<NodeId, Vec<Bound>>,
+    /// The function each call expression calls, with what the generics of the function
+    /// and of its impl stand for.
+    pub calls: HashMap<NodeId, (DefId, HashMap<DefId, Ty>)>,
+    /// What each closure captures and how it is lowered, by the node of the closure.
+    pub closures: HashMap<NodeId, Closure>,
+    /// The instances of the functions the program runs, found only if it has no errors.
+    pub instances: Vec<Instance>,
+    /// The instances of the structs, enums and unions those functions use, each once for
+    /// every list of type arguments.
+    pub type_instances: Vec<TypeInstance>,
+    pub warnings: Vec<Error>,
+    pub errors: Vec<Error>,
+}
+
+/// A trait with its types resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trait {
+    pub generics: Vec<DefId>,
+    /// The `Self` of the trait, which its items see as a generic parameter.
+    pub self_param: DefId,
+    /// The functions of the trait, by name.
+    pub items: HashMap<Box<str>, Vec<DefId>>,
+}
+
+/// Checks the types of `program`, whose names `resolution` resolved.
+pub fn check(program: &Program, map: &SourceMap, resolution: &Resolution) -> Typing {
+    let mut index = ItemIndex {
+        items: HashMap::new(),
+    };
+    index.visit_expr(&program.root);
+    let items = index.items;
+    let mut containers = HashMap::new();
+    for item in items.values() {
+        if let ExprKind::Statement(Statement::Trait { items, .. } | Statement::Impl { items, .. }) =
+            &item.kind
+        {
+            for child in items {
+                containers.insert(child.id, *item);
+            }
+        }
+    }
+    let mut checker = Checker {
+        program,
+        map,
+        res: resolution,
+        items,
+        containers,
+        modules: HashMap::new(),
+        typing: Typing::default(),
+        self_types: HashMap::new(),
+        expanding: vec![],
+        bodies: vec![],
+        pending: vec![],
+        body_calls: HashMap::new(),
+    };
+    if let ExprKind::Statement(Statement::Module { children, .. }) = &program.root.kind {
+        checker.module_items(ModuleId(0), children);
+    }
+    checker.collect_impls();
+    checker.coherence();
+    checker.item_bodies(&program.root);
+    checker.captures();
+    checker.flow();
+    if checker.typing.errors.is_empty() {
+        checker.monomorphise();
+    }
+    let mut typing = checker.typing;
+    typing
+        .errors
+        .sort_by_key(|error| error.span.map(|span| (span.file, span.start)));
+    typing
+}
+
+/// Finds the items of the AST and everything in it, by node.
+struct ItemIndex<'a> {
+    items: HashMap<NodeId, &'a Expr>,
+}
+
+impl<'a> Visitor<'a> for ItemIndex<'a> {
+    fn visit_expr(&mut self, expr: &'a Expr) {
+        if let ExprKind::Statement(_) = expr.kind {
+            self.items.insert(expr.id, expr);
+        }
+        walk::expr(self, expr);
+    }
+}
+
+/// What is known while checking one function body.
+#[derive(Default)]
+struct Body {
+    /// The function being checked.
+    def: Option<DefId>,
+    /// The bounds that hold in the body.
+    environment: Vec<Bound>,
+    /// The bounds uses of generic items require, checked at the end of the body.
+    obligations: Vec<Obligation>,
+    infer: Infer,
+    /// The return types of the function and the closures being checked, innermost last.
+    /// Empty in the value of a static, which can't return.
+    returns: Vec<Ty>,
+    exprs: HashMap<NodeId, Ty>,
+    bindings: HashMap<DefId, Ty>,
+    /// The variables declared with `let`, to report those whose type isn't known.
+    lets: Vec<DefId>,
+    calls: HashMap<NodeId, (DefId, HashMap<DefId, Ty>)>,
+}
+
+struct Checker<'a> {
+    program: &'a Program,
+    map: &'a SourceMap,
+    res: &'a Resolution,
+    items: HashMap<NodeId, &'a Expr>,
+    /// The trait or impl of each item in one, by the node of the item.
+    containers: HashMap<NodeId, &'a Expr>,
+    /// The module of each item declared directly in one, by the node of the item.
+    modules: HashMap<NodeId, ModuleId>,
+    typing: Typing,
+    /// The type `Self` stands for in each trait and impl, by the `Self` definition.
+    self_types: HashMap<DefId, Ty>,
+    /// The type aliases being expanded, to catch aliases that refer to themselves.
+    expanding: Vec<DefId>,
+    bodies: Vec<Body>,
+    /// The bounds types written outside a body require, checked with the item they are
+    /// written in.
+    pending: Vec<Obligation>,
+    /// The calls and function names in the body of each function.
+    body_calls: HashMap<DefId, Vec<NodeId>>,
+}
+
+impl<'a> Checker<'a> {
+    fn error(&mut self, code: ErrorCode, message: &str, span: Span) {
+        let error = Error::at(code, message, self.map, span);
+        self.typing.errors.push(error);
+    }
+
+    fn body(&mut self) -> &mut Body {
+        self.bodies.last_mut().unwrap()
+    }
+
+    fn infer(&mut self) -> &mut Infer {
+        &mut self.body().infer
+    }
+
+    fn display(&self, ty: &Ty) -> String {
+        let ty = match self.bodies.last() {
+            Some(body) => body.infer.resolve(ty),
+            None => ty.clone(),
+        };
+        let text = ty.display(self.res).to_string();
+        text
+    }
+
+    fn mismatch(&mut self, span: Span, expected: &Ty, found: &Ty) {
+        let message = msg!(
+            "analysis-mismatched-types",
+            expected = self.display(expected),
+            found = self.display(found)
+        );
+        self.error(TYPE_MISMATCH, &message, span);
+    }
+
+    /// Runs `f` as if outside any body, for types that are the same wherever they are
+    /// used, and must not take in the variables of the body being checked.
+    fn outside_body<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let bodies = std::mem::take(&mut self.bodies);
+        let result = f(self);
+        self.bodies = bodies;
+        result
+    }
+
+    /// The item `def` was declared by.
+    fn item(&self, def: DefId) -> Option<&'a Expr> {
+        self.items.get(&self.res.def(def).node?).copied()
+    }
+
+    /// What the last name of a path like `a`, `a.b` or `a.<T>` refers to.
+    fn path_def(&self, path: &Expr) -> Option<DefId> {
+        match &path.kind {
+            ExprKind::Identifier(_) => self.res.resolved(path.id).first().copied(),
+            ExprKind::Binary { right, .. } => self.res.resolved(right.id).first().copied(),
+            ExprKind::Generic { path, .. } => self.path_def(path),
+            _ => None,
+        }
+    }
+
+    /// Fresh variables for `generics`, as a substitution and in order.
+    fn instantiate(&mut self, generics: &[DefId]) -> (HashMap<DefId, Ty>, Vec<Ty>) {
+        let args: Vec<_> = generics.iter().map(|_| self.infer().var()).collect();
+        (generics.iter().copied().zip(args.clone()).collect(), args)
+    }
+
+    fn generics(&self, item: &Expr) -> Vec<DefId> {
+        self.res.generics.get(&item.id).cloned().unwrap_or_default()
+    }
+
+    /// The type written as `ty`.
+    fn lower(&mut self, ty: &TypeExpr) -> Ty {
+        match &ty.kind {
+            TypeKind::Path { path, args } => {
+                let Some(def) = self.path_def(path) else {
+                    return Ty::Error;
+                };
+                let args: Vec<_> = args.iter().map(|arg| self.lower(arg)).collect();
+                self.lower_path(def, args, ty.span)
+            }
+            TypeKind::Reference { mutable, ty } => Ty::Reference {
+                mutable: *mutable,
+                ty: Box::new(self.lower(ty)),
+            },
+            TypeKind::Pointer { mutable, ty } => Ty::Pointer {
+                mutable: *mutable,
+                ty: Box::new(self.lower(ty)),
+            },
+            TypeKind::Array { ty, len } => {
+                let len = match &len.kind {
+                    ExprKind::Literal(Literal::Integer { radix, .. }, text) => {
+                        u64::from_str_radix(text, *radix as u32).ok()
+                    }
+                    _ => None,
+                };
+                Ty::Array {
+                    ty: Box::new(self.lower(ty)),
+                    len,
+                }
+            }
+            TypeKind::Slice(ty) => Ty::Slice(Box::new(self.lower(ty))),
+            TypeKind::Tuple(types) => Ty::Tuple(types.iter().map(|ty| self.lower(ty)).collect()),
+            TypeKind::Fn { params, returns } => Ty::Fn {
+                params: params.iter().map(|ty| self.lower(ty)).collect(),
+                returns: Box::new(self.lower(returns)),
+            },
+            TypeKind::Optional(ty) => Ty::Optional(Box::new(self.lower(ty))),
+            TypeKind::Result { ok, error } => Ty::Result {
+                ok: Box::new(self.lower(ok)),
+                error: Box::new(self.lower(error)),
+            },
+        }
+    }
+
+    /// The type named by `def` applied to `args`.
+    fn lower_path(&mut self, def: DefId, args: Vec<Ty>, span: Span) -> Ty {
+        let name = &self.res.def(def).name;
+        let generics = match self.res.def(def).kind {
+            DefKind::Primitive(primitive) => {
+                self.type_args(name, 0, &args, span);
+                return Ty::Primitive(primitive);
+            }
+            DefKind::GenericParam | DefKind::AssociatedType => {
+                self.type_args(name, 0, &args, span);
+                return Ty::Param(def);
+            }
+            DefKind::SelfType => {
+                self.type_args(name, 0, &args, span);
+                return self.self_type(def);
+            }
+            DefKind::Struct | DefKind::Enum | DefKind::Union => self
+                .item(def)
+                .map(|item| self.generics(item))
+                .unwrap_or_default(),
+            DefKind::TypeAlias => return self.expand_alias(def, args, span),
+            _ => {
+                let message = msg!("analysis-not-a-type", name = name);
+                self.error(EXPECTED_TYPE, &message, span);
+                return Ty::Error;
+            }
+        };
+        // Type arguments left out inside a body are inferred.
+        let args = if args.is_empty() && !generics.is_empty() && !self.bodies.is_empty() {
+            self.instantiate(&generics).1
+        } else if self.type_args(name, generics.len(), &args, span) {
+            args
+        } else {
+            vec![Ty::Error; generics.len()]
+        };
+        let ty = Ty::Adt { def, args };
+        self.require_adt_bounds(&ty, span);
+        ty
+    }
+
+    /// Checks the number of type arguments given to `name`.
+    fn type_args(&mut self, name: &str, expected: usize, args: &[Ty], span: Span) -> bool {
+        if args.len() == expected {
+            return true;
+        }
+        let message = msg!(
+            "analysis-type-argument-count",
+            name = name,
+            expected = expected,
+            found = args.len()
+        );
+        self.error(WRONG_ARGUMENT_COUNT, &message, span);
+        false
+    }
+
+    fn expand_alias(&mut self, def: DefId, args: Vec<Ty>, span: Span) -> Ty {
+        let Some(item) = self.item(def) else {
+            return Ty::Error;
+        };
+        let ExprKind::Statement(Statement::TypeAlias { ty, .. }) = &item.kind else {
+            // An alias in an impl that defines an associated type.
+            return Ty::Param(def);
+        };
+        let name = self.res.def(def).name.clone();
+        if self.expanding.contains(&def) {
+            let message = msg!("analysis-recursive-alias", name = name);
+            self.error(RECURSIVE_ALIAS, &message, span);
+            return Ty::Error;
+        }
+        let generics = self.generics(item);
+        if !self.type_args(&name, generics.len(), &args, span) {
+            return Ty::Error;
+        }
+        self.expanding.push(def);
+        let ty = self.lower(ty);
+        self.expanding.pop();
+        ty.substitute(&generics.into_iter().zip(args).collect())
+    }
+
+    /// The type `Self` stands for, given its definition.
+    fn self_type(&mut self, def: DefId) -> Ty {
+        if let Some(ty) = self.self_types.get(&def) {
+            return ty.clone();
+        }
+        let ty = match self.item(def).map(|item| &item.kind) {
+            Some(ExprKind::Statement(Statement::Impl { target, .. })) => {
+                self.outside_body(|this| this.lower(target))
+            }
+            _ => Ty::Param(def),
+        };
+        self.self_types.insert(def, ty.clone());
+        ty
+    }
+
+    /// The signature of the function `def`.
+    fn signature(&mut self, def: DefId) -> FnSig {
+        if let Some(sig) = self.typing.signatures.get(&def) {
+            return sig.clone();
+        }
+        let Some(item) = self.item(def) else {
+            return FnSig {
+                generics: vec![],
+                params: vec![],
+                returns: Ty::Error,
+            };
+        };
+        let ExprKind::Statement(Statement::Fn { signature, .. }) = &item.kind else {
+            unreachable!("a function is declared by a fn item");
+        };
+        let sig = self.outside_body(|this| FnSig {
+            generics: this.generics(item),
+            params: signature
+                .named_args
+                .iter()
+                .map(|(name, ty)| (name.clone(), this.lower(ty)))
+                .collect(),
+            returns: this.lower(&signature.returns),
+        });
+        self.typing.signatures.insert(def, sig.clone());
+        self.solve_pending(item);
+        sig
+    }
+
+    /// The struct, enum or union `def`.
+    fn adt(&mut self, def: DefId) -> Adt {
+        if let Some(adt) = self.typing.adts.get(&def) {
+            return adt.clone();
+        }
+        let item = self.item(def).expect("types are declared by items");
+        let generics = self.generics(item);
+        let ExprKind::Statement(statement) = &item.kind else {
+            unreachable!();
+        };
+        let adt = self.outside_body(|this| match statement {
+            Statement::Struct { fields, .. } => Adt {
+                kind: AdtKind::Struct,
+                generics,
+                variants: vec![this.variant(def, fields)],
+            },
+            Statement::Union { fields, .. } => Adt {
+                kind: AdtKind::Union,
+                generics,
+                variants: vec![this.variant(def, &Fields::Named(fields.clone()))],
+            },
+            Statement::Enum { variants, .. } => {
+                let defs: Vec<_> = this.res.variants(def).collect();
+                Adt {
+                    kind: AdtKind::Enum,
+                    generics,
+                    variants: variants
+                        .iter()
+                        .zip(defs)
+                        .map(|(variant, def)| this.variant(def, &variant.fields))
+                        .collect(),
+                }
+            }
+            _ => unreachable!("types are declared by structs, enums and unions"),
+        });
+        self.typing.adts.insert(def, adt.clone());
+        self.solve_pending(item);
+        adt
+    }
+
+    fn variant(&mut self, def: DefId, fields: &Fields) -> AdtVariant {
+        let (tuple, fields) = match fields {
+            Fields::Named(fields) => (false, &fields[..]),
+            Fields::Tuple(fields) => (true, &fields[..]),
+            Fields::Unit => (false, &[][..]),
+        };
+        AdtVariant {
+            def,
+            tuple,
+            fields: fields
+                .iter()
+                .map(|field| (field.name.text.clone(), self.lower(&field.ty)))
+                .collect(),
+        }
+    }
+
+    /// The variant `def` of its enum, or the only variant of a struct, with the
+    /// substitution of fresh variables for the generics of the type.
+    fn instantiate_variant(&mut self, def: DefId) -> Option<(Ty, AdtVariant)> {
+        let (adt_def, index) = match self.res.def(def).kind {
+            DefKind::Variant { parent, index } => (parent, index),
+            DefKind::Struct | DefKind::Union => (def, 0),
+            _ => return None,
+        };
+        let adt = self.adt(adt_def);
+        let (subst, args) = self.instantiate(&adt.generics);
+        let mut variant = adt.variants.get(index)?.clone();
+        for (_, ty) in &mut variant.fields {
+            *ty = ty.substitute(&subst);
+        }
+        Some((Ty::Adt { def: adt_def, args }, variant))
+    }
+
+    fn static_type(&mut self, def: DefId) -> Ty {
+        if let Some(ty) = self.typing.statics.get(&def) {
+            return ty.clone();
+        }
+        let Some(item) = self.item(def) else {
+            return Ty::Error;
+        };
+        let ty = match &item.kind {
+            ExprKind::Statement(Statement::Static { ty, .. }) => {
+                self.outside_body(|this| this.lower(ty))
+            }
+            _ => Ty::Error,
+        };
+        self.typing.statics.insert(def, ty.clone());
+        self.solve_pending(item);
+        ty
+    }
+
+    fn trait_info(&mut self, def: DefId) -> Option<Trait> {
+        if let Some(info) = self.typing.traits.get(&def) {
+            return Some(info.clone());
+        }
+        let item = self.item(def)?;
+        let ExprKind::Statement(Statement::Trait { items, .. }) = &item.kind else {
+            return None;
+        };
+        let info = Trait {
+            generics: self.generics(item),
+            self_param: self.self_param(item)?,
+            items: self.functions(items),
+        };
+        self.typing.traits.insert(def, info.clone());
+        Some(info)
+    }
+
+    /// The functions among `items`, by name.
+    fn functions(&self, items: &[Expr]) -> HashMap<Box<str>, Vec<DefId>> {
+        let mut functions: HashMap<_, Vec<_>> = HashMap::new();
+        for item in items {
+            let Some(&def) = self.res.declarations.get(&item.id) else {
+                continue;
+            };
+            if self.res.def(def).kind == DefKind::Fn {
+                let name = self.res.def(def).name.clone();
+                functions.entry(name).or_default().push(def);
+            }
+        }
+        functions
+    }
+
+    /// Records every impl of the program.
+    fn collect_impls(&mut self) {
+        let mut impls: Vec<_> = self
+            .items
+            .values()
+            .filter(|item| matches!(item.kind, ExprKind::Statement(Statement::Impl { .. })))
+            .copied()
+            .collect();
+        impls.sort_by_key(|item| item.id);
+        // The bounds of the types in an impl can only be checked once every impl is known.
+        let mut deferred = vec![];
+        for item in impls {
+            let ExprKind::Statement(Statement::Impl {
+                trait_: trait_expr,
+                target,
+                items,
+                ..
+            }) = &item.kind
+            else {
+                unreachable!();
+            };
+            let trait_ = trait_expr
+                .as_ref()
+                .and_then(|trait_| self.trait_ref(trait_));
+            let target = self.lower(target);
+            if let Some(self_param) = self.self_param(item) {
+                self.self_types.insert(self_param, target.clone());
+            }
+            if let (Some((def, args)), Some(trait_expr)) = (&trait_, trait_expr) {
+                self.require_supertraits(*def, args, &target, trait_expr.span);
+            }
+            deferred.push((item, std::mem::take(&mut self.pending)));
+            self.typing.impls.push(Impl {
+                node: item.id,
+                generics: self.generics(item),
+                trait_,
+                target,
+                items: self.functions(items),
+            });
+        }
+        for (item, pending) in deferred {
+            self.pending = pending;
+            self.solve_pending(item);
+        }
+    }
+
+    /// The trait named by `ty` and its type arguments.
+    fn trait_ref(&mut self, ty: &TypeExpr) -> Option<(DefId, Vec<Ty>)> {
+        let TypeKind::Path { path, args } = &ty.kind else {
+            return None;
+        };
+        let def = self.path_def(path)?;
+        if self.res.def(def).kind != DefKind::Trait {
+            let message = msg!("analysis-not-a-trait", name = self.res.def(def).name);
+            self.error(EXPECTED_TYPE, &message, ty.span);
+            return None;
+        }
+        let args = args.iter().map(|arg| self.lower(arg)).collect();
+        Some((def, args))
+    }
+
+    /// Checks the bodies in the item `item`.
+    fn item_bodies(&mut self, item: &Expr) {
+        let ExprKind::Statement(statement) = &item.kind else {
+            return;
+        };
+        match statement {
+            Statement::Module { children, .. } => {
+                for child in children {
+                    self.item_bodies(child);
+                }
+            }
+            Statement::Trait { items, .. }
+            | Statement::Impl { items, .. }
+            | Statement::Extern { items, .. } => {
+                for item in items {
+                    self.item_bodies(item);
+                }
+            }
+            Statement::Fn {
+                body: Some(body), ..
+            } => {
+                let Some(&def) = self.res.declarations.get(&item.id) else {
+                    return;
+                };
+                let sig = self.signature(def);
+                let environment = match self.item(def) {
+                    Some(item) => self.environment(item),
+                    None => vec![],
+                };
+                self.bodies.push(Body {
+                    def: Some(def),
+                    environment,
+                    ..Body::default()
+                });
+                let params = self.res.params.get(&item.id).cloned().unwrap_or_default();
+                for (param, (_, ty)) in params.into_iter().zip(&sig.params) {
+                    self.body().bindings.insert(param, ty.clone());
+                }
+                self.body().returns.push(sig.returns.clone());
+                self.check(body, &sig.returns);
+                self.finish_body();
+            }
+            Statement::Fn { body: None, .. } => {
+                if let Some(&def) = self.res.declarations.get(&item.id) {
+                    self.signature(def);
+                }
+            }
+            Statement::Static {
+                value: Some(value), ..
+            } => {
+                let Some(&def) = self.res.declarations.get(&item.id) else {
+                    return;
+                };
+                let ty = self.static_type(def);
+                self.bodies.push(Body {
+                    def: Some(def),
+                    ..Body::default()
+                });
+                self.check(value, &ty);
+                self.finish_body();
+            }
+            Statement::Struct { .. } | Statement::Enum { .. } | Statement::Union { .. } => {
+                if let Some(&def) = self.res.declarations.get(&item.id) {
+                    self.adt(def);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Ends the innermost body, settling its types.
+    fn finish_body(&mut self) {
+        self.body().infer.default_literals();
+        self.solve_obligations();
+        let mut body = self.bodies.pop().unwrap();
+        if let Some(def) = body.def {
+            let calls = self.body_calls.entry(def).or_default();
+            calls.extend(body.calls.keys().copied());
+        }
+        for def in std::mem::take(&mut body.lets) {
+            let Some(ty) = body.bindings.get(&def) else {
+                continue;
+            };
+            if body.infer.resolve(ty).has_vars() {
+                let name = &self.res.def(def).name;
+                let message = msg!("analysis-cannot-infer", name = name);
+                let note = msg!("analysis-note-annotate", name = name);
+                let error = Error::at(CANNOT_INFER, &message, self.map, self.res.def(def).span)
+                    .with_note(&note);
+                self.typing.errors.push(error);
+            }
+        }
+        // What is still unknown was reported or comes from code with errors.
+        let settle = |ty: &Ty| {
+            body.infer.resolve(ty).map(&mut |ty| match ty {
+                Ty::Var(_) | Ty::IntVar(_) | Ty::FloatVar(_) => Ty::Error,
+                ty => ty,
+            })
+        };
+        for (node, ty) in &body.exprs {
+            self.typing.exprs.insert(*node, settle(ty));
+        }
+        for (def, ty) in &body.bindings {
+            self.typing.bindings.insert(*def, settle(ty));
+        }
+        for (node, (def, args)) in &body.calls {
+            let args = args
+                .iter()
+                .map(|(param, ty)| (*param, settle(ty)))
+                .collect();
+            self.typing.calls.insert(*node, (*def, args));
+        }
+    }
+}
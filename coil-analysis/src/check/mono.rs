@@ -0,0 +1,280 @@
+use std::collections::{HashMap, HashSet};
+
+use coil_error::{msg, Error};
+use coil_parser::{walk, Expr, ExprKind, NodeId, Statement, Visitor};
+
+use super::{Body, Checker};
+use crate::{AdtVariant, DefId, Instance, Ty, TypeInstance, INSTANCE_LIMIT};
+
+/// How deeply the type arguments of an instance may nest before the generic functions
+/// involved are taken to instantiate each other without end.
+const MAX_NESTING: usize = 32;
+
+impl Checker<'_> {
+    /// The generics an instance of the function `def` gives types to: those of its impl,
+    /// or `Self` and those of its trait, then its own.
+    pub(super) fn instance_generics(&self, def: DefId) -> Vec<DefId> {
+        let Some(item) = self.item(def) else {
+            return vec![];
+        };
+        let mut generics = vec![];
+        if let Some(&container) = self.containers.get(&item.id) {
+            if let ExprKind::Statement(Statement::Trait { .. }) = container.kind {
+                generics.extend(self.self_param(container));
+            }
+            generics.extend(self.generics(container));
+        }
+        generics.extend(self.generics(item));
+        generics
+    }
+
+    /// Collects an instance of every function reachable from the functions that aren't
+    /// generic, each once however many places use it, then of every type those and the
+    /// statics use.
+    pub(super) fn monomorphise(&mut self) {
+        let mut roots: Vec<_> = self
+            .body_calls
+            .keys()
+            .copied()
+            .filter(|&def| self.instance_generics(def).is_empty())
+            .collect();
+        roots.sort();
+        let mut keys = HashMap::new();
+        for def in roots {
+            instance(&mut self.typing.instances, &mut keys, def, vec![]);
+        }
+        self.bodies.push(Body::default());
+        let mut next = 0;
+        while next < self.typing.instances.len() {
+            self.instance_calls(next, &mut keys);
+            next += 1;
+        }
+        self.bodies.pop();
+        let mut statics: Vec<_> = self.typing.statics.iter().collect();
+        statics.sort_by_key(|&(&def, _)| def);
+        let mut types: Vec<_> = statics.into_iter().map(|(_, ty)| ty.clone()).collect();
+        for index in 0..self.typing.instances.len() {
+            types.extend(self.instance_types(index));
+        }
+        self.type_instances(types);
+    }
+
+    /// The types the instance `index` uses, in its signature and in its body.
+    fn instance_types(&mut self, index: usize) -> Vec<Ty> {
+        let Instance { def, args, .. } = self.typing.instances[index].clone();
+        let subst: HashMap<_, _> = self.instance_generics(def).into_iter().zip(args).collect();
+        let sig = self.signature(def);
+        let mut types: Vec<_> = sig.params.into_iter().map(|(_, ty)| ty).collect();
+        types.push(sig.returns);
+        if let Some(ExprKind::Statement(Statement::Fn {
+            body: Some(body), ..
+        })) = self.item(def).map(|item| &item.kind)
+        {
+            let mut nodes = BodyNodes(vec![]);
+            nodes.visit_expr(body);
+            let exprs = &self.typing.exprs;
+            types.extend(nodes.0.iter().filter_map(|node| exprs.get(node)).cloned());
+        }
+        types.iter().map(|ty| ty.substitute(&subst)).collect()
+    }
+
+    /// Collects an instance of every struct, enum and union in `types` and in the fields
+    /// of those, each once for every list of type arguments.
+    fn type_instances(&mut self, mut types: Vec<Ty>) {
+        let mut keys = HashSet::new();
+        let mut next = 0;
+        while next < types.len() {
+            let ty = types[next].clone();
+            next += 1;
+            let Ty::Adt { def, args } = &ty else {
+                types.extend(children(&ty).into_iter().cloned());
+                continue;
+            };
+            if !keys.insert((*def, args.clone())) {
+                continue;
+            }
+            if nesting(&ty) > MAX_NESTING {
+                let name = &self.res.def(*def).name;
+                let message = msg!("analysis-instance-limit", name = name);
+                let span = self.res.def(*def).span;
+                let error = Error::at(INSTANCE_LIMIT, &message, self.map, span);
+                self.typing.errors.push(error);
+                return;
+            }
+            let adt = self.adt(*def);
+            let subst: HashMap<_, _> = adt.generics.into_iter().zip(args.clone()).collect();
+            let variants: Vec<_> = adt
+                .variants
+                .into_iter()
+                .map(|variant| AdtVariant {
+                    fields: variant
+                        .fields
+                        .into_iter()
+                        .map(|(name, ty)| (name, ty.substitute(&subst)))
+                        .collect(),
+                    ..variant
+                })
+                .collect();
+            types.extend(args.iter().cloned());
+            let fields = variants.iter().flat_map(|variant| &variant.fields);
+            types.extend(fields.map(|(_, ty)| ty.clone()));
+            self.typing.type_instances.push(TypeInstance {
+                def: *def,
+                args: args.clone(),
+                variants,
+            });
+        }
+    }
+
+    /// Finds the instances the body of the instance `index` refers to.
+    fn instance_calls(&mut self, index: usize, keys: &mut HashMap<(DefId, Vec<Ty>), usize>) {
+        let Instance { def, args, .. } = self.typing.instances[index].clone();
+        let subst: HashMap<_, _> = self.instance_generics(def).into_iter().zip(args).collect();
+        let mut nodes = self.body_calls.get(&def).cloned().unwrap_or_default();
+        nodes.sort();
+        for node in nodes {
+            let (callee, call_subst) = self.typing.calls[&node].clone();
+            let call_subst = call_subst
+                .into_iter()
+                .map(|(param, ty)| (param, ty.substitute(&subst)))
+                .collect();
+            let Some((callee, args)) = self.resolve_instance(callee, &call_subst) else {
+                continue;
+            };
+            if args.iter().any(|ty| nesting(ty) > MAX_NESTING) {
+                let name = &self.res.def(callee).name;
+                let message = msg!("analysis-instance-limit", name = name);
+                let span = self.res.def(def).span;
+                let error = Error::at(INSTANCE_LIMIT, &message, self.map, span);
+                self.typing.errors.push(error);
+                return;
+            }
+            let callee = instance(&mut self.typing.instances, keys, callee, args);
+            self.typing.instances[index].calls.insert(node, callee);
+        }
+    }
+
+    /// The function a use of `def` runs, with the types of its instance generics, when
+    /// `subst` says what the generics of `def` stand for. A function of a trait runs the
+    /// function of the same name and labels in the impl for the type `Self` stands for,
+    /// or its own body if the impl leaves it out.
+    fn resolve_instance(
+        &mut self,
+        def: DefId,
+        subst: &HashMap<DefId, Ty>,
+    ) -> Option<(DefId, Vec<Ty>)> {
+        let item = self.item(def)?;
+        let mut def = def;
+        let mut subst = subst.clone();
+        if let Some(container) = self.containers.get(&item.id).copied() {
+            if let ExprKind::Statement(Statement::Trait { .. }) = container.kind {
+                let trait_ = *self.res.declarations.get(&container.id)?;
+                let info = self.trait_info(trait_)?;
+                let self_ty = subst.get(&info.self_param)?.clone();
+                let args: Vec<_> = info
+                    .generics
+                    .iter()
+                    .map(|param| subst.get(param).cloned().unwrap_or(Ty::Error))
+                    .collect();
+                let (index, impl_subst) = self.select_impl(&self_ty, trait_, &args, 0)?;
+                let sig = self.signature(def);
+                let labels = sig.labels();
+                let name = self.res.def(def).name.clone();
+                let candidates = self.typing.impls[index]
+                    .items
+                    .get(&name)
+                    .cloned()
+                    .unwrap_or_default();
+                let found = candidates
+                    .into_iter()
+                    .find(|&candidate| self.signature(candidate).match_labels(0, &labels).is_ok());
+                if let Some(found) = found {
+                    // The generics of the function in the impl line up with those in
+                    // the trait.
+                    let own = self.generics(item);
+                    let found_item = self.item(found)?;
+                    let mut found_subst = impl_subst;
+                    for (theirs, ours) in self.generics(found_item).into_iter().zip(own) {
+                        found_subst.insert(theirs, subst.get(&ours).cloned()?);
+                    }
+                    def = found;
+                    subst = found_subst;
+                }
+            }
+        }
+        let args = self
+            .instance_generics(def)
+            .iter()
+            .map(|param| {
+                let ty = subst.get(param).cloned().unwrap_or(Ty::Error);
+                self.infer().resolve(&ty)
+            })
+            .collect();
+        Some((def, args))
+    }
+}
+
+/// The index of the instance of `def` with `args`, added if there is none yet.
+fn instance(
+    instances: &mut Vec<Instance>,
+    keys: &mut HashMap<(DefId, Vec<Ty>), usize>,
+    def: DefId,
+    args: Vec<Ty>,
+) -> usize {
+    *keys.entry((def, args.clone())).or_insert_with(|| {
+        instances.push(Instance {
+            def,
+            args,
+            calls: HashMap::new(),
+        });
+        instances.len() - 1
+    })
+}
+
+/// The nodes of the expressions in a body, leaving out the items declared in it, which
+/// have instances of their own.
+struct BodyNodes(Vec<NodeId>);
+
+impl Visitor<'_> for BodyNodes {
+    fn visit_expr(&mut self, expr: &Expr) {
+        self.0.push(expr.id);
+        walk::expr(self, expr);
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Let { .. }
+            | Statement::Return(_)
+            | Statement::Break { .. }
+            | Statement::Continue { .. }
+            | Statement::Fallthrough => walk::statement(self, statement),
+            _ => {}
+        }
+    }
+}
+
+/// How deeply types nest in `ty`.
+fn nesting(ty: &Ty) -> usize {
+    1 + children(ty).into_iter().map(nesting).max().unwrap_or(0)
+}
+
+/// The types `ty` is made of, one level down.
+fn children(ty: &Ty) -> Vec<&Ty> {
+    let mut children = vec![];
+    match ty {
+        Ty::Adt { args: types, .. } | Ty::Tuple(types) => children.extend(types),
+        Ty::Reference { ty, .. }
+        | Ty::Pointer { ty, .. }
+        | Ty::Array { ty, .. }
+        | Ty::Slice(ty)
+        | Ty::Optional(ty)
+        | Ty::Range(ty) => children.push(&**ty),
+        Ty::Fn { params, returns } => {
+            children.extend(params);
+            children.push(returns);
+        }
+        Ty::Result { ok, error } => children.extend([&**ok, &**error]),
+        _ => {}
+    }
+    children
+}
@@ -0,0 +1,203 @@
+use coil_error::{msg, Span};
+use coil_parser::{Expr, ExprKind, Pattern, PatternKind};
+
+use super::Checker;
+use crate::{
+    AdtKind, AdtVariant, DefKind, Primitive, Ty, EXPECTED_TYPE, MISSING_FIELDS,
+    WRONG_ARGUMENT_COUNT,
+};
+
+impl Checker<'_> {
+    /// Checks that `pattern` can match a value of type `expected`, and gives the bindings
+    /// in it their types.
+    pub(super) fn pattern(&mut self, pattern: &Pattern, expected: &Ty) {
+        let expected = self.infer().shallow(expected);
+        match &pattern.kind {
+            PatternKind::Wildcard => {}
+            PatternKind::Binding { .. } => self.bind_pattern(pattern, &expected),
+            PatternKind::Literal(literal, text) => {
+                let (digits, negative) = match text.strip_prefix('-') {
+                    Some(digits) => (digits, true),
+                    None => (&**text, false),
+                };
+                let found = self.literal(pattern.span, literal, digits, Some(&expected), negative);
+                self.pattern_type(pattern, &found, &expected);
+            }
+            PatternKind::Bool(_) => {
+                self.pattern_type(pattern, &Ty::Primitive(Primitive::Bool), &expected);
+            }
+            PatternKind::Range { start, end } => {
+                self.pattern(start, &expected);
+                self.pattern(end, &expected);
+            }
+            PatternKind::Path(path) => {
+                let found = self.expr(path, Some(&expected));
+                self.pattern_type(pattern, &found, &expected);
+            }
+            PatternKind::Tuple(patterns) => {
+                let types = match &expected {
+                    Ty::Tuple(types) if types.len() == patterns.len() => types.clone(),
+                    Ty::Error => vec![Ty::Error; patterns.len()],
+                    _ => {
+                        let types: Vec<_> = patterns.iter().map(|_| self.infer().var()).collect();
+                        self.pattern_type(pattern, &Ty::Tuple(types.clone()), &expected);
+                        types
+                    }
+                };
+                for (pattern, ty) in patterns.iter().zip(&types) {
+                    self.pattern(pattern, ty);
+                }
+            }
+            PatternKind::Variant { path, args } => {
+                let types = match self.struct_path(path) {
+                    Some((ty, variant)) => {
+                        self.pattern_type(pattern, &ty, &expected);
+                        if variant.fields.len() == args.len() {
+                            variant.fields.into_iter().map(|(_, ty)| ty).collect()
+                        } else {
+                            let message = msg!(
+                                "analysis-pattern-field-count",
+                                expected = variant.fields.len(),
+                                found = args.len()
+                            );
+                            self.error(WRONG_ARGUMENT_COUNT, &message, pattern.span);
+                            vec![Ty::Error; args.len()]
+                        }
+                    }
+                    None => vec![Ty::Error; args.len()],
+                };
+                for (arg, ty) in args.iter().zip(&types) {
+                    self.pattern(arg, ty);
+                }
+            }
+            PatternKind::Struct { path, fields, rest } => {
+                let Some((ty, variant)) = self.struct_path(path) else {
+                    for (_, field) in fields {
+                        self.pattern(field, &Ty::Error);
+                    }
+                    return;
+                };
+                self.pattern_type(pattern, &ty, &expected);
+                for (name, field) in fields {
+                    match variant.fields.iter().find(|(other, _)| other == name) {
+                        Some((_, ty)) => self.pattern(field, ty),
+                        None => {
+                            self.unknown_field(&ty, &variant.fields, name, field.span);
+                            self.pattern(field, &Ty::Error);
+                        }
+                    }
+                }
+                let union =
+                    matches!(&ty, Ty::Adt { def, .. } if self.adt(*def).kind == AdtKind::Union);
+                if !rest && !union {
+                    let named: Vec<_> = fields.iter().map(|(name, _)| &**name).collect();
+                    self.missing_fields(&ty, &variant.fields, &named, pattern.span);
+                }
+            }
+            PatternKind::Or(alternatives) => {
+                for alternative in alternatives {
+                    self.pattern(alternative, &expected);
+                }
+            }
+            PatternKind::Is { ty, .. } => {
+                let ty = self.lower(ty);
+                self.bind_pattern(pattern, &ty);
+            }
+        }
+    }
+
+    /// Gives the variable `pattern` binds the type `ty`, or checks that it has it when
+    /// another alternative of an or-pattern bound it first.
+    fn bind_pattern(&mut self, pattern: &Pattern, ty: &Ty) {
+        let Some(&def) = self.res.declarations.get(&pattern.id) else {
+            return;
+        };
+        match self.body().bindings.get(&def).cloned() {
+            Some(first) => self.pattern_type(pattern, ty, &first),
+            None => {
+                self.body().bindings.insert(def, ty.clone());
+            }
+        }
+    }
+
+    /// Reports a pattern matching values of type `found` where `expected` is matched.
+    fn pattern_type(&mut self, pattern: &Pattern, found: &Ty, expected: &Ty) {
+        if !self.infer().unify(found, expected) {
+            self.mismatch(pattern.span, expected, found);
+        }
+    }
+
+    /// The struct or variant named by the path of a struct literal or pattern, with fresh
+    /// variables for its generics unless they are written out.
+    pub(super) fn struct_path(&mut self, path: &Expr) -> Option<(Ty, AdtVariant)> {
+        let def = self.path_def(path)?;
+        let explicit = match &path.kind {
+            ExprKind::Generic { args, .. } => {
+                let args: Vec<_> = args.iter().map(|arg| self.lower(arg)).collect();
+                Some(args)
+            }
+            _ => None,
+        };
+        let name = self.res.def(def).name.clone();
+        match self.res.def(def).kind {
+            DefKind::Variant { .. } | DefKind::Struct | DefKind::Union => {
+                let (ty, variant) = self.instantiate_variant(def)?;
+                if let (Some(explicit), Ty::Adt { def: adt, args }) = (explicit, &ty) {
+                    let adt_name = self.res.def(*adt).name.clone();
+                    if self.type_args(&adt_name, args.len(), &explicit, path.span) {
+                        for (var, arg) in args.iter().zip(&explicit) {
+                            self.infer().unify(var, arg);
+                        }
+                    }
+                }
+                Some((ty, variant))
+            }
+            DefKind::SelfType | DefKind::TypeAlias => {
+                let ty = self.lower_path(def, explicit.unwrap_or_default(), path.span);
+                match &ty {
+                    Ty::Adt { def: adt, args } if self.adt(*adt).kind != AdtKind::Enum => {
+                        let adt = self.adt(*adt);
+                        let subst = adt.generics.iter().copied().zip(args.clone()).collect();
+                        let mut variant = adt.variants[0].clone();
+                        for (_, field) in &mut variant.fields {
+                            *field = field.substitute(&subst);
+                        }
+                        Some((ty, variant))
+                    }
+                    Ty::Error => None,
+                    _ => self.not_a_struct(&name, path.span),
+                }
+            }
+            _ => self.not_a_struct(&name, path.span),
+        }
+    }
+
+    fn not_a_struct(&mut self, name: &str, span: Span) -> Option<(Ty, AdtVariant)> {
+        let message = msg!("analysis-not-a-struct", name = name);
+        self.error(EXPECTED_TYPE, &message, span);
+        None
+    }
+
+    /// Reports the fields of a value of type `ty` that aren't among `named`.
+    pub(super) fn missing_fields(
+        &mut self,
+        ty: &Ty,
+        fields: &[(Box<str>, Ty)],
+        named: &[&str],
+        span: Span,
+    ) {
+        let missing: Vec<_> = fields
+            .iter()
+            .filter(|(name, _)| !named.contains(&&**name))
+            .map(|(name, _)| format!("'{name}'"))
+            .collect();
+        if !missing.is_empty() {
+            let message = msg!(
+                "analysis-missing-fields",
+                fields = missing.join(", "),
+                name = self.display(ty)
+            );
+            self.error(MISSING_FIELDS, &message, span);
+        }
+    }
+}
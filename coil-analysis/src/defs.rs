@@ -0,0 +1,153 @@
+use coil_error::Span;
+use coil_parser::{ModuleId, NodeId};
+
+/// Identifies a [`Definition`] of a [`Resolution`](crate::Resolution).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DefId(pub u32);
+
+/// Something a name can refer to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Definition {
+    pub name: Box<str>,
+    pub kind: DefKind,
+    /// Where the name is declared, which is empty for builtins.
+    pub span: Span,
+    /// The node declaring the name: an item, `let`, `for`, pattern, or the function or
+    /// closure of a parameter. Modules and builtins have none.
+    pub node: Option<NodeId>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefKind {
+    Primitive(Primitive),
+    Module(ModuleId),
+    Fn,
+    Struct,
+    Enum,
+    /// The `index`th variant of the enum `parent`.
+    Variant {
+        parent: DefId,
+        index: usize,
+    },
+    Union,
+    Trait,
+    TypeAlias,
+    AssociatedType,
+    Static,
+    GenericParam,
+    /// `Self` in a trait or impl, whose node is the trait or impl.
+    SelfType,
+    Local {
+        mutable: bool,
+    },
+    Param,
+}
+
+impl DefKind {
+    /// Whether the name is a type, where it can be used in type position.
+    pub fn is_type(self) -> bool {
+        matches!(
+            self,
+            Self::Primitive(_)
+                | Self::Struct
+                | Self::Enum
+                | Self::Union
+                | Self::Trait
+                | Self::TypeAlias
+                | Self::AssociatedType
+                | Self::GenericParam
+                | Self::SelfType
+        )
+    }
+
+    /// Whether the name is a variable, whose uses are tracked to warn about unused ones.
+    pub fn is_binding(self) -> bool {
+        matches!(self, Self::Local { .. } | Self::Param)
+    }
+}
+
+/// The builtin types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Primitive {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+    Bool,
+    Char,
+    Str,
+}
+
+impl Primitive {
+    /// The names of the builtin types. `Int`, `UInt` and `Float` are the 64-bit ones.
+    pub const NAMES: [(&'static str, Primitive); 16] = [
+        ("Int", Self::I64),
+        ("UInt", Self::U64),
+        ("Float", Self::F64),
+        ("I8", Self::I8),
+        ("I16", Self::I16),
+        ("I32", Self::I32),
+        ("I64", Self::I64),
+        ("U8", Self::U8),
+        ("U16", Self::U16),
+        ("U32", Self::U32),
+        ("U64", Self::U64),
+        ("F32", Self::F32),
+        ("F64", Self::F64),
+        ("Bool", Self::Bool),
+        ("Char", Self::Char),
+        ("Str", Self::Str),
+    ];
+
+    /// The name the type is written with, `Int` rather than `I64`.
+    pub fn name(self) -> &'static str {
+        Self::NAMES
+            .iter()
+            .find(|&&(_, primitive)| primitive == self)
+            .map(|&(name, _)| name)
+            .unwrap()
+    }
+
+    /// The type named by the suffix of a number literal, `u8` for `U8`.
+    pub fn from_suffix(suffix: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(suffix))
+            .map(|&(_, primitive)| primitive)
+    }
+
+    pub fn is_integer(self) -> bool {
+        matches!(
+            self,
+            Self::I8
+                | Self::I16
+                | Self::I32
+                | Self::I64
+                | Self::U8
+                | Self::U16
+                | Self::U32
+                | Self::U64
+        )
+    }
+
+    pub fn is_signed(self) -> bool {
+        matches!(self, Self::I8 | Self::I16 | Self::I32 | Self::I64)
+    }
+
+    /// The size of an integer type in bits.
+    pub fn bits(self) -> Option<u32> {
+        match self {
+            Self::I8 | Self::U8 => Some(8),
+            Self::I16 | Self::U16 => Some(16),
+            Self::I32 | Self::U32 | Self::F32 => Some(32),
+            Self::I64 | Self::U64 | Self::F64 => Some(64),
+            Self::Bool | Self::Char | Self::Str => None,
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Semantic analysis of a loaded [`Program`](coil_parser::Program): name resolution,
+//! then type checking.
+
+mod check;
+mod defs;
+mod resolve;
+mod ty;
+
+pub use check::{check, Trait, Typing};
+use coil_error::{msg, Error, ErrorCode};
+pub use defs::{DefId, DefKind, Definition, Primitive};
+pub use resolve::{resolve, Resolution};
+pub use ty::{
+    Adt, AdtKind, AdtVariant, Bound, Capture, CaptureMode, Closure, FnSig, Impl, Instance, Ty,
+    TypeInstance,
+};
+
+const UNRESOLVED_NAME: ErrorCode = ErrorCode::analysis(1);
+const AMBIGUOUS_NAME: ErrorCode = ErrorCode::analysis(2);
+const DUPLICATE_DEFINITION: ErrorCode = ErrorCode::analysis(3);
+const UNUSED_BINDING: ErrorCode = ErrorCode::analysis(4);
+const UNUSED_IMPORT: ErrorCode = ErrorCode::analysis(5);
+const TYPE_MISMATCH: ErrorCode = ErrorCode::analysis(6);
+const EXPECTED_TYPE: ErrorCode = ErrorCode::analysis(7);
+const EXPECTED_VALUE: ErrorCode = ErrorCode::analysis(8);
+const RECURSIVE_ALIAS: ErrorCode = ErrorCode::analysis(9);
+const UNKNOWN_FIELD: ErrorCode = ErrorCode::analysis(10);
+const UNKNOWN_METHOD: ErrorCode = ErrorCode::analysis(11);
+const MISSING_FIELDS: ErrorCode = ErrorCode::analysis(12);
+const NOT_CALLABLE: ErrorCode = ErrorCode::analysis(13);
+const WRONG_ARGUMENT_COUNT: ErrorCode = ErrorCode::analysis(14);
+const CANNOT_INFER: ErrorCode = ErrorCode::analysis(15);
+const INVALID_OPERAND: ErrorCode = ErrorCode::analysis(16);
+const LITERAL_OUT_OF_RANGE: ErrorCode = ErrorCode::analysis(17);
+const UNSATISFIED_BOUND: ErrorCode = ErrorCode::analysis(18);
+const INSTANCE_LIMIT: ErrorCode = ErrorCode::analysis(19);
+const CONFLICTING_IMPLS: ErrorCode = ErrorCode::analysis(20);
+const ORPHAN_IMPL: ErrorCode = ErrorCode::analysis(21);
+const MISSING_TRAIT_ITEMS: ErrorCode = ErrorCode::analysis(22);
+const TRAIT_ITEM_MISMATCH: ErrorCode = ErrorCode::analysis(23);
+const NOT_A_TRAIT_ITEM: ErrorCode = ErrorCode::analysis(24);
+const IMMUTABLE_BINDING: ErrorCode = ErrorCode::analysis(25);
+const UNINITIALISED_USE: ErrorCode = ErrorCode::analysis(26);
+const MUTATION_THROUGH_REFERENCE: ErrorCode = ErrorCode::analysis(27);
+const MISSING_LABEL: ErrorCode = ErrorCode::analysis(28);
+const UNEXPECTED_LABEL: ErrorCode = ErrorCode::analysis(29);
+const MISORDERED_LABEL: ErrorCode = ErrorCode::analysis(30);
+const WRONG_LABEL: ErrorCode = ErrorCode::analysis(31);
+const RETURN_OUTSIDE_FN: ErrorCode = ErrorCode::analysis(32);
+const FOREIGN_IMPL: ErrorCode = ErrorCode::analysis(33);
+const BUILTIN_IMPL: ErrorCode = ErrorCode::analysis(34);
+
+/// Adds a note to `error` suggesting the name among `names` closest to `name`, if one is
+/// close enough to be a typo.
+fn suggest(error: Error, name: &str, names: &[Box<str>]) -> Error {
+    let limit = (name.chars().count() / 3).max(1);
+    let best = names
+        .iter()
+        .filter(|candidate| &***candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|&(distance, _)| distance <= limit)
+        .min();
+    match best {
+        Some((_, candidate)) => {
+            error.with_note(&msg!("analysis-note-did-you-mean", name = candidate))
+        }
+        None => error,
+    }
+}
+
+/// The Levenshtein distance between `a` and `b`, in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,984 @@
+//! Name resolution: links every name in the AST of a [`Program`] to its [`Definition`].
+//!
+//! Names are looked up in the lexical scopes around them, innermost first: blocks, match
+//! arms, closures, functions and the generics of items, then the items, modules and
+//! imports of the module, then the builtin types. The items of a block or module can be
+//! used before they are declared, while a `let` only comes into scope after it and hides
+//! earlier names. An item doesn't see the variables and generic parameters of the
+//! function it is nested in, except that the items of a trait or impl see its generics
+//! and `Self`. Modules don't see the names of their parent; those have to be imported.
+//!
+//! The variants of an enum can be named without the enum, as in `Some(x)`, wherever the
+//! enum itself is in scope.
+
+use std::collections::{HashMap, HashSet};
+
+use coil_error::{msg, Error, ErrorCode, SourceMap, Span};
+use coil_parser::{
+    walk, ArgName, BinaryOperator, ClosureParam, Expr, ExprKind, Field, Fields, Generics, Ident,
+    ImportTarget, ModuleId, NodeId, Pattern, PatternKind, Program, Signature, Statement, TypeExpr,
+    TypeKind, Visitor,
+};
+
+use crate::{
+    suggest, DefId, DefKind, Definition, Primitive, AMBIGUOUS_NAME, DUPLICATE_DEFINITION,
+    UNRESOLVED_NAME, UNUSED_BINDING, UNUSED_IMPORT,
+};
+
+/// The result of [`resolve`].
+#[derive(Debug, Clone, Default)]
+pub struct Resolution {
+    pub defs: Vec<Definition>,
+    /// What the names of the AST refer to, by the node of the name: an identifier or the
+    /// right side of a dotted path. A name of overloaded functions refers to all of them.
+    pub uses: HashMap<NodeId, Vec<DefId>>,
+    /// The definitions made by items, `let`s, `for` loops and patterns.
+    pub declarations: HashMap<NodeId, DefId>,
+    /// The parameters of every function and closure, in order.
+    pub params: HashMap<NodeId, Vec<DefId>>,
+    /// The generic parameters of every item, in order.
+    pub generics: HashMap<NodeId, Vec<DefId>>,
+    /// The definition of every module of the program, by [`ModuleId`].
+    pub modules: Vec<DefId>,
+    pub warnings: Vec<Error>,
+    pub errors: Vec<Error>,
+}
+
+impl Resolution {
+    pub fn def(&self, id: DefId) -> &Definition {
+        &self.defs[id.0 as usize]
+    }
+
+    /// What the name at `node` refers to, if it was resolved.
+    pub fn resolved(&self, node: NodeId) -> &[DefId] {
+        self.uses.get(&node).map_or(&[], Vec::as_slice)
+    }
+
+    /// The variants of the enum `parent`, in order.
+    pub fn variants(&self, parent: DefId) -> impl Iterator<Item = DefId> + '_ {
+        self.defs
+            .iter()
+            .enumerate()
+            .filter_map(move |(i, def)| match def.kind {
+                DefKind::Variant { parent: p, .. } if p == parent => Some(DefId(i as u32)),
+                _ => None,
+            })
+    }
+}
+
+/// Resolves the names of `program`, which was loaded into `map`.
+pub fn resolve(program: &Program, map: &SourceMap) -> Resolution {
+    let mut resolver = Resolver {
+        program,
+        map,
+        res: Resolution::default(),
+        used: vec![],
+        module_items: vec![HashMap::new(); program.modules.len()],
+        imports_used: program
+            .modules
+            .iter()
+            .map(|module| vec![false; module.imports.len()])
+            .collect(),
+        variants: HashMap::new(),
+        signatures: HashMap::new(),
+        builtins: HashMap::new(),
+        module: ModuleId(0),
+        scopes: vec![],
+        rebinding: false,
+        bound: HashMap::new(),
+        associated: false,
+        labelled: HashSet::new(),
+    };
+    for (i, module) in program.modules.iter().enumerate() {
+        let span = Span::new(module.file, 0, 0);
+        let def = resolver.define(
+            &module.name,
+            DefKind::Module(ModuleId(i as u32)),
+            span,
+            None,
+        );
+        resolver.res.modules.push(def);
+    }
+    for (name, primitive) in Primitive::NAMES {
+        let def = resolver.define(name, DefKind::Primitive(primitive), Span::default(), None);
+        resolver.builtins.insert(name, def);
+    }
+    if let ExprKind::Statement(Statement::Module { children, .. }) = &program.root.kind {
+        if !program.modules.is_empty() {
+            resolver.collect_module(ModuleId(0), children);
+            resolver.module_body(ModuleId(0), children);
+        }
+    }
+    let mut res = resolver.res;
+    for errors in [&mut res.errors, &mut res.warnings] {
+        errors.sort_by_key(|error| error.span.map(|span| (span.file, span.start)));
+    }
+    res
+}
+
+struct Resolver<'a> {
+    program: &'a Program,
+    map: &'a SourceMap,
+    res: Resolution,
+    /// Whether each definition is used, to warn about unused variables.
+    used: Vec<bool>,
+    module_items: Vec<HashMap<Box<str>, Vec<DefId>>>,
+    /// Whether each import of each module is used.
+    imports_used: Vec<Vec<bool>>,
+    variants: HashMap<DefId, Vec<DefId>>,
+    /// The signatures of the functions declared so far, to tell overloads from
+    /// duplicates.
+    signatures: HashMap<DefId, Signature>,
+    builtins: HashMap<&'static str, DefId>,
+    /// The module being resolved.
+    module: ModuleId,
+    scopes: Vec<Scope>,
+    /// Set in the second and later alternatives of an or-pattern, which bind the same
+    /// names as the first.
+    rebinding: bool,
+    /// The names bound by the pattern being resolved so far.
+    bound: HashMap<Box<str>, DefId>,
+    /// Set for the items of a trait or impl, which see its generics and `Self`.
+    associated: bool,
+    /// The parameters whose name is also their label, so that renaming them would change
+    /// the calls.
+    labelled: HashSet<DefId>,
+}
+
+#[derive(Default)]
+struct Scope {
+    names: HashMap<Box<str>, Vec<DefId>>,
+    /// The variables declared in the scope, hidden ones included.
+    bindings: Vec<DefId>,
+    /// Set for the scope of an item, beyond which variables and generic parameters can't
+    /// be seen.
+    boundary: bool,
+}
+
+impl Resolver<'_> {
+    fn define(&mut self, name: &str, kind: DefKind, span: Span, node: Option<NodeId>) -> DefId {
+        let id = DefId(self.res.defs.len() as u32);
+        self.res.defs.push(Definition {
+            name: name.into(),
+            kind,
+            span,
+            node,
+        });
+        self.used.push(false);
+        id
+    }
+
+    fn def(&self, id: DefId) -> &Definition {
+        self.res.def(id)
+    }
+
+    fn error(&self, code: ErrorCode, message: &str, span: Span) -> Error {
+        Error::at(code, message, self.map, span)
+    }
+
+    /// Adds `def` to `names`, reporting it if the name is taken by something other than
+    /// overloads of a function, or by a function with the same argument names.
+    fn insert(&mut self, names: &mut HashMap<Box<str>, Vec<DefId>>, def: DefId) {
+        let name = self.def(def).name.clone();
+        let Some(existing) = names.get_mut(&name) else {
+            names.insert(name, vec![def]);
+            return;
+        };
+        let first = existing[0];
+        if self.def(first).kind != DefKind::Fn || self.def(def).kind != DefKind::Fn {
+            self.duplicate(first, def);
+            return;
+        }
+        let same = existing.iter().copied().find(|other| {
+            match (self.signatures.get(other), self.signatures.get(&def)) {
+                (Some(a), Some(b)) => a.same_overload(b),
+                _ => false,
+            }
+        });
+        match same {
+            Some(other) => self.duplicate(other, def),
+            None => existing.push(def),
+        }
+    }
+
+    fn duplicate(&mut self, first: DefId, def: DefId) {
+        let first = self.def(first).span;
+        let line = self.map.get(first.file).location(first.start).line;
+        let def = self.def(def);
+        let message = msg!("analysis-duplicate-definition", name = def.name);
+        let error = self
+            .error(DUPLICATE_DEFINITION, &message, def.span)
+            .with_note(&msg!("analysis-note-first-definition", line = line));
+        self.res.errors.push(error);
+    }
+
+    /// Defines the item `item` declares, and the variants of an enum, if it is an item
+    /// with a name.
+    fn declare_item(&mut self, item: &Expr) -> Option<DefId> {
+        let ExprKind::Statement(statement) = &item.kind else {
+            return None;
+        };
+        let (name, kind) = match statement {
+            Statement::Fn { name, .. } => (name, DefKind::Fn),
+            Statement::Struct { name, .. } => (name, DefKind::Struct),
+            Statement::Enum { name, .. } => (name, DefKind::Enum),
+            Statement::Union { name, .. } => (name, DefKind::Union),
+            Statement::Trait { name, .. } => (name, DefKind::Trait),
+            Statement::TypeAlias { name, .. } => (name, DefKind::TypeAlias),
+            Statement::AssociatedType { name, .. } => (name, DefKind::AssociatedType),
+            Statement::Static { name, .. } => (name, DefKind::Static),
+            _ => return None,
+        };
+        let def = self.define(name, kind, name.span, Some(item.id));
+        self.res.declarations.insert(item.id, def);
+        if let Statement::Fn { signature, .. } = statement {
+            self.signatures.insert(def, signature.clone());
+        }
+        if let Statement::Enum { variants, .. } = statement {
+            let mut names = HashMap::new();
+            let mut defs = vec![];
+            for (index, variant) in variants.iter().enumerate() {
+                let kind = DefKind::Variant { parent: def, index };
+                let span = variant.name.span;
+                let variant = self.define(&variant.name, kind, span, Some(item.id));
+                self.insert(&mut names, variant);
+                defs.push(variant);
+            }
+            self.variants.insert(def, defs);
+        }
+        Some(def)
+    }
+
+    /// Defines the items of module `id` and of the modules declared in it.
+    fn collect_module(&mut self, id: ModuleId, items: &[Expr]) {
+        let mut names = std::mem::take(&mut self.module_items[id.0 as usize]);
+        for item in items {
+            match &item.kind {
+                ExprKind::Statement(Statement::Module { name, children, .. }) => {
+                    if let Some(child) = self.child_module(id, item, name) {
+                        let def = self.res.modules[child.0 as usize];
+                        self.res.defs[def.0 as usize].span = name.span;
+                        self.res.defs[def.0 as usize].node = Some(item.id);
+                        self.collect_module(child, children);
+                    }
+                }
+                ExprKind::Statement(Statement::Extern { items, .. }) => {
+                    for item in items {
+                        if let Some(def) = self.declare_item(item) {
+                            self.insert(&mut names, def);
+                        }
+                    }
+                }
+                _ => {
+                    if let Some(def) = self.declare_item(item) {
+                        self.insert(&mut names, def);
+                        let name = &self.def(def).name;
+                        if let Some(&child) = self.program.module(id).children.get(name) {
+                            self.duplicate(self.res.modules[child.0 as usize], def);
+                        }
+                    }
+                }
+            }
+        }
+        self.module_items[id.0 as usize] = names;
+    }
+
+    /// The module a `module` declaration in module `id` declares, unless the loader left
+    /// it out as a duplicate.
+    fn child_module(&self, id: ModuleId, item: &Expr, name: &Expr) -> Option<ModuleId> {
+        let ExprKind::Identifier(name) = &name.kind else {
+            return None;
+        };
+        let child = *self.program.module(id).children.get(name)?;
+        let def = self.def(self.res.modules[child.0 as usize]);
+        def.node.is_none_or(|node| node == item.id).then_some(child)
+    }
+
+    fn module_body(&mut self, id: ModuleId, items: &[Expr]) {
+        let outer = std::mem::replace(&mut self.module, id);
+        let scopes = std::mem::take(&mut self.scopes);
+        for item in items {
+            self.visit_expr(item);
+        }
+        let module = self.program.module(id);
+        for (import, used) in module.imports.iter().zip(&self.imports_used[id.0 as usize]) {
+            if !used {
+                let message = msg!("analysis-unused-import", name = import.name);
+                let warning = self.error(UNUSED_IMPORT, &message, import.span).warning();
+                self.res.warnings.push(warning);
+            }
+        }
+        self.scopes = scopes;
+        self.module = outer;
+    }
+
+    fn push_scope(&mut self, boundary: bool) {
+        self.scopes.push(Scope {
+            boundary,
+            ..Scope::default()
+        });
+    }
+
+    /// Leaves the innermost scope, warning about its unused variables.
+    fn pop_scope(&mut self) {
+        let scope = self.scopes.pop().unwrap();
+        for def in scope.bindings {
+            let Definition { name, span, .. } = self.def(def);
+            // A method may ignore `self`, which can't be renamed without making it a
+            // function of its own instead.
+            if self.used[def.0 as usize] || name.starts_with('_') || name.as_ref() == "self" {
+                continue;
+            }
+            let note = if self.labelled.contains(&def) {
+                msg!("analysis-note-keep-label", name = name)
+            } else {
+                msg!("analysis-note-prefix-underscore", name = name)
+            };
+            let warning = self
+                .error(
+                    UNUSED_BINDING,
+                    &msg!("analysis-unused-binding", name = name),
+                    *span,
+                )
+                .warning()
+                .with_note(&note);
+            self.res.warnings.push(warning);
+        }
+    }
+
+    /// Brings `def` into the innermost scope, hiding what had its name there.
+    fn bind(&mut self, def: DefId) {
+        let name = self.def(def).name.clone();
+        let is_binding = self.def(def).kind.is_binding();
+        let scope = self.scopes.last_mut().unwrap();
+        scope.names.insert(name, vec![def]);
+        if is_binding {
+            scope.bindings.push(def);
+        }
+    }
+
+    /// Adds items to the innermost scope, reporting duplicates.
+    fn hoist(&mut self, defs: Vec<DefId>) {
+        let mut names = std::mem::take(&mut self.scopes.last_mut().unwrap().names);
+        for def in defs {
+            self.insert(&mut names, def);
+        }
+        self.scopes.last_mut().unwrap().names = names;
+    }
+
+    /// What `name` refers to in the current scope, without marking it used.
+    fn lookup(&mut self, name: &str) -> Option<Vec<DefId>> {
+        let mut crossed_boundary = false;
+        for scope in self.scopes.iter().rev() {
+            if let Some(defs) = scope.names.get(name) {
+                let kind = self.def(defs[0]).kind;
+                let local =
+                    kind.is_binding() || matches!(kind, DefKind::GenericParam | DefKind::SelfType);
+                if !crossed_boundary || !local {
+                    return Some(defs.clone());
+                }
+            }
+            crossed_boundary |= scope.boundary;
+        }
+        self.lookup_in_module(self.module, name)
+            .or_else(|| self.builtins.get(name).map(|&def| vec![def]))
+    }
+
+    /// What `name` refers to among the items, modules and imports of module `id`.
+    fn lookup_in_module(&mut self, id: ModuleId, name: &str) -> Option<Vec<DefId>> {
+        if let Some(defs) = self.module_items[id.0 as usize].get(name) {
+            return Some(defs.clone());
+        }
+        let module = self.program.module(id);
+        if let Some(child) = module.children.get(name) {
+            return Some(vec![self.res.modules[child.0 as usize]]);
+        }
+        let index = module
+            .imports
+            .iter()
+            .position(|import| &*import.name == name)?;
+        self.imports_used[id.0 as usize][index] = true;
+        match &module.imports[index].target {
+            ImportTarget::Module(target) => Some(vec![self.res.modules[target.0 as usize]]),
+            ImportTarget::Item(target, name) => {
+                self.module_items[target.0 as usize].get(name).cloned()
+            }
+        }
+    }
+
+    /// The enums that can be named here, to find variants named without them.
+    fn enums_in_scope(&mut self) -> Vec<DefId> {
+        let mut names: Vec<Box<str>> = self
+            .scopes
+            .iter()
+            .flat_map(|scope| scope.names.keys().cloned())
+            .collect();
+        names.extend(self.module_items[self.module.0 as usize].keys().cloned());
+        let module = self.program.module(self.module);
+        names.extend(module.imports.iter().map(|import| import.name.clone()));
+        let mut enums = vec![];
+        for name in names {
+            let Some(defs) = self.lookup(&name) else {
+                continue;
+            };
+            if self.def(defs[0]).kind == DefKind::Enum && !enums.contains(&defs[0]) {
+                enums.push(defs[0]);
+            }
+        }
+        enums
+    }
+
+    /// The names that can be seen here, for suggestions.
+    fn names_in_scope(&self) -> Vec<Box<str>> {
+        let mut names = vec![];
+        let mut crossed_boundary = false;
+        for scope in self.scopes.iter().rev() {
+            for (name, defs) in &scope.names {
+                let kind = self.def(defs[0]).kind;
+                let local =
+                    kind.is_binding() || matches!(kind, DefKind::GenericParam | DefKind::SelfType);
+                if !crossed_boundary || !local {
+                    names.push(name.clone());
+                }
+            }
+            crossed_boundary |= scope.boundary;
+        }
+        names.extend(self.names_in_module(self.module));
+        names.extend(self.builtins.keys().map(|&name| name.into()));
+        names
+    }
+
+    fn names_in_module(&self, id: ModuleId) -> Vec<Box<str>> {
+        let module = self.program.module(id);
+        let mut names: Vec<Box<str>> = self.module_items[id.0 as usize].keys().cloned().collect();
+        names.extend(module.children.keys().cloned());
+        names.extend(module.imports.iter().map(|import| import.name.clone()));
+        names
+    }
+
+    /// Records that the name at `node` refers to `defs`.
+    fn use_defs(&mut self, node: NodeId, defs: &[DefId]) {
+        for def in defs {
+            self.used[def.0 as usize] = true;
+        }
+        self.res.uses.insert(node, defs.to_vec());
+    }
+
+    /// Resolves the plain name `name` at `expr`, reporting it if it can't be found.
+    fn resolve_name(&mut self, node: NodeId, span: Span, name: &str, ty: bool) -> Option<DefId> {
+        if let Some(defs) = self.lookup(name) {
+            self.use_defs(node, &defs);
+            return Some(defs[0]);
+        }
+        let mut variants = vec![];
+        for parent in self.enums_in_scope() {
+            variants.extend(
+                self.variants[&parent]
+                    .iter()
+                    .copied()
+                    .filter(|&variant| &*self.def(variant).name == name),
+            );
+        }
+        match variants[..] {
+            [variant] => {
+                self.use_defs(node, &[variant]);
+                return Some(variant);
+            }
+            [] => {}
+            _ => {
+                let mut error = self.error(
+                    AMBIGUOUS_NAME,
+                    &msg!("analysis-ambiguous-name", name = name),
+                    span,
+                );
+                for variant in variants {
+                    let DefKind::Variant { parent, .. } = self.def(variant).kind else {
+                        continue;
+                    };
+                    let path = format!("{}.{name}", self.def(parent).name);
+                    error = error.with_note(&msg!("analysis-note-candidate", path = path));
+                }
+                self.res.errors.push(error);
+                return None;
+            }
+        }
+        let key = match ty {
+            true => "analysis-unresolved-type",
+            false => "analysis-unresolved-name",
+        };
+        let names = self.names_in_scope();
+        let error = self.error(UNRESOLVED_NAME, &msg!(key, name = name), span);
+        self.res.errors.push(suggest(error, name, &names));
+        None
+    }
+
+    /// Resolves a name or a dotted path through modules and enums, as far as it names
+    /// something other than a field or method.
+    fn resolve_path(&mut self, expr: &Expr, ty: bool) -> Option<DefId> {
+        match &expr.kind {
+            ExprKind::Identifier(name) => self.resolve_name(expr.id, expr.span, name, ty),
+            ExprKind::Binary {
+                op: BinaryOperator::Dot,
+                left,
+                right,
+            } => {
+                let base = self.resolve_path(left, false)?;
+                let ExprKind::Identifier(name) = &right.kind else {
+                    return None;
+                };
+                match self.def(base).kind {
+                    DefKind::Module(id) => self.resolve_in_module(id, right, name),
+                    DefKind::Enum => {
+                        let variant = self.variants[&base]
+                            .iter()
+                            .copied()
+                            .find(|&variant| self.def(variant).name == *name);
+                        if let Some(variant) = variant {
+                            self.use_defs(right.id, &[variant]);
+                            return Some(variant);
+                        }
+                        let names: Vec<_> = self.variants[&base]
+                            .iter()
+                            .map(|&variant| self.def(variant).name.clone())
+                            .collect();
+                        let parent = &self.def(base).name;
+                        let message =
+                            msg!("analysis-unresolved-variant", name = name, parent = parent);
+                        let error = self.error(UNRESOLVED_NAME, &message, right.span);
+                        self.res.errors.push(suggest(error, name, &names));
+                        None
+                    }
+                    _ => None,
+                }
+            }
+            ExprKind::Generic { path, args } => {
+                let def = self.resolve_path(path, ty);
+                for arg in args {
+                    self.visit_type(arg);
+                }
+                def
+            }
+            _ => {
+                self.visit_expr(expr);
+                None
+            }
+        }
+    }
+
+    fn resolve_in_module(&mut self, id: ModuleId, right: &Expr, name: &str) -> Option<DefId> {
+        if let Some(defs) = self.lookup_in_module(id, name) {
+            self.use_defs(right.id, &defs);
+            return Some(defs[0]);
+        }
+        let module = self.program.module_path(id);
+        let message = msg!(
+            "analysis-unresolved-in-module",
+            name = name,
+            module = module
+        );
+        let error = self.error(UNRESOLVED_NAME, &message, right.span);
+        let names = self.names_in_module(id);
+        self.res.errors.push(suggest(error, name, &names));
+        None
+    }
+
+    /// A `for` loop, whose binding is only in scope in its body.
+    fn for_loop(&mut self, expr: &Expr, binding: &Ident, iterable: &Expr, body: &Expr) {
+        self.visit_expr(iterable);
+        self.push_scope(false);
+        let kind = DefKind::Local { mutable: false };
+        let def = self.define(binding, kind, binding.span, Some(expr.id));
+        self.res.declarations.insert(expr.id, def);
+        self.bind(def);
+        self.visit_expr(body);
+        self.pop_scope();
+    }
+
+    fn closure(
+        &mut self,
+        expr: &Expr,
+        params: &[ClosureParam],
+        returns: Option<&TypeExpr>,
+        body: &Expr,
+    ) {
+        self.push_scope(false);
+        let mut names = HashSet::new();
+        let mut defs = vec![];
+        for param in params {
+            walk::closure_param(self, param);
+            let span = param.name.span;
+            let def = self.define(&param.name, DefKind::Param, span, Some(expr.id));
+            if !names.insert(param.name.clone()) {
+                self.duplicate(defs[0], def);
+            }
+            defs.push(def);
+        }
+        if let Some(returns) = returns {
+            self.visit_type(returns);
+        }
+        for &def in &defs {
+            self.bind(def);
+        }
+        self.res.params.insert(expr.id, defs);
+        self.visit_expr(body);
+        self.pop_scope();
+    }
+
+    /// The statements of a block, in a scope of its own which the caller opens.
+    fn block(&mut self, statements: &[Expr]) {
+        let items = statements
+            .iter()
+            .filter_map(|statement| self.declare_item(statement))
+            .collect();
+        self.hoist(items);
+        for statement in statements {
+            self.visit_expr(statement);
+        }
+    }
+
+    fn statement(&mut self, expr: &Expr, statement: &Statement) {
+        let boundary = !std::mem::take(&mut self.associated);
+        match statement {
+            Statement::Module { name, children, .. } => {
+                if let Some(child) = self.child_module(self.module, expr, name) {
+                    self.module_body(child, children);
+                }
+            }
+            // The loader already resolved the imports.
+            Statement::Use { .. } => {}
+            Statement::Fn {
+                generics,
+                signature,
+                body,
+                ..
+            } => self.function(expr, generics, signature, body.as_deref(), boundary),
+            Statement::Struct {
+                generics, fields, ..
+            } => {
+                self.generics(expr, generics, boundary);
+                self.fields(fields_of(fields));
+                self.pop_scope();
+            }
+            Statement::Union {
+                generics, fields, ..
+            } => {
+                self.generics(expr, generics, boundary);
+                self.fields(fields);
+                self.pop_scope();
+            }
+            Statement::Enum {
+                generics, variants, ..
+            } => {
+                self.generics(expr, generics, boundary);
+                for variant in variants {
+                    self.fields(fields_of(&variant.fields));
+                    if let Some(discriminant) = &variant.discriminant {
+                        self.visit_expr(discriminant);
+                    }
+                }
+                self.pop_scope();
+            }
+            Statement::Trait {
+                generics,
+                supertraits,
+                items,
+                ..
+            } => {
+                self.generics(expr, generics, boundary);
+                self.associated_items(items);
+                for supertrait in supertraits {
+                    self.visit_type(supertrait);
+                }
+                for item in items {
+                    self.associated = true;
+                    self.visit_expr(item);
+                }
+                self.pop_scope();
+            }
+            Statement::Impl {
+                generics,
+                trait_,
+                target,
+                items,
+            } => {
+                self.generics(expr, generics, boundary);
+                self.associated_items(items);
+                if let Some(trait_) = trait_ {
+                    self.visit_type(trait_);
+                }
+                self.visit_type(target);
+                for item in items {
+                    self.associated = true;
+                    self.visit_expr(item);
+                }
+                self.pop_scope();
+            }
+            Statement::TypeAlias { generics, ty, .. } => {
+                self.generics(expr, generics, boundary);
+                self.visit_type(ty);
+                self.pop_scope();
+            }
+            Statement::AssociatedType { .. }
+            | Statement::Extern { .. }
+            | Statement::Static { .. }
+            | Statement::Return(_)
+            | Statement::Break { .. }
+            | Statement::Continue { .. }
+            | Statement::Fallthrough => walk::statement(self, statement),
+            Statement::Let {
+                mutable,
+                name,
+                ty,
+                value,
+            } => {
+                if let Some(ty) = ty {
+                    self.visit_type(ty);
+                }
+                if let Some(value) = value {
+                    self.visit_expr(value);
+                }
+                let kind = DefKind::Local { mutable: *mutable };
+                let def = self.define(name, kind, name.span, Some(expr.id));
+                self.res.declarations.insert(expr.id, def);
+                if !self.scopes.is_empty() {
+                    self.bind(def);
+                }
+            }
+        }
+    }
+
+    /// Opens the scope of an item with generic parameters, which the caller closes.
+    fn generics(&mut self, item: &Expr, generics: &Generics, boundary: bool) {
+        self.push_scope(boundary);
+        let mut defs = vec![];
+        let mut names = HashMap::new();
+        for param in &generics.params {
+            let span = param.name.span;
+            let def = self.define(&param.name, DefKind::GenericParam, span, Some(item.id));
+            self.insert(&mut names, def);
+            defs.push(def);
+        }
+        self.scopes.last_mut().unwrap().names = names;
+        if matches!(
+            item.kind,
+            ExprKind::Statement(Statement::Trait { .. } | Statement::Impl { .. })
+        ) {
+            let def = self.define("Self", DefKind::SelfType, item.span, Some(item.id));
+            self.bind(def);
+        }
+        walk::generics(self, generics);
+        self.res.generics.insert(item.id, defs);
+    }
+
+    /// Brings the associated types and statics of a trait or impl into its scope. Its
+    /// functions are defined too, but are only reached through a type or value.
+    fn associated_items(&mut self, items: &[Expr]) {
+        for item in items {
+            if let ExprKind::Statement(Statement::Fn { .. }) = item.kind {
+                self.declare_item(item);
+            }
+        }
+        let defs = items
+            .iter()
+            .filter(|item| {
+                matches!(
+                    item.kind,
+                    ExprKind::Statement(
+                        Statement::AssociatedType { .. }
+                            | Statement::TypeAlias { .. }
+                            | Statement::Static { .. }
+                    )
+                )
+            })
+            .filter_map(|item| self.declare_item(item))
+            .collect();
+        self.hoist(defs);
+    }
+
+    fn function(
+        &mut self,
+        item: &Expr,
+        generics: &Generics,
+        signature: &Signature,
+        body: Option<&Expr>,
+        boundary: bool,
+    ) {
+        self.generics(item, generics, boundary);
+        let mut names = HashSet::new();
+        let mut defs: Vec<DefId> = vec![];
+        for (arg, ty) in &signature.named_args {
+            self.visit_type(ty);
+            let name = arg.binding();
+            let def = self.define(name, DefKind::Param, name.span, Some(item.id));
+            if let ArgName::Single(_) = arg {
+                self.labelled.insert(def);
+            }
+            if !names.insert(name) {
+                let first = defs
+                    .iter()
+                    .copied()
+                    .find(|&d| self.def(d).name == name.text);
+                self.duplicate(first.unwrap_or(def), def);
+            }
+            // Parameters of functions without a body can't be used.
+            if body.is_none() {
+                self.used[def.0 as usize] = true;
+            }
+            defs.push(def);
+        }
+        self.visit_type(&signature.returns);
+        self.push_scope(false);
+        for &def in &defs {
+            self.bind(def);
+        }
+        self.res.params.insert(item.id, defs);
+        if let Some(body) = body {
+            self.visit_expr(body);
+        }
+        self.pop_scope();
+        self.pop_scope();
+    }
+
+    fn fields(&mut self, fields: &[Field]) {
+        let mut names: HashMap<&str, Span> = HashMap::new();
+        for field in fields {
+            self.visit_type(&field.ty);
+            if let Some(&first) = names.get(&*field.name) {
+                let message = msg!("analysis-duplicate-field", name = field.name);
+                let error = self.error(DUPLICATE_DEFINITION, &message, field.name.span);
+                let line = self.map.get(first.file).location(first.start).line;
+                let note = msg!("analysis-note-first-definition", line = line);
+                self.res.errors.push(error.with_note(&note));
+            } else {
+                names.insert(&field.name, field.name.span);
+            }
+        }
+    }
+
+    fn bind_pattern(&mut self, pattern: &Pattern, name: &Ident, kind: DefKind) {
+        if let Some(&first) = self.bound.get(&name.text) {
+            if self.rebinding {
+                self.res.declarations.insert(pattern.id, first);
+            } else {
+                let def = self.define(name, kind, name.span, Some(pattern.id));
+                self.duplicate(first, def);
+            }
+            return;
+        }
+        let def = self.define(name, kind, name.span, Some(pattern.id));
+        self.res.declarations.insert(pattern.id, def);
+        self.bound.insert(name.text.clone(), def);
+        self.bind(def);
+    }
+}
+
+impl Visitor<'_> for Resolver<'_> {
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Statement(statement) => self.statement(expr, statement),
+            ExprKind::Binary {
+                op: BinaryOperator::Dot,
+                ..
+            }
+            | ExprKind::Generic { .. } => {
+                self.resolve_path(expr, false);
+            }
+            ExprKind::Identifier(name) => {
+                self.resolve_name(expr.id, expr.span, name, false);
+            }
+            ExprKind::StructLiteral { path, fields } => {
+                self.resolve_path(path, true);
+                for (_, value) in fields {
+                    self.visit_expr(value);
+                }
+            }
+            ExprKind::Block(statements) => {
+                self.push_scope(false);
+                self.block(statements);
+                self.pop_scope();
+            }
+            ExprKind::For {
+                binding,
+                iterable,
+                body,
+                ..
+            } => self.for_loop(expr, binding, iterable, body),
+            ExprKind::Match { scrutinee, arms } => {
+                self.visit_expr(scrutinee);
+                for arm in arms {
+                    self.push_scope(false);
+                    self.bound.clear();
+                    walk::match_arm(self, arm);
+                    self.pop_scope();
+                }
+            }
+            ExprKind::Closure {
+                params,
+                returns,
+                body,
+            } => self.closure(expr, params, returns.as_deref(), body),
+            _ => walk::expr(self, expr),
+        }
+    }
+
+    fn visit_type(&mut self, ty: &TypeExpr) {
+        match &ty.kind {
+            TypeKind::Path { path, args } => {
+                self.resolve_path(path, true);
+                for arg in args {
+                    self.visit_type(arg);
+                }
+            }
+            _ => walk::ty(self, ty),
+        }
+    }
+
+    /// Resolves the paths in `pattern` and binds its names, which `bound` collects so
+    /// that a name can't be bound twice.
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        match &pattern.kind {
+            PatternKind::Binding { mutable, name } => {
+                let kind = DefKind::Local { mutable: *mutable };
+                self.bind_pattern(pattern, name, kind);
+            }
+            PatternKind::Is { binding, ty } => {
+                self.visit_type(ty);
+                if let Some(name) = binding {
+                    let kind = DefKind::Local { mutable: false };
+                    self.bind_pattern(pattern, name, kind);
+                }
+            }
+            PatternKind::Path(path) => {
+                self.resolve_path(path, false);
+            }
+            PatternKind::Variant { path, args } => {
+                self.resolve_path(path, false);
+                for arg in args {
+                    self.visit_pattern(arg);
+                }
+            }
+            PatternKind::Struct { path, fields, .. } => {
+                self.resolve_path(path, true);
+                for (_, field) in fields {
+                    self.visit_pattern(field);
+                }
+            }
+            PatternKind::Or(alternatives) => {
+                let outer = self.rebinding;
+                for (i, alternative) in alternatives.iter().enumerate() {
+                    self.rebinding = outer || i > 0;
+                    self.visit_pattern(alternative);
+                }
+                self.rebinding = outer;
+            }
+            _ => walk::pattern(self, pattern),
+        }
+    }
+}
+
+fn fields_of(fields: &Fields) -> &[Field] {
+    match fields {
+        Fields::Named(fields) | Fields::Tuple(fields) => fields,
+        Fields::Unit => &[],
+    }
+}
@@ -0,0 +1,1012 @@
+use coil_error::{Error, ErrorCode, SourceMap};
+use coil_parser::{load, walk, Expr, ExprKind, Program, Statement, Visitor};
+
+use crate::{
+    check, edit_distance, resolve, CaptureMode, DefKind, Primitive, Resolution, Ty, Typing,
+    AMBIGUOUS_NAME, BUILTIN_IMPL, CANNOT_INFER, CONFLICTING_IMPLS, DUPLICATE_DEFINITION,
+    FOREIGN_IMPL, IMMUTABLE_BINDING, INSTANCE_LIMIT, INVALID_OPERAND, LITERAL_OUT_OF_RANGE,
+    MISORDERED_LABEL, MISSING_FIELDS, MISSING_LABEL, MISSING_TRAIT_ITEMS,
+    MUTATION_THROUGH_REFERENCE, NOT_A_TRAIT_ITEM, ORPHAN_IMPL, RETURN_OUTSIDE_FN,
+    TRAIT_ITEM_MISMATCH, TYPE_MISMATCH, UNEXPECTED_LABEL, UNINITIALISED_USE, UNKNOWN_FIELD,
+    UNKNOWN_METHOD, UNRESOLVED_NAME, UNSATISFIED_BOUND, UNUSED_BINDING, UNUSED_IMPORT,
+    WRONG_ARGUMENT_COUNT, WRONG_LABEL,
+};
+
+/// Loads `files`, the first of which is the entry file, and resolves the program.
+fn quick_resolve(test: &str, files: &[(&str, &str)]) -> (Program, SourceMap, Resolution) {
+    let dir = std::env::temp_dir().join(format!("coil-analysis-{test}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    for (path, source) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, source).unwrap();
+    }
+    let mut map = SourceMap::new();
+    let program = load(&mut map, &dir.join(files[0].0)).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(program.errors.is_empty(), "{:?}", program.errors);
+    let resolution = resolve(&program, &map);
+    (program, map, resolution)
+}
+
+/// Resolves and checks the single file `source`, which must resolve without errors.
+fn quick_check(test: &str, source: &str) -> (SourceMap, Resolution, Typing) {
+    let (program, map, resolution) = quick_resolve(test, &[("main.coil", source)]);
+    assert!(resolution.errors.is_empty(), "{:?}", resolution.errors);
+    let typing = check(&program, &map, &resolution);
+    (map, resolution, typing)
+}
+
+/// The type of the variable `name`, as it would be written.
+fn binding(resolution: &Resolution, typing: &Typing, name: &str) -> String {
+    let (_, ty) = typing
+        .bindings
+        .iter()
+        .find(|(&def, _)| &*resolution.def(def).name == name)
+        .unwrap_or_else(|| panic!("no variable '{name}'"));
+    ty.display(resolution).to_string()
+}
+
+fn codes(errors: &[Error]) -> Vec<ErrorCode> {
+    errors.iter().map(|error| error.code).collect()
+}
+
+/// The text of every identifier in `expr`, with what it resolved to.
+fn uses(map: &SourceMap, resolution: &Resolution, expr: &Expr) -> Vec<(String, Vec<String>)> {
+    struct Uses<'a>(&'a SourceMap, &'a Resolution, Vec<(String, Vec<String>)>);
+
+    impl Visitor<'_> for Uses<'_> {
+        fn visit_expr(&mut self, expr: &Expr) {
+            if let ExprKind::Identifier(name) = &expr.kind {
+                let defs = self.1.resolved(expr.id);
+                let defs = defs
+                    .iter()
+                    .map(|&def| {
+                        let def = self.1.def(def);
+                        let line = self.0.get(def.span.file).location(def.span.start).line;
+                        format!("{}@{line}", def.name)
+                    })
+                    .collect();
+                self.2.push((name.to_string(), defs));
+            }
+            walk::expr(self, expr);
+        }
+    }
+
+    let mut visitor = Uses(map, resolution, vec![]);
+    visitor.visit_expr(expr);
+    visitor.2
+}
+
+#[test]
+fn test_edit_distance() {
+    assert_eq!(edit_distance("", ""), 0);
+    assert_eq!(edit_distance("count", "count"), 0);
+    assert_eq!(edit_distance("count", "cuont"), 2);
+    assert_eq!(edit_distance("count", "counts"), 1);
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("ğüş", "güs"), 2);
+}
+
+#[test]
+fn test_resolve_scopes_and_shadowing() {
+    let (program, map, resolution) = quick_resolve(
+        "scopes",
+        &[(
+            "main.coil",
+            "fn main() {\n    let x = 1\n    let y = x + 1\n    let x = y\n    {\n        let x = x\n        helper(x)\n    }\n    helper(x)\n}\nfn helper(_ value: Int) -> Int { value }",
+        )],
+    );
+    assert!(resolution.errors.is_empty(), "{:?}", resolution.errors);
+    assert!(resolution.warnings.is_empty(), "{:?}", resolution.warnings);
+    let found = uses(&map, &resolution, &program.root);
+    let expected = [
+        ("x", "x@2"),
+        ("y", "y@3"),
+        ("x", "x@4"),
+        ("helper", "helper@11"),
+        ("x", "x@6"),
+        ("helper", "helper@11"),
+        ("x", "x@4"),
+        ("value", "value@11"),
+    ];
+    let found: Vec<_> = found
+        .iter()
+        .filter(|(name, _)| name != "main" && name != "Int")
+        .map(|(name, defs)| (name.as_str(), defs.join(",")))
+        .collect();
+    let expected: Vec<_> = expected
+        .iter()
+        .map(|&(name, def)| (name, def.to_string()))
+        .collect();
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn test_resolve_items_and_builtins() {
+    let (program, _, resolution) = quick_resolve(
+        "items",
+        &[(
+            "main.coil",
+            "enum Shape { Circle(F32), Square { side: F32 } }\nfn area(_ shape: Shape) -> F32 {\n    match shape {\n        Circle(radius) => radius * radius,\n        Shape.Square { side } => side * side,\n    }\n}\nstruct Pair<T> { first: T, second: T }\nimpl<T> Pair<T> { fn swap(self: Self) -> Self { Pair { first: self.second, second: self.first } } }",
+        )],
+    );
+    assert!(resolution.errors.is_empty(), "{:?}", resolution.errors);
+    assert!(resolution.warnings.is_empty(), "{:?}", resolution.warnings);
+    let ExprKind::Statement(Statement::Module { children, .. }) = &program.root.kind else {
+        panic!("the root is not a module");
+    };
+    let shape = resolution.declarations[&children[0].id];
+    assert_eq!(resolution.def(shape).kind, DefKind::Enum);
+    let variants: Vec<_> = resolution
+        .variants(shape)
+        .map(|variant| &*resolution.def(variant).name)
+        .collect();
+    assert_eq!(variants, ["Circle", "Square"]);
+    let pair = resolution.declarations[&children[2].id];
+    let generics = &resolution.generics[&children[2].id];
+    assert_eq!(resolution.def(pair).kind, DefKind::Struct);
+    assert_eq!(generics.len(), 1);
+    assert_eq!(resolution.def(generics[0]).kind, DefKind::GenericParam);
+    let f32 = resolution
+        .defs
+        .iter()
+        .filter(|def| def.kind == DefKind::Primitive(Primitive::F32))
+        .count();
+    assert_eq!(f32, 1);
+}
+
+#[test]
+fn test_resolve_modules_and_imports() {
+    let (_, _, resolution) = quick_resolve(
+        "modules",
+        &[
+            (
+                "main.coil",
+                "module util\nmodule shapes { pub struct Square {} }\nimport util.helper\nimport shapes.Square\nfn main() { helper(); util.helper(); let _s = shapes.Square {} }",
+            ),
+            ("util.coil", "pub fn helper() {}\nfn secret() { helper() }"),
+        ],
+    );
+    assert!(resolution.errors.is_empty(), "{:?}", resolution.errors);
+    assert_eq!(codes(&resolution.warnings), [UNUSED_IMPORT]);
+    assert_eq!(&*resolution.warnings[0].message, "unused import 'Square'");
+}
+
+#[test]
+fn test_resolve_unresolved_names() {
+    let (_, _, resolution) = quick_resolve(
+        "unresolved",
+        &[
+            (
+                "main.coil",
+                "module util\nenum Color { Red, Green }\nfn main() {\n    let counter = 0\n    print(countr)\n    let c: Colour = Color.Gren\n    util.helpr()\n    missing\n}",
+            ),
+            ("util.coil", "pub fn helper() {}"),
+        ],
+    );
+    let messages: Vec<_> = resolution
+        .errors
+        .iter()
+        .map(|error| (error.line, &*error.message, error.notes.join("; ")))
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            (5, "cannot find 'print' in this scope", String::new()),
+            (
+                5,
+                "cannot find 'countr' in this scope",
+                "did you mean 'counter'?".into()
+            ),
+            (
+                6,
+                "cannot find type 'Colour' in this scope",
+                "did you mean 'Color'?".into()
+            ),
+            (
+                6,
+                "no variant 'Gren' in enum 'Color'",
+                "did you mean 'Green'?".into()
+            ),
+            (
+                7,
+                "no item 'helpr' in module 'main.util'",
+                "did you mean 'helper'?".into()
+            ),
+            (8, "cannot find 'missing' in this scope", String::new()),
+        ]
+    );
+    assert!(codes(&resolution.errors)
+        .iter()
+        .all(|&code| code == UNRESOLVED_NAME));
+}
+
+#[test]
+fn test_resolve_ambiguous_variant() {
+    let (_, _, resolution) = quick_resolve(
+        "ambiguous",
+        &[(
+            "main.coil",
+            "enum Light { Red, Green }\nenum Team { Red, Blue }\nfn main() { let _a = Green; let _b = Red }",
+        )],
+    );
+    assert_eq!(codes(&resolution.errors), [AMBIGUOUS_NAME]);
+    let error = &resolution.errors[0];
+    assert_eq!(
+        &*error.message,
+        "'Red' could refer to more than one variant"
+    );
+    let mut notes: Vec<_> = error.notes.iter().map(|note| &**note).collect();
+    notes.sort();
+    assert_eq!(notes, ["it could be 'Light.Red'", "it could be 'Team.Red'"]);
+}
+
+#[test]
+fn test_resolve_duplicates() {
+    let (_, _, resolution) = quick_resolve(
+        "duplicates",
+        &[(
+            "main.coil",
+            "struct Point { x: Int, x: Int }\nfn draw(_ p: Point) {}\nfn draw(_ p: Point, _ q: Point) {}\nstruct Point {}\nfn add(_ a: Int, _ a: Int) -> Int { a }\nfn main() { match (1, 2) { (x, x) => x } }\nfn move(from a: Int) {}\nfn move(from b: Float) {}",
+        )],
+    );
+    let messages: Vec<_> = resolution
+        .errors
+        .iter()
+        .map(|error| (error.line, &*error.message, error.notes.join("; ")))
+        .collect();
+    let first = |line| format!("first defined on line {line}");
+    assert_eq!(
+        messages,
+        [
+            (1, "field 'x' is declared more than once", first(1)),
+            (4, "'Point' is defined more than once", first(1)),
+            (5, "'a' is defined more than once", first(5)),
+            (6, "'x' is defined more than once", first(6)),
+            (8, "'move' is defined more than once", first(7)),
+        ]
+    );
+    assert!(codes(&resolution.errors)
+        .iter()
+        .all(|&code| code == DUPLICATE_DEFINITION));
+}
+
+#[test]
+fn test_resolve_unused_bindings() {
+    let (_, _, resolution) = quick_resolve(
+        "unused",
+        &[(
+            "main.coil",
+            "fn main() {\n    let used = 1\n    let unused = used\n    let _ignored = 2\n    for i in 0..3 {}\n    let f = \\x -> 1\n    f(1)\n}\nfn scale(_ v: Int, by factor: Int) -> Int { v }\ntrait Shape { fn area(self: Self) -> Float }\nimpl Shape for Int { fn area(self: Self) -> Float { 1.0 } }\nfn shift(amount: Int) -> Int { 1 }",
+        )],
+    );
+    assert!(resolution.errors.is_empty(), "{:?}", resolution.errors);
+    let messages: Vec<_> = resolution
+        .warnings
+        .iter()
+        .map(|warning| (warning.line, &*warning.message))
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            (3, "unused variable 'unused'"),
+            (5, "unused variable 'i'"),
+            (6, "unused variable 'x'"),
+            (9, "unused variable 'factor'"),
+            (12, "unused variable 'amount'"),
+        ]
+    );
+    assert!(codes(&resolution.warnings)
+        .iter()
+        .all(|&code| code == UNUSED_BINDING));
+    assert_eq!(
+        &*resolution.warnings[0].notes[0],
+        "if this is intentional, name it '_unused'"
+    );
+    let span = resolution.warnings[3].span.unwrap();
+    assert_eq!(span.end - span.start, "factor".len() as u32);
+    // Renaming a parameter that is its own label would change its calls.
+    assert_eq!(
+        &*resolution.warnings[4].notes[0],
+        "if this is intentional, keep the label and name it 'amount _amount'"
+    );
+}
+
+#[test]
+fn test_resolve_binding_spans() {
+    // The names also appear before their declarations: in a comment, as a loop label and
+    // as the name of the struct.
+    let (_, map, resolution) = quick_resolve(
+        "binding-spans",
+        &[(
+            "main.coil",
+            "// i T\nfn main() {\n    i: for i in 0..3 {}\n}\nstruct T<T> {}",
+        )],
+    );
+    assert!(resolution.errors.is_empty(), "{:?}", resolution.errors);
+    let location = |span| {
+        let location = map.location(span);
+        (location.line, location.column)
+    };
+    let unused: Vec<_> = resolution
+        .warnings
+        .iter()
+        .map(|warning| location(warning.span.unwrap()))
+        .collect();
+    assert_eq!(unused, [(3, 12)]);
+    let param = resolution
+        .defs
+        .iter()
+        .find(|def| def.kind == DefKind::GenericParam)
+        .unwrap();
+    assert_eq!(location(param.span), (5, 10));
+}
+
+#[test]
+fn test_resolve_nested_functions() {
+    let (_, _, resolution) = quick_resolve(
+        "nested",
+        &[(
+            "main.coil",
+            "fn outer<T>(_ x: T) -> T {\n    fn inner() -> Int { x }\n    fn generic(_ y: T) {}\n    let _ = inner()\n    x\n}",
+        )],
+    );
+    let messages: Vec<_> = resolution
+        .errors
+        .iter()
+        .map(|error| (error.line, &*error.message))
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            (2, "cannot find 'x' in this scope"),
+            (3, "cannot find type 'T' in this scope"),
+        ]
+    );
+}
+
+#[test]
+fn test_check_literals_and_inference() {
+    let (_, resolution, typing) = quick_check(
+        "inference",
+        "fn main() {\n    let a = 5\n    let b: U8 = 5\n    let c = 0.5\n    let d = b + 1\n    let e = [1, 2, 3]\n    let f = (a, \"text\", true)\n    let g = \\x: I32 -> x * 2\n    let h = g(4)\n    let mut i = 0\n    i += d\n    let j = match 'x' { 'a'..'z' => 'y', _ => '\\n' }\n}",
+    );
+    assert!(typing.errors.is_empty(), "{:?}", typing.errors);
+    let types = [
+        ("a", "Int"),
+        ("b", "U8"),
+        ("c", "Float"),
+        ("d", "U8"),
+        ("e", "[Int; 3]"),
+        ("f", "(Int, Str, Bool)"),
+        ("g", "fn(I32) -> I32"),
+        ("h", "I32"),
+        ("i", "U8"),
+        ("j", "Char"),
+    ];
+    for (name, ty) in types {
+        assert_eq!(binding(&resolution, &typing, name), ty, "type of '{name}'");
+    }
+}
+
+#[test]
+fn test_check_mismatches() {
+    let (map, _, typing) = quick_check(
+        "mismatches",
+        "fn half(_ x: Int) -> Int { x / 2 }\nfn main() {\n    let a: Bool = half(3)\n    let b = half(\"three\")\n    let c: U8 = 300\n    let d: I8 = -128\n    let _e = if a { b } else { true }\n}",
+    );
+    let messages: Vec<_> = typing
+        .errors
+        .iter()
+        .map(|error| (error.code, error.line, &*error.message))
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            (
+                TYPE_MISMATCH,
+                3,
+                "mismatched types: expected 'Bool', found 'Int'"
+            ),
+            (
+                TYPE_MISMATCH,
+                4,
+                "mismatched types: expected 'Int', found 'Str'"
+            ),
+            (LITERAL_OUT_OF_RANGE, 5, "literal out of range for 'U8'"),
+            (
+                TYPE_MISMATCH,
+                7,
+                "mismatched types: expected 'Int', found 'Bool'"
+            ),
+        ]
+    );
+    assert_eq!(
+        &*typing.errors[2].notes[0],
+        "'U8' holds values from 0 to 255"
+    );
+    let span = typing.errors[1].span.unwrap();
+    let source = &map.get(span.file).source;
+    assert_eq!(&source[span.start as usize..span.end as usize], "\"three\"");
+}
+
+#[test]
+fn test_check_literal_suffixes() {
+    let (_, resolution, typing) = quick_check(
+        "suffixes",
+        "fn main() {\n    let a = 255u8\n    let b = -128i8\n    let c = 1f32\n    let d = [1u16, 2]\n    let _e = 256u8\n    let _f: Int = 1u8\n}",
+    );
+    let types = [("a", "U8"), ("b", "I8"), ("c", "F32"), ("d", "[U16; 2]")];
+    for (name, ty) in types {
+        assert_eq!(binding(&resolution, &typing, name), ty, "type of '{name}'");
+    }
+    let messages: Vec<_> = typing
+        .errors
+        .iter()
+        .map(|error| (error.code, error.line, &*error.message))
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            (LITERAL_OUT_OF_RANGE, 6, "literal out of range for 'U8'"),
+            (
+                TYPE_MISMATCH,
+                7,
+                "mismatched types: expected 'Int', found 'U8'"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_check_return_outside_fn() {
+    let (_, _, typing) = quick_check(
+        "return",
+        "fn half(_ x: Int) -> Int? { x / 2 }\nstatic A: Int = { return 1 }\nstatic B: Int = half(4)?\nstatic C: fn(Int) -> Int = \\x: Int -> { return x }",
+    );
+    let messages: Vec<_> = typing
+        .errors
+        .iter()
+        .map(|error| (error.code, error.line, &*error.message))
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            (
+                RETURN_OUTSIDE_FN,
+                2,
+                "'return' is only allowed in the body of a function or closure"
+            ),
+            (
+                INVALID_OPERAND,
+                3,
+                "'?' can only be used in a function returning an optional or a result"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_check_structs_and_methods() {
+    let (_, resolution, typing) = quick_check(
+        "structs",
+        "struct Point { x: F32, y: F32 }\nimpl Point {\n    fn new(x: F32, y: F32) -> Self { Point { x, y } }\n    fn length(self: Self) -> F32 { self.x * self.x + self.y * self.y }\n}\nenum Shape { Circle(F32), Square { side: F32 } }\nfn main() {\n    let p = Point.new(x: 1.0, y: 2.0)\n    let l = p.length()\n    let q = Point { x: 1.0 }\n    let z = p.z\n    let s = Shape.Circle(l)\n    let area = match s {\n        Shape.Circle(r) => r * r,\n        Shape.Square { side } => side * side,\n    }\n    p.scale(2.0)\n}",
+    );
+    assert_eq!(binding(&resolution, &typing, "p"), "Point");
+    assert_eq!(binding(&resolution, &typing, "l"), "F32");
+    assert_eq!(binding(&resolution, &typing, "s"), "Shape");
+    assert_eq!(binding(&resolution, &typing, "r"), "F32");
+    assert_eq!(binding(&resolution, &typing, "area"), "F32");
+    let messages: Vec<_> = typing
+        .errors
+        .iter()
+        .map(|error| (error.code, error.line, &*error.message))
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            (MISSING_FIELDS, 10, "missing fields 'y' in 'Point'"),
+            (UNKNOWN_FIELD, 11, "no field 'z' on type 'Point'"),
+            (UNKNOWN_METHOD, 17, "no method 'scale' on type 'Point'"),
+        ]
+    );
+}
+
+#[test]
+fn test_check_call_labels() {
+    let (_, _, typing) = quick_check(
+        "labels",
+        "struct Point { x: Int }\nimpl Point {\n    fn go(self: &Self, to t: Int) {}\n}\nfn move(from src: Int, to dst: Int, _ speed: Int) {}\nfn move(by amount: Int) {}\nfn go(from a: Int, to b: Int) {}\nfn main() {\n    move(from: 1, to: 2, 3)\n    move(by: 2)\n    let go = \\x: Int -> x\n    let _ = go(1)\n    let p = Point { x: 1 }\n    p.go(to: p.x)\n    p.go(from: 1)\n    p.go(1)\n    move(from: 1, to: 2)\n    move(1, to: 2, 3)\n    move(from: 1, to: 2, speed: 3)\n    move(to: 2, from: 1, 3)\n    move(form: 1, to: 2, 3)\n}",
+    );
+    let messages: Vec<_> = typing
+        .errors
+        .iter()
+        .map(|error| (error.code, error.line, &*error.message, &*error.notes[0]))
+        .collect();
+    let go = "'go' is declared as 'go(to:)'";
+    let declared = "'move' is declared as 'move(from:to:_:)'";
+    assert_eq!(
+        messages,
+        [
+            (
+                WRONG_LABEL,
+                15,
+                "expected argument label 'to:' but found 'from:'",
+                go
+            ),
+            (
+                MISSING_LABEL,
+                16,
+                "missing argument label 'to:' in call to 'go'",
+                go
+            ),
+            (
+                WRONG_ARGUMENT_COUNT,
+                17,
+                "wrong number of arguments: expected 3, found 2",
+                declared
+            ),
+            (
+                MISSING_LABEL,
+                18,
+                "missing argument label 'from:' in call to 'move'",
+                declared
+            ),
+            (
+                UNEXPECTED_LABEL,
+                19,
+                "argument 3 of 'move' takes no label but found 'speed:'",
+                declared
+            ),
+            (
+                MISORDERED_LABEL,
+                20,
+                "argument label 'to:' is out of order in call to 'move'",
+                declared
+            ),
+            (
+                WRONG_LABEL,
+                21,
+                "expected argument label 'from:' but found 'form:'",
+                declared
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_check_generics() {
+    let (_, resolution, typing) = quick_check(
+        "generics",
+        "struct Pair<T> { first: T, second: T }\nfn first<T>(_ p: Pair<T>) -> T { p.first }\nfn main() {\n    let pair = Pair { first: 1, second: 2 }\n    let one = first(pair)\n    let words = Pair { first: \"a\", second: 3 }\n    let _ = first.<Bool>(Pair { first: true, second: false })\n}",
+    );
+    assert_eq!(binding(&resolution, &typing, "pair"), "Pair<Int>");
+    assert_eq!(binding(&resolution, &typing, "one"), "Int");
+    assert_eq!(codes(&typing.errors), [TYPE_MISMATCH]);
+    assert_eq!(typing.errors[0].line, 6);
+    let call = typing.calls.values().find(|(_, args)| {
+        args.values()
+            .any(|ty| *ty == Ty::Primitive(Primitive::Bool))
+    });
+    assert!(call.is_some(), "{:?}", typing.calls);
+}
+
+#[test]
+fn test_check_cannot_infer() {
+    let (_, _, typing) = quick_check(
+        "infer",
+        "fn main() {\n    let empty = []\n    let known: [Int; 0] = []\n}",
+    );
+    assert_eq!(codes(&typing.errors), [CANNOT_INFER]);
+    assert_eq!(
+        &*typing.errors[0].message,
+        "cannot infer the type of 'empty'"
+    );
+    assert_eq!(
+        &*typing.errors[0].notes[0],
+        "give 'empty' a type, as in 'let empty: Type'"
+    );
+}
+
+#[test]
+fn test_check_bounds() {
+    let (_, _, typing) = quick_check(
+        "bounds",
+        "trait Eq { fn eq(self: Self, _ other: Self) -> Bool }\ntrait Ord: Eq { fn less(self: Self, _ other: Self) -> Bool }\nimpl Eq for Int { fn eq(self: Self, _ other: Self) -> Bool { true } }\nimpl Ord for Int { fn less(self: Self, _ other: Self) -> Bool { true } }\nfn max<T: Ord>(_ a: T, _ b: T) -> T { if a.less(b) { b } else { a } }\nfn same<T>(_ a: T, _ b: T) -> Bool where T: Ord { a.eq(b) }\nstruct Sorted<T: Ord> { items: [T] }\nfn main() {\n    let _a = max(1, 2)\n    let _b = same(1.0, 2.0)\n}\nfn loose<T>(_ a: T) -> T { max(a, a) }\nfn field(_ s: Sorted<Float>) {}\nimpl Ord for Bool { fn less(self: Self, _ other: Self) -> Bool { self } }",
+    );
+    let messages: Vec<_> = typing
+        .errors
+        .iter()
+        .map(|error| (error.line, &*error.message, error.notes.join("; ")))
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            (
+                10,
+                "the trait 'Ord' is not implemented for 'Float'",
+                "required by the bound 'T: Ord' of 'same'".into()
+            ),
+            (
+                12,
+                "the trait 'Ord' is not implemented for 'T'",
+                "required by the bound 'T: Ord' of 'max'; add the bound 'T: Ord' to 'loose'".into()
+            ),
+            (
+                13,
+                "the trait 'Ord' is not implemented for 'Float'",
+                "required by the bound 'T: Ord' of 'Sorted'".into()
+            ),
+            (
+                14,
+                "the trait 'Eq' is not implemented for 'Bool'",
+                "required by the bound 'Self: Eq' of 'Ord'".into()
+            ),
+        ]
+    );
+    assert!(codes(&typing.errors)
+        .iter()
+        .all(|&code| code == UNSATISFIED_BOUND));
+    assert!(typing.instances.is_empty());
+}
+
+#[test]
+fn test_check_instances() {
+    let (program, map, resolution) = quick_resolve(
+        "instances",
+        &[
+            (
+                "main.coil",
+                "module util\ntrait Show {\n    fn show(self: Self) -> Str\n    fn twice(self: Self) -> Str { self.show() }\n}\nimpl Show for Int { fn show(self: Self) -> Str { \"int\" } }\nimpl Show for Bool { fn show(self: Self) -> Str { \"bool\" } }\nfn describe<T: Show>(_ value: T) -> Str { value.twice() }\nfn main() {\n    describe(1)\n    describe(true)\n    describe(2)\n    util.pair(1)\n}",
+            ),
+            (
+                "util.coil",
+                "pub fn pair<T>(_ x: T) -> (T, T) { (x, x) }\nfn again() { pair(3) }",
+            ),
+        ],
+    );
+    let typing = check(&program, &map, &resolution);
+    assert!(typing.errors.is_empty(), "{:?}", typing.errors);
+    let mut instances: Vec<_> = typing
+        .instances
+        .iter()
+        .map(|instance| {
+            let args: Vec<_> = instance
+                .args
+                .iter()
+                .map(|ty| ty.display(&resolution).to_string())
+                .collect();
+            format!("{}<{}>", resolution.def(instance.def).name, args.join(", "))
+        })
+        .collect();
+    instances.sort();
+    assert_eq!(
+        instances,
+        [
+            "again<>",
+            "describe<Bool>",
+            "describe<Int>",
+            "main<>",
+            "pair<Int>",
+            "show<>",
+            "show<>",
+            "twice<Bool>",
+            "twice<Int>",
+        ]
+    );
+    let main = typing
+        .instances
+        .iter()
+        .find(|instance| &*resolution.def(instance.def).name == "main")
+        .unwrap();
+    assert_eq!(main.calls.len(), 4);
+    let mut callees: Vec<_> = main.calls.values().collect();
+    callees.sort();
+    callees.dedup();
+    assert_eq!(callees.len(), 3);
+}
+
+#[test]
+fn test_check_type_instances() {
+    let (_, resolution, typing) = quick_check(
+        "type_instances",
+        "struct Pair<T> { first: T, second: T }\nstruct Wrapper<T> { inner: Pair<T> }\nenum Tree<T> { Leaf(T), Node(*Tree<T>, *Tree<T>) }\nfn make<T>(_ x: T) -> Pair<T> { Pair { first: x, second: x } }\nfn main() {\n    let _a = make(1)\n    let _b = Wrapper { inner: make(true) }\n    let _t = Tree.Leaf(2u8)\n}",
+    );
+    assert!(typing.errors.is_empty(), "{:?}", typing.errors);
+    let mut instances: Vec<_> = typing
+        .type_instances
+        .iter()
+        .map(|instance| {
+            let fields: Vec<_> = instance
+                .variants
+                .iter()
+                .flat_map(|variant| &variant.fields)
+                .map(|(_, ty)| ty.display(&resolution).to_string())
+                .collect();
+            let ty = Ty::Adt {
+                def: instance.def,
+                args: instance.args.clone(),
+            };
+            format!("{} ({})", ty.display(&resolution), fields.join(", "))
+        })
+        .collect();
+    instances.sort();
+    assert_eq!(
+        instances,
+        [
+            "Pair<Bool> (Bool, Bool)",
+            "Pair<Int> (Int, Int)",
+            "Tree<U8> (U8, *Tree<U8>, *Tree<U8>)",
+            "Wrapper<Bool> (Pair<Bool>)",
+        ]
+    );
+
+    let (_, _, typing) = quick_check(
+        "type_instance_limit",
+        "struct Grow<T> { next: *Grow<[T]> }\nfn take(_ g: *Grow<Int>) {}\nfn main() {}",
+    );
+    assert_eq!(codes(&typing.errors), [INSTANCE_LIMIT]);
+}
+
+#[test]
+fn test_check_coherence() {
+    let (program, map, resolution) = quick_resolve(
+        "coherence",
+        &[
+            (
+                "main.coil",
+                "module shapes\ntrait Show {\n    fn show(self: Self) -> Str\n    fn pad(self: Self, width w: Int) -> Str\n    fn twice(self: Self) -> Str { self.show() }\n}\nimpl Show for Int {\n    fn show(self: Self) -> Str { \"\" }\n    fn pad(self: Self, _ w: Int) -> Str { \"\" }\n}\nimpl<T> Show for [T] {\n    fn show(self: Self) -> Int { 0 }\n    fn pad(self: Self, width w: Int) -> Str { \"\" }\n    fn extra(self: Self) {}\n}\nimpl Show for [Bool] {}\nimpl Show for shapes.Square {\n    fn show(self: Self) -> Str { \"\" }\n    fn pad(self: Self, width w: Int) -> Str { \"\" }\n}\nimpl shapes.Named for Int { fn name(self: Self) -> Str { \"\" } }\nimpl shapes.Square { fn area(self: Self) -> Int { 0 } }\nimpl Int { fn double(self: Self) -> Int { 0 } }",
+            ),
+            (
+                "shapes.coil",
+                "pub struct Square {}\npub trait Named { fn name(self: Self) -> Str }\nimpl Named for Square { fn name(self: Self) -> Str { \"\" } }\nimpl Square { fn side(self: Self) -> Int { 0 } }",
+            ),
+        ],
+    );
+    let typing = check(&program, &map, &resolution);
+    let messages: Vec<_> = typing
+        .errors
+        .iter()
+        .map(|error| (error.line, &*error.message, error.notes.join("; ")))
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            (
+                9,
+                "'pad(_:)' doesn't match its declaration in the trait 'Show'",
+                "the trait expects 'fn pad(self: Int, width: Int) -> Str'".into()
+            ),
+            (
+                12,
+                "'show()' doesn't match its declaration in the trait 'Show'",
+                "the trait expects 'fn show(self: [T]) -> Str'".into()
+            ),
+            (
+                14,
+                "'extra()' is not a function of the trait 'Show'",
+                "".into()
+            ),
+            (
+                16,
+                "conflicting impls of 'Show' for '[Bool]'",
+                "the other impl is on line 11 of 'main'".into()
+            ),
+            (
+                16,
+                "the impl of 'Show' is missing 'show()', 'pad(width:)'",
+                "".into()
+            ),
+            (
+                21,
+                "'Named' can only be implemented for 'Int' in the module declaring one of them",
+                "move the impl into 'main.shapes'".into()
+            ),
+            (
+                22,
+                "functions can only be added to 'Square' in the module declaring it",
+                "move the impl into 'main.shapes'".into()
+            ),
+            (
+                23,
+                "functions can't be added to the builtin type 'Int'",
+                "declare a trait with them and implement it for 'Int'".into()
+            ),
+        ]
+    );
+    assert_eq!(
+        codes(&typing.errors),
+        [
+            TRAIT_ITEM_MISMATCH,
+            TRAIT_ITEM_MISMATCH,
+            NOT_A_TRAIT_ITEM,
+            CONFLICTING_IMPLS,
+            MISSING_TRAIT_ITEMS,
+            ORPHAN_IMPL,
+            FOREIGN_IMPL,
+            BUILTIN_IMPL,
+        ]
+    );
+}
+
+#[test]
+fn test_check_inherent_duplicates() {
+    let (_, _, typing) = quick_check(
+        "inherent_duplicates",
+        "struct Point { x: Int }\nimpl Point { fn len(self: Self) -> Int { 0 } }\nimpl Point {\n    fn len(self: Self) -> Int { 1 }\n    fn scale(self: Self, by: Int) {}\n}\nimpl Point { fn scale(self: Self, to: Int) {} }\nstruct Pair<T> { a: T }\nimpl<T> Pair<T> { fn get(self: Self) -> Int { 0 } }\nimpl Pair<Int> { fn get(self: Self) -> Int { 1 } }\nimpl Pair<Bool> { fn other(self: Self) {} }",
+    );
+    let messages: Vec<_> = typing
+        .errors
+        .iter()
+        .map(|error| (error.line, &*error.message, error.notes.join("; ")))
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            (
+                4,
+                "'len' is defined more than once",
+                "first defined on line 2".into()
+            ),
+            (
+                10,
+                "'get' is defined more than once",
+                "first defined on line 9".into()
+            ),
+        ]
+    );
+    assert_eq!(
+        codes(&typing.errors),
+        [DUPLICATE_DEFINITION, DUPLICATE_DEFINITION]
+    );
+}
+
+#[test]
+fn test_check_mutability() {
+    let (_, _, typing) = quick_check(
+        "mutability",
+        "struct Point { x: Int }\nimpl Point {\n    fn bump(self: &mut Self) { self.x += 1 }\n    fn reset(self: &Self) { self.x = 0 }\n}\nstatic LIMIT: Int = 10\nstatic mut COUNT: Int = 0\nfn scale(by: Int) { by *= 2 }\nfn main() {\n    let count = 0\n    count <<= 1\n    let mut total = count\n    total -= 1\n    let p = Point { x: 1 }\n    p.bump()\n    let q = &p\n    q.x = 3\n    let mut m = Point { x: 2 }\n    let n = &mut m\n    n.x = 4\n    n.bump()\n    LIMIT = 3\n    COUNT = 1\n    match total { mut t => t += 1 }\n    for i in 0..3 { i = 2 }\n}",
+    );
+    let messages: Vec<_> = typing
+        .errors
+        .iter()
+        .map(|error| (error.line, &*error.message, error.notes.join("; ")))
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            (
+                4,
+                "'self.x' is behind a '&' reference, so it can't be assigned to",
+                "change the type of 'self' to '&mut Point'".into()
+            ),
+            (
+                8,
+                "'by' isn't declared 'mut', so it can't be assigned to",
+                "copy it into a mutable variable with 'let mut by = by'".into()
+            ),
+            (
+                11,
+                "'count' isn't declared 'mut', so it can't be assigned to",
+                "declare it as 'let mut count'".into()
+            ),
+            (
+                15,
+                "'p' isn't declared 'mut', so it can't be borrowed as mutable",
+                "declare it as 'let mut p'".into()
+            ),
+            (
+                17,
+                "'q.x' is behind a '&' reference, so it can't be assigned to",
+                "borrow it as mutable where 'q' is declared, with '&mut p'".into()
+            ),
+            (
+                22,
+                "'LIMIT' isn't declared 'mut', so it can't be assigned to",
+                "declare it as 'static mut LIMIT'".into()
+            ),
+            (
+                25,
+                "'i' isn't declared 'mut', so it can't be assigned to",
+                "copy it into a mutable variable with 'let mut i = i'".into()
+            ),
+        ]
+    );
+    assert_eq!(
+        codes(&typing.errors),
+        [
+            MUTATION_THROUGH_REFERENCE,
+            IMMUTABLE_BINDING,
+            IMMUTABLE_BINDING,
+            IMMUTABLE_BINDING,
+            MUTATION_THROUGH_REFERENCE,
+            IMMUTABLE_BINDING,
+            IMMUTABLE_BINDING,
+        ]
+    );
+}
+
+#[test]
+fn test_check_initialisation() {
+    let (_, _, typing) = quick_check(
+        "initialisation",
+        "fn pick(_ flag: Bool) -> Int {\n    let both: Int\n    let one: Int\n    let never: Int\n    if flag { both = 1; one = 1 } else { both = 2 }\n    let _a = both + one + never\n    let late: Int\n    if flag { return 0 }\n    late = 3\n    late = 4\n    let mut looped: Int\n    let mut i = 0\n    while i < 3 {\n        looped = i\n        i += 1\n        if flag { break }\n    }\n    let _b = looped\n    let mut done: Int\n    do { done = 1 } while false\n    let fallen: Int\n    match i {\n        0 => { fallen = 1; fallthrough }\n        _ => fallen = 2,\n    }\n    let _c = \\ -> done\n    fallen + late\n}",
+    );
+    let messages: Vec<_> = typing
+        .errors
+        .iter()
+        .map(|error| (error.line, &*error.message))
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            (6, "'one' might not have been given a value here"),
+            (6, "'never' is used before it is given a value"),
+            (
+                10,
+                "'late' might already have a value, and isn't declared 'mut'"
+            ),
+            (18, "'looped' might not have been given a value here"),
+            (
+                24,
+                "'fallen' might already have a value, and isn't declared 'mut'"
+            ),
+        ]
+    );
+    assert_eq!(
+        codes(&typing.errors),
+        [
+            UNINITIALISED_USE,
+            UNINITIALISED_USE,
+            IMMUTABLE_BINDING,
+            UNINITIALISED_USE,
+            IMMUTABLE_BINDING,
+        ]
+    );
+}
+
+#[test]
+fn test_check_closure_captures() {
+    let (_, resolution, typing) = quick_check(
+        "captures",
+        "struct Point { x: Int }\nimpl Point {\n    fn shift(self: &mut Self, by d: Int) { self.x += d }\n}\nfn main() {\n    let n = 1\n    let mut total = 0\n    let mut p = Point { x: 1 }\n    let q = Point { x: 2 }\n    let s = \"s\"\n    let add = \\x: Int -> x + n\n    let count = \\x: Int -> { total += x; total }\n    let shift = \\d: Int -> p.shift(by: d)\n    let take = \\ -> { let t = q; t }\n    let read = \\ -> q.x == n && s == \"s\"\n    let outer = \\ -> { let inner = \\ -> &mut total; inner() }\n    let _ = (add(1), count(2), shift(3), take(), read(), outer())\n}",
+    );
+    let mut closures: Vec<_> = typing.closures.iter().collect();
+    closures.sort_by_key(|(node, _)| **node);
+    let captures: Vec<Vec<_>> = closures
+        .iter()
+        .map(|(_, closure)| {
+            let captures = closure.captures.iter();
+            captures
+                .map(|capture| (&*resolution.def(capture.def).name, capture.mode))
+                .collect()
+        })
+        .collect();
+    use CaptureMode::{Move, MutRef, Ref};
+    assert_eq!(
+        captures,
+        [
+            vec![("n", Ref)],
+            vec![("total", MutRef)],
+            vec![("p", MutRef)],
+            vec![("q", Move)],
+            vec![("q", Ref), ("n", Ref), ("s", Ref)],
+            vec![("total", MutRef)],
+            vec![("total", MutRef)],
+        ]
+    );
+    let (_, count) = closures[1];
+    assert_eq!(
+        count.environment().display(&resolution).to_string(),
+        "(&mut Int,)"
+    );
+    let lowered = count.lowered();
+    let params: Vec<_> = lowered
+        .params
+        .iter()
+        .map(|(name, ty)| format!("{}: {}", name.binding(), ty.display(&resolution)))
+        .collect();
+    assert_eq!(params, ["env: &mut (&mut Int,)", "x: Int"]);
+    assert_eq!(lowered.returns, Ty::Primitive(Primitive::I64));
+}
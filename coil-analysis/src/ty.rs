@@ -0,0 +1,434 @@
+use std::{collections::HashMap, fmt};
+
+use coil_parser::{match_labels, ArgName, LabelMismatch, NodeId};
+
+use crate::{DefId, Primitive, Resolution};
+
+/// A type, as the type checker sees it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Ty {
+    Primitive(Primitive),
+    /// A struct, enum or union, applied to its type arguments.
+    Adt {
+        def: DefId,
+        args: Vec<Ty>,
+    },
+    Reference {
+        mutable: bool,
+        ty: Box<Ty>,
+    },
+    Pointer {
+        mutable: bool,
+        ty: Box<Ty>,
+    },
+    /// `[T; N]`, whose length is `None` when it isn't written as a number.
+    Array {
+        ty: Box<Ty>,
+        len: Option<u64>,
+    },
+    Slice(Box<Ty>),
+    /// `()` is the unit type.
+    Tuple(Vec<Ty>),
+    Fn {
+        params: Vec<Ty>,
+        returns: Box<Ty>,
+    },
+    Optional(Box<Ty>),
+    Result {
+        ok: Box<Ty>,
+        error: Box<Ty>,
+    },
+    /// The type of `start..end`.
+    Range(Box<Ty>),
+    /// A generic parameter or associated type, seen from inside the item declaring it.
+    Param(DefId),
+    /// A type still to be inferred.
+    Var(u32),
+    /// A type still to be inferred that must be an integer, like that of `5`.
+    IntVar(u32),
+    /// A type still to be inferred that must be a float, like that of `0.5`.
+    FloatVar(u32),
+    /// The type of expressions that never produce a value, like `return`, which fits
+    /// wherever a value is expected.
+    Never,
+    /// Stands in for the type of code with an error already reported, and fits anywhere
+    /// so that the error isn't reported again.
+    Error,
+}
+
+impl Ty {
+    pub fn unit() -> Self {
+        Ty::Tuple(vec![])
+    }
+
+    pub fn is_unit(&self) -> bool {
+        matches!(self, Ty::Tuple(types) if types.is_empty())
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Ty::Primitive(primitive) if primitive.is_integer())
+            || matches!(self, Ty::IntVar(_))
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(
+            self,
+            Ty::Primitive(Primitive::F32 | Primitive::F64) | Ty::FloatVar(_)
+        )
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.is_float()
+    }
+
+    /// Whether the type has variables left to infer.
+    pub fn has_vars(&self) -> bool {
+        let mut found = false;
+        self.walk(&mut |ty| found |= matches!(ty, Ty::Var(_) | Ty::IntVar(_) | Ty::FloatVar(_)));
+        found
+    }
+
+    /// Calls `f` on this type and every type in it.
+    pub fn walk(&self, f: &mut impl FnMut(&Ty)) {
+        f(self);
+        match self {
+            Ty::Adt { args: types, .. } | Ty::Tuple(types) => {
+                types.iter().for_each(|ty| ty.walk(f));
+            }
+            Ty::Reference { ty, .. }
+            | Ty::Pointer { ty, .. }
+            | Ty::Array { ty, .. }
+            | Ty::Slice(ty)
+            | Ty::Optional(ty)
+            | Ty::Range(ty) => ty.walk(f),
+            Ty::Fn { params, returns } => {
+                params.iter().for_each(|ty| ty.walk(f));
+                returns.walk(f);
+            }
+            Ty::Result { ok, error } => {
+                ok.walk(f);
+                error.walk(f);
+            }
+            Ty::Primitive(_)
+            | Ty::Param(_)
+            | Ty::Var(_)
+            | Ty::IntVar(_)
+            | Ty::FloatVar(_)
+            | Ty::Never
+            | Ty::Error => {}
+        }
+    }
+
+    /// The type with `f` applied to every type in it, bottom up.
+    pub fn map(&self, f: &mut impl FnMut(Ty) -> Ty) -> Ty {
+        let map_all = |types: &[Ty], f: &mut _| types.iter().map(|ty| ty.map(f)).collect();
+        let ty = match self {
+            Ty::Adt { def, args } => Ty::Adt {
+                def: *def,
+                args: map_all(args, f),
+            },
+            Ty::Reference { mutable, ty } => Ty::Reference {
+                mutable: *mutable,
+                ty: Box::new(ty.map(f)),
+            },
+            Ty::Pointer { mutable, ty } => Ty::Pointer {
+                mutable: *mutable,
+                ty: Box::new(ty.map(f)),
+            },
+            Ty::Array { ty, len } => Ty::Array {
+                ty: Box::new(ty.map(f)),
+                len: *len,
+            },
+            Ty::Slice(ty) => Ty::Slice(Box::new(ty.map(f))),
+            Ty::Tuple(types) => Ty::Tuple(map_all(types, f)),
+            Ty::Fn { params, returns } => Ty::Fn {
+                params: map_all(params, f),
+                returns: Box::new(returns.map(f)),
+            },
+            Ty::Optional(ty) => Ty::Optional(Box::new(ty.map(f))),
+            Ty::Result { ok, error } => Ty::Result {
+                ok: Box::new(ok.map(f)),
+                error: Box::new(error.map(f)),
+            },
+            Ty::Range(ty) => Ty::Range(Box::new(ty.map(f))),
+            ty => ty.clone(),
+        };
+        f(ty)
+    }
+
+    /// The type with the generic parameters in `args` replaced.
+    pub fn substitute(&self, args: &HashMap<DefId, Ty>) -> Ty {
+        self.map(&mut |ty| match ty {
+            Ty::Param(def) => args.get(&def).cloned().unwrap_or(ty),
+            ty => ty,
+        })
+    }
+
+    /// Writes the type as it would be written in Coil, naming definitions through
+    /// `resolution`.
+    pub fn display<'a>(&'a self, resolution: &'a Resolution) -> impl fmt::Display + 'a {
+        DisplayTy(self, resolution)
+    }
+}
+
+struct DisplayTy<'a>(&'a Ty, &'a Resolution);
+
+impl fmt::Display for DisplayTy<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let res = self.1;
+        let list = |f: &mut fmt::Formatter<'_>, types: &[Ty]| {
+            for (i, ty) in types.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{}", ty.display(res))?;
+            }
+            Ok(())
+        };
+        match self.0 {
+            Ty::Primitive(primitive) => f.write_str(primitive.name()),
+            Ty::Adt { def, args } => {
+                f.write_str(&res.def(*def).name)?;
+                if !args.is_empty() {
+                    f.write_str("<")?;
+                    list(f, args)?;
+                    f.write_str(">")?;
+                }
+                Ok(())
+            }
+            Ty::Reference { mutable, ty } => {
+                let mutable = if *mutable { "mut " } else { "" };
+                write!(f, "&{mutable}{}", ty.display(res))
+            }
+            Ty::Pointer { mutable, ty } => {
+                let mutable = if *mutable { "mut " } else { "" };
+                write!(f, "*{mutable}{}", ty.display(res))
+            }
+            Ty::Array { ty, len: Some(len) } => write!(f, "[{}; {len}]", ty.display(res)),
+            Ty::Array { ty, len: None } => write!(f, "[{}; _]", ty.display(res)),
+            Ty::Slice(ty) => write!(f, "[{}]", ty.display(res)),
+            Ty::Tuple(types) => {
+                f.write_str("(")?;
+                list(f, types)?;
+                if types.len() == 1 {
+                    f.write_str(",")?;
+                }
+                f.write_str(")")
+            }
+            Ty::Fn { params, returns } => {
+                f.write_str("fn(")?;
+                list(f, params)?;
+                f.write_str(")")?;
+                if !returns.is_unit() {
+                    write!(f, " -> {}", returns.display(res))?;
+                }
+                Ok(())
+            }
+            Ty::Optional(ty) => write!(f, "{}?", ty.display(res)),
+            Ty::Result { ok, error } => write!(f, "{}!{}", ok.display(res), error.display(res)),
+            Ty::Range(ty) => write!(f, "Range<{}>", ty.display(res)),
+            Ty::Param(def) => f.write_str(&res.def(*def).name),
+            Ty::Var(_) => f.write_str("_"),
+            Ty::IntVar(_) => f.write_str("{integer}"),
+            Ty::FloatVar(_) => f.write_str("{float}"),
+            Ty::Never => f.write_str("!"),
+            Ty::Error => f.write_str("{error}"),
+        }
+    }
+}
+
+/// The signature of a function with its types resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FnSig {
+    pub generics: Vec<DefId>,
+    pub params: Vec<(ArgName, Ty)>,
+    pub returns: Ty,
+}
+
+impl FnSig {
+    /// Whether the function is a method, taking `self` first.
+    pub fn has_self(&self) -> bool {
+        self.params
+            .first()
+            .is_some_and(|(name, _)| name.binding() == "self")
+    }
+
+    /// The labels of the parameters, as a call that names every one would give them.
+    pub fn labels(&self) -> Vec<Option<&str>> {
+        self.params.iter().map(|(name, _)| name.label()).collect()
+    }
+
+    /// Checks the labels of a call against the parameters after `skip`.
+    pub fn match_labels(&self, skip: usize, labels: &[Option<&str>]) -> Result<(), LabelMismatch> {
+        let params = self.params.get(skip..).unwrap_or_default();
+        match_labels(params.iter().map(|(name, _)| name), labels)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdtKind {
+    Struct,
+    Enum,
+    Union,
+}
+
+/// A struct, enum or union with its field types resolved. A struct or union has a single
+/// variant, named after it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adt {
+    pub kind: AdtKind,
+    pub generics: Vec<DefId>,
+    pub variants: Vec<AdtVariant>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdtVariant {
+    /// The variant, or the struct or union itself.
+    pub def: DefId,
+    /// Whether the fields are in parentheses and named by position.
+    pub tuple: bool,
+    pub fields: Vec<(Box<str>, Ty)>,
+}
+
+/// An `impl` block with its types resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Impl {
+    pub node: NodeId,
+    pub generics: Vec<DefId>,
+    /// The trait implemented and its type arguments, for trait impls.
+    pub trait_: Option<(DefId, Vec<Ty>)>,
+    pub target: Ty,
+    /// The functions of the impl, by name.
+    pub items: HashMap<Box<str>, Vec<DefId>>,
+}
+
+/// A requirement that `ty` implements the trait `trait_` with the type arguments `args`,
+/// as in `T: Into<Int>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Bound {
+    pub ty: Ty,
+    pub trait_: DefId,
+    pub args: Vec<Ty>,
+}
+
+impl Bound {
+    pub fn substitute(&self, args: &HashMap<DefId, Ty>) -> Bound {
+        Bound {
+            ty: self.ty.substitute(args),
+            trait_: self.trait_,
+            args: self.args.iter().map(|ty| ty.substitute(args)).collect(),
+        }
+    }
+
+    /// Writes the bound as `T: Trait<A>`.
+    pub fn display<'a>(&'a self, resolution: &'a Resolution) -> impl fmt::Display + 'a {
+        DisplayBound(self, resolution)
+    }
+}
+
+struct DisplayBound<'a>(&'a Bound, &'a Resolution);
+
+impl fmt::Display for DisplayBound<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Bound { ty, trait_, args } = self.0;
+        let trait_ = Ty::Adt {
+            def: *trait_,
+            args: args.clone(),
+        };
+        let trait_ = trait_.display(self.1).to_string();
+        write!(f, "{}: {trait_}", ty.display(self.1))
+    }
+}
+
+/// A function with concrete types for all its generics and those of its impl or trait,
+/// as codegen compiles it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instance {
+    pub def: DefId,
+    /// The types of the generics of the impl or trait of the function, `Self` first for a
+    /// trait, then of the function itself.
+    pub args: Vec<Ty>,
+    /// The instance each call or function name in the body refers to, by node.
+    pub calls: HashMap<NodeId, usize>,
+}
+
+/// A struct, enum or union with concrete types for its generics, as codegen lays it out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeInstance {
+    pub def: DefId,
+    pub args: Vec<Ty>,
+    /// The variants of the type, with `args` put in the types of their fields.
+    pub variants: Vec<AdtVariant>,
+}
+
+/// How a closure holds a variable of the function around it, from the weakest to the
+/// strongest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CaptureMode {
+    /// By `&` reference, for a variable the closure only reads, or copies.
+    Ref,
+    /// By `&mut` reference, for a variable the closure assigns to or borrows as mutable.
+    MutRef,
+    /// By value, for a variable the closure uses the value of and that isn't copied.
+    Move,
+}
+
+/// A variable a closure uses from the function around it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capture {
+    pub def: DefId,
+    pub mode: CaptureMode,
+    /// The type of the variable.
+    pub ty: Ty,
+}
+
+impl Capture {
+    /// The type of the field of the environment holding the capture.
+    pub fn field(&self) -> Ty {
+        match self.mode {
+            CaptureMode::Ref | CaptureMode::MutRef => Ty::Reference {
+                mutable: self.mode == CaptureMode::MutRef,
+                ty: Box::new(self.ty.clone()),
+            },
+            CaptureMode::Move => self.ty.clone(),
+        }
+    }
+}
+
+/// A closure, as it is lowered to a function taking an environment with its captures.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Closure {
+    /// The variables the closure captures, in the order it first uses them.
+    pub captures: Vec<Capture>,
+    /// The parameters of the closure, which calls give no labels.
+    pub params: Vec<(ArgName, Ty)>,
+    pub returns: Ty,
+}
+
+impl Closure {
+    /// The environment of the closure: a tuple with the field of each capture, in order.
+    pub fn environment(&self) -> Ty {
+        Ty::Tuple(self.captures.iter().map(Capture::field).collect())
+    }
+
+    /// The function the closure is lowered to. It takes the environment first, by `&mut`
+    /// reference if it changes a variable it holds by `&mut` reference and by `&`
+    /// reference otherwise, then the parameters of the closure.
+    pub fn lowered(&self) -> FnSig {
+        let mutable = self
+            .captures
+            .iter()
+            .any(|capture| capture.mode == CaptureMode::MutRef);
+        let env = Ty::Reference {
+            mutable,
+            ty: Box::new(self.environment()),
+        };
+        FnSig {
+            generics: vec![],
+            params: std::iter::once((ArgName::Unnamed("env".into()), env))
+                .chain(self.params.iter().cloned())
+                .collect(),
+            returns: self.returns.clone(),
+        }
+    }
+}
@@ -1,3 +1,9 @@
+mod locale;
+pub use locale::{lang, message, message_in, placeholders, set_lang, Lang};
+
+mod source_map;
+pub use source_map::{module_name, FileId, Location, SourceFile, SourceMap, Span};
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ErrorCode(pub u16);
 
@@ -34,12 +40,21 @@ impl std::fmt::Debug for ErrorCode {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Severity {
+    #[default]
+    Error,
+    Warning,
+}
+
 #[derive(Debug, Clone, Hash, PartialEq)]
 pub struct Error {
     pub code: ErrorCode,
+    pub severity: Severity,
     pub message: Box<str>,
     pub file: Box<str>,
     pub line: usize,
+    pub span: Option<Span>,
     pub notes: Vec<Box<str>>,
 }
 
@@ -47,28 +62,106 @@ impl Error {
     pub fn new(code: ErrorCode, message: &str, file: &str, line: usize) -> Self {
         Self {
             code,
+            severity: Severity::Error,
             message: message.into(),
             file: file.into(),
             line,
+            span: None,
             notes: Vec::new(),
         }
     }
 
+    /// Creates an error pointing at `span`, taking the file name and line from `map`.
+    pub fn at(code: ErrorCode, message: &str, map: &SourceMap, span: Span) -> Self {
+        let file = map.get(span.file);
+        Self::new(code, message, &file.name, file.location(span.start).line).with_span(span)
+    }
+
+    pub fn warning(mut self) -> Self {
+        self.severity = Severity::Warning;
+        self
+    }
+
+    pub fn is_warning(&self) -> bool {
+        self.severity == Severity::Warning
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
     pub fn with_note(mut self, note: &str) -> Self {
         self.notes.push(note.into());
         self
     }
 }
 
+impl Error {
+    /// Displays the error together with the offending source line, resolved through `map`.
+    pub fn display<'a>(&'a self, map: &'a SourceMap) -> impl std::fmt::Display + 'a {
+        DisplayWithSource { error: self, map }
+    }
+
+    fn fmt_header(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_warning() {
+            write!(f, "{}: ", message("warning", &[]))?;
+        }
+        writeln!(f, "{:?}: {}", self.code, self.message)
+    }
+
+    fn fmt_notes(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for note in self.notes.iter() {
+            writeln!(f, "{}: {note}", message("note", &[]))?;
+        }
+        Ok(())
+    }
+}
+
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "{:?}: {}", self.code, self.message)?;
+        self.fmt_header(f)?;
         writeln!(f, "{}:{}", self.file, self.line)?;
-        // TODO: add line printing
-        for note in self.notes.iter() {
-            writeln!(f, "note: {note}")?;
+        self.fmt_notes(f)
+    }
+}
+
+struct DisplayWithSource<'a> {
+    error: &'a Error,
+    map: &'a SourceMap,
+}
+
+impl std::fmt::Display for DisplayWithSource<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some(span) = self.error.span else {
+            return self.error.fmt(f);
+        };
+        let file = self.map.get(span.file);
+        let start = file.location(span.start);
+        self.error.fmt_header(f)?;
+        writeln!(f, "{}:{}:{}", file.name, start.line, start.column)?;
+        if let Some(text) = file.line_text(start.line) {
+            let end = file.location(span.end);
+            let width = if end.line == start.line {
+                end.column.saturating_sub(start.column).max(1)
+            } else {
+                (text.chars().count() + 1)
+                    .saturating_sub(start.column)
+                    .max(1)
+            };
+            let gutter = start.line.to_string().len();
+            writeln!(f, "{:gutter$} |", "")?;
+            writeln!(f, "{} | {text}", start.line)?;
+            writeln!(
+                f,
+                "{:gutter$} | {:pad$}{}",
+                "",
+                "",
+                "^".repeat(width),
+                pad = start.column - 1
+            )?;
         }
-        Ok(())
+        self.error.fmt_notes(f)
     }
 }
 
@@ -0,0 +1,181 @@
+pub(super) static MESSAGES: &[(&str, &str)] = &[
+    // diagnostics
+    ("warning", "warning"),
+    ("note", "note"),
+    // tokens
+    ("token-identifier", "identifier '{name}'"),
+    ("token-string", "string {value}"),
+    ("token-number", "number '{value}'"),
+    ("token-char", "char {value}"),
+    // lexer
+    ("lexer-expected-digit", "expected a digit but found {found}"),
+    (
+        "lexer-expected-digit-eof",
+        "expected a digit but found end of file",
+    ),
+    (
+        "lexer-expected-exponent",
+        "expected a digit, '+' or '-' but found {found}",
+    ),
+    (
+        "lexer-expected-exponent-eof",
+        "expected a digit, '+', '-' but found end of file",
+    ),
+    (
+        "lexer-unexpected-character",
+        "found unexpected character: {found} (U+{codepoint})",
+    ),
+    ("lexer-unfinished-string", "unfinished string"),
+    ("lexer-unfinished-string-escape", "unfinished string escape"),
+    (
+        "lexer-invalid-string-escape",
+        "invalid string escape: '\\{escape}'",
+    ),
+    (
+        "lexer-expected-string-start",
+        "expected one of '#' or '\"' but found {found}",
+    ),
+    (
+        "lexer-expected-string-start-eof",
+        "expected one of '#' or '\"' but found end of file",
+    ),
+    (
+        "lexer-note-finish-string",
+        "maybe finish the string with a '{terminator}'",
+    ),
+    (
+        "lexer-note-raw-string",
+        "if you wanted to make a raw string, add r before the string",
+    ),
+    ("lexer-unfinished-comment", "unfinished comment"),
+    (
+        "lexer-note-finish-comment",
+        "close the comment with a '*/'; block comments nest, so every '/*' needs one",
+    ),
+    (
+        "lexer-invalid-suffix",
+        "invalid suffix '{suffix}' for a number; expected one of i8, i16, i32, i64, u8, u16, u32, u64, f32 or f64",
+    ),
+    ("lexer-empty-char", "empty char literal"),
+    ("lexer-unfinished-char", "unfinished char literal"),
+    (
+        "lexer-note-char-string",
+        "a char literal holds one char between two 's; write longer text as a string in \"...\"",
+    ),
+    // parser
+    ("parser-expected", "expected {expected} but found {found}"),
+    (
+        "parser-expected-eof",
+        "expected {expected} but found end of file",
+    ),
+    ("parser-expression", "an expression"),
+    ("parser-item", "an item"),
+    ("parser-type", "a type"),
+    ("parser-identifier", "an identifier"),
+    ("parser-loop", "a loop"),
+    ("parser-pattern", "a pattern"),
+    ("parser-statement-end", "';' or a new line"),
+    ("parser-missing-body", "function '{name}' has no body"),
+    (
+        "parser-extern-body",
+        "functions inside 'extern' cannot have a body",
+    ),
+    ("parser-pub-not-allowed", "'pub' is not allowed here"),
+    ("parser-chained-operator", "'{first}' and '{second}' cannot be chained"),
+    ("parser-note-chained-comparison", "use '&&' to combine comparisons, as in 'a < b && b < c'"),
+    ("parser-note-parenthesize", "add parentheses to make the order explicit"),
+    ("parser-struct-literal-in-condition", "a struct literal of '{path}' cannot be written directly in a condition"),
+    ("parser-note-struct-literal-in-condition", "wrap it in parentheses, as in '({path} {{ ... }})', since a '{{' after the condition starts the block"),
+    ("parser-ambiguous-line-break", "line starting with {operator} continues the previous line"),
+    ("parser-note-ambiguous-line-break", "end the previous line with ';' to start a new statement, or move the operator to the end of the previous line"),
+    ("parser-ambiguous-line-break-statement", "line starting with {operator} starts a new statement"),
+    ("parser-note-ambiguous-line-break-statement", "end the previous line with ';' to make this clear, or move the operator to the end of the previous line to continue it"),
+    ("parser-unknown-loop-label", "no loop labelled '{label}' around this 'continue'"),
+    ("parser-fallthrough-outside-match", "'fallthrough' is only allowed in the arm of a match"),
+    ("parser-fallthrough-in-last-arm", "'fallthrough' in the last arm has no arm to fall through to"),
+    ("parser-missing-static-value", "static '{name}' has no value"),
+    ("parser-module-not-found", "no file found for module '{name}'"),
+    ("parser-note-module-files", "looked for '{file}' and '{dir_file}'"),
+    ("parser-module-unreadable", "cannot read the file of module '{name}': {error}"),
+    ("parser-ambiguous-module-file", "module '{name}' is in both '{file}' and '{dir_file}'"),
+    ("parser-module-loaded-twice", "'{file}' is already loaded as another module"),
+    ("parser-duplicate-module", "module '{name}' is declared more than once"),
+    ("parser-duplicate-import", "'{name}' is already defined in this module"),
+    ("parser-unresolved-module", "no module '{name}' in '{module}'"),
+    ("parser-unresolved-import", "no item '{name}' in '{module}'"),
+    ("parser-private-import", "'{name}' is private to '{module}'"),
+    ("parser-note-make-public", "declare it 'pub' to import it from other modules"),
+    ("parser-import-cycle", "modules import each other in a cycle: {cycle}"),
+    // analysis
+    ("analysis-unresolved-name", "cannot find '{name}' in this scope"),
+    ("analysis-unresolved-type", "cannot find type '{name}' in this scope"),
+    ("analysis-unresolved-in-module", "no item '{name}' in module '{module}'"),
+    ("analysis-unresolved-variant", "no variant '{name}' in enum '{parent}'"),
+    ("analysis-note-did-you-mean", "did you mean '{name}'?"),
+    ("analysis-ambiguous-name", "'{name}' could refer to more than one variant"),
+    ("analysis-note-candidate", "it could be '{path}'"),
+    ("analysis-duplicate-definition", "'{name}' is defined more than once"),
+    ("analysis-duplicate-field", "field '{name}' is declared more than once"),
+    ("analysis-note-first-definition", "first defined on line {line}"),
+    ("analysis-unused-binding", "unused variable '{name}'"),
+    ("analysis-note-prefix-underscore", "if this is intentional, name it '_{name}'"),
+    ("analysis-note-keep-label", "if this is intentional, keep the label and name it '{name} _{name}'"),
+    ("analysis-unused-import", "unused import '{name}'"),
+    ("analysis-mismatched-types", "mismatched types: expected '{expected}', found '{found}'"),
+    ("analysis-not-a-type", "'{name}' is not a type"),
+    ("analysis-not-a-trait", "'{name}' is not a trait"),
+    ("analysis-not-a-struct", "'{name}' is not a struct or a variant with fields"),
+    ("analysis-not-a-value", "'{name}' is not a value"),
+    ("analysis-recursive-alias", "type alias '{name}' refers to itself"),
+    ("analysis-type-argument-count", "wrong number of type arguments for '{name}': expected {expected}, found {found}"),
+    ("analysis-argument-count", "wrong number of arguments: expected {expected}, found {found}"),
+    ("analysis-pattern-field-count", "wrong number of fields in this pattern: expected {expected}, found {found}"),
+    ("analysis-unknown-field", "no field '{name}' on type '{ty}'"),
+    ("analysis-unknown-method", "no method '{name}' on type '{ty}'"),
+    ("analysis-unknown-function", "no function '{name}' on type '{ty}'"),
+    ("analysis-missing-fields", "missing fields {fields} in '{name}'"),
+    ("analysis-not-callable", "a value of type '{ty}' cannot be called"),
+    ("analysis-cannot-infer", "cannot infer the type of '{name}'"),
+    ("analysis-note-annotate", "give '{name}' a type, as in 'let {name}: Type'"),
+    ("analysis-invalid-operand", "'{op}' cannot be applied to type '{ty}'"),
+    ("analysis-not-dereferenceable", "a value of type '{ty}' cannot be dereferenced"),
+    ("analysis-not-indexable", "a value of type '{ty}' cannot be indexed"),
+    ("analysis-not-iterable", "a value of type '{ty}' cannot be iterated over"),
+    ("analysis-try-return", "'?' can only be used in a function returning an optional or a result"),
+    ("analysis-literal-out-of-range", "literal out of range for '{ty}'"),
+    ("analysis-note-range", "'{ty}' holds values from {min} to {max}"),
+    ("analysis-unsatisfied-bound", "the trait '{name}' is not implemented for '{ty}'"),
+    ("analysis-note-required-by", "required by the bound '{bound}' of '{name}'"),
+    ("analysis-note-add-bound", "add the bound '{bound}' to '{name}'"),
+    ("analysis-instance-limit", "'{name}' instantiates itself with ever larger types"),
+    ("analysis-conflicting-impls", "conflicting impls of '{name}' for '{ty}'"),
+    ("analysis-note-other-impl", "the other impl is on line {line} of '{module}'"),
+    ("analysis-orphan-impl", "'{name}' can only be implemented for '{ty}' in the module declaring one of them"),
+    ("analysis-foreign-impl", "functions can only be added to '{ty}' in the module declaring it"),
+    ("analysis-builtin-impl", "functions can't be added to the builtin type '{ty}'"),
+    ("analysis-note-move-impl", "move the impl into '{module}'"),
+    ("analysis-note-use-trait", "declare a trait with them and implement it for '{ty}'"),
+    ("analysis-missing-trait-items", "the impl of '{trait_}' is missing {names}"),
+    ("analysis-trait-item-mismatch", "'{name}' doesn't match its declaration in the trait '{trait_}'"),
+    ("analysis-note-trait-declares", "the trait expects '{signature}'"),
+    ("analysis-not-a-trait-item", "'{name}' is not a function of the trait '{trait_}'"),
+    ("analysis-assign-immutable", "'{name}' isn't declared 'mut', so it can't be assigned to"),
+    ("analysis-assign-twice", "'{name}' might already have a value, and isn't declared 'mut'"),
+    ("analysis-borrow-immutable", "'{name}' isn't declared 'mut', so it can't be borrowed as mutable"),
+    ("analysis-note-add-mut", "declare it as '{declaration}'"),
+    ("analysis-note-mutable-copy", "copy it into a mutable variable with 'let mut {name} = {name}'"),
+    ("analysis-uninitialised", "'{name}' is used before it is given a value"),
+    ("analysis-possibly-uninitialised", "'{name}' might not have been given a value here"),
+    ("analysis-assign-through-reference", "'{place}' is behind a '&' reference, so it can't be assigned to"),
+    ("analysis-borrow-through-reference", "'{place}' is behind a '&' reference, so it can't be borrowed as mutable"),
+    ("analysis-note-mutable-reference", "change the type of '{name}' to '{ty}'"),
+    ("analysis-missing-label", "missing argument label '{label}:' in call to '{name}'"),
+    ("analysis-unexpected-label", "argument {position} of '{name}' takes no label but found '{label}:'"),
+    ("analysis-misordered-label", "argument label '{label}:' is out of order in call to '{name}'"),
+    ("analysis-wrong-label", "expected argument label '{expected}:' but found '{label}:'"),
+    ("analysis-note-declared-as", "'{name}' is declared as '{signature}'"),
+    ("analysis-return-outside-fn", "'return' is only allowed in the body of a function or closure"),
+    ("analysis-note-borrow-mutable", "borrow it as mutable where '{name}' is declared, with '{borrow}'"),
+    // command line
+    ("cli-emit-conflict", "`--emit` needs `--until {step}`, but the compiler halts at `{until}`"),
+];
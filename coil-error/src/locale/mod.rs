@@ -0,0 +1,163 @@
+use std::{
+    fmt::Display,
+    str::FromStr,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+mod en;
+mod tr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[repr(u8)]
+pub enum Lang {
+    #[default]
+    English,
+    Turkish,
+}
+
+static CURRENT_LANG: AtomicU8 = AtomicU8::new(Lang::English as u8);
+
+impl Lang {
+    pub const ALL: [Lang; 2] = [Lang::English, Lang::Turkish];
+
+    /// Picks the language from `LC_ALL`, `LC_MESSAGES` and `LANG`, in that order,
+    /// falling back to English.
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default()
+    }
+
+    pub fn catalogue(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Lang::English => en::MESSAGES,
+            Lang::Turkish => tr::MESSAGES,
+        }
+    }
+
+    pub fn get(self, key: &str) -> Option<&'static str> {
+        self.catalogue()
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, template)| *template)
+    }
+}
+
+impl FromStr for Lang {
+    type Err = String;
+
+    /// Accepts both plain codes (`en`, `tr`) and POSIX locales (`tr_TR.UTF-8`).
+    fn from_str(s: &str) -> Result<Self, String> {
+        let code = s
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match code.as_str() {
+            "c" | "posix" | "en" | "english" => Ok(Lang::English),
+            "tr" | "turkish" | "türkçe" => Ok(Lang::Turkish),
+            _ => Err(format!("unsupported language: {s}")),
+        }
+    }
+}
+
+impl Display for Lang {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Lang::English => "en",
+            Lang::Turkish => "tr",
+        })
+    }
+}
+
+pub fn set_lang(lang: Lang) {
+    CURRENT_LANG.store(lang as u8, Ordering::Relaxed);
+}
+
+pub fn lang() -> Lang {
+    match CURRENT_LANG.load(Ordering::Relaxed) {
+        1 => Lang::Turkish,
+        _ => Lang::English,
+    }
+}
+
+/// Renders the message `key` in the current language.
+pub fn message(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    message_in(lang(), key, args)
+}
+
+/// Renders the message `key` in `lang`, substituting every `{name}` with the
+/// matching argument. `{{` and `}}` produce literal braces.
+///
+/// Falls back to English when `lang` lacks the key, and to the key itself when
+/// no catalogue has it.
+pub fn message_in(lang: Lang, key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let Some(template) = lang.get(key).or_else(|| Lang::English.get(key)) else {
+        debug_assert!(false, "missing message key: {key}");
+        return key.into();
+    };
+    let mut buf = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(i) = rest.find(['{', '}']) {
+        buf.push_str(&rest[..i]);
+        let brace = rest.as_bytes()[i];
+        rest = &rest[i + 1..];
+        if rest.as_bytes().first() == Some(&brace) {
+            buf.push(brace as char);
+            rest = &rest[1..];
+            continue;
+        }
+        if brace == b'}' {
+            buf.push('}');
+            continue;
+        }
+        let Some(end) = rest.find('}') else {
+            buf.push('{');
+            continue;
+        };
+        let name = &rest[..end];
+        match args.iter().find(|(n, _)| *n == name) {
+            Some((_, value)) => buf.push_str(&value.to_string()),
+            None => {
+                debug_assert!(false, "missing argument {name:?} for message key {key}");
+                buf.push('{');
+                buf.push_str(name);
+                buf.push('}');
+            }
+        }
+        rest = &rest[end + 1..];
+    }
+    buf.push_str(rest);
+    buf
+}
+
+/// Lists the `{name}` placeholders used by `template`, in order of appearance.
+pub fn placeholders(template: &str) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut rest = template;
+    while let Some(i) = rest.find('{') {
+        rest = &rest[i + 1..];
+        if let Some(stripped) = rest.strip_prefix('{') {
+            rest = stripped;
+            continue;
+        }
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        result.push(&rest[..end]);
+        rest = &rest[end + 1..];
+    }
+    result
+}
+
+/// Renders a message from the current language's catalogue with named arguments:
+/// `msg!("lexer-expected-digit", found = format!("{c:?}"))`.
+#[macro_export]
+macro_rules! msg {
+    ($key:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::message($key, &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),*])
+    };
+}
@@ -0,0 +1,187 @@
+pub(super) static MESSAGES: &[(&str, &str)] = &[
+    // diagnostics
+    ("warning", "uyarı"),
+    ("note", "not"),
+    // tokens
+    ("token-identifier", "'{name}' tanımlayıcısı"),
+    ("token-string", "{value} dizesi"),
+    ("token-number", "'{value}' sayısı"),
+    ("token-char", "{value} karakteri"),
+    // lexer
+    (
+        "lexer-expected-digit",
+        "bir rakam bekleniyordu ama {found} bulundu",
+    ),
+    (
+        "lexer-expected-digit-eof",
+        "bir rakam bekleniyordu ama dosyanın sonuna gelindi",
+    ),
+    (
+        "lexer-expected-exponent",
+        "bir rakam, '+' ya da '-' bekleniyordu ama {found} bulundu",
+    ),
+    (
+        "lexer-expected-exponent-eof",
+        "bir rakam, '+' ya da '-' bekleniyordu ama dosyanın sonuna gelindi",
+    ),
+    (
+        "lexer-unexpected-character",
+        "beklenmeyen karakter bulundu: {found} (U+{codepoint})",
+    ),
+    ("lexer-unfinished-string", "bitmemiş dize"),
+    ("lexer-unfinished-string-escape", "bitmemiş kaçış dizisi"),
+    (
+        "lexer-invalid-string-escape",
+        "geçersiz kaçış dizisi: '\\{escape}'",
+    ),
+    (
+        "lexer-expected-string-start",
+        "'#' ya da '\"' bekleniyordu ama {found} bulundu",
+    ),
+    (
+        "lexer-expected-string-start-eof",
+        "'#' ya da '\"' bekleniyordu ama dosyanın sonuna gelindi",
+    ),
+    (
+        "lexer-note-finish-string",
+        "dizeyi bir '{terminator}' ile bitirmeyi deneyin",
+    ),
+    (
+        "lexer-note-raw-string",
+        "ham bir dize yazmak istediyseniz dizenin önüne r ekleyin",
+    ),
+    ("lexer-unfinished-comment", "bitmemiş yorum"),
+    (
+        "lexer-note-finish-comment",
+        "yorumu bir '*/' ile kapatın; blok yorumları iç içe olabildiğinden her '/*' için bir '*/' gerekir",
+    ),
+    (
+        "lexer-invalid-suffix",
+        "sayı için geçersiz sonek '{suffix}'; i8, i16, i32, i64, u8, u16, u32, u64, f32 veya f64 bekleniyordu",
+    ),
+    ("lexer-empty-char", "boş karakter değeri"),
+    ("lexer-unfinished-char", "bitmemiş karakter değeri"),
+    (
+        "lexer-note-char-string",
+        "bir karakter değeri iki ' arasında tek bir karakter tutar; daha uzun metni \"...\" içinde bir dize olarak yazın",
+    ),
+    // parser
+    (
+        "parser-expected",
+        "{expected} bekleniyordu ama {found} bulundu",
+    ),
+    (
+        "parser-expected-eof",
+        "{expected} bekleniyordu ama dosyanın sonuna gelindi",
+    ),
+    ("parser-expression", "bir ifade"),
+    ("parser-item", "bir öğe"),
+    ("parser-type", "bir tür"),
+    ("parser-identifier", "bir tanımlayıcı"),
+    ("parser-loop", "bir döngü"),
+    ("parser-pattern", "bir örüntü"),
+    ("parser-statement-end", "';' ya da yeni bir satır"),
+    ("parser-missing-body", "'{name}' fonksiyonunun gövdesi yok"),
+    (
+        "parser-extern-body",
+        "'extern' içindeki fonksiyonların gövdesi olamaz",
+    ),
+    ("parser-pub-not-allowed", "'pub' burada kullanılamaz"),
+    ("parser-chained-operator", "'{first}' ve '{second}' art arda kullanılamaz"),
+    ("parser-note-chained-comparison", "karşılaştırmaları '&&' ile birleştirin, örneğin 'a < b && b < c'"),
+    ("parser-note-parenthesize", "sırayı açıkça belirtmek için parantez ekleyin"),
+    ("parser-struct-literal-in-condition", "'{path}' struct değişmezi bir koşulda doğrudan yazılamaz"),
+    ("parser-note-struct-literal-in-condition", "koşuldan sonraki '{{' bloğu başlattığı için onu '({path} {{ ... }})' gibi parantez içine alın"),
+    ("parser-ambiguous-line-break", "{operator} ile başlayan satır önceki satırın devamı sayılıyor"),
+    ("parser-note-ambiguous-line-break", "yeni bir deyim başlatmak için önceki satırı ';' ile bitirin ya da işleci önceki satırın sonuna taşıyın"),
+    ("parser-ambiguous-line-break-statement", "{operator} ile başlayan satır yeni bir deyim başlatıyor"),
+    ("parser-note-ambiguous-line-break-statement", "bunu açıkça belirtmek için önceki satırı ';' ile bitirin ya da devam ettirmek için işleci önceki satırın sonuna taşıyın"),
+    ("parser-unknown-loop-label", "bu 'continue' çevresinde '{label}' etiketli bir döngü yok"),
+    ("parser-fallthrough-outside-match", "'fallthrough' yalnızca bir match kolunda kullanılabilir"),
+    ("parser-fallthrough-in-last-arm", "son koldaki 'fallthrough' için geçilecek bir kol yok"),
+    ("parser-missing-static-value", "'{name}' statiğinin değeri yok"),
+    ("parser-module-not-found", "'{name}' modülünün dosyası bulunamadı"),
+    ("parser-note-module-files", "'{file}' ve '{dir_file}' arandı"),
+    ("parser-module-unreadable", "'{name}' modülünün dosyası okunamıyor: {error}"),
+    ("parser-ambiguous-module-file", "'{name}' modülü hem '{file}' hem de '{dir_file}' içinde"),
+    ("parser-module-loaded-twice", "'{file}' zaten başka bir modül olarak yüklendi"),
+    ("parser-duplicate-module", "'{name}' modülü birden fazla kez tanımlanmış"),
+    ("parser-duplicate-import", "'{name}' bu modülde zaten tanımlı"),
+    ("parser-unresolved-module", "'{module}' içinde '{name}' modülü yok"),
+    ("parser-unresolved-import", "'{module}' içinde '{name}' öğesi yok"),
+    ("parser-private-import", "'{name}', '{module}' modülüne özel"),
+    ("parser-note-make-public", "başka modüllerden içe aktarmak için 'pub' ile tanımlayın"),
+    ("parser-import-cycle", "modüller birbirini döngüsel olarak içe aktarıyor: {cycle}"),
+    // analysis
+    ("analysis-unresolved-name", "bu kapsamda '{name}' bulunamadı"),
+    ("analysis-unresolved-type", "bu kapsamda '{name}' türü bulunamadı"),
+    ("analysis-unresolved-in-module", "'{module}' modülünde '{name}' öğesi yok"),
+    ("analysis-unresolved-variant", "'{parent}' enum'unda '{name}' varyantı yok"),
+    ("analysis-note-did-you-mean", "'{name}' mi demek istediniz?"),
+    ("analysis-ambiguous-name", "'{name}' birden fazla varyanta karşılık gelebilir"),
+    ("analysis-note-candidate", "'{path}' olabilir"),
+    ("analysis-duplicate-definition", "'{name}' birden fazla kez tanımlanmış"),
+    ("analysis-duplicate-field", "'{name}' alanı birden fazla kez tanımlanmış"),
+    ("analysis-note-first-definition", "ilk olarak {line}. satırda tanımlandı"),
+    ("analysis-unused-binding", "'{name}' değişkeni kullanılmıyor"),
+    ("analysis-note-prefix-underscore", "bilerek yapıldıysa adını '_{name}' koyun"),
+    ("analysis-note-keep-label", "bilerek yapıldıysa etiketi koruyup adını '{name} _{name}' yapın"),
+    ("analysis-unused-import", "'{name}' içe aktarımı kullanılmıyor"),
+    ("analysis-mismatched-types", "türler uyuşmuyor: '{expected}' bekleniyordu, '{found}' bulundu"),
+    ("analysis-not-a-type", "'{name}' bir tür değil"),
+    ("analysis-not-a-trait", "'{name}' bir trait değil"),
+    ("analysis-not-a-struct", "'{name}' bir struct ya da alanları olan bir varyant değil"),
+    ("analysis-not-a-value", "'{name}' bir değer değil"),
+    ("analysis-recursive-alias", "'{name}' tür takma adı kendine başvuruyor"),
+    ("analysis-type-argument-count", "'{name}' {expected} tür argümanı alıyor ama {found} verildi"),
+    ("analysis-argument-count", "{expected} argüman bekleniyordu ama {found} bulundu"),
+    ("analysis-pattern-field-count", "bu desende {expected} alan bekleniyordu ama {found} bulundu"),
+    ("analysis-unknown-field", "'{ty}' türünde '{name}' alanı yok"),
+    ("analysis-unknown-method", "'{ty}' türünde '{name}' metodu yok"),
+    ("analysis-unknown-function", "'{ty}' türünde '{name}' fonksiyonu yok"),
+    ("analysis-missing-fields", "'{name}' içinde {fields} alanları eksik"),
+    ("analysis-not-callable", "'{ty}' türünde bir değer çağrılamaz"),
+    ("analysis-cannot-infer", "'{name}' değişkeninin türü çıkarılamıyor"),
+    ("analysis-note-annotate", "'{name}' için bir tür yazın, örneğin 'let {name}: Tür'"),
+    ("analysis-invalid-operand", "'{op}', '{ty}' türüne uygulanamaz"),
+    ("analysis-not-dereferenceable", "'{ty}' türünde bir değerin gösterdiğine erişilemez"),
+    ("analysis-not-indexable", "'{ty}' türünde bir değer indekslenemez"),
+    ("analysis-not-iterable", "'{ty}' türünde bir değer üzerinde dolaşılamaz"),
+    ("analysis-try-return", "'?' yalnızca opsiyonel ya da sonuç döndüren bir fonksiyonda kullanılabilir"),
+    ("analysis-literal-out-of-range", "'{ty}' için değer aralık dışında"),
+    ("analysis-note-range", "'{ty}', {min} ile {max} arasındaki değerleri tutar"),
+    ("analysis-unsatisfied-bound", "'{name}' trait'i '{ty}' için uygulanmamış"),
+    ("analysis-note-required-by", "'{name}' öğesinin '{bound}' sınırı gerektiriyor"),
+    ("analysis-note-add-bound", "'{name}' öğesine '{bound}' sınırını ekleyin"),
+    ("analysis-instance-limit", "'{name}' kendini giderek büyüyen türlerle örnekliyor"),
+    ("analysis-conflicting-impls", "'{ty}' için '{name}' impl'leri çakışıyor"),
+    ("analysis-note-other-impl", "diğer impl '{module}' içinde {line}. satırda"),
+    ("analysis-orphan-impl", "'{name}', '{ty}' için yalnızca ikisinden birini tanımlayan modülde uygulanabilir"),
+    ("analysis-foreign-impl", "'{ty}' türüne yalnızca onu tanımlayan modülde fonksiyon eklenebilir"),
+    ("analysis-builtin-impl", "yerleşik '{ty}' türüne fonksiyon eklenemez"),
+    ("analysis-note-move-impl", "impl'i '{module}' içine taşıyın"),
+    ("analysis-note-use-trait", "bunlarla bir trait tanımlayıp '{ty}' için uygulayın"),
+    ("analysis-missing-trait-items", "'{trait_}' impl'inde {names} eksik"),
+    ("analysis-trait-item-mismatch", "'{name}', '{trait_}' trait'indeki tanımıyla uyuşmuyor"),
+    ("analysis-note-trait-declares", "trait '{signature}' bekliyor"),
+    ("analysis-not-a-trait-item", "'{name}', '{trait_}' trait'inin bir fonksiyonu değil"),
+    ("analysis-assign-immutable", "'{name}' 'mut' olarak tanımlanmadığı için ona atama yapılamaz"),
+    ("analysis-assign-twice", "'{name}' zaten bir değere sahip olabilir ve 'mut' olarak tanımlanmamış"),
+    ("analysis-borrow-immutable", "'{name}' 'mut' olarak tanımlanmadığı için değiştirilebilir olarak ödünç alınamaz"),
+    ("analysis-note-add-mut", "'{declaration}' olarak tanımlayın"),
+    ("analysis-note-mutable-copy", "'let mut {name} = {name}' ile değiştirilebilir bir değişkene kopyalayın"),
+    ("analysis-uninitialised", "'{name}' bir değer verilmeden kullanılıyor"),
+    ("analysis-possibly-uninitialised", "'{name}' burada bir değer almamış olabilir"),
+    ("analysis-assign-through-reference", "'{place}' bir '&' referansının arkasında olduğu için ona atama yapılamaz"),
+    ("analysis-borrow-through-reference", "'{place}' bir '&' referansının arkasında olduğu için değiştirilebilir olarak ödünç alınamaz"),
+    ("analysis-note-mutable-reference", "'{name}' türünü '{ty}' olarak değiştirin"),
+    ("analysis-missing-label", "'{name}' çağrısında '{label}:' argüman etiketi eksik"),
+    ("analysis-unexpected-label", "'{name}' fonksiyonunun {position}. argümanı etiket almıyor ama '{label}:' bulundu"),
+    ("analysis-misordered-label", "'{name}' çağrısında '{label}:' argüman etiketi yanlış sırada"),
+    ("analysis-wrong-label", "'{expected}:' argüman etiketi bekleniyordu ama '{label}:' bulundu"),
+    ("analysis-note-declared-as", "'{name}' şöyle tanımlandı: '{signature}'"),
+    ("analysis-return-outside-fn", "'return' yalnızca bir fonksiyonun ya da kapanışın gövdesinde kullanılabilir"),
+    ("analysis-note-borrow-mutable", "'{name}' bildirildiği yerde '{borrow}' ile değiştirilebilir olarak ödünç alın"),
+    // command line
+    ("cli-emit-conflict", "`--emit` için `--until {step}` gerekiyor ama derleyici `{until}` adımında duruyor"),
+];
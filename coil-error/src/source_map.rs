@@ -0,0 +1,161 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct FileId(pub u32);
+
+/// A byte range `start..end` inside one file of a [`SourceMap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    pub file: FileId,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Span {
+    pub const fn new(file: FileId, start: u32, end: u32) -> Self {
+        Self { file, start, end }
+    }
+
+    /// The smallest span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Span {
+        debug_assert_eq!(self.file, other.file);
+        Span {
+            file: self.file,
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    pub fn len(&self) -> u32 {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// A resolved, 1-based position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub id: FileId,
+    pub name: Box<str>,
+    pub path: Option<PathBuf>,
+    pub source: Box<str>,
+    line_starts: Box<[u32]>,
+}
+
+impl SourceFile {
+    fn new(id: FileId, name: &str, path: Option<PathBuf>, source: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i as u32 + 1))
+            .collect();
+        Self {
+            id,
+            name: name.into(),
+            path,
+            source: source.into(),
+            line_starts,
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Maps a byte offset to its line and column; columns count characters.
+    pub fn location(&self, offset: u32) -> Location {
+        let offset = offset.min(self.source.len() as u32);
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.line_starts[line] as usize;
+        let column = self.source[start..offset as usize].chars().count() + 1;
+        Location {
+            line: line + 1,
+            column,
+        }
+    }
+
+    /// The text of the 1-based `line`, without its line terminator.
+    pub fn line_text(&self, line: usize) -> Option<&str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)? as usize;
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.source.len(), |&end| end as usize);
+        Some(self.source[start..end].trim_end_matches(['\n', '\r']))
+    }
+
+    pub fn text(&self, span: Span) -> &str {
+        &self.source[span.start as usize..span.end as usize]
+    }
+
+    pub fn module_name(&self) -> &str {
+        module_name(&self.name)
+    }
+}
+
+/// The name of the module a file defines: its file stem.
+pub fn module_name(file: &str) -> &str {
+    Path::new(file)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(file)
+}
+
+/// Owns every file loaded during a compilation; every stage refers to them by [`FileId`].
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file that does not live on disk, such as `<inline>` test sources.
+    pub fn add(&mut self, name: &str, source: &str) -> FileId {
+        let id = FileId(self.files.len() as u32);
+        self.files.push(SourceFile::new(id, name, None, source));
+        id
+    }
+
+    /// Reads the file at `path`, or returns the existing id if it was already loaded.
+    pub fn load(&mut self, path: &Path) -> io::Result<FileId> {
+        let path = path.canonicalize()?;
+        if let Some(file) = self.files.iter().find(|f| f.path.as_deref() == Some(&path)) {
+            return Ok(file.id);
+        }
+        let source = std::fs::read_to_string(&path)?;
+        let id = FileId(self.files.len() as u32);
+        let name = path.to_string_lossy().into_owned();
+        self.files
+            .push(SourceFile::new(id, &name, Some(path), &source));
+        Ok(id)
+    }
+
+    pub fn get(&self, id: FileId) -> &SourceFile {
+        &self.files[id.0 as usize]
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &SourceFile> {
+        self.files.iter()
+    }
+
+    pub fn location(&self, span: Span) -> Location {
+        self.get(span.file).location(span.start)
+    }
+
+    pub fn text(&self, span: Span) -> &str {
+        self.get(span.file).text(span)
+    }
+}
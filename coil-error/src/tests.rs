@@ -1,4 +1,6 @@
-use crate::{Error, ErrorCode};
+use crate::{
+    message_in, placeholders, Error, ErrorCode, Lang, Location, Severity, SourceMap, Span,
+};
 
 #[test]
 fn test_error_new() {
@@ -10,10 +12,12 @@ fn test_error_new() {
     assert_eq!(
         Error {
             code,
+            severity: Severity::Error,
             message: message.into(),
             file: file.into(),
             line,
-            notes: notes,
+            span: None,
+            notes,
         },
         Error::new(code, message, file, line),
     )
@@ -29,11 +33,131 @@ fn test_error_with_note() {
     assert_eq!(
         Error {
             code,
+            severity: Severity::Error,
             message: message.into(),
             file: file.into(),
             line,
-            notes: notes,
+            span: None,
+            notes,
         },
         Error::new(code, message, file, line).with_note("blah blah"),
     )
 }
+
+#[test]
+fn test_catalogues_complete() {
+    for lang in Lang::ALL {
+        for other in Lang::ALL {
+            for (key, template) in lang.catalogue() {
+                let translation = other
+                    .get(key)
+                    .unwrap_or_else(|| panic!("{key:?} has no translation for {other}"));
+                // translations may reorder arguments, but must use the same ones
+                let mut expected = placeholders(template);
+                let mut found = placeholders(translation);
+                expected.sort_unstable();
+                found.sort_unstable();
+                assert_eq!(
+                    expected, found,
+                    "{key:?} uses different arguments in {lang} and {other}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_catalogues_no_duplicates() {
+    for lang in Lang::ALL {
+        let catalogue = lang.catalogue();
+        for (i, (key, _)) in catalogue.iter().enumerate() {
+            assert!(
+                catalogue[i + 1..].iter().all(|(k, _)| k != key),
+                "{key:?} is defined twice for {lang}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_message_named_arguments() {
+    let found = "'.'";
+    assert_eq!(
+        message_in(Lang::English, "lexer-expected-digit", &[("found", &found)]),
+        "expected a digit but found '.'"
+    );
+    assert_eq!(
+        message_in(Lang::Turkish, "lexer-expected-digit", &[("found", &found)]),
+        "bir rakam bekleniyordu ama '.' bulundu"
+    );
+    assert_eq!(
+        message_in(
+            Lang::English,
+            "lexer-invalid-string-escape",
+            &[("escape", &'z')]
+        ),
+        "invalid string escape: '\\z'"
+    );
+}
+
+#[test]
+fn test_lang_from_str() {
+    assert_eq!("en".parse(), Ok(Lang::English));
+    assert_eq!("C".parse(), Ok(Lang::English));
+    assert_eq!("en_US.UTF-8".parse(), Ok(Lang::English));
+    assert_eq!("tr".parse(), Ok(Lang::Turkish));
+    assert_eq!("tr_TR.UTF-8".parse(), Ok(Lang::Turkish));
+    assert!("xx_XX".parse::<Lang>().is_err());
+}
+
+#[test]
+fn test_source_map_location() {
+    let mut map = SourceMap::new();
+    let file = map.add("dir/main.coil", "let a = 1\nlet bü = 2\n\nend");
+    let file = map.get(file);
+    assert_eq!(file.line_count(), 4);
+    assert_eq!(file.location(0), Location { line: 1, column: 1 });
+    assert_eq!(file.location(4), Location { line: 1, column: 5 });
+    assert_eq!(file.location(10), Location { line: 2, column: 1 });
+    // 'ü' takes two bytes but is a single column
+    assert_eq!(file.location(17), Location { line: 2, column: 7 });
+    assert_eq!(file.location(23), Location { line: 4, column: 1 });
+    assert_eq!(file.line_text(2), Some("let bü = 2"));
+    assert_eq!(file.line_text(3), Some(""));
+    assert_eq!(file.line_text(5), None);
+    assert_eq!(file.module_name(), "main");
+}
+
+#[test]
+fn test_source_map_files() {
+    let mut map = SourceMap::new();
+    let a = map.add("a.coil", "fn a");
+    let b = map.add("b.coil", "fn b");
+    assert_ne!(a, b);
+    assert_eq!(map.text(Span::new(b, 3, 4)), "b");
+    assert_eq!(
+        map.location(Span::new(a, 3, 4)),
+        Location { line: 1, column: 4 }
+    );
+    assert_eq!(map.files().count(), 2);
+}
+
+#[test]
+fn test_error_display_with_source() {
+    let mut map = SourceMap::new();
+    let file = map.add("main.coil", "let a = 1\nlet b = $\n");
+    let error =
+        Error::at(ErrorCode(1), "message", &map, Span::new(file, 18, 19)).with_note("blah blah");
+    assert_eq!(error.line, 2);
+    assert_eq!(
+        error.display(&map).to_string(),
+        "E1: message\nmain.coil:2:9\n  |\n2 | let b = $\n  |         ^\nnote: blah blah\n"
+    );
+}
+
+#[test]
+fn test_warning_display() {
+    let warning = Error::new(ErrorCode(1), "message", "<inline>", 2).warning();
+    assert!(warning.is_warning());
+    assert_eq!(warning.to_string(), "warning: E1: message\n<inline>:2\n");
+}
@@ -0,0 +1,76 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+/// The settings of `coil-fmt.toml`, all of which may be left out:
+///
+/// ```toml
+/// max_width = 100
+/// indent_width = 4
+/// hard_tabs = false
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Lines longer than this are broken inside their brackets where possible.
+    pub max_width: usize,
+    /// The width of one level of indentation.
+    pub indent_width: usize,
+    /// Indents with tabs rather than spaces, each counting as `indent_width` columns.
+    pub hard_tabs: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_width: 100,
+            indent_width: 4,
+            hard_tabs: false,
+        }
+    }
+}
+
+impl Config {
+    pub const FILE_NAME: &'static str = "coil-fmt.toml";
+
+    pub fn parse(text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    /// The settings for the files of `dir`, from the closest `coil-fmt.toml` in `dir` or
+    /// one of its parents, or the defaults if there is none.
+    pub fn find(dir: &Path) -> io::Result<Self> {
+        match dir
+            .ancestors()
+            .map(|dir| dir.join(Self::FILE_NAME))
+            .find(|path| path.is_file())
+        {
+            Some(path) => Self::load(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| invalid(path.to_owned(), e))
+    }
+
+    /// The text of `levels` levels of indentation.
+    pub(crate) fn indent(&self, levels: usize) -> String {
+        if self.hard_tabs {
+            "\t".repeat(levels)
+        } else {
+            " ".repeat(levels * self.indent_width)
+        }
+    }
+}
+
+fn invalid(path: PathBuf, error: toml::de::Error) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), error.message()),
+    )
+}
@@ -0,0 +1,48 @@
+//! Prints Coil source in one canonical style. The formatter works on the tokens and
+//! comments of a file rather than on its AST, so nothing but whitespace ever changes:
+//!
+//! - line breaks stay only where they end a statement, next to a comment, or between
+//!   the items of a `{` that isn't a body, like the fields of a struct; the others are
+//!   joined, so `}` and `else` share a line, and runs of blank lines shrink to one,
+//! - the inside of a body, like that of a block, a `match` or a trait, starts on the line
+//!   after its `{`, and its `}` goes on the line after the inside,
+//! - lines are indented by the brackets open around them, plus one level for a line
+//!   continuing an expression, like one starting with `.` or following a `=`,
+//! - spaces within a line follow the kind of the tokens on either side, with the AST
+//!   telling the `<` and `>` of generics from comparisons,
+//! - lines longer than [`Config::max_width`] are broken after the `(` or `[` of a list,
+//!   with one item per line.
+
+mod config;
+mod printer;
+
+use coil_error::{Error, FileId, SourceMap};
+use coil_lexer::Lexer;
+use coil_parser::Parser;
+pub use config::Config;
+
+/// Formats a file of `map`. A file has to parse to be formatted, so its syntax errors are
+/// returned instead if it has any.
+pub fn format(map: &SourceMap, file: FileId, config: &Config) -> Result<String, Vec<Error>> {
+    let mut parser = Parser::new(Lexer::from_source(map, file));
+    let ast = parser.parse_recovering();
+    if !parser.errors().is_empty() {
+        return Err(parser.errors().to_vec());
+    }
+    let source = &map.get(file).source;
+    let mut lexer = Lexer::from_source(map, file);
+    let mut tokens = vec![];
+    while let Some(token) = lexer.next_token().map_err(|e| vec![e])? {
+        tokens.push((token.kind, lexer.span()));
+    }
+    Ok(printer::print(
+        source,
+        &ast,
+        tokens,
+        lexer.comments(),
+        config,
+    ))
+}
+
+#[cfg(test)]
+mod tests;
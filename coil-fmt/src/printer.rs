@@ -0,0 +1,604 @@
+use std::collections::HashSet;
+
+use coil_error::Span;
+use coil_lexer::{Comment, Keyword, Lexer, Operator, Parenthesis, TokenKind};
+use coil_parser::{ends_statement, starts_statement, walk, Expr, ExprKind, Statement, Visitor};
+
+use crate::Config;
+
+/// A token or a comment, with its text as written.
+#[derive(Debug, Clone)]
+struct Piece<'a> {
+    text: &'a str,
+    kind: PieceKind,
+    span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PieceKind {
+    Token(TokenKind),
+    LineComment,
+    BlockComment,
+}
+
+/// How an operator is used where it appears, which decides the spaces around it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    /// Anything but an operator, or an operator with a space on each side.
+    Plain,
+    /// A prefix operator, like the `-` of `-x`, with no space after it.
+    Prefix,
+    /// An operator without spaces around it, like the `..` of `0..n` or the `!` of `T!E`.
+    Tight,
+    /// The `<` opening the arguments or parameters of a generic, like that of `Vec<T>`.
+    Open,
+    /// The `>` closing the arguments or parameters of a generic.
+    Close,
+}
+
+/// A line of output, which is a blank line when it has no items.
+#[derive(Debug, Clone)]
+struct Line<'a> {
+    indent: usize,
+    items: Vec<Item<'a>>,
+}
+
+#[derive(Debug, Clone)]
+struct Item<'a> {
+    piece: Piece<'a>,
+    /// Whether a space separates the item from the one before it on the line.
+    space: bool,
+}
+
+pub(crate) fn print(
+    source: &str,
+    ast: &Expr,
+    tokens: Vec<(TokenKind, Span)>,
+    comments: &[Comment],
+    config: &Config,
+) -> String {
+    let bodies = body_braces(ast, &tokens);
+    let operators = angle_operators(ast, &tokens);
+    let mut pieces: Vec<_> = tokens
+        .into_iter()
+        .map(|(kind, span)| Piece {
+            text: &source[span.start as usize..span.end as usize],
+            kind: PieceKind::Token(kind),
+            span,
+        })
+        .chain(comments.iter().map(|comment| Piece {
+            text: source[comment.span.start as usize..comment.span.end as usize].trim_end(),
+            kind: if comment.block {
+                PieceKind::BlockComment
+            } else {
+                PieceKind::LineComment
+            },
+            span: comment.span,
+        }))
+        .collect();
+    pieces.sort_by_key(|piece| piece.span.start);
+    let mut out = String::new();
+    for line in lines(source, pieces, &bodies, &operators) {
+        write_line(&line, config, &mut out);
+    }
+    out
+}
+
+/// The offsets of the `{` opening the body of a block, a `match`, or an item holding
+/// other items, like a trait or an inline module.
+fn body_braces(ast: &Expr, tokens: &[(TokenKind, Span)]) -> HashSet<u32> {
+    let mut bodies = Bodies { ranges: vec![] };
+    bodies.visit_expr(ast);
+    let open_curly = TokenKind::Parenthesis {
+        closing: false,
+        kind: Parenthesis::Curly,
+    };
+    bodies
+        .ranges
+        .into_iter()
+        .filter_map(|(start, end)| {
+            let first = tokens.partition_point(|(_, span)| span.start < start);
+            let last = tokens.partition_point(|(_, span)| span.start < end);
+            tokens[first..last]
+                .iter()
+                .rev()
+                .find(|(kind, _)| *kind == open_curly)
+                .map(|(_, span)| span.start)
+        })
+        .collect()
+}
+
+/// Finds where bodies start: each is opened by the last `{` within one of `ranges`.
+/// Bodies without anything inside are left out, since they stay `{}`.
+struct Bodies {
+    ranges: Vec<(u32, u32)>,
+}
+
+impl Visitor<'_> for Bodies {
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Block(statements) if !statements.is_empty() => {
+                self.ranges.push((expr.span.start, expr.span.start + 1));
+            }
+            ExprKind::Match { arms, .. } => {
+                if let Some(arm) = arms.first() {
+                    self.ranges.push((expr.span.start, arm.pattern.span.start));
+                }
+            }
+            ExprKind::Statement(
+                Statement::Module {
+                    inline: true,
+                    children: items,
+                    ..
+                }
+                | Statement::Trait { items, .. }
+                | Statement::Impl { items, .. }
+                | Statement::Extern { items, .. },
+            ) => {
+                if let Some(item) = items.first() {
+                    self.ranges.push((expr.span.start, item.span.start));
+                }
+            }
+            _ => {}
+        }
+        walk::expr(self, expr);
+    }
+}
+
+/// The offsets of the `<` and `>` tokens that are operators, like those of `a < b` and
+/// `x >> 2`, rather than the brackets of generics.
+fn angle_operators(ast: &Expr, tokens: &[(TokenKind, Span)]) -> HashSet<u32> {
+    let mut operands = Operands { gaps: vec![] };
+    operands.visit_expr(ast);
+    operands
+        .gaps
+        .into_iter()
+        .filter_map(|(start, end)| {
+            let first = tokens.partition_point(|(_, span)| span.start < start);
+            let last = tokens.partition_point(|(_, span)| span.start < end);
+            tokens[first..last]
+                .iter()
+                .find(|(kind, _)| is_angle(kind))
+                .map(|(_, span)| span.start)
+        })
+        .collect()
+}
+
+/// Finds the gap between the operands of each binary operation, which holds its operator
+/// and maybe some brackets.
+struct Operands {
+    gaps: Vec<(u32, u32)>,
+}
+
+impl Visitor<'_> for Operands {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let ExprKind::Binary { left, right, .. } = &expr.kind {
+            self.gaps.push((left.span.end, right.span.start));
+        }
+        walk::expr(self, expr);
+    }
+}
+
+/// Tokens that are either operators or the brackets of generics.
+fn is_angle(kind: &TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::Operator(
+            Operator::Lesser
+                | Operator::Greater
+                | Operator::GreaterEq
+                | Operator::BitShiftRight
+                | Operator::BitShiftRightAssign
+        )
+    )
+}
+
+/// Lays the pieces out on lines, with their indentation and the spaces between them
+/// worked out. A line break stays only where it ends a statement, or next to a comment,
+/// or around the items in a `{` that isn't a body, like the fields of a struct. The
+/// inside of a body always starts on the line after its `{`, or after a line comment
+/// following it, and its `}` on a line of its own.
+fn lines<'a>(
+    source: &str,
+    pieces: Vec<Piece<'a>>,
+    bodies: &HashSet<u32>,
+    operators: &HashSet<u32>,
+) -> Vec<Line<'a>> {
+    let mut lines: Vec<Line> = vec![];
+    // The brackets open so far, with the indentation of the line each was opened on and
+    // whether they open a body.
+    let mut open: Vec<(Parenthesis, usize, bool)> = vec![];
+    let mut prev: Option<(Span, Role, bool)> = None;
+    let mut last_token: Option<TokenKind> = None;
+    for piece in pieces {
+        let role = match &piece.kind {
+            PieceKind::Token(kind) if is_angle(kind) && !operators.contains(&piece.span.start) => {
+                if *kind == TokenKind::Operator(Operator::Lesser) {
+                    Role::Open
+                } else {
+                    Role::Close
+                }
+            }
+            _ => role(&piece.kind, last_token.as_ref()),
+        };
+        let opens_body = bodies.contains(&piece.span.start);
+        let closes_body =
+            bracket(&piece.kind) == Some(true) && open.last().is_some_and(|&(_, _, body)| body);
+        let space = match prev {
+            None => {
+                lines.push(Line {
+                    indent: 0,
+                    items: vec![],
+                });
+                false
+            }
+            Some((span, prev_role, prev_opens)) => {
+                let gap = &source[span.end as usize..piece.span.start as usize];
+                let after_body = prev_opens && open.last().is_some_and(|&(_, _, body)| body);
+                let trailing_comment = piece.kind == PieceKind::LineComment && !gap.contains('\n');
+                let forced =
+                    after_body && !closes_body && !trailing_comment || closes_body && !prev_opens;
+                let prev = &lines.last().unwrap().items.last().unwrap().piece;
+                let written = match gap.matches('\n').count() {
+                    breaks if keeps_break(&prev.kind, prev_role, &piece.kind, &open) => breaks,
+                    _ => 0,
+                };
+                let breaks = written.max(forced as usize);
+                if breaks == 0 {
+                    spacing(prev, prev_role, &piece, role, !gap.is_empty())
+                } else {
+                    if breaks > 1 && !prev_opens && bracket(&piece.kind) != Some(true) {
+                        lines.push(Line {
+                            indent: 0,
+                            items: vec![],
+                        });
+                    }
+                    lines.push(Line {
+                        indent: indent(&piece.kind, &open, last_token.as_ref()),
+                        items: vec![],
+                    });
+                    false
+                }
+            }
+        };
+        let line = lines.last_mut().unwrap();
+        // The parser takes a `*` starting a line in a block as a dereference.
+        let role = if line.items.is_empty()
+            && piece.kind == PieceKind::Token(TokenKind::Operator(Operator::Star))
+            && in_block(&open)
+        {
+            Role::Prefix
+        } else {
+            role
+        };
+        if let PieceKind::Token(kind) = &piece.kind {
+            match kind {
+                TokenKind::Parenthesis {
+                    closing: false,
+                    kind,
+                } => open.push((*kind, line.indent, opens_body)),
+                TokenKind::Parenthesis { closing: true, .. } => {
+                    open.pop();
+                }
+                _ => {}
+            }
+            last_token = Some(kind.clone());
+        }
+        prev = Some((piece.span, role, bracket(&piece.kind) == Some(false)));
+        line.items.push(Item { piece, space });
+    }
+    lines
+}
+
+/// Whether a line break written between `prev` and `next` stays.
+fn keeps_break(
+    prev: &PieceKind,
+    prev_role: Role,
+    next: &PieceKind,
+    open: &[(Parenthesis, usize, bool)],
+) -> bool {
+    let (PieceKind::Token(p), PieceKind::Token(n)) = (prev, next) else {
+        return true;
+    };
+    in_block(open)
+        && ((ends_statement(p) || prev_role == Role::Close) && starts_statement(n)
+            || matches!(
+                p,
+                TokenKind::Operator(Operator::Comma | Operator::Semicolon)
+                    | TokenKind::Parenthesis {
+                        closing: false,
+                        kind: Parenthesis::Curly
+                    }
+            )
+            || matches!(
+                n,
+                TokenKind::Parenthesis {
+                    closing: true,
+                    kind: Parenthesis::Curly
+                }
+            ))
+}
+
+/// The indentation of a line starting with `kind`. Closing brackets line up with the line
+/// of their opening bracket, and everything else goes one level further than that, plus
+/// one level when the line continues an expression in a block.
+fn indent(
+    kind: &PieceKind,
+    open: &[(Parenthesis, usize, bool)],
+    last_token: Option<&TokenKind>,
+) -> usize {
+    if bracket(kind) == Some(true) {
+        return open.last().map_or(0, |&(_, indent, _)| indent);
+    }
+    let base = open.last().map_or(0, |&(_, indent, _)| indent + 1);
+    // A `*` starts a statement there, like a prefix-only operator.
+    let starts_with_operator = matches!(
+        kind,
+        PieceKind::Token(TokenKind::Operator(op)) if !matches!(
+            op,
+            Operator::Not | Operator::BitNot | Operator::Backslash | Operator::Star
+        )
+    );
+    // A `>` at the end of a line is more likely to close a type than to compare.
+    let follows_operator = matches!(
+        last_token,
+        Some(TokenKind::Operator(op)) if !matches!(
+            op,
+            Operator::Comma
+                | Operator::Semicolon
+                | Operator::QuestionMark
+                | Operator::Greater
+                | Operator::BitShiftRight
+        )
+    );
+    base + (in_block(open) && (starts_with_operator || follows_operator)) as usize
+}
+
+/// Whether the innermost bracket of `open` is a `{`, or there is none, so that a line
+/// break there may end a statement.
+fn in_block(open: &[(Parenthesis, usize, bool)]) -> bool {
+    matches!(open.last(), None | Some((Parenthesis::Curly, _, _)))
+}
+
+/// `Some(true)` for closing brackets, `Some(false)` for opening ones.
+fn bracket(kind: &PieceKind) -> Option<bool> {
+    match kind {
+        PieceKind::Token(TokenKind::Parenthesis { closing, .. }) => Some(*closing),
+        _ => None,
+    }
+}
+
+fn role(kind: &PieceKind, before: Option<&TokenKind>) -> Role {
+    let PieceKind::Token(TokenKind::Operator(op)) = kind else {
+        return Role::Plain;
+    };
+    let after_operand = before.is_some_and(ends_operand);
+    match op {
+        Operator::DoubleDot | Operator::Not if after_operand => Role::Tight,
+        Operator::DoubleDot
+        | Operator::Not
+        | Operator::Minus
+        | Operator::Plus
+        | Operator::Star
+        | Operator::BitAnd
+        | Operator::And
+        | Operator::BitNot
+            if !after_operand =>
+        {
+            Role::Prefix
+        }
+        Operator::Backslash => Role::Prefix,
+        _ => Role::Plain,
+    }
+}
+
+/// Tokens after which an operator is binary or postfix rather than prefix.
+fn ends_operand(kind: &TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::Identifier(_)
+            | TokenKind::Literal(..)
+            | TokenKind::Keyword(Keyword::True | Keyword::False | Keyword::SelfType)
+            | TokenKind::Operator(Operator::QuestionMark)
+            | TokenKind::Parenthesis { closing: true, .. }
+    )
+}
+
+/// Whether a space goes between two pieces on the same line. Between tokens it follows
+/// their kinds; next to a block comment, `had_space` tells whether the source had one.
+fn spacing(prev: &Piece, prev_role: Role, next: &Piece, next_role: Role, had_space: bool) -> bool {
+    let (p, n) = match (&prev.kind, &next.kind) {
+        (_, PieceKind::LineComment) => return true,
+        (PieceKind::Token(p), PieceKind::Token(n)) => (p, n),
+        _ => return had_space,
+    };
+    token_spacing(p, prev_role, n, next_role) || joins(prev.text, next.text, p, n)
+}
+
+/// Whether a space goes between two tokens, unless they would lex as something else
+/// without one.
+fn token_spacing(p: &TokenKind, prev_role: Role, n: &TokenKind, next_role: Role) -> bool {
+    let is_op = |kind: &TokenKind, ops: &[Operator]| matches!(kind, TokenKind::Operator(op) if ops.contains(op));
+    let is_bracket = |kind: &TokenKind, closing: bool, kinds: &[Parenthesis]| matches!(kind, TokenKind::Parenthesis { closing: c, kind } if *c == closing && kinds.contains(kind));
+    const SEPARATORS: &[Operator] = &[Operator::Comma, Operator::Semicolon, Operator::Colon];
+    const LISTS: &[Parenthesis] = &[Parenthesis::Normal, Parenthesis::Square];
+
+    if is_op(n, SEPARATORS) || is_bracket(n, true, LISTS) {
+        return false;
+    }
+    if is_op(p, SEPARATORS) {
+        return true;
+    }
+    if is_bracket(p, false, LISTS) {
+        return false;
+    }
+    if is_bracket(p, false, &[Parenthesis::Curly]) {
+        return !is_bracket(n, true, &[Parenthesis::Curly]);
+    }
+    if is_bracket(n, true, &[Parenthesis::Curly]) {
+        return true;
+    }
+    if matches!(next_role, Role::Open | Role::Close) || prev_role == Role::Open {
+        return false;
+    }
+    if is_op(p, &[Operator::Dot]) || is_op(n, &[Operator::Dot]) {
+        return false;
+    }
+    if is_bracket(n, false, &[Parenthesis::Curly]) {
+        return true;
+    }
+    match prev_role {
+        // The arguments of a generic call or a type like `T!E` follow without a space.
+        Role::Close => {
+            return !(is_bracket(n, false, LISTS)
+                || is_op(n, &[Operator::Not, Operator::QuestionMark]))
+        }
+        Role::Prefix | Role::Tight => return false,
+        Role::Plain | Role::Open => {}
+    }
+    if next_role == Role::Tight || is_op(n, &[Operator::QuestionMark]) && ends_operand(p) {
+        return false;
+    }
+    if is_bracket(n, false, LISTS) {
+        return !matches!(
+            p,
+            TokenKind::Identifier(_)
+                | TokenKind::Literal(..)
+                | TokenKind::Keyword(Keyword::Fn | Keyword::SelfType)
+                | TokenKind::Parenthesis { closing: true, .. }
+        );
+    }
+    true
+}
+
+/// Whether two tokens would lex as something else without a space between them, like
+/// `0` and `.` before a `5`.
+fn joins(prev: &str, next: &str, p: &TokenKind, n: &TokenKind) -> bool {
+    let text = format!("{prev}{next}");
+    let mut lexer = Lexer::new("", &text);
+    let mut kinds = vec![];
+    while let Ok(Some(token)) = lexer.next_token() {
+        kinds.push(token.kind);
+    }
+    kinds.len() != 2 || kinds[0] != *p || kinds[1] != *n || !lexer.comments().is_empty()
+}
+
+fn write_line(line: &Line, config: &Config, out: &mut String) {
+    if line.items.is_empty() {
+        out.push('\n');
+        return;
+    }
+    if width(line, config) > config.max_width {
+        if let Some(lines) = break_line(line) {
+            for line in &lines {
+                write_line(line, config, out);
+            }
+            return;
+        }
+    }
+    out.push_str(&config.indent(line.indent));
+    for (i, item) in line.items.iter().enumerate() {
+        if i > 0 && item.space {
+            out.push(' ');
+        }
+        out.push_str(item.piece.text);
+    }
+    out.push('\n');
+}
+
+/// The width of the first line of `line`, which only has more when it holds a multiline
+/// string or comment.
+fn width(line: &Line, config: &Config) -> usize {
+    let mut width = line.indent * config.indent_width;
+    for (i, item) in line.items.iter().enumerate() {
+        width += (i > 0 && item.space) as usize;
+        match item.piece.text.split_once('\n') {
+            Some((first, _)) => return width + first.chars().count(),
+            None => width += item.piece.text.chars().count(),
+        }
+    }
+    width
+}
+
+/// Breaks a list in `(` or `[` that opens and closes on the line, putting each of its
+/// items on a line of its own. The outermost list is broken first, and the first one
+/// when several are as deep. Line breaks directly inside `(` and `[` don't end
+/// statements, so this doesn't change the meaning of the code.
+fn break_line<'a>(line: &Line<'a>) -> Option<Vec<Line<'a>>> {
+    let items = &line.items;
+    let mut open = vec![];
+    let mut best: Option<(usize, usize, usize)> = None;
+    for (i, item) in items.iter().enumerate() {
+        match &item.piece.kind {
+            PieceKind::Token(TokenKind::Parenthesis {
+                closing: false,
+                kind,
+            }) => open.push((i, *kind)),
+            PieceKind::Token(TokenKind::Parenthesis { closing: true, .. }) => {
+                let Some((start, kind)) = open.pop() else {
+                    continue;
+                };
+                let depth = open.len();
+                let breakable = kind != Parenthesis::Curly
+                    && i > start + 1
+                    && !has_top_level(&items[start + 1..i], Operator::Semicolon);
+                if breakable && best.is_none_or(|(d, s, _)| (depth, start) < (d, s)) {
+                    best = Some((depth, start, i));
+                }
+            }
+            _ => {}
+        }
+    }
+    let (_, start, end) = best?;
+    let mut lines = vec![Line {
+        indent: line.indent,
+        items: items[..=start].to_vec(),
+    }];
+    let mut first = start + 1;
+    let mut depth = 0;
+    for (i, item) in items.iter().enumerate().take(end).skip(start + 1) {
+        match bracket(&item.piece.kind) {
+            Some(false) => depth += 1,
+            Some(true) => depth -= 1,
+            None if depth == 0 && is_comma(&item.piece.kind) => {
+                lines.push(Line {
+                    indent: line.indent + 1,
+                    items: items[first..=i].to_vec(),
+                });
+                first = i + 1;
+            }
+            None => {}
+        }
+    }
+    if first < end {
+        lines.push(Line {
+            indent: line.indent + 1,
+            items: items[first..end].to_vec(),
+        });
+    }
+    lines.push(Line {
+        indent: line.indent,
+        items: items[end..].to_vec(),
+    });
+    Some(lines)
+}
+
+fn is_comma(kind: &PieceKind) -> bool {
+    *kind == PieceKind::Token(TokenKind::Operator(Operator::Comma))
+}
+
+/// Whether `op` appears in `items` outside of any bracket.
+fn has_top_level(items: &[Item], op: Operator) -> bool {
+    let mut depth = 0;
+    for item in items {
+        match bracket(&item.piece.kind) {
+            Some(false) => depth += 1,
+            Some(true) => depth -= 1,
+            None if depth == 0 && item.piece.kind == PieceKind::Token(TokenKind::Operator(op)) => {
+                return true
+            }
+            None => {}
+        }
+    }
+    false
+}
@@ -0,0 +1,398 @@
+use coil_error::SourceMap;
+use coil_lexer::{Lexer, Operator, Parenthesis, TokenKind};
+use coil_parser::{ends_statement, starts_statement, Expr, Parser};
+use proptest::prelude::*;
+
+use crate::{format, Config};
+
+fn quick_format(source: &str) -> String {
+    format_with(source, &Config::default())
+}
+
+fn format_with(source: &str, config: &Config) -> String {
+    let mut map = SourceMap::new();
+    let file = map.add("<inline>", source);
+    match format(&map, file, config) {
+        Ok(formatted) => formatted,
+        Err(errors) => panic!("expected {source:?} to parse but got {errors:?}"),
+    }
+}
+
+fn quick_parse(source: &str) -> Expr {
+    let mut parser = Parser::new(Lexer::new("<inline>", source));
+    parser.parse().expect("expected source to parse")
+}
+
+const FORMATTED: &str = "\
+// A pair of anything.
+struct Pair<T: Show> { a: T, b: [Int; 4] }
+
+/* Block comments /* nest */
+   and keep their lines. */
+pub fn f(x: Int, ys: &[Int]) -> Int!Str where T: Eq {
+    let y: Vec<Bool> = g(x, -x, !done) // trailing
+    let z = ys[0..x].len() + *p - 1
+    let h = \\w -> w.0
+    match y {
+        Some(z) if z => h(w)?
+
+        Point { a: (b, c), .. } => c
+    }
+    return x
+}
+";
+
+#[test]
+fn test_formatted_is_unchanged() {
+    assert_eq!(quick_format(FORMATTED), FORMATTED);
+}
+
+#[test]
+fn test_spacing() {
+    let source = "fn  f( x:Int ,ys : & [ Int ] )->Int ! Str{let y=g (x,- x, ! done)\nlet z = ys [0 .. x] . len ( )+ * p\nreturn x ?}\n";
+    assert_eq!(
+        quick_format(source),
+        "fn f(x: Int, ys: &[Int]) -> Int!Str {\n    let y = g(x, -x, !done)\n    let z = ys[0..x].len() + *p\n    return x?\n}\n"
+    );
+    assert_eq!(
+        quick_format("static X: Int = - - 1"),
+        "static X: Int = --1\n"
+    );
+    assert_eq!(
+        quick_format("static X: Int = x . 0 . 1"),
+        "static X: Int = x.0 .1\n"
+    );
+    assert_eq!(quick_format("static X: Int=a<b"), "static X: Int = a < b\n");
+    assert_eq!(
+        quick_format("static X: Int = if(a>=b) { x>>2 } else { [1,2,] [0] }"),
+        "static X: Int = if (a >= b) {\n    x >> 2\n} else {\n    [1, 2,][0]\n}\n"
+    );
+}
+
+#[test]
+fn test_generics() {
+    // Angle brackets are told apart from comparisons by the AST.
+    let source = "fn g < T > (x : Vec < Vec < T >>) -> Vec<T> ! E { size . < Int > ( ) >> 2 }";
+    assert_eq!(
+        quick_format(source),
+        "fn g<T>(x: Vec<Vec<T>>) -> Vec<T>!E {\n    size.<Int>() >> 2\n}\n"
+    );
+    assert_eq!(
+        quick_format("impl < T : Eq > Show for Pair<T>{}\nstatic X: Vec<T> = (a<b) < c"),
+        "impl<T: Eq> Show for Pair<T> {}\nstatic X: Vec<T> = (a < b) < c\n"
+    );
+}
+
+#[test]
+fn test_indentation() {
+    // Line breaks that don't end a statement are joined.
+    let source =
+        "fn f() {\nif a {\n        g(1,\n2)\n  }\n        else { h() }\nlet x =\ny\n.z()\n}\n";
+    assert_eq!(
+        quick_format(source),
+        "fn f() {\n    if a {\n        g(1, 2)\n    } else {\n        h()\n    }\n    let x = y.z()\n}\n"
+    );
+    // Those next to a comment stay, and a line continuing an expression goes one level
+    // further.
+    assert_eq!(
+        quick_format("fn f() {\nlet x = a + // b\nc\n}"),
+        "fn f() {\n    let x = a + // b\n        c\n}\n"
+    );
+    // So do those around the items of a `{` that isn't a body.
+    let source = "enum E {\n    A,\n    B\n}\nstruct P {\n    x: Int\n    y: Int\n}\n";
+    assert_eq!(quick_format(source), source);
+}
+
+#[test]
+fn test_dereference_lines() {
+    // A `*` starting a line in a block starts a statement, but continues one inside `(`.
+    assert_eq!(
+        quick_format("fn f() {\nlet r = &mut x\n* r = 3\nlet y =\n*r\n}"),
+        "fn f() {\n    let r = &mut x\n    *r = 3\n    let y = *r\n}\n"
+    );
+    assert_eq!(
+        quick_format("static X: Int = (a\n*b)"),
+        "static X: Int = (a * b)\n"
+    );
+}
+
+#[test]
+fn test_bodies() {
+    // The insides of blocks, matches and items holding items go on lines of their own.
+    assert_eq!(
+        quick_format("fn f() { match x { _ => if a { 1 } else { 2 } } }"),
+        "fn f() {\n    match x {\n        _ => if a {\n            1\n        } else {\n            2\n        }\n    }\n}\n"
+    );
+    assert_eq!(
+        quick_format("trait T { fn f() }\nimpl T for Int { fn f() {} }"),
+        "trait T {\n    fn f()\n}\nimpl T for Int {\n    fn f() {}\n}\n"
+    );
+    assert_eq!(
+        quick_format("fn f() { /* a */ do { i += 1 } while i < 10 }"),
+        "fn f() {\n    /* a */ do {\n        i += 1\n    } while i < 10\n}\n"
+    );
+    // Other braces stay on their line.
+    let source = "import a.{ b, c }\nstruct P { x: Int }\nstatic X: P = P { x: 1 }\n";
+    assert_eq!(quick_format(source), source);
+    assert_eq!(
+        quick_format("fn f() { match p { P { x } => x } }"),
+        "fn f() {\n    match p {\n        P { x } => x\n    }\n}\n"
+    );
+}
+
+#[test]
+fn test_blank_lines() {
+    let source = "\n\nfn f() {\n\n    a()\n\n\n\n    b()\n\n}\n\n\nfn g() {}\n\n";
+    assert_eq!(
+        quick_format(source),
+        "fn f() {\n    a()\n\n    b()\n}\n\nfn g() {}\n"
+    );
+}
+
+#[test]
+fn test_comments() {
+    let source = "fn f() {   // trailing   \n  /* block */a(/*x*/1)\n// own line\n}\n";
+    assert_eq!(
+        quick_format(source),
+        "fn f() { // trailing\n    /* block */a(/*x*/1)\n    // own line\n}\n"
+    );
+}
+
+#[test]
+fn test_long_lines() {
+    let config = Config {
+        max_width: 30,
+        ..Config::default()
+    };
+    let source = "fn f() {\n    let x = call(first, second(a, b), [third])\n}\n";
+    assert_eq!(
+        format_with(source, &config),
+        "fn f() {\n    let x = call(\n        first,\n        second(a, b),\n        [third]\n    )\n}\n"
+    );
+    let config = Config {
+        max_width: 16,
+        ..Config::default()
+    };
+    assert_eq!(
+        format_with("static X: Int = f(aaaa, g(bbbb, cccc))", &config),
+        "static X: Int = f(\n    aaaa,\n    g(\n        bbbb,\n        cccc\n    )\n)\n"
+    );
+    // Nothing can be broken here, so the line stays too long.
+    assert_eq!(
+        format_with("static XXXXXXXXXXXXX: Int = 1", &config),
+        "static XXXXXXXXXXXXX: Int = 1\n"
+    );
+}
+
+#[test]
+fn test_config() {
+    let config = Config {
+        indent_width: 2,
+        hard_tabs: true,
+        ..Config::default()
+    };
+    assert_eq!(format_with("fn f() {\na\n}", &config), "fn f() {\n\ta\n}\n");
+    let config = Config::parse("max_width = 80\nindent_width = 2").unwrap();
+    assert_eq!(
+        config,
+        Config {
+            max_width: 80,
+            indent_width: 2,
+            hard_tabs: false,
+        }
+    );
+    assert_eq!(format_with("fn f() {\na\n}", &config), "fn f() {\n  a\n}\n");
+    assert_eq!(Config::parse("").unwrap(), Config::default());
+    assert!(Config::parse("width = 80").is_err());
+}
+
+#[test]
+fn test_syntax_errors() {
+    let mut map = SourceMap::new();
+    let file = map.add("<inline>", "fn f( {}");
+    assert!(format(&map, file, &Config::default()).is_err());
+}
+
+/// Sources covering most of the syntax, for the tests below.
+const SOURCES: [&str; 4] = [
+    FORMATTED,
+    "fn f() {\n    let a = b\n        + c\n    d(e, [f, g], (h))\n    x = if a { 1 } else { 2 }\n}\n",
+    "fn f() {\n    outer: for x in xs {\n        break outer x\n    }\n    do {\n        i += 1\n    } while i < 10\n}\n",
+    "import a.b.{c, d as e}\nenum Option<T> { Some(T), None }\nimpl Show for Int {}\n",
+];
+
+/// Expressions from a small grammar, with every operation in brackets so that it reads
+/// the same whatever the precedence.
+fn expr() -> impl Strategy<Value = String> {
+    let leaf = prop::sample::select(&["a", "xs", "1", "2.5", "true", "\"s\"", "size.<Int>()"][..])
+        .prop_map(String::from);
+    leaf.prop_recursive(4, 32, 3, |inner| {
+        const BINARY: &[&str] = &["+", "-", "*", "<", ">", ">=", "<<", ">>", "==", "&&", ".."];
+        prop_oneof![
+            (inner.clone(), prop::sample::select(BINARY), inner.clone())
+                .prop_map(|(l, op, r)| format!("({l} {op} {r})")),
+            (prop::sample::select(&["-", "!", "&"][..]), inner.clone())
+                .prop_map(|(op, x)| format!("{op}{x}")),
+            prop::collection::vec(inner.clone(), 0..4)
+                .prop_map(|args| format!("f({})", args.join(", "))),
+            prop::collection::vec(inner.clone(), 1..4)
+                .prop_map(|xs| format!("[{}]", xs.join(", "))),
+            (inner.clone(), inner.clone()).prop_map(|(x, i)| format!("({x})[{i}]")),
+            inner.clone().prop_map(|x| format!("({x}).len()")),
+            (inner.clone(), inner.clone(), inner.clone())
+                .prop_map(|(c, a, b)| format!("if {c} {{ {a} }} else {{ {b} }}")),
+            inner.clone().prop_map(|x| format!("(\\y -> {x})")),
+        ]
+    })
+}
+
+fn statement() -> impl Strategy<Value = String> {
+    let statement = prop_oneof![
+        expr().prop_map(|x| format!("let x = {x}")),
+        expr().prop_map(|x| format!("let v: Vec<Vec<T>> = {x}")),
+        (expr(), expr()).prop_map(|(i, x)| format!("xs[{i}] = {x}")),
+        expr().prop_map(|x| format!("*r = {x}")),
+        expr().prop_map(|x| format!("f({x})")),
+        (expr(), expr()).prop_map(|(c, x)| format!("while {c} {{\nlet y = {x}\n}}")),
+        (expr(), expr()).prop_map(|(xs, x)| format!("for i in {xs} {{\nf(i, {x})\n}}")),
+        expr().prop_map(|x| format!("match {x} {{\nSome(y) => y\n_ => 0\n}}")),
+    ];
+    (statement, any::<bool>()).prop_map(|(statement, comment)| match comment {
+        true => format!("{statement} // note"),
+        false => statement,
+    })
+}
+
+/// The sources above, and functions made of generated statements.
+fn sources() -> impl Strategy<Value = String> {
+    let function = (
+        prop::collection::vec(statement(), 1..6),
+        prop::sample::select(&["a", "size.<Int>() >> 2"][..]),
+    )
+        .prop_map(|(statements, last)| {
+            format!(
+                "fn f<T>(r: &Int, xs: Vec<T>) -> Int!Str {{\n{}\nreturn {last}\n}}\n",
+                statements.join("\n")
+            )
+        });
+    prop_oneof![
+        prop::sample::select(&SOURCES[..]).prop_map(String::from),
+        function,
+    ]
+}
+
+fn narrow() -> Config {
+    Config {
+        max_width: 40,
+        ..Config::default()
+    }
+}
+
+/// Whether a line break between `p` and `n` may be added or taken away without changing
+/// the code or how it formats. Any `>` is left alone, since it may close generics.
+fn joinable(p: &TokenKind, n: &TokenKind, open: &[Parenthesis]) -> bool {
+    let curly = |closing| TokenKind::Parenthesis {
+        closing,
+        kind: Parenthesis::Curly,
+    };
+    !matches!(open.last(), None | Some(Parenthesis::Curly))
+        || !(ends_statement(p) && starts_statement(n))
+            && !matches!(
+                p,
+                TokenKind::Operator(
+                    Operator::Comma
+                        | Operator::Semicolon
+                        | Operator::Greater
+                        | Operator::GreaterEq
+                        | Operator::BitShiftRight
+                        | Operator::BitShiftRightAssign
+                )
+            )
+            && *p != curly(false)
+            && *n != curly(true)
+}
+
+/// Rewrites the whitespace between the tokens and comments of `source`. Line breaks stay
+/// where they matter, but runs of blank lines change and the others come and go. Each gap
+/// takes the next of `widths`, which are the extra blank lines and the indentation after a
+/// line break, or the extra spaces within a line, and whether it holds a line break where
+/// that is up to the formatter.
+fn perturb(source: &str, widths: &[(usize, usize, bool)]) -> String {
+    let mut lexer = Lexer::new("<inline>", source);
+    let mut pieces = vec![];
+    while let Some(token) = lexer.next_token().unwrap() {
+        pieces.push((Some(token.kind), lexer.span()));
+    }
+    pieces.extend(lexer.comments().iter().map(|comment| (None, comment.span)));
+    pieces.sort_by_key(|(_, span)| span.start);
+    let mut widths = widths.iter().cycle();
+    let mut open = vec![];
+    let mut prev = None;
+    let mut out = String::new();
+    let mut end = 0;
+    for (kind, span) in pieces {
+        let gap = &source[end as usize..span.start as usize];
+        let breaks = gap.matches('\n').count();
+        let &(extra, indent, split) = widths.next().unwrap();
+        let breaks = match (&prev, &kind) {
+            (Some(Some(p)), Some(n)) if joinable(p, n, &open) => split as usize,
+            _ => breaks,
+        };
+        if breaks > 0 {
+            let breaks = if breaks == 1 { 1 } else { 2 + extra };
+            out.push_str(&"\n".repeat(breaks));
+            out.push_str(&" ".repeat(indent));
+        } else if !gap.is_empty() {
+            out.push_str(&" ".repeat(1 + extra));
+        }
+        // A line comment runs to the end of its line, so it is cut off before the break.
+        out.push_str(source[span.start as usize..span.end as usize].trim_end_matches(['\r', '\n']));
+        match &kind {
+            Some(TokenKind::Parenthesis {
+                closing: false,
+                kind,
+            }) => open.push(*kind),
+            Some(TokenKind::Parenthesis { closing: true, .. }) => {
+                open.pop();
+            }
+            _ => {}
+        }
+        prev = Some(kind);
+        end = span.end;
+    }
+    out.push_str(&source[end as usize..]);
+    out
+}
+
+proptest! {
+    #[test]
+    fn test_formatting_is_idempotent(source in sources()) {
+        let expected = format_with(&source, &narrow());
+        prop_assert_eq!(
+            format_with(&expected, &narrow()),
+            expected.clone(),
+            "not idempotent"
+        );
+        prop_assert_eq!(
+            quick_parse(&expected),
+            quick_parse(&source),
+            "changed the AST"
+        );
+    }
+
+    /// However the whitespace of a source is laid out, it formats the same.
+    #[test]
+    fn test_formatting_ignores_spaces(
+        source in sources(),
+        widths in prop::collection::vec((0..3usize, 0..9usize, any::<bool>()), 1..64),
+    ) {
+        let perturbed = perturb(&source, &widths);
+        prop_assert_eq!(quick_parse(&perturbed), quick_parse(&source));
+        prop_assert_eq!(
+            format_with(&perturbed, &narrow()),
+            format_with(&source, &narrow()),
+            "from {:?}",
+            perturbed
+        );
+    }
+}
@@ -1,27 +1,57 @@
 pub struct LexerCursor {
-    pub chars: Box<[char]>,
+    pub source: Box<str>,
+    /// The byte offset of the next char. Reading past the end keeps counting one byte per
+    /// call, so that rewinding afterwards lands back on the end.
     pub position: usize,
 }
 
 impl LexerCursor {
     pub fn new(s: &str) -> Self {
         Self {
-            chars: s.chars().collect(),
+            source: s.into(),
             position: 0,
         }
     }
 
     pub fn current(&self) -> Option<char> {
-        self.chars.get(self.position).map(|x| *x)
+        self.peek(0)
+    }
+
+    /// The char `n` chars after the current position, without moving.
+    pub fn peek(&self, n: usize) -> Option<char> {
+        self.source.get(self.position..)?.chars().nth(n)
+    }
+
+    /// The byte offset of the current position in the source.
+    pub fn byte_offset(&self) -> u32 {
+        self.position.min(self.source.len()) as u32
+    }
+
+    /// The source from the byte offset `start` up to the current position.
+    pub fn since(&self, start: usize) -> &str {
+        &self.source[start..self.byte_offset() as usize]
     }
 
     pub fn advance(&mut self, by: usize) {
-        self.position += by;
-        self.position = self.position.min(self.chars.len())
+        for _ in 0..by {
+            if self.next().is_none() {
+                self.position = self.source.len();
+                break;
+            }
+        }
     }
 
     pub fn rewind(&mut self, by: usize) {
-        self.position = self.position.saturating_sub(by);
+        for _ in 0..by {
+            if self.position > self.source.len() {
+                self.position -= 1;
+                continue;
+            }
+            match self.source[..self.position].char_indices().next_back() {
+                Some((i, _)) => self.position = i,
+                None => break,
+            }
+        }
     }
 }
 
@@ -29,15 +59,13 @@ impl Iterator for LexerCursor {
     type Item = char;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let result = self.chars.get(self.position).map(|x| *x);
-        self.position += 1;
+        let result = self.current();
+        self.position += result.map_or(1, char::len_utf8);
         result
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        (
-            self.chars.len() - self.position,
-            Some(self.chars.len() - self.position),
-        )
+        let rest = self.source.len().saturating_sub(self.position);
+        (rest.div_ceil(4), Some(rest))
     }
 }
@@ -1,8 +1,11 @@
-#![feature(iter_advance_by)]
 use std::hint::unreachable_unchecked;
 
 use coil_error::Error;
 use coil_error::ErrorCode;
+use coil_error::FileId;
+use coil_error::SourceMap;
+use coil_error::Span;
+use coil_error::msg;
 
 mod cursor;
 use cursor::LexerCursor;
@@ -12,6 +15,7 @@ mod token;
 use char_trait_ext::CharTraitExt;
 
 use phf::phf_map;
+pub use token::Comment;
 pub use token::Keyword;
 pub use token::Literal;
 pub use token::Operator;
@@ -26,7 +30,11 @@ struct ParseStringOptions {
 pub struct Lexer {
     cursor: LexerCursor,
     pub file: Box<str>,
+    pub file_id: FileId,
     line: usize,
+    token_start: u32,
+    span: Span,
+    comments: Vec<Comment>,
 }
 
 fn next_after_while<I: Iterator>(
@@ -45,8 +53,13 @@ const UNEXPECTED: ErrorCode = ErrorCode::lexer(2);
 const UNFINISHED_STRING: ErrorCode = ErrorCode::lexer(3);
 const UNFINISHED_STRING_ESCAPE: ErrorCode = ErrorCode::lexer(4);
 const INVALID_STRING_ESCAPE: ErrorCode = ErrorCode::lexer(5);
+const UNFINISHED_COMMENT: ErrorCode = ErrorCode::lexer(6);
+const INVALID_SUFFIX: ErrorCode = ErrorCode::lexer(7);
+const EMPTY_CHAR: ErrorCode = ErrorCode::lexer(8);
+const UNFINISHED_CHAR: ErrorCode = ErrorCode::lexer(9);
 
 static KEYWORDS: phf::Map<&'static str, Keyword> = phf_map! {
+    "as" => Keyword::As,
     "break" => Keyword::Break,
     "consttime" => Keyword::Consttime,
     "continue" => Keyword::Continue,
@@ -78,6 +91,7 @@ static KEYWORDS: phf::Map<&'static str, Keyword> = phf_map! {
     "type" => Keyword::Type,
     "unsafe" => Keyword::Unsafe,
     "union" => Keyword::Union,
+    "unless" => Keyword::Unless,
     "where" => Keyword::Where,
     "while" => Keyword::While,
 };
@@ -88,7 +102,19 @@ impl Lexer {
         Self {
             cursor: LexerCursor::new(source),
             file: file.into(),
+            file_id: FileId::default(),
             line: 1,
+            token_start: 0,
+            span: Span::default(),
+            comments: Vec::new(),
+        }
+    }
+
+    pub fn from_source(map: &SourceMap, id: FileId) -> Self {
+        let file = map.get(id);
+        Self {
+            file_id: id,
+            ..Self::new(&file.name, &file.source)
         }
     }
 
@@ -97,6 +123,18 @@ impl Lexer {
         self.line
     }
 
+    /// The span of the last token returned by [`Lexer::next_token`].
+    #[inline]
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The comments skipped so far, in source order.
+    #[inline]
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
     #[inline]
     pub fn cursor(&self) -> &LexerCursor {
         &self.cursor
@@ -105,7 +143,10 @@ impl Lexer {
     pub fn reset(&mut self) {
         self.cursor.position = 0;
         self.line = 1;
-    } 
+        self.token_start = 0;
+        self.span = Span::new(self.file_id, 0, 0);
+        self.comments.clear();
+    }
 
     pub fn parse_num(&mut self, radix: usize) -> Result<Token, Error> {
         let mut buf = String::new();
@@ -127,7 +168,7 @@ impl Lexer {
                 let Some(nx) = self.cursor.next() else {
                     return Err(Error::new(
                         UNEXPECTED_EOF,
-                        "expected a digit but found end of file",
+                        &msg!("lexer-expected-digit-eof"),
                         &self.file,
                         self.line,
                     ));
@@ -136,7 +177,7 @@ impl Lexer {
                 if !num_range.contains(&nx) && !alph_range.contains(&nx.to_ascii_uppercase()) {
                     self.cursor.rewind(1);
                     return Ok(Token::new(
-                        TokenKind::Literal(Literal::Integer { radix }, buf.into()),
+                        TokenKind::Literal(Literal::Integer { radix, suffix: None }, buf.into()),
                         self.line,
                     ));
                 }
@@ -149,7 +190,7 @@ impl Lexer {
                 let Some(nx) = self.cursor.next() else {
                     return Err(Error::new(
                         UNEXPECTED_EOF,
-                        "expected a digit, '+', '-' but found end of file",
+                        &msg!("lexer-expected-exponent-eof"),
                         &self.file,
                         self.line,
                     ));
@@ -161,7 +202,7 @@ impl Lexer {
                 {
                     return Err(Error::new(
                         UNEXPECTED,
-                        &format!("expected a digit, '+' or '-' but found {nx:?}"),
+                        &msg!("lexer-expected-exponent", found = format!("{nx:?}")),
                         &self.file,
                         self.line,
                     ));
@@ -171,7 +212,7 @@ impl Lexer {
                     let Some(nx) = self.cursor.next() else {
                         return Err(Error::new(
                             UNEXPECTED_EOF,
-                            "expected a digit but found end of file",
+                            &msg!("lexer-expected-digit-eof"),
                             &self.file,
                             self.line,
                         ));
@@ -179,7 +220,7 @@ impl Lexer {
                     if !num_range.contains(&nx) && !alph_range.contains(&nx.to_ascii_uppercase()) {
                         return Err(Error::new(
                             UNEXPECTED,
-                            &format!("expected a digit but found {nx:?}"),
+                            &msg!("lexer-expected-digit", found = format!("{nx:?}")),
                             &self.file,
                             self.line,
                         ));
@@ -192,46 +233,75 @@ impl Lexer {
             }
             if !num_range.contains(&x) && !alph_range.contains(&x.to_ascii_uppercase()) {
                 self.cursor.rewind(1);
-                return Ok(Token::new(
-                    TokenKind::Literal(
-                        if float {
-                            Literal::Float { radix }
-                        } else {
-                            Literal::Integer { radix }
-                        },
-                        buf.into(),
-                    ),
-                    self.line,
-                ));
+                break;
             }
             buf.push(x);
         }
-        return Ok(Token::new(
-            TokenKind::Literal(
-                if float {
-                    Literal::Float { radix }
-                } else {
-                    Literal::Integer { radix }
-                },
-                buf.into(),
-            ),
-            self.line,
-        ));
+        self.number(radix, float, buf)
+    }
+
+    /// Finishes the number with the digits `buf`, reading the type written right after
+    /// them if there is one. A float type after an integer makes it a float.
+    fn number(&mut self, radix: usize, float: bool, buf: String) -> Result<Token, Error> {
+        let start = self.cursor.byte_offset() as usize;
+        if self.cursor.current().is_some_and(|c| c.is_alphabetic() || c == '_') {
+            while self.cursor.current().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                self.cursor.advance(1);
+            }
+        }
+        let suffix = self.cursor.since(start);
+        let literal = match suffix {
+            "" => None,
+            "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" if !float => Some(false),
+            "f32" | "f64" if radix == 10 => Some(true),
+            _ => {
+                return Err(Error::new(
+                    INVALID_SUFFIX,
+                    &msg!("lexer-invalid-suffix", suffix = suffix),
+                    &self.file,
+                    self.line,
+                ))
+            }
+        };
+        let suffix = (!suffix.is_empty()).then(|| suffix.into());
+        let literal = match literal.unwrap_or(float) {
+            true => Literal::Float { radix, suffix },
+            false => Literal::Integer { radix, suffix },
+        };
+        Ok(Token::new(TokenKind::Literal(literal, buf.into()), self.line))
     }
 
     pub fn next_token(&mut self) -> Result<Option<Token>, Error> {
-        let Some(current) = next_after_while(&mut self.cursor, |c| {
-            if *c == '\n' {
-                self.line += 1;
+        let result = self.lex_token();
+        let end = self.cursor.byte_offset().max(self.token_start);
+        self.span = Span::new(self.file_id, self.token_start, end);
+        result.map_err(|e| match e.span {
+            Some(_) => e,
+            None => e.with_span(self.span),
+        })
+    }
+
+    fn lex_token(&mut self) -> Result<Option<Token>, Error> {
+        let current = loop {
+            let Some(current) = next_after_while(&mut self.cursor, |c| {
+                if *c == '\n' {
+                    self.line += 1;
+                }
+                c.is_ascii_whitespace()
+            }) else {
+                self.token_start = self.cursor.byte_offset();
+                return Ok(None);
+            };
+            self.cursor.rewind(1);
+            self.token_start = self.cursor.byte_offset();
+            if current != '/' || !matches!(self.cursor.peek(1), Some('/' | '*')) {
+                break current;
             }
-            c.is_ascii_whitespace()
-        }) else {
-            return Ok(None);
+            self.lex_comment()?;
         };
-        self.cursor.rewind(1);
         match current {
             '0' => {
-                let _ = self.cursor.advance(1);
+                self.cursor.advance(1);
                 match self.cursor.next() {
                     Some(base @ ('x' | 'o' | 'b')) => {
                         let radix = match base {
@@ -251,7 +321,7 @@ impl Lexer {
                         let Some(x) = self.cursor.next() else {
                             return Err(Error::new(
                                 UNEXPECTED_EOF,
-                                "expected a digit but found end of file",
+                                &msg!("lexer-expected-digit-eof"),
                                 &self.file,
                                 self.line,
                             ));
@@ -259,119 +329,115 @@ impl Lexer {
                         if !num_range.contains(&x) && !alph_range.contains(&x) {
                             return Err(Error::new(
                                 UNEXPECTED,
-                                &format!("expected a digit but found {x:?}"),
+                                &msg!("lexer-expected-digit", found = format!("{x:?}")),
                                 &self.file,
                                 self.line,
                             ));
                         };
                         self.cursor.rewind(1);
-                        return self.parse_num(radix).map(Some);
+                        self.parse_num(radix).map(Some)
                     }
                     Some('.') => {
                         self.cursor.rewind(2);
-                        return self.parse_num(10).map(Some);
+                        self.parse_num(10).map(Some)
                     }
                     _ => {
                         self.cursor.rewind(1);
-                        return Ok(Token {
-                            kind: TokenKind::Literal(Literal::Integer { radix: 10 }, "0".into()),
-                            line: self.line,
-                        })
-                        .map(Some);
+                        self.number(10, false, "0".into()).map(Some)
                     }
                 }
             }
             '1'..='9' => {
-                return self.parse_num(10).map(Some);
+                self.parse_num(10).map(Some)
             }
             ',' => {
-                let _ = self.cursor.advance(1);
-                return Ok(Some(Token::new(
+                self.cursor.advance(1);
+                Ok(Some(Token::new(
                     TokenKind::Operator(Operator::Comma),
                     self.line,
-                )));
+                )))
             }
             ';' => {
-                let _ = self.cursor.advance(1);
-                return Ok(Some(Token::new(
+                self.cursor.advance(1);
+                Ok(Some(Token::new(
                     TokenKind::Operator(Operator::Semicolon),
                     self.line,
-                )));
+                )))
             }
             ':' => {
-                let _ = self.cursor.advance(1);
-                return Ok(Some(Token::new(
+                self.cursor.advance(1);
+                Ok(Some(Token::new(
                     TokenKind::Operator(Operator::Colon),
                     self.line,
-                )));
+                )))
             }
             '?' => {
-                let _ = self.cursor.advance(1);
-                return Ok(Some(Token::new(
+                self.cursor.advance(1);
+                Ok(Some(Token::new(
                     TokenKind::Operator(Operator::QuestionMark),
                     self.line,
-                )));
+                )))
             }
             '.' => {
-                let _ = self.cursor.advance(1);
+                self.cursor.advance(1);
                 if let Some('.') = self.cursor.current() {
-                    let _ = self.cursor.advance(1);
+                    self.cursor.advance(1);
                     return Ok(Some(Token::new(
                         TokenKind::Operator(Operator::DoubleDot),
                         self.line,
                     )));
                 }
-                return Ok(Some(Token::new(
+                Ok(Some(Token::new(
                     TokenKind::Operator(Operator::Dot),
                     self.line,
-                )));
+                )))
             }
             '=' => {
-                let _ = self.cursor.advance(1);
+                self.cursor.advance(1);
                 if let Some('>') = self.cursor.current() {
-                    let _ = self.cursor.advance(1);
+                    self.cursor.advance(1);
                     return Ok(Some(Token::new(
                         TokenKind::Operator(Operator::Bolt),
                         self.line,
                     )));
                 }
                 if let Some('=') = self.cursor.current() {
-                    let _ = self.cursor.advance(1);
+                    self.cursor.advance(1);
                     return Ok(Some(Token::new(
                         TokenKind::Operator(Operator::Eq),
                         self.line,
                     )));
                 }
-                return Ok(Some(Token::new(
+                Ok(Some(Token::new(
                     TokenKind::Operator(Operator::Assign),
                     self.line,
-                )));
+                )))
             }
             '-' => {
-                let _ = self.cursor.advance(1);
+                self.cursor.advance(1);
                 if let Some('>') = self.cursor.current() {
-                    let _ = self.cursor.advance(1);
+                    self.cursor.advance(1);
                     return Ok(Some(Token::new(
                         TokenKind::Operator(Operator::Arrow),
                         self.line,
                     )));
                 }
                 if let Some('=') = self.cursor.current() {
-                    let _ = self.cursor.advance(1);
+                    self.cursor.advance(1);
                     return Ok(Some(Token::new(
                         TokenKind::Operator(Operator::MinusAssign),
                         self.line,
                     )));
                 }
-                return Ok(Some(Token::new(
+                Ok(Some(Token::new(
                     TokenKind::Operator(Operator::Minus),
                     self.line,
-                )));
+                )))
             }
             '+' | '/' | '*' | '%' | '!' | '^' => {
-                let _ = self.cursor.advance(1);
+                self.cursor.advance(1);
                 if let Some('=') = self.cursor.current() {
-                    let _ = self.cursor.advance(1);
+                    self.cursor.advance(1);
                     return Ok(Some(Token::new(
                         TokenKind::Operator(match current {
                             '+' => Operator::PlusAssign,
@@ -385,7 +451,7 @@ impl Lexer {
                         self.line,
                     )));
                 }
-                return Ok(Some(Token::new(
+                Ok(Some(Token::new(
                     TokenKind::Operator(match current {
                         '+' => Operator::Plus,
                         '/' => Operator::Slash,
@@ -396,12 +462,12 @@ impl Lexer {
                         _ => unreachable!(),
                     }),
                     self.line,
-                )));
+                )))
             }
             '>' | '<' | '&' | '|' => {
-                let _ = self.cursor.advance(1);
+                self.cursor.advance(1);
                 if let Some('=') = self.cursor.current() {
-                    let _ = self.cursor.advance(1);
+                    self.cursor.advance(1);
                     return Ok(Some(Token::new(
                         TokenKind::Operator(match current {
                             '>' => Operator::GreaterEq,
@@ -414,9 +480,9 @@ impl Lexer {
                     )));
                 }
                 if Some(current) == self.cursor.current() {
-                    let _ = self.cursor.advance(1);
+                    self.cursor.advance(1);
                     if let Some('=') = self.cursor.current() {
-                        let _ = self.cursor.advance(1);
+                        self.cursor.advance(1);
                         return Ok(Some(Token::new(
                             TokenKind::Operator(match current {
                                 '>' => Operator::BitShiftRightAssign,
@@ -439,7 +505,7 @@ impl Lexer {
                         self.line,
                     )));
                 }
-                return Ok(Some(Token::new(
+                Ok(Some(Token::new(
                     TokenKind::Operator(match current {
                         '>' => Operator::Greater,
                         '<' => Operator::Lesser,
@@ -448,110 +514,157 @@ impl Lexer {
                         _ => unreachable!(),
                     }),
                     self.line,
-                )));
+                )))
             }
             '~' => {
-                let _ = self.cursor.advance(1);
-                return Ok(Some(Token::new(
+                self.cursor.advance(1);
+                Ok(Some(Token::new(
                     TokenKind::Operator(Operator::BitNot),
                     self.line,
-                )));
+                )))
             }
             '(' => {
-                let _ = self.cursor.advance(1);
-                return Ok(Some(Token::new(
+                self.cursor.advance(1);
+                Ok(Some(Token::new(
                     TokenKind::Parenthesis {
                         closing: false,
                         kind: Parenthesis::Normal,
                     },
                     self.line,
-                )));
+                )))
             }
             ')' => {
-                let _ = self.cursor.advance(1);
-                return Ok(Some(Token::new(
+                self.cursor.advance(1);
+                Ok(Some(Token::new(
                     TokenKind::Parenthesis {
                         closing: true,
                         kind: Parenthesis::Normal,
                     },
                     self.line,
-                )));
+                )))
             }
             '[' => {
-                let _ = self.cursor.advance(1);
-                return Ok(Some(Token::new(
+                self.cursor.advance(1);
+                Ok(Some(Token::new(
                     TokenKind::Parenthesis {
                         closing: false,
                         kind: Parenthesis::Square,
                     },
                     self.line,
-                )));
+                )))
             }
             ']' => {
-                let _ = self.cursor.advance(1);
-                return Ok(Some(Token::new(
+                self.cursor.advance(1);
+                Ok(Some(Token::new(
                     TokenKind::Parenthesis {
                         closing: true,
                         kind: Parenthesis::Square,
                     },
                     self.line,
-                )));
+                )))
             }
             '{' => {
-                let _ = self.cursor.advance(1);
-                return Ok(Some(Token::new(
+                self.cursor.advance(1);
+                Ok(Some(Token::new(
                     TokenKind::Parenthesis {
                         closing: false,
                         kind: Parenthesis::Curly,
                     },
                     self.line,
-                )));
+                )))
             }
             '}' => {
-                let _ = self.cursor.advance(1);
-                return Ok(Some(Token::new(
+                self.cursor.advance(1);
+                Ok(Some(Token::new(
                     TokenKind::Parenthesis {
                         closing: true,
                         kind: Parenthesis::Curly,
                     },
                     self.line,
-                )));
+                )))
             }
             '\\' => {
-                let _ = self.cursor.advance(1);
-                return Ok(Some(Token::new(
+                self.cursor.advance(1);
+                Ok(Some(Token::new(
                     TokenKind::Operator(Operator::Backslash),
                     self.line,
-                )));
+                )))
             }
             '"' => {
-                return self
+                self
                     .parse_string(ParseStringOptions { raw: false })
                     .map(Some)
             }
+            '\'' => self.parse_char().map(Some),
             '#' => {
-                return self
+                self
                     .parse_multiline_string(ParseStringOptions { raw: false })
                     .map(Some)
             }
             c if c.is_ascii_whitespace() => unreachable!(),
-            c if c.is_alphabetic() => return self.parse_ident().map(Some),
+            c if c.is_alphabetic() || c == '_' => self.parse_ident().map(Some),
             c => {
-                return Err(Error::new(UNEXPECTED, &format!("found unexpected character: {c:?} (U+{:06x})", c as u32), self.file.as_ref(), self.line));
+                self.cursor.advance(1);
+                Err(Error::new(UNEXPECTED, &msg!("lexer-unexpected-character", found = format!("{c:?}"), codepoint = format!("{:06x}", c as u32)), self.file.as_ref(), self.line))
             }
         }
     }
 
+    /// Skips a `// line comment` or a `/* block comment */`, which may nest, and keeps it
+    /// in [`Lexer::comments`].
+    fn lex_comment(&mut self) -> Result<(), Error> {
+        let start = self.cursor.position;
+        let start_line = self.line;
+        self.cursor.advance(1);
+        let block = self.cursor.next() == Some('*');
+        if block {
+            let mut depth = 1;
+            while depth > 0 {
+                match self.cursor.next() {
+                    Some('*') if self.cursor.current() == Some('/') => {
+                        self.cursor.advance(1);
+                        depth -= 1;
+                    }
+                    Some('/') if self.cursor.current() == Some('*') => {
+                        self.cursor.advance(1);
+                        depth += 1;
+                    }
+                    Some('\n') => self.line += 1,
+                    Some(_) => {}
+                    None => {
+                        return Err(Error::new(
+                            UNFINISHED_COMMENT,
+                            &msg!("lexer-unfinished-comment"),
+                            self.file.as_ref(),
+                            start_line,
+                        )
+                        .with_note(&msg!("lexer-note-finish-comment")));
+                    }
+                }
+            }
+        } else {
+            while !matches!(self.cursor.current(), None | Some('\n')) {
+                self.cursor.advance(1);
+            }
+        }
+        self.comments.push(Comment {
+            span: Span::new(self.file_id, self.token_start, self.cursor.byte_offset()),
+            block,
+            text: self.cursor.since(start).into(),
+        });
+        Ok(())
+    }
+
     fn parse_string_escape(&mut self, buf: &mut String) -> Result<(), Error> {
         let Some(esc_c) = self.cursor.next() else {
             return Err(Error::new(
                 UNFINISHED_STRING_ESCAPE,
-                "unfinished string escape",
+                &msg!("lexer-unfinished-string-escape"),
                 self.file.as_ref(),
                 self.line,
             )
-            .with_note("maybe finish the string with a '\"'")
-            .with_note("if you wanted to make a raw string, add r before the string"));
+            .with_note(&msg!("lexer-note-finish-string", terminator = "\""))
+            .with_note(&msg!("lexer-note-raw-string")));
         };
         match esc_c {
             '\\' => buf.push('\\'),
@@ -570,12 +683,12 @@ impl Lexer {
                     let Some(c) = self.cursor.next() else {
                         return Err(Error::new(
                             UNFINISHED_STRING_ESCAPE,
-                            "unfinished string escape",
+                            &msg!("lexer-unfinished-string-escape"),
                             self.file.as_ref(),
                             self.line,
                         )
-                        .with_note("maybe finish the string with a '\"'")
-                        .with_note("if you wanted to make a raw string, add r before the string"));
+                        .with_note(&msg!("lexer-note-finish-string", terminator = "\""))
+                        .with_note(&msg!("lexer-note-raw-string")));
                     };
                     n.push(c);
                 }
@@ -584,7 +697,7 @@ impl Lexer {
                     Err(_) => {
                         return Err(Error::new(
                             INVALID_STRING_ESCAPE,
-                            &format!("invalid string escape: '\\x{n}'"),
+                            &msg!("lexer-invalid-string-escape", escape = format!("x{n}")),
                             self.file.as_ref(),
                             self.line,
                         ))
@@ -598,12 +711,12 @@ impl Lexer {
                     let Some(c) = self.cursor.next() else {
                         return Err(Error::new(
                             UNFINISHED_STRING_ESCAPE,
-                            "unfinished string escape",
+                            &msg!("lexer-unfinished-string-escape"),
                             self.file.as_ref(),
                             self.line,
                         )
-                        .with_note("maybe finish the string with a '\"'")
-                        .with_note("if you wanted to make a raw string, add r before the string"));
+                        .with_note(&msg!("lexer-note-finish-string", terminator = "\""))
+                        .with_note(&msg!("lexer-note-raw-string")));
                     };
                     n.push(c);
                 }
@@ -612,7 +725,7 @@ impl Lexer {
                     Err(_) => {
                         return Err(Error::new(
                             INVALID_STRING_ESCAPE,
-                            &format!("invalid string escape: '\\u{n}'"),
+                            &msg!("lexer-invalid-string-escape", escape = format!("u{n}")),
                             self.file.as_ref(),
                             self.line,
                         ))
@@ -628,12 +741,12 @@ impl Lexer {
                     let Some(c) = self.cursor.next() else {
                         return Err(Error::new(
                             UNFINISHED_STRING_ESCAPE,
-                            "unfinished string escape",
+                            &msg!("lexer-unfinished-string-escape"),
                             self.file.as_ref(),
                             self.line,
                         )
-                        .with_note("maybe finish the string with a '\"'")
-                        .with_note("if you wanted to make a raw string, add r before the string"));
+                        .with_note(&msg!("lexer-note-finish-string", terminator = "\""))
+                        .with_note(&msg!("lexer-note-raw-string")));
                     };
                     n.push(c);
                 }
@@ -642,7 +755,7 @@ impl Lexer {
                     Err(_) => {
                         return Err(Error::new(
                             INVALID_STRING_ESCAPE,
-                            &format!("invalid string escape: '\\U{n}'"),
+                            &msg!("lexer-invalid-string-escape", escape = format!("U{n}")),
                             self.file.as_ref(),
                             self.line,
                         ))
@@ -655,7 +768,7 @@ impl Lexer {
             _ => {
                 return Err(Error::new(
                     INVALID_STRING_ESCAPE,
-                    &format!("invalid string escape: '\\{esc_c}'"),
+                    &msg!("lexer-invalid-string-escape", escape = esc_c),
                     self.file.as_ref(),
                     self.line,
                 ))
@@ -676,11 +789,11 @@ impl Lexer {
                 None => {
                     return Err(Error::new(
                         UNFINISHED_STRING,
-                        "unfinished string",
+                        &msg!("lexer-unfinished-string"),
                         self.file.as_ref(),
                         self.line,
                     )
-                    .with_note("maybe finish the string with a '\"'"))
+                    .with_note(&msg!("lexer-note-finish-string", terminator = "\"")))
                 }
             };
             if ch == '"' {
@@ -689,11 +802,11 @@ impl Lexer {
             if ch == '\n' {
                 return Err(Error::new(
                     UNFINISHED_STRING,
-                    "unfinished string",
+                    &msg!("lexer-unfinished-string"),
                     self.file.as_ref(),
                     self.line,
                 )
-                .with_note("maybe finish the string with a '\"'"));
+                .with_note(&msg!("lexer-note-finish-string", terminator = "\"")));
             }
             if !raw && ch == '\\' {
                 self.parse_string_escape(&mut buf)?;
@@ -707,6 +820,46 @@ impl Lexer {
         ))
     }
 
+    /// Lexes a `'c'` char literal, which holds a single char or escape.
+    fn parse_char(&mut self) -> Result<Token, Error> {
+        assert_eq!(self.cursor.next(), Some('\''));
+        let mut buf = String::new();
+        match self.cursor.next() {
+            Some('\\') => self.parse_string_escape(&mut buf)?,
+            Some('\'') => {
+                return Err(Error::new(
+                    EMPTY_CHAR,
+                    &msg!("lexer-empty-char"),
+                    self.file.as_ref(),
+                    self.line,
+                ))
+            }
+            Some(ch) if ch != '\n' => buf.push(ch),
+            _ => {
+                self.cursor.rewind(1);
+                return Err(self.unfinished_char());
+            }
+        }
+        if self.cursor.current() != Some('\'') {
+            return Err(self.unfinished_char());
+        }
+        self.cursor.advance(1);
+        Ok(Token::new(
+            TokenKind::Literal(Literal::Char, buf.into()),
+            self.line,
+        ))
+    }
+
+    fn unfinished_char(&self) -> Error {
+        Error::new(
+            UNFINISHED_CHAR,
+            &msg!("lexer-unfinished-char"),
+            self.file.as_ref(),
+            self.line,
+        )
+        .with_note(&msg!("lexer-note-char-string"))
+    }
+
     fn parse_multiline_string(
         &mut self,
         ParseStringOptions { raw }: ParseStringOptions,
@@ -720,7 +873,7 @@ impl Lexer {
                 None => {
                     return Err(Error::new(
                         UNEXPECTED,
-                        "expected one of '#' or '\"' but found end of file",
+                        &msg!("lexer-expected-string-start-eof"),
                         self.file.as_ref(),
                         start_line,
                     ))
@@ -729,7 +882,7 @@ impl Lexer {
             if x != '#' && x != '"' {
                 return Err(Error::new(
                     UNEXPECTED,
-                    &format!("expected one of '#' or '\"' but found {x:?}"),
+                    &msg!("lexer-expected-string-start", found = format!("{x:?}")),
                     self.file.as_ref(),
                     start_line,
                 ));
@@ -746,13 +899,13 @@ impl Lexer {
                 None => {
                     return Err(Error::new(
                         UNFINISHED_STRING,
-                        "unfinished string",
+                        &msg!("lexer-unfinished-string"),
                         self.file.as_ref(),
                         start_line,
                     )
-                    .with_note(&format!(
-                        "maybe finish the string with a '\"{}'",
-                        "#".repeat(octothorp_count)
+                    .with_note(&msg!(
+                        "lexer-note-finish-string",
+                        terminator = format!("\"{}", "#".repeat(octothorp_count))
                     )));
                 }
             };
@@ -762,13 +915,13 @@ impl Lexer {
                     let Some(x) = self.cursor.next() else {
                         return Err(Error::new(
                             UNFINISHED_STRING,
-                            "unfinished string",
+                            &msg!("lexer-unfinished-string"),
                             self.file.as_ref(),
                             start_line,
                         )
-                        .with_note(&format!(
-                            "maybe finish the string with a '\"{}'",
-                            "#".repeat(octothorp_count)
+                        .with_note(&msg!(
+                            "lexer-note-finish-string",
+                            terminator = format!("\"{}", "#".repeat(octothorp_count))
                         )));
                     };
                     if x == '#' {
@@ -799,7 +952,7 @@ impl Lexer {
     fn parse_ident(&mut self) -> Result<Token, Error> {
         let mut buf = String::new();
         while let Some(x) = self.cursor.next() {
-            if buf.as_str().to_ascii_lowercase() == "r" {
+            if buf.eq_ignore_ascii_case("r") {
                 if x == '#' {
                     self.cursor.rewind(1);
                     return self.parse_multiline_string(ParseStringOptions { raw: true });
@@ -809,7 +962,7 @@ impl Lexer {
                     return self.parse_string(ParseStringOptions { raw: true });
                 }
             }
-            if !x.is_alphanumeric() && !x.is_combining_character() {
+            if !x.is_alphanumeric() && x != '_' && !x.is_combining_character() {
                 self.cursor.rewind(1);
                 return Ok(Token::new(
                     KEYWORDS
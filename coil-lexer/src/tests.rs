@@ -1,8 +1,9 @@
-use coil_error::Error;
+use coil_error::{Error, FileId, SourceMap, Span};
 
 use crate::{
     token::{Keyword, Literal, Operator, Parenthesis, TokenKind},
-    Lexer, Token, INVALID_STRING_ESCAPE, UNEXPECTED, UNFINISHED_STRING, UNFINISHED_STRING_ESCAPE,
+    Lexer, Token, EMPTY_CHAR, INVALID_STRING_ESCAPE, INVALID_SUFFIX, UNEXPECTED, UNFINISHED_CHAR,
+    UNFINISHED_COMMENT, UNFINISHED_STRING, UNFINISHED_STRING_ESCAPE,
 };
 
 fn quick_lex(source: &str) -> Result<Vec<Token>, Error> {
@@ -18,12 +19,12 @@ fn quick_lex(source: &str) -> Result<Vec<Token>, Error> {
 fn test_num_integer() {
     let source = "0 1 24 0x12fA 0o07 0b1001";
     let expected = [
-        TokenKind::Literal(Literal::Integer { radix: 10 }, "0".into()),
-        TokenKind::Literal(Literal::Integer { radix: 10 }, "1".into()),
-        TokenKind::Literal(Literal::Integer { radix: 10 }, "24".into()),
-        TokenKind::Literal(Literal::Integer { radix: 16 }, "12fA".into()),
-        TokenKind::Literal(Literal::Integer { radix: 8 }, "07".into()),
-        TokenKind::Literal(Literal::Integer { radix: 2 }, "1001".into()),
+        TokenKind::Literal(Literal::Integer { radix: 10, suffix: None }, "0".into()),
+        TokenKind::Literal(Literal::Integer { radix: 10, suffix: None }, "1".into()),
+        TokenKind::Literal(Literal::Integer { radix: 10, suffix: None }, "24".into()),
+        TokenKind::Literal(Literal::Integer { radix: 16, suffix: None }, "12fA".into()),
+        TokenKind::Literal(Literal::Integer { radix: 8, suffix: None }, "07".into()),
+        TokenKind::Literal(Literal::Integer { radix: 2, suffix: None }, "1001".into()),
     ]
     .map(|x| Token::new(x, 1));
     let tokens = quick_lex(source).expect("expected source to be fully lexed");
@@ -31,7 +32,7 @@ fn test_num_integer() {
 
     let source = "0. ";
     let expected = [
-        TokenKind::Literal(Literal::Integer { radix: 10 }, "0".into()),
+        TokenKind::Literal(Literal::Integer { radix: 10, suffix: None }, "0".into()),
         TokenKind::Operator(Operator::Dot),
     ]
     .map(|x| Token::new(x, 1));
@@ -43,14 +44,14 @@ fn test_num_integer() {
 fn test_num_float() {
     let source = "1.0 3.14159 0x2.b7e151628aed2a6abf7158809cf4f 0o7.0 0b1.1";
     let expected = [
-        TokenKind::Literal(Literal::Float { radix: 10 }, "1.0".into()),
-        TokenKind::Literal(Literal::Float { radix: 10 }, "3.14159".into()),
+        TokenKind::Literal(Literal::Float { radix: 10, suffix: None }, "1.0".into()),
+        TokenKind::Literal(Literal::Float { radix: 10, suffix: None }, "3.14159".into()),
         TokenKind::Literal(
-            Literal::Float { radix: 16 },
+            Literal::Float { radix: 16, suffix: None },
             "2.b7e151628aed2a6abf7158809cf4f".into(),
         ),
-        TokenKind::Literal(Literal::Float { radix: 8 }, "7.0".into()),
-        TokenKind::Literal(Literal::Float { radix: 2 }, "1.1".into()),
+        TokenKind::Literal(Literal::Float { radix: 8, suffix: None }, "7.0".into()),
+        TokenKind::Literal(Literal::Float { radix: 2, suffix: None }, "1.1".into()),
     ]
     .map(|x| Token::new(x, 1));
     let tokens = quick_lex(source).expect("expected source to be fully lexed");
@@ -61,18 +62,45 @@ fn test_num_float() {
 fn test_num_exp() {
     let source = "1e6 0.314159e1 2.1e-9 6.3e+2 0o3.7e4 0b0101.1001e101";
     let expected = [
-        TokenKind::Literal(Literal::Float { radix: 10 }, "1e6".into()),
-        TokenKind::Literal(Literal::Float { radix: 10 }, "0.314159e1".into()),
-        TokenKind::Literal(Literal::Float { radix: 10 }, "2.1e-9".into()),
-        TokenKind::Literal(Literal::Float { radix: 10 }, "6.3e+2".into()),
-        TokenKind::Literal(Literal::Float { radix: 8 }, "3.7e4".into()),
-        TokenKind::Literal(Literal::Float { radix: 2 }, "0101.1001e101".into()),
+        TokenKind::Literal(Literal::Float { radix: 10, suffix: None }, "1e6".into()),
+        TokenKind::Literal(Literal::Float { radix: 10, suffix: None }, "0.314159e1".into()),
+        TokenKind::Literal(Literal::Float { radix: 10, suffix: None }, "2.1e-9".into()),
+        TokenKind::Literal(Literal::Float { radix: 10, suffix: None }, "6.3e+2".into()),
+        TokenKind::Literal(Literal::Float { radix: 8, suffix: None }, "3.7e4".into()),
+        TokenKind::Literal(Literal::Float { radix: 2, suffix: None }, "0101.1001e101".into()),
     ]
     .map(|x| Token::new(x, 1));
     let tokens = quick_lex(source).expect("expected source to be fully lexed");
     assert_eq!(&tokens, &expected,);
 }
 
+#[test]
+fn test_num_suffix() {
+    let source = "255u8 0i32 0xFFu64 1f32 2.5f64 1e3f32";
+    let suffixed = |suffix: &str| Some(suffix.into());
+    let expected = [
+        TokenKind::Literal(Literal::Integer { radix: 10, suffix: suffixed("u8") }, "255".into()),
+        TokenKind::Literal(Literal::Integer { radix: 10, suffix: suffixed("i32") }, "0".into()),
+        TokenKind::Literal(Literal::Integer { radix: 16, suffix: suffixed("u64") }, "FF".into()),
+        TokenKind::Literal(Literal::Float { radix: 10, suffix: suffixed("f32") }, "1".into()),
+        TokenKind::Literal(Literal::Float { radix: 10, suffix: suffixed("f64") }, "2.5".into()),
+        TokenKind::Literal(Literal::Float { radix: 10, suffix: suffixed("f32") }, "1e3".into()),
+    ]
+    .map(|x| Token::new(x, 1));
+    let tokens = quick_lex(source).expect("expected source to be fully lexed");
+    assert_eq!(&tokens, &expected,);
+
+    for source in ["1abc", "2.5u8", "0b1f32", "3_u8"] {
+        let err = quick_lex(source).expect_err("expected to get an error");
+        assert_eq!(err.code, INVALID_SUFFIX, "{source}");
+    }
+    let err = quick_lex("1usize").expect_err("expected to get an error");
+    assert_eq!(
+        err.message.as_ref(),
+        "invalid suffix 'usize' for a number; expected one of i8, i16, i32, i64, u8, u16, u32, u64, f32 or f64"
+    );
+}
+
 #[test]
 fn test_num_err() {
     let source = "1.";
@@ -294,9 +322,10 @@ fn test_op_parenthesis() {
 }
 
 #[test]
-fn test_keywords_b2e() {
-    let source = "break consttime continue do else enum extern";
+fn test_keywords_a2e() {
+    let source = "as break consttime continue do else enum extern";
     let expected = [
+        Keyword::As,
         Keyword::Break,
         Keyword::Consttime,
         Keyword::Continue,
@@ -363,10 +392,11 @@ fn test_keywords_r2t() {
 
 #[test]
 fn test_keywords_u2w() {
-    let source = "unsafe union where while";
+    let source = "unsafe union unless where while";
     let expected = [
         Keyword::Unsafe,
         Keyword::Union,
+        Keyword::Unless,
         Keyword::Where,
         Keyword::While,
     ]
@@ -377,10 +407,13 @@ fn test_keywords_u2w() {
 
 #[test]
 fn test_identifiers() {
-    let source = "self abcdef123 æʁ̥õʰɹıüş ЇЈЉЊЋЌЎЏАБ զէըթժի اللهواكبر 私の名前は";
+    let source = "self abcdef123 _ snake_case _private æʁ̥õʰɹıüş ЇЈЉЊЋЌЎЏАБ զէըթժի اللهواكبر 私の名前は";
     let expected = [
         TokenKind::Identifier("self".into()),
         TokenKind::Identifier("abcdef123".into()),
+        TokenKind::Identifier("_".into()),
+        TokenKind::Identifier("snake_case".into()),
+        TokenKind::Identifier("_private".into()),
         TokenKind::Identifier("æʁ̥õʰɹıüş".into()),
         TokenKind::Identifier("ЇЈЉЊЋЌЎЏАБ".into()),
         TokenKind::Identifier("զէըթժի".into()),
@@ -541,6 +574,24 @@ fn test_string_err() {
     }
 }
 
+#[test]
+fn test_char() {
+    let source = r"'a' 'ç' '\n' '\'' '\x41' '\u00e7'";
+    let expected = ["a", "ç", "\n", "'", "A", "ç"]
+        .map(|c| Token::new(TokenKind::Literal(Literal::Char, c.into()), 1));
+    let tokens = quick_lex(source).expect("expected source to be fully lexed");
+    assert_eq!(&tokens, &expected);
+
+    let err = quick_lex("''").expect_err("expected to get an error");
+    assert_eq!(err.code, EMPTY_CHAR);
+    assert_eq!(err.message.as_ref(), "empty char literal");
+    for source in ["'ab'", "'a", "'", "'\n'"] {
+        let err = quick_lex(source).expect_err("expected to get an error");
+        assert_eq!(err.code, UNFINISHED_CHAR, "{source:?}");
+        assert_eq!(err.message.as_ref(), "unfinished char literal");
+    }
+}
+
 #[test]
 fn test_unexpected_character() {
     let source = "$";
@@ -550,3 +601,55 @@ fn test_unexpected_character() {
     assert_eq!(tokens.line, 1);
     assert_eq!(tokens.file.as_ref(), "<inline>");
 }
+
+#[test]
+fn test_spans() {
+    let mut map = SourceMap::new();
+    let file = map.add("<inline>", "let ü = 0x1f\n  \"a\\n\" ..");
+    let mut lx = Lexer::from_source(&map, file);
+    let mut spans = Vec::new();
+    while lx.next_token().expect("expected source to be fully lexed").is_some() {
+        spans.push(map.text(lx.span()));
+    }
+    assert_eq!(spans, ["let", "ü", "=", "0x1f", "\"a\\n\"", ".."]);
+
+    let file = map.add("<inline>", "a\n  $");
+    let err = Lexer::from_source(&map, file)
+        .collect::<Result<Vec<_>, _>>()
+        .expect_err("expected to get an error");
+    assert_eq!(err.span, Some(Span::new(file, 4, 5)));
+}
+
+#[test]
+fn test_comments() {
+    let source = "a // one\n/* two /* nested\n */ */ b / c /**/";
+    let mut lx = Lexer::new("<inline>", source);
+    let mut tokens = Vec::new();
+    while let Some(token) = lx.next_token().expect("expected source to be fully lexed") {
+        tokens.push(token);
+    }
+    let expected = [
+        Token::new(TokenKind::Identifier("a".into()), 1),
+        Token::new(TokenKind::Identifier("b".into()), 3),
+        Token::new(TokenKind::Operator(Operator::Slash), 3),
+        Token::new(TokenKind::Identifier("c".into()), 3),
+    ];
+    assert_eq!(tokens, expected);
+    let comments: Vec<_> = lx
+        .comments()
+        .iter()
+        .map(|comment| (comment.block, comment.text.as_ref(), comment.span.start))
+        .collect();
+    assert_eq!(
+        comments,
+        [
+            (false, "// one", 2),
+            (true, "/* two /* nested\n */ */", 9),
+            (true, "/**/", 39)
+        ]
+    );
+
+    let err = quick_lex("a /* /* */").expect_err("expected to get an error");
+    assert_eq!(err.code, UNFINISHED_COMMENT);
+    assert_eq!(err.span, Some(Span::new(FileId(0), 2, 10)));
+}
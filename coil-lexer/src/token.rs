@@ -1,3 +1,5 @@
+use coil_error::{msg, Span};
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum TokenKind {
     Identifier(Box<str>),
@@ -19,8 +21,20 @@ impl Token {
     }
 }
 
+/// A comment, which the lexer skips like whitespace but keeps aside for tools that print
+/// the source back, such as the formatter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    pub span: Span,
+    /// Whether this is a `/* block comment */` rather than a `// line comment`.
+    pub block: bool,
+    /// The whole comment, `//` or `/*` and `*/` included.
+    pub text: Box<str>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Keyword {
+    As,
     Break,
     Consttime,
     Continue,
@@ -60,8 +74,26 @@ pub enum Keyword {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Literal {
     String,
-    Integer { radix: usize },
-    Float { radix: usize },
+    Char,
+    /// A number, with the type written after its digits, like the `u8` of `255u8`.
+    Integer {
+        radix: usize,
+        suffix: Option<Box<str>>,
+    },
+    Float {
+        radix: usize,
+        suffix: Option<Box<str>>,
+    },
+}
+
+impl Literal {
+    /// The type written after the digits of a number, like `u8`.
+    pub fn suffix(&self) -> Option<&str> {
+        match self {
+            Literal::Integer { suffix, .. } | Literal::Float { suffix, .. } => suffix.as_deref(),
+            Literal::String | Literal::Char => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -116,3 +148,126 @@ pub enum Parenthesis {
     Square,
     Curly,
 }
+
+impl Keyword {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Keyword::As => "as",
+            Keyword::Break => "break",
+            Keyword::Consttime => "consttime",
+            Keyword::Continue => "continue",
+            Keyword::Do => "do",
+            Keyword::Else => "else",
+            Keyword::Enum => "enum",
+            Keyword::Extern => "extern",
+            Keyword::Fallthrough => "fallthrough",
+            Keyword::False => "false",
+            Keyword::Fn => "fn",
+            Keyword::For => "for",
+            Keyword::If => "if",
+            Keyword::Impl => "impl",
+            Keyword::Import => "import",
+            Keyword::In => "in",
+            Keyword::Is => "is",
+            Keyword::Launch => "launch",
+            Keyword::Let => "let",
+            Keyword::Match => "match",
+            Keyword::Module => "module",
+            Keyword::Mut => "mut",
+            Keyword::Pub => "pub",
+            Keyword::Return => "return",
+            Keyword::SelfType => "Self",
+            Keyword::Static => "static",
+            Keyword::Struct => "struct",
+            Keyword::Trait => "trait",
+            Keyword::True => "true",
+            Keyword::Type => "type",
+            Keyword::Union => "union",
+            Keyword::Unless => "unless",
+            Keyword::Unsafe => "unsafe",
+            Keyword::Where => "where",
+            Keyword::While => "while",
+        }
+    }
+}
+
+impl Operator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operator::Dot => ".",
+            Operator::Comma => ",",
+            Operator::Colon => ":",
+            Operator::Semicolon => ";",
+            Operator::DoubleDot => "..",
+            Operator::QuestionMark => "?",
+            Operator::Arrow => "->",
+            Operator::Bolt => "=>",
+            Operator::Backslash => "\\",
+            Operator::Plus => "+",
+            Operator::PlusAssign => "+=",
+            Operator::Minus => "-",
+            Operator::MinusAssign => "-=",
+            Operator::Star => "*",
+            Operator::StarAssign => "*=",
+            Operator::Slash => "/",
+            Operator::SlashAssign => "/=",
+            Operator::Percent => "%",
+            Operator::PercentAssign => "%=",
+            Operator::Eq => "==",
+            Operator::NotEq => "!=",
+            Operator::Greater => ">",
+            Operator::GreaterEq => ">=",
+            Operator::Lesser => "<",
+            Operator::LesserEq => "<=",
+            Operator::Not => "!",
+            Operator::And => "&&",
+            Operator::AndAssign => "&&=",
+            Operator::Or => "||",
+            Operator::OrAssign => "||=",
+            Operator::BitNot => "~",
+            Operator::BitAnd => "&",
+            Operator::BitAndAssign => "&=",
+            Operator::BitOr => "|",
+            Operator::BitOrAssign => "|=",
+            Operator::BitXor => "^",
+            Operator::BitXorAssign => "^=",
+            Operator::BitShiftLeft => "<<",
+            Operator::BitShiftLeftAssign => "<<=",
+            Operator::BitShiftRight => ">>",
+            Operator::BitShiftRightAssign => ">>=",
+            Operator::Assign => "=",
+        }
+    }
+}
+
+impl Parenthesis {
+    pub fn as_str(&self, closing: bool) -> &'static str {
+        match (self, closing) {
+            (Parenthesis::Normal, false) => "(",
+            (Parenthesis::Normal, true) => ")",
+            (Parenthesis::Square, false) => "[",
+            (Parenthesis::Square, true) => "]",
+            (Parenthesis::Curly, false) => "{",
+            (Parenthesis::Curly, true) => "}",
+        }
+    }
+}
+
+/// Prints the token the way diagnostics quote it: `'fn'`, `'+='`, `identifier 'foo'`.
+impl std::fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenKind::Identifier(name) => f.write_str(&msg!("token-identifier", name = name)),
+            TokenKind::Keyword(kw) => write!(f, "'{}'", kw.as_str()),
+            TokenKind::Literal(Literal::String, value) => {
+                f.write_str(&msg!("token-string", value = format!("{value:?}")))
+            }
+            TokenKind::Literal(Literal::Char, value) => {
+                f.write_str(&msg!("token-char", value = format!("{value:?}")))
+            }
+            TokenKind::Literal(_, value) => f.write_str(&msg!("token-number", value = value)),
+            TokenKind::Operator(op) => write!(f, "'{}'", op.as_str()),
+            TokenKind::Parenthesis { closing, kind } => write!(f, "'{}'", kind.as_str(*closing)),
+        }
+    }
+}
@@ -0,0 +1,734 @@
+use std::fmt::Write;
+
+use coil_error::{FileId, SourceMap, Span};
+use coil_lexer::Literal;
+
+use crate::{
+    ArgName, BinaryOperator, ClosureParam, Expr, ExprKind, Field, Fields, GenericParam, Generics,
+    Ident, ImportTree, MatchArm, Pattern, PatternKind, Signature, Statement, TypeExpr, TypeKind,
+    UnaryOperator, Variant, WherePredicate,
+};
+
+/// A plain view of the AST, which [`Dump::to_json`], [`Dump::to_sexp`] and
+/// [`Dump::to_tree`] print. Nodes are named after their variant, and their fields after
+/// the fields of the variant.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Dump {
+    Node {
+        name: &'static str,
+        span: Option<Span>,
+        fields: Vec<(&'static str, Dump)>,
+    },
+    List(Vec<Dump>),
+    Str(Box<str>),
+    Number(u64),
+    Bool(bool),
+    None,
+}
+
+/// The formats an AST can be printed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+    Json,
+    Sexp,
+    Tree,
+}
+
+/// Converts a part of the AST into a [`Dump`].
+pub trait ToDump {
+    fn to_dump(&self) -> Dump;
+}
+
+/// Lines longer than this are broken in S-expressions.
+const SEXP_WIDTH: usize = 80;
+
+impl Dump {
+    fn node(name: &'static str, fields: Vec<(&'static str, Dump)>) -> Self {
+        Dump::Node {
+            name,
+            span: None,
+            fields,
+        }
+    }
+
+    fn at(mut self, at: Span) -> Self {
+        if let Dump::Node { span, .. } = &mut self {
+            *span = Some(at);
+        }
+        self
+    }
+
+    /// Prints in the given format, with spans when `map` is given. Without them, the
+    /// output depends only on the structure of the tree, as golden tests want.
+    pub fn render(&self, format: DumpFormat, map: Option<&SourceMap>) -> String {
+        match format {
+            DumpFormat::Json => self.to_json(map),
+            DumpFormat::Sexp => self.to_sexp(map),
+            DumpFormat::Tree => self.to_tree(map),
+        }
+    }
+
+    /// Indented JSON, with nodes as objects whose `"node"` is the name of the node and
+    /// whose `"span"` is a string like `"main.coil:1:1-2:5"`.
+    pub fn to_json(&self, map: Option<&SourceMap>) -> String {
+        let mut out = String::new();
+        self.write_json(map, 0, &mut out);
+        out.push('\n');
+        out
+    }
+
+    fn write_json(&self, map: Option<&SourceMap>, depth: usize, out: &mut String) {
+        let indent = "  ".repeat(depth + 1);
+        match self {
+            Dump::Node { name, span, fields } => {
+                write!(out, "{{\n{indent}\"node\": {}", json_string(name)).unwrap();
+                if let Some(span) = span_text(*span, map, None) {
+                    write!(out, ",\n{indent}\"span\": {}", json_string(&span)).unwrap();
+                }
+                for (name, value) in fields {
+                    write!(out, ",\n{indent}{}: ", json_string(name)).unwrap();
+                    value.write_json(map, depth + 1, out);
+                }
+                write!(out, "\n{}}}", &indent[2..]).unwrap();
+            }
+            Dump::List(items) if items.is_empty() => out.push_str("[]"),
+            Dump::List(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    out.push_str(if i == 0 { "\n" } else { ",\n" });
+                    out.push_str(&indent);
+                    item.write_json(map, depth + 1, out);
+                }
+                write!(out, "\n{}]", &indent[2..]).unwrap();
+            }
+            Dump::Str(text) => out.push_str(&json_string(text)),
+            Dump::Number(n) => write!(out, "{n}").unwrap(),
+            Dump::Bool(b) => write!(out, "{b}").unwrap(),
+            Dump::None => out.push_str("null"),
+        }
+    }
+
+    /// `(Name @span :field value ...)`, with lists in brackets and `nil` for missing
+    /// values. Nodes that don't fit on a line get one line per field.
+    pub fn to_sexp(&self, map: Option<&SourceMap>) -> String {
+        let mut out = String::new();
+        self.write_sexp(map, None, 0, 0, &mut out);
+        out.push('\n');
+        out
+    }
+
+    /// Writes a value that starts at `column` of the current line.
+    fn write_sexp(
+        &self,
+        map: Option<&SourceMap>,
+        file: Option<FileId>,
+        depth: usize,
+        column: usize,
+        out: &mut String,
+    ) {
+        let flat = self.flat_sexp(map, file);
+        if column + flat.len() <= SEXP_WIDTH {
+            out.push_str(&flat);
+            return;
+        }
+        let indent = "  ".repeat(depth + 1);
+        match self {
+            Dump::Node { name, span, fields } => {
+                write!(out, "({name}").unwrap();
+                if let Some(span) = span_text(*span, map, file) {
+                    write!(out, " @{span}").unwrap();
+                }
+                let file = span.map(|span| span.file).or(file);
+                for (name, value) in fields {
+                    write!(out, "\n{indent}:{name} ").unwrap();
+                    let column = indent.len() + name.len() + 2;
+                    value.write_sexp(map, file, depth + 1, column, out);
+                }
+                out.push(')');
+            }
+            Dump::List(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    let column = if i == 0 {
+                        column + 1
+                    } else {
+                        write!(out, "\n{indent}").unwrap();
+                        indent.len()
+                    };
+                    item.write_sexp(map, file, depth + 1, column, out);
+                }
+                out.push(']');
+            }
+            _ => out.push_str(&flat),
+        }
+    }
+
+    fn flat_sexp(&self, map: Option<&SourceMap>, file: Option<FileId>) -> String {
+        match self {
+            Dump::Node { name, span, fields } => {
+                let mut out = format!("({name}");
+                if let Some(span) = span_text(*span, map, file) {
+                    write!(out, " @{span}").unwrap();
+                }
+                let file = span.map(|span| span.file).or(file);
+                for (name, value) in fields {
+                    write!(out, " :{name} {}", value.flat_sexp(map, file)).unwrap();
+                }
+                out + ")"
+            }
+            Dump::List(items) => {
+                let items: Vec<_> = items.iter().map(|item| item.flat_sexp(map, file)).collect();
+                format!("[{}]", items.join(" "))
+            }
+            Dump::Str(text) => format!("{text:?}"),
+            Dump::Number(n) => n.to_string(),
+            Dump::Bool(b) => b.to_string(),
+            Dump::None => "nil".into(),
+        }
+    }
+
+    /// An outline with one line per node and field, indented by depth.
+    pub fn to_tree(&self, map: Option<&SourceMap>) -> String {
+        let mut out = String::new();
+        self.write_tree(map, None, 0, &mut out);
+        out
+    }
+
+    /// Writes the rest of the line of a value, and its children on the following lines.
+    fn write_tree(
+        &self,
+        map: Option<&SourceMap>,
+        file: Option<FileId>,
+        depth: usize,
+        out: &mut String,
+    ) {
+        let indent = "  ".repeat(depth + 1);
+        match self {
+            Dump::Node { name, span, fields } => {
+                out.push_str(name);
+                if let Some(span) = span_text(*span, map, file) {
+                    write!(out, " @ {span}").unwrap();
+                }
+                out.push('\n');
+                let file = span.map(|span| span.file).or(file);
+                for (name, value) in fields {
+                    let separator = match value {
+                        Dump::List(items) if !items.is_empty() => "",
+                        _ => " ",
+                    };
+                    write!(out, "{indent}{name}:{separator}").unwrap();
+                    value.write_tree(map, file, depth + 1, out);
+                }
+            }
+            Dump::List(items) if items.is_empty() => out.push_str("[]\n"),
+            Dump::List(items) => {
+                out.push('\n');
+                for item in items {
+                    write!(out, "{indent}- ").unwrap();
+                    item.write_tree(map, file, depth + 1, out);
+                }
+            }
+            _ => writeln!(out, "{}", self.flat_sexp(map, file)).unwrap(),
+        }
+    }
+}
+
+/// `main.coil:1:1-2:5`, if there is both a span and a map to find its lines in. The file
+/// is left out when it is `parent`, the file of the enclosing node.
+fn span_text(
+    span: Option<Span>,
+    map: Option<&SourceMap>,
+    parent: Option<FileId>,
+) -> Option<String> {
+    let (span, map) = span.zip(map)?;
+    let file = map.get(span.file);
+    let start = file.location(span.start);
+    let end = file.location(span.end);
+    let lines = format!(
+        "{}:{}-{}:{}",
+        start.line, start.column, end.line, end.column
+    );
+    Some(if parent == Some(span.file) {
+        lines
+    } else {
+        format!("{}:{lines}", file.name)
+    })
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Builds a node from its name and fields, each converted with [`ToDump`].
+macro_rules! node {
+    ($name:literal $(, $field:ident: $value:expr)* $(,)?) => {
+        Dump::node($name, vec![$((stringify!($field), $value.to_dump())),*])
+    };
+}
+
+impl<T: ToDump + ?Sized> ToDump for Box<T> {
+    fn to_dump(&self) -> Dump {
+        (**self).to_dump()
+    }
+}
+
+impl<T: ToDump> ToDump for Option<T> {
+    fn to_dump(&self) -> Dump {
+        self.as_ref().map_or(Dump::None, T::to_dump)
+    }
+}
+
+impl<T: ToDump> ToDump for Vec<T> {
+    fn to_dump(&self) -> Dump {
+        Dump::List(self.iter().map(T::to_dump).collect())
+    }
+}
+
+impl ToDump for str {
+    fn to_dump(&self) -> Dump {
+        Dump::Str(self.into())
+    }
+}
+
+impl ToDump for Ident {
+    fn to_dump(&self) -> Dump {
+        self.text.to_dump()
+    }
+}
+
+impl ToDump for bool {
+    fn to_dump(&self) -> Dump {
+        Dump::Bool(*self)
+    }
+}
+
+impl ToDump for BinaryOperator {
+    fn to_dump(&self) -> Dump {
+        Dump::Str(format!("{self:?}").into())
+    }
+}
+
+impl ToDump for UnaryOperator {
+    fn to_dump(&self) -> Dump {
+        Dump::Str(format!("{self:?}").into())
+    }
+}
+
+impl ToDump for usize {
+    fn to_dump(&self) -> Dump {
+        Dump::Number(*self as u64)
+    }
+}
+
+impl ToDump for Literal {
+    fn to_dump(&self) -> Dump {
+        let (name, radix, suffix) = match self {
+            Literal::String => return node!("String"),
+            Literal::Char => return node!("Char"),
+            Literal::Integer { radix, suffix } => ("Integer", radix, suffix),
+            Literal::Float { radix, suffix } => ("Float", radix, suffix),
+        };
+        let mut fields = vec![("radix", radix.to_dump())];
+        if let Some(suffix) = suffix {
+            fields.push(("suffix", suffix.to_dump()));
+        }
+        Dump::node(name, fields)
+    }
+}
+
+impl ToDump for Expr {
+    fn to_dump(&self) -> Dump {
+        self.kind.to_dump().at(self.span)
+    }
+}
+
+impl ToDump for ExprKind {
+    fn to_dump(&self) -> Dump {
+        match self {
+            ExprKind::Statement(statement) => statement.to_dump(),
+            ExprKind::Binary { op, left, right } => {
+                node!("Binary", op: op, left: left, right: right)
+            }
+            ExprKind::Unary { op, expr } => node!("Unary", op: op, expr: expr),
+            ExprKind::Call { callee, args } => {
+                let args = args
+                    .iter()
+                    .map(|(label, value)| node!("Arg", label: label, value: value))
+                    .collect();
+                Dump::node(
+                    "Call",
+                    vec![("callee", callee.to_dump()), ("args", Dump::List(args))],
+                )
+            }
+            ExprKind::Index { expr, index } => node!("Index", expr: expr, index: index),
+            ExprKind::Tuple(items) => node!("Tuple", items: items),
+            ExprKind::Array(items) => node!("Array", items: items),
+            ExprKind::Generic { path, args } => node!("Generic", path: path, args: args),
+            ExprKind::StructLiteral { path, fields } => {
+                let fields = fields
+                    .iter()
+                    .map(|(name, value)| node!("FieldValue", name: name, value: value))
+                    .collect();
+                Dump::node(
+                    "StructLiteral",
+                    vec![("path", path.to_dump()), ("fields", Dump::List(fields))],
+                )
+            }
+            ExprKind::Block(items) => node!("Block", items: items),
+            ExprKind::If {
+                unless,
+                condition,
+                then,
+                otherwise,
+            } => node!(
+                "If",
+                unless: unless,
+                condition: condition,
+                then: then,
+                otherwise: otherwise,
+            ),
+            ExprKind::While {
+                label,
+                condition,
+                body,
+            } => node!("While", label: label, condition: condition, body: body),
+            ExprKind::For {
+                label,
+                binding,
+                iterable,
+                body,
+            } => node!(
+                "For",
+                label: label,
+                binding: binding,
+                iterable: iterable,
+                body: body,
+            ),
+            ExprKind::Match { scrutinee, arms } => {
+                node!("Match", scrutinee: scrutinee, arms: arms)
+            }
+            ExprKind::DoWhile {
+                label,
+                body,
+                condition,
+            } => node!("DoWhile", label: label, body: body, condition: condition),
+            ExprKind::Closure {
+                params,
+                returns,
+                body,
+            } => node!("Closure", params: params, returns: returns, body: body),
+            ExprKind::Literal(kind, text) => node!("Literal", kind: kind, text: text),
+            ExprKind::Bool(value) => node!("Bool", value: value),
+            ExprKind::Identifier(name) => node!("Identifier", name: name),
+            ExprKind::Error => node!("Error"),
+        }
+    }
+}
+
+impl ToDump for Statement {
+    fn to_dump(&self) -> Dump {
+        match self {
+            Statement::Module {
+                name,
+                inline,
+                children,
+            } => node!("Module", name: name, inline: inline, children: children),
+            Statement::Use { tree } => node!("Use", tree: tree),
+            Statement::Fn {
+                public,
+                name,
+                generics,
+                signature,
+                body,
+            } => node!(
+                "Fn",
+                public: public,
+                name: name,
+                generics: generics,
+                signature: signature,
+                body: body,
+            ),
+            Statement::Struct {
+                public,
+                name,
+                generics,
+                fields,
+            } => node!(
+                "Struct",
+                public: public,
+                name: name,
+                generics: generics,
+                fields: fields,
+            ),
+            Statement::Enum {
+                public,
+                name,
+                generics,
+                variants,
+            } => node!(
+                "Enum",
+                public: public,
+                name: name,
+                generics: generics,
+                variants: variants,
+            ),
+            Statement::Union {
+                public,
+                name,
+                generics,
+                fields,
+            } => node!(
+                "Union",
+                public: public,
+                name: name,
+                generics: generics,
+                fields: fields,
+            ),
+            Statement::Trait {
+                public,
+                name,
+                generics,
+                supertraits,
+                items,
+            } => node!(
+                "Trait",
+                public: public,
+                name: name,
+                generics: generics,
+                supertraits: supertraits,
+                items: items,
+            ),
+            Statement::Impl {
+                generics,
+                trait_,
+                target,
+                items,
+            } => Dump::node(
+                "Impl",
+                vec![
+                    ("generics", generics.to_dump()),
+                    ("trait", trait_.to_dump()),
+                    ("target", target.to_dump()),
+                    ("items", items.to_dump()),
+                ],
+            ),
+            Statement::TypeAlias {
+                public,
+                name,
+                generics,
+                ty,
+            } => node!(
+                "TypeAlias",
+                public: public,
+                name: name,
+                generics: generics,
+                ty: ty,
+            ),
+            Statement::AssociatedType {
+                name,
+                bounds,
+                default,
+            } => node!("AssociatedType", name: name, bounds: bounds, default: default),
+            Statement::Extern { abi, items } => node!("Extern", abi: abi, items: items),
+            Statement::Static {
+                public,
+                mutable,
+                name,
+                ty,
+                value,
+            } => node!(
+                "Static",
+                public: public,
+                mutable: mutable,
+                name: name,
+                ty: ty,
+                value: value,
+            ),
+            Statement::Let {
+                mutable,
+                name,
+                ty,
+                value,
+            } => node!("Let", mutable: mutable, name: name, ty: ty, value: value),
+            Statement::Return(value) => node!("Return", value: value),
+            Statement::Break { label, value } => node!("Break", label: label, value: value),
+            Statement::Continue { label } => node!("Continue", label: label),
+            Statement::Fallthrough => node!("Fallthrough"),
+        }
+    }
+}
+
+impl ToDump for TypeExpr {
+    fn to_dump(&self) -> Dump {
+        self.kind.to_dump().at(self.span)
+    }
+}
+
+impl ToDump for TypeKind {
+    fn to_dump(&self) -> Dump {
+        match self {
+            TypeKind::Path { path, args } => node!("PathType", path: path, args: args),
+            TypeKind::Reference { mutable, ty } => {
+                node!("ReferenceType", mutable: mutable, ty: ty)
+            }
+            TypeKind::Pointer { mutable, ty } => node!("PointerType", mutable: mutable, ty: ty),
+            TypeKind::Array { ty, len } => node!("ArrayType", ty: ty, len: len),
+            TypeKind::Slice(ty) => node!("SliceType", ty: ty),
+            TypeKind::Tuple(types) => node!("TupleType", types: types),
+            TypeKind::Fn { params, returns } => node!("FnType", params: params, returns: returns),
+            TypeKind::Optional(ty) => node!("OptionalType", ty: ty),
+            TypeKind::Result { ok, error } => node!("ResultType", ok: ok, error: error),
+        }
+    }
+}
+
+impl ToDump for Pattern {
+    fn to_dump(&self) -> Dump {
+        self.kind.to_dump().at(self.span)
+    }
+}
+
+impl ToDump for PatternKind {
+    fn to_dump(&self) -> Dump {
+        match self {
+            PatternKind::Wildcard => node!("WildcardPattern"),
+            PatternKind::Binding { mutable, name } => {
+                node!("BindingPattern", mutable: mutable, name: name)
+            }
+            PatternKind::Literal(kind, text) => node!("LiteralPattern", kind: kind, text: text),
+            PatternKind::Bool(value) => node!("BoolPattern", value: value),
+            PatternKind::Range { start, end } => node!("RangePattern", start: start, end: end),
+            PatternKind::Path(path) => node!("PathPattern", path: path),
+            PatternKind::Tuple(patterns) => node!("TuplePattern", patterns: patterns),
+            PatternKind::Variant { path, args } => {
+                node!("VariantPattern", path: path, args: args)
+            }
+            PatternKind::Struct { path, fields, rest } => {
+                let fields = fields
+                    .iter()
+                    .map(|(name, pattern)| node!("FieldPattern", name: name, pattern: pattern))
+                    .collect();
+                Dump::node(
+                    "StructPattern",
+                    vec![
+                        ("path", path.to_dump()),
+                        ("fields", Dump::List(fields)),
+                        ("rest", rest.to_dump()),
+                    ],
+                )
+            }
+            PatternKind::Or(patterns) => node!("OrPattern", patterns: patterns),
+            PatternKind::Is { binding, ty } => node!("IsPattern", binding: binding, ty: ty),
+        }
+    }
+}
+
+impl ToDump for MatchArm {
+    fn to_dump(&self) -> Dump {
+        node!("MatchArm", pattern: self.pattern, guard: self.guard, body: self.body)
+    }
+}
+
+impl ToDump for ClosureParam {
+    fn to_dump(&self) -> Dump {
+        node!("ClosureParam", name: self.name, ty: self.ty)
+    }
+}
+
+impl ToDump for Generics {
+    fn to_dump(&self) -> Dump {
+        node!("Generics", params: self.params, where_clause: self.where_clause)
+    }
+}
+
+impl ToDump for GenericParam {
+    fn to_dump(&self) -> Dump {
+        node!("GenericParam", name: self.name, bounds: self.bounds)
+    }
+}
+
+impl ToDump for WherePredicate {
+    fn to_dump(&self) -> Dump {
+        node!("WherePredicate", ty: self.ty, bounds: self.bounds)
+    }
+}
+
+impl ToDump for Signature {
+    fn to_dump(&self) -> Dump {
+        let args = self
+            .named_args
+            .iter()
+            .map(|(name, ty)| node!("Param", name: name, ty: ty))
+            .collect();
+        Dump::node(
+            "Signature",
+            vec![
+                ("args", Dump::List(args)),
+                ("returns", self.returns.to_dump()),
+            ],
+        )
+    }
+}
+
+impl ToDump for ArgName {
+    fn to_dump(&self) -> Dump {
+        match self {
+            ArgName::Unnamed(inner) => node!("Unnamed", inner: inner),
+            ArgName::Single(name) => node!("Single", name: name),
+            ArgName::Assigned { outer, inner } => {
+                node!("Assigned", outer: outer, inner: inner)
+            }
+        }
+    }
+}
+
+impl ToDump for Field {
+    fn to_dump(&self) -> Dump {
+        node!("Field", public: self.public, name: self.name, ty: self.ty)
+    }
+}
+
+impl ToDump for Fields {
+    fn to_dump(&self) -> Dump {
+        match self {
+            Fields::Named(fields) => node!("NamedFields", fields: fields),
+            Fields::Tuple(fields) => node!("TupleFields", fields: fields),
+            Fields::Unit => node!("UnitFields"),
+        }
+    }
+}
+
+impl ToDump for Variant {
+    fn to_dump(&self) -> Dump {
+        node!(
+            "Variant",
+            name: self.name,
+            fields: self.fields,
+            discriminant: self.discriminant,
+        )
+    }
+}
+
+impl ToDump for ImportTree {
+    fn to_dump(&self) -> Dump {
+        match self {
+            ImportTree::Path { path, alias } => node!("ImportPath", path: path, alias: alias),
+            ImportTree::Group { prefix, items } => {
+                node!("ImportGroup", prefix: prefix, items: items)
+            }
+        }
+    }
+}
@@ -1,53 +1,234 @@
-use coil_error::Error;
+use coil_error::Span;
 use coil_lexer::Literal;
 
-#[derive(Debug, Clone, Hash)]
-pub enum ArgName {
-    Unnamed(Box<str>), // _ inner: T
-    Single(Box<str>), // argname: T
-    Assigned { outer: Box<str>, inner: Box<str> }, // outer inner: T
+use crate::{Pattern, TypeExpr};
+
+/// A name as written in the source, like that of an item, a field or a variable. Like
+/// [`Expr`], names compare and hash by their text only.
+#[derive(Debug, Clone)]
+pub struct Ident {
+    pub text: Box<str>,
+    pub span: Span,
 }
 
-impl PartialEq for ArgName {
+impl PartialEq for Ident {
     fn eq(&self, other: &Self) -> bool {
-        match self {
-            Self::Unnamed(_) => false,
-            Self::Single(x) => match other {
-                Self::Unnamed(_) => false,
-                Self::Single(y) => x == y,
-                Self::Assigned { outer: y, .. } => x == y,
-            },
-            Self::Assigned { outer: x, .. } => match other {
-                Self::Unnamed(_) => false,
-                Self::Single(y) => x == y,
-                Self::Assigned { outer: y, .. } => x == y,
-            },
+        self.text == other.text
+    }
+}
+
+impl Eq for Ident {}
+
+impl PartialEq<str> for Ident {
+    fn eq(&self, other: &str) -> bool {
+        *self.text == *other
+    }
+}
+
+impl std::hash::Hash for Ident {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.text.hash(state)
+    }
+}
+
+impl std::ops::Deref for Ident {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl std::fmt::Display for Ident {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// A name without a location, for trees built by hand.
+impl From<&str> for Ident {
+    fn from(text: &str) -> Self {
+        Self {
+            text: text.into(),
+            span: Span::default(),
         }
     }
 }
 
-#[derive(Debug, Clone, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ArgName {
+    Unnamed(Ident),                          // _ inner: T
+    Single(Ident),                           // argname: T
+    Assigned { outer: Ident, inner: Ident }, // outer inner: T
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub struct Signature {
-    returns: Box<Expr>,
-    named_args: Vec<(ArgName, Expr)>,
+    pub returns: Box<TypeExpr>,
+    pub named_args: Vec<(ArgName, TypeExpr)>,
 }
 
-#[derive(Debug, Clone, Hash)]
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Field {
+    pub public: bool,
+    pub name: Ident,
+    pub ty: TypeExpr,
+}
+
+/// The generic parameters of an item, `<T: Bound, U>`, and its `where` clause.
+#[derive(Debug, Clone, PartialEq, Hash, Default)]
+pub struct Generics {
+    pub params: Vec<GenericParam>,
+    pub where_clause: Vec<WherePredicate>,
+}
+
+/// `T` or `T: Bound + Bound`.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct GenericParam {
+    pub name: Ident,
+    pub bounds: Vec<TypeExpr>,
+}
+
+/// `Type: Bound + Bound` in a `where` clause.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct WherePredicate {
+    pub ty: TypeExpr,
+    pub bounds: Vec<TypeExpr>,
+}
+
+/// The fields of a struct or an enum variant.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub enum Fields {
+    /// `{ x: Int, y: Int }`
+    Named(Vec<Field>),
+    /// `(Int, Int)`; the fields are named after their position, `0`, `1`, ...
+    Tuple(Vec<Field>),
+    /// No fields at all, as in `struct Marker` or the `Red` of `enum Color { Red }`.
+    Unit,
+}
+
+/// `Name`, `Name(T, ...)` or `Name { field: T, ... }`, optionally followed by
+/// `= discriminant`.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Variant {
+    pub name: Ident,
+    pub fields: Fields,
+    pub discriminant: Option<Box<Expr>>,
+}
+
+/// The names an `import` brings into scope.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub enum ImportTree {
+    /// `a.b` or `a.b as c`.
+    Path {
+        path: Box<Expr>,
+        alias: Option<Box<str>>,
+    },
+    /// `a.b.{c, d as e}`, where every item continues the prefix.
+    Group {
+        prefix: Box<Expr>,
+        items: Vec<ImportTree>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub enum Statement {
+    /// `module name { ... }` when `inline`, or `module name` for a module stored in another
+    /// file, whose children [`load`](crate::load) fills in.
     Module {
         name: Box<Expr>,
+        inline: bool,
         children: Vec<Expr>,
     },
     Use {
-        name: Box<Expr>,
+        tree: ImportTree,
     },
     Fn {
-        name: Box<str>,
+        public: bool,
+        name: Ident,
+        generics: Generics,
         signature: Signature,
+        body: Option<Box<Expr>>,
+    },
+    Struct {
+        public: bool,
+        name: Ident,
+        generics: Generics,
+        fields: Fields,
+    },
+    Enum {
+        public: bool,
+        name: Ident,
+        generics: Generics,
+        variants: Vec<Variant>,
+    },
+    Union {
+        public: bool,
+        name: Ident,
+        generics: Generics,
+        fields: Vec<Field>,
+    },
+    /// `trait Name<T>: Supertrait + Supertrait { ... }`, with methods, associated types
+    /// and associated statics as items.
+    Trait {
+        public: bool,
+        name: Ident,
+        generics: Generics,
+        supertraits: Vec<TypeExpr>,
+        items: Vec<Expr>,
     },
+    /// `impl<T> Trait for Type { ... }`, or `impl<T> Type { ... }` without a trait.
+    Impl {
+        generics: Generics,
+        trait_: Option<Box<TypeExpr>>,
+        target: Box<TypeExpr>,
+        items: Vec<Expr>,
+    },
+    TypeAlias {
+        public: bool,
+        name: Ident,
+        generics: Generics,
+        ty: Box<TypeExpr>,
+    },
+    /// `type Name: Bound = Default` in a trait, which implementations define with a
+    /// [`Statement::TypeAlias`].
+    AssociatedType {
+        name: Ident,
+        bounds: Vec<TypeExpr>,
+        default: Option<Box<TypeExpr>>,
+    },
+    Extern {
+        abi: Option<Box<str>>,
+        items: Vec<Expr>,
+    },
+    /// A static, whose value may only be left out in a trait.
+    Static {
+        public: bool,
+        mutable: bool,
+        name: Ident,
+        ty: Box<TypeExpr>,
+        value: Option<Box<Expr>>,
+    },
+    Let {
+        mutable: bool,
+        name: Ident,
+        ty: Option<Box<TypeExpr>>,
+        value: Option<Box<Expr>>,
+    },
+    Return(Option<Box<Expr>>),
+    /// `break`, `break value`, `break label` or `break label value`.
+    Break {
+        label: Option<Box<str>>,
+        value: Option<Box<Expr>>,
+    },
+    Continue {
+        label: Option<Box<str>>,
+    },
+    /// Goes on to the body of the next arm of a `match`.
+    Fallthrough,
 }
 
-#[derive(Debug, Clone, Copy, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum BinaryOperator {
     Dot,
     Comma,
@@ -72,7 +253,6 @@ pub enum BinaryOperator {
     AndAssign,
     Or,
     OrAssign,
-    BitNot,
     BitAnd,
     BitAndAssign,
     BitOr,
@@ -86,19 +266,59 @@ pub enum BinaryOperator {
     Assign,
 }
 
-#[derive(Debug, Clone, Copy, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum UnaryOperator {
     Try,
     Not,
+    BitNot,
     DoubleReference,
     Reference,
+    MutReference,
     Dereference,
     Positive,
     Negative,
 }
 
-#[derive(Debug, Clone, Hash)]
-pub enum Expr {
+/// Identifies a node of the AST, unique within a parsed file, or within a whole
+/// [`Program`](crate::Program) when loaded with [`load`](crate::load).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct NodeId(pub u32);
+
+/// A node of the AST: what it is, where it is in the source, and an id to refer to it.
+/// Nodes compare and hash by their contents only, so parsed trees can be compared to
+/// trees built by hand or parsed from different source.
+#[derive(Debug, Clone)]
+pub struct Expr {
+    pub id: NodeId,
+    pub span: Span,
+    pub kind: ExprKind,
+}
+
+impl PartialEq for Expr {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+impl std::hash::Hash for Expr {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.kind.hash(state)
+    }
+}
+
+/// A node without a location, for trees built by hand.
+impl From<ExprKind> for Expr {
+    fn from(kind: ExprKind) -> Self {
+        Self {
+            id: NodeId::default(),
+            span: Span::default(),
+            kind,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub enum ExprKind {
     Statement(Statement),
     Binary {
         op: BinaryOperator,
@@ -109,6 +329,89 @@ pub enum Expr {
         op: UnaryOperator,
         expr: Box<Expr>,
     },
+    /// A call; each argument carries its label, as in `move(from: a, to: b)`.
+    Call {
+        callee: Box<Expr>,
+        args: Vec<(Option<Box<str>>, Expr)>,
+    },
+    Index {
+        expr: Box<Expr>,
+        index: Box<Expr>,
+    },
+    Tuple(Vec<Expr>),
+    Array(Vec<Expr>),
+    /// A path with explicit type arguments, `size.<Int>`.
+    Generic {
+        path: Box<Expr>,
+        args: Vec<TypeExpr>,
+    },
+    /// `Point { x: 1, y }`; `y` alone is short for `y: y`.
+    StructLiteral {
+        path: Box<Expr>,
+        fields: Vec<(Box<str>, Expr)>,
+    },
+    Block(Vec<Expr>),
+    /// `if condition { ... } else ...`, or `unless` when `unless` is set, which runs the
+    /// `then` block when the condition is false. `otherwise` is a block or another `if`.
+    If {
+        unless: bool,
+        condition: Box<Expr>,
+        then: Box<Expr>,
+        otherwise: Option<Box<Expr>>,
+    },
+    While {
+        label: Option<Box<str>>,
+        condition: Box<Expr>,
+        body: Box<Expr>,
+    },
+    /// `for binding in iterable { ... }`, where `iterable` is often a range `a..b`.
+    For {
+        label: Option<Box<str>>,
+        binding: Ident,
+        iterable: Box<Expr>,
+        body: Box<Expr>,
+    },
+    /// `match scrutinee { pattern if guard => body, ... }`.
+    Match {
+        scrutinee: Box<Expr>,
+        arms: Vec<MatchArm>,
+    },
+    /// `do { ... } while condition`, which runs the body at least once.
+    DoWhile {
+        label: Option<Box<str>>,
+        body: Box<Expr>,
+        condition: Box<Expr>,
+    },
+    /// `\x, y -> x + y`, or `\(x: Int) -> Int { ... }` with a block body.
+    Closure {
+        params: Vec<ClosureParam>,
+        returns: Option<Box<TypeExpr>>,
+        body: Box<Expr>,
+    },
     Literal(Literal, Box<str>),
+    Bool(bool),
     Identifier(Box<str>),
+    /// Stands in for code that failed to parse, after the error was recorded.
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Box<Expr>>,
+    pub body: Box<Expr>,
+}
+
+/// A closure parameter, whose type may be left to inference.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct ClosureParam {
+    pub name: Ident,
+    pub ty: Option<TypeExpr>,
+}
+
+impl ExprKind {
+    /// The unit value, `()`.
+    pub fn unit() -> Self {
+        ExprKind::Tuple(Vec::new())
+    }
 }
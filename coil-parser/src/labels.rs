@@ -0,0 +1,120 @@
+//! Argument labels: how a call's labels are matched against the arguments a function
+//! declares.
+use crate::{ArgName, Ident, Signature};
+
+impl ArgName {
+    /// The label callers must write, or `None` for `_` arguments.
+    pub fn label(&self) -> Option<&str> {
+        match self {
+            ArgName::Unnamed(_) => None,
+            ArgName::Single(name) | ArgName::Assigned { outer: name, .. } => Some(name),
+        }
+    }
+
+    /// The name the argument is bound to inside the function body.
+    pub fn binding(&self) -> &Ident {
+        match self {
+            ArgName::Unnamed(name)
+            | ArgName::Single(name)
+            | ArgName::Assigned { inner: name, .. } => name,
+        }
+    }
+}
+
+/// Why a call's labels do not fit a signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LabelMismatch {
+    Count {
+        expected: usize,
+        found: usize,
+    },
+    Missing {
+        index: usize,
+        expected: Box<str>,
+    },
+    Unexpected {
+        index: usize,
+        found: Box<str>,
+    },
+    Misordered {
+        index: usize,
+        found: Box<str>,
+        expected: Box<str>,
+    },
+    Wrong {
+        index: usize,
+        found: Box<str>,
+        expected: Box<str>,
+    },
+}
+
+impl Signature {
+    /// Two signatures declare the same overload when their arguments have the same labels,
+    /// whatever their inner names. A `_` argument never matches another, so a signature
+    /// with one is not the same overload as any other, itself included.
+    pub fn same_overload(&self, other: &Signature) -> bool {
+        self.named_args.len() == other.named_args.len()
+            && self
+                .named_args
+                .iter()
+                .zip(other.named_args.iter())
+                .all(|((a, _), (b, _))| a.label().is_some() && a.label() == b.label())
+    }
+
+    /// Checks the labels of a call, in order, against the labels this signature expects.
+    pub fn match_labels(&self, labels: &[Option<&str>]) -> Result<(), LabelMismatch> {
+        match_labels(self.named_args.iter().map(|(name, _)| name), labels)
+    }
+}
+
+/// Checks the labels of a call, in order, against the argument names `params` of the
+/// function called.
+pub fn match_labels<'a, I>(params: I, labels: &[Option<&str>]) -> Result<(), LabelMismatch>
+where
+    I: IntoIterator<Item = &'a ArgName>,
+    I::IntoIter: Clone,
+{
+    let params = params.into_iter();
+    let expected = params.clone().count();
+    if labels.len() != expected {
+        return Err(LabelMismatch::Count {
+            expected,
+            found: labels.len(),
+        });
+    }
+    for (index, (name, found)) in params.clone().zip(labels).enumerate() {
+        match (name.label(), found) {
+            (None, None) => {}
+            (Some(expected), Some(found)) if expected == *found => {}
+            (Some(expected), None) => {
+                return Err(LabelMismatch::Missing {
+                    index,
+                    expected: expected.into(),
+                })
+            }
+            (None, Some(found)) => {
+                return Err(LabelMismatch::Unexpected {
+                    index,
+                    found: (*found).into(),
+                })
+            }
+            (Some(expected), Some(found)) => {
+                let declared = params.clone().any(|n| n.label() == Some(found));
+                return Err(if declared {
+                    LabelMismatch::Misordered {
+                        index,
+                        found: (*found).into(),
+                        expected: expected.into(),
+                    }
+                } else {
+                    LabelMismatch::Wrong {
+                        index,
+                        found: (*found).into(),
+                        expected: expected.into(),
+                    }
+                });
+            }
+        }
+    }
+    Ok(())
+}
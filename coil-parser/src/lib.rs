@@ -1,64 +1,536 @@
+mod dump;
 mod expr;
-use std::{ffi::{OsStr, OsString}, path::PathBuf, str::FromStr};
-
-use coil_error::Error;
-use coil_lexer::{Keyword, Lexer, Literal, Operator, Token, TokenKind};
+mod labels;
+mod loader;
+mod operator;
+mod parse;
+mod pattern;
+mod pretty;
+pub mod syntax;
+mod types;
+mod visit;
+use coil_error::{module_name, msg, Error, ErrorCode, Span};
+use coil_lexer::{Keyword, Lexer, Operator, Parenthesis, Token, TokenKind};
+pub use dump::{Dump, DumpFormat, ToDump};
 pub use expr::*;
+pub use labels::{match_labels, LabelMismatch};
+pub use loader::{load, Import, ImportTarget, Module, ModuleId, Program};
+pub use operator::{Associativity, Precedence};
+pub use pattern::*;
+pub use types::*;
+pub use visit::{fold, walk, walk_mut, Fold, Visitor, VisitorMut};
+
+const UNEXPECTED_EOF: ErrorCode = ErrorCode::parser(1);
+const UNEXPECTED: ErrorCode = ErrorCode::parser(2);
+const EXPECTED_EXPRESSION: ErrorCode = ErrorCode::parser(3);
+const EXPECTED_ITEM: ErrorCode = ErrorCode::parser(4);
+const EXPECTED_TYPE: ErrorCode = ErrorCode::parser(5);
+const EXPECTED_IDENTIFIER: ErrorCode = ErrorCode::parser(6);
+const MISSING_FN_BODY: ErrorCode = ErrorCode::parser(7);
+const EXTERN_FN_BODY: ErrorCode = ErrorCode::parser(8);
+const PUB_NOT_ALLOWED: ErrorCode = ErrorCode::parser(9);
+const CHAINED_OPERATOR: ErrorCode = ErrorCode::parser(10);
+const AMBIGUOUS_LINE_BREAK: ErrorCode = ErrorCode::parser(16);
+const UNKNOWN_LOOP_LABEL: ErrorCode = ErrorCode::parser(17);
+const EXPECTED_LOOP: ErrorCode = ErrorCode::parser(18);
+const EXPECTED_PATTERN: ErrorCode = ErrorCode::parser(19);
+const FALLTHROUGH_OUTSIDE_MATCH: ErrorCode = ErrorCode::parser(20);
+const FALLTHROUGH_IN_LAST_ARM: ErrorCode = ErrorCode::parser(21);
+const MISSING_STATIC_VALUE: ErrorCode = ErrorCode::parser(22);
+const MODULE_NOT_FOUND: ErrorCode = ErrorCode::parser(23);
+const AMBIGUOUS_MODULE_FILE: ErrorCode = ErrorCode::parser(24);
+const MODULE_LOADED_TWICE: ErrorCode = ErrorCode::parser(25);
+const DUPLICATE_NAME: ErrorCode = ErrorCode::parser(26);
+const UNRESOLVED_IMPORT: ErrorCode = ErrorCode::parser(27);
+const PRIVATE_IMPORT: ErrorCode = ErrorCode::parser(28);
+const IMPORT_CYCLE: ErrorCode = ErrorCode::parser(29);
+const STRUCT_LITERAL_IN_CONDITION: ErrorCode = ErrorCode::parser(30);
 
 pub struct Parser {
     lexer: Lexer,
-    saved_token: Option<Token>,
+    saved_token: Option<(Token, Span)>,
+    peeked: Option<(Token, Span)>,
+    peeked_depth: usize,
     line: usize,
     maybe_insert_semicolon: bool,
+    delimiters: Vec<Parenthesis>,
+    span: Span,
+    prev_span: Span,
+    prev_line: usize,
+    prev_closed_block: bool,
+    /// Labels of the loops around the code being parsed, innermost last.
+    loop_labels: Vec<Box<str>>,
+    /// The `fallthrough`s of the match arm being parsed, if any.
+    fallthroughs: Option<Vec<(usize, Span)>>,
+    /// Set in the condition of an `if`, `while`, ... where a `{` after a name starts the
+    /// body rather than a struct literal.
+    no_struct_literal: bool,
+    /// The path ending the condition of an `if`, `while` or `for`, with its line, until
+    /// the first statement of the body after it. A `name:` starting that statement is
+    /// then taken as a struct literal cut short rather than a loop label.
+    condition_path: Option<(Expr, usize)>,
+    warnings: Vec<Error>,
+    errors: Vec<Error>,
+    /// Set when the lexer fails without moving forward, after which the file is treated
+    /// as ending there.
+    lexer_stuck: bool,
+    /// The id of the first node, so that files parsed one after another get distinct ids.
+    first_id: u32,
+    next_id: u32,
 }
 
 impl Parser {
     pub fn new(lexer: Lexer) -> Self {
+        let span = Span::new(lexer.file_id, 0, 0);
         Self {
             lexer,
             saved_token: None,
+            peeked: None,
+            peeked_depth: 0,
             line: 1,
             maybe_insert_semicolon: false,
+            delimiters: vec![],
+            span,
+            prev_span: span,
+            prev_line: 1,
+            prev_closed_block: false,
+            loop_labels: vec![],
+            fallthroughs: None,
+            no_struct_literal: false,
+            condition_path: None,
+            warnings: vec![],
+            errors: vec![],
+            lexer_stuck: false,
+            first_id: 0,
+            next_id: 0,
         }
     }
 
+    /// Numbers the nodes from `id` on rather than from 0.
+    pub fn with_first_id(mut self, id: NodeId) -> Self {
+        self.first_id = id.0;
+        self
+    }
+
+    /// The id the next node will get, which is one past the last one given out.
+    pub fn next_id(&self) -> NodeId {
+        NodeId(self.next_id)
+    }
+
+    /// Returns the next token, inserting a `;` at a line break when the line ends in
+    /// something that can end a statement (see [`ends_statement`]) unless:
+    ///
+    /// - the line break is directly inside `(` or `[`,
+    /// - the next line starts with a token that can only continue an expression, like
+    ///   `.`, `?`, a binary operator, `else` or `where` (see [`continues_line`]).
+    ///
+    /// A next line starting with an operator that is also a prefix operator (`-`, `+`,
+    /// `&`, `&&`) continues the expression too, with an [`AMBIGUOUS_LINE_BREAK`] warning.
+    /// One starting with `*` gets the same warning but starts a new statement, since it
+    /// is usually a dereference, as in `*r = x`.
     pub fn get_token(&mut self) -> Result<Option<Token>, Error> {
         let saved = self.saved_token.take();
-        let Some(x) = (if saved.is_some() {
+        let Some((x, span)) = (if saved.is_some() {
             saved
         } else {
-            self.lexer.next_token()?
+            self.lexer.next_token()?.map(|x| (x, self.lexer.span()))
         }) else {
             return Ok(None);
         };
-        if self.maybe_insert_semicolon && self.line < x.line {
-            self.saved_token = Some(x);
+        if self.maybe_insert_semicolon
+            && self.line < x.line
+            && !matches!(
+                self.delimiters.last(),
+                Some(Parenthesis::Normal | Parenthesis::Square)
+            )
+        {
             self.maybe_insert_semicolon = false;
-            return Ok(Some(Token::new(TokenKind::Operator(Operator::Semicolon), self.line)));
+            // A `*` is taken as a dereference, as in `*r = x`, and the others as binary
+            // operators.
+            let dereference = x.kind == TokenKind::Operator(Operator::Star);
+            if ambiguous_continuation(&x.kind) {
+                let (message, note) = if dereference {
+                    (
+                        msg!("parser-ambiguous-line-break-statement", operator = x.kind),
+                        msg!("parser-note-ambiguous-line-break-statement"),
+                    )
+                } else {
+                    (
+                        msg!("parser-ambiguous-line-break", operator = x.kind),
+                        msg!("parser-note-ambiguous-line-break"),
+                    )
+                };
+                let error = self.error(AMBIGUOUS_LINE_BREAK, &message, x.line, span);
+                self.warnings.push(error.warning().with_note(&note));
+            }
+            if starts_statement(&x.kind) {
+                self.saved_token = Some((x, span));
+                self.span = Span::new(self.span.file, self.span.end, self.span.end);
+                return Ok(Some(Token::new(
+                    TokenKind::Operator(Operator::Semicolon),
+                    self.line,
+                )));
+            }
         }
         self.line = x.line;
-        self.maybe_insert_semicolon = match x.kind {
-            TokenKind::Identifier(_)
-            | TokenKind::Keyword(Keyword::Break | Keyword::Continue | Keyword::Fallthrough | Keyword::Return)
-            | TokenKind::Literal(_, _)
-            | TokenKind::Parenthesis { closing: true, .. } => true,
-            _ => false,
-        };
+        self.span = span;
+        self.maybe_insert_semicolon = ends_statement(&x.kind);
+        match x.kind {
+            TokenKind::Parenthesis {
+                closing: false,
+                kind,
+            } => self.delimiters.push(kind),
+            // Also closes brackets left open inside, so that a missing `)` doesn't last
+            // past the end of its block.
+            TokenKind::Parenthesis {
+                closing: true,
+                kind,
+            } => {
+                let open = self.delimiters.iter().rposition(|&x| x == kind);
+                self.delimiters
+                    .truncate(open.unwrap_or(self.delimiters.len().saturating_sub(1)));
+            }
+            _ => {}
+        }
         Ok(Some(x))
     }
 
-    pub fn parse(&mut self) -> Expr {
+    /// Warnings collected by the last [`Parser::parse`].
+    pub fn warnings(&self) -> &[Error] {
+        &self.warnings
+    }
+
+    /// Errors collected by the last [`Parser::parse_recovering`], in source order.
+    pub fn errors(&self) -> &[Error] {
+        &self.errors
+    }
+
+    /// Parses the whole file into a `Statement::Module` named after the file, failing
+    /// with the first error.
+    pub fn parse(&mut self) -> Result<Expr, Error> {
+        let module = self.parse_recovering();
+        match self.errors.first() {
+            Some(error) => Err(error.clone()),
+            None => Ok(module),
+        }
+    }
+
+    /// Parses the whole file like [`Parser::parse`], but carries on after syntax errors:
+    /// broken code becomes [`ExprKind::Error`] and the errors are kept in [`Parser::errors`].
+    pub fn parse_recovering(&mut self) -> Expr {
+        self.reset();
+        let start = Span::new(self.lexer.file_id, 0, 0);
+        let name = module_name(&self.lexer.file).into();
+        let name = Box::new(self.node(ExprKind::Identifier(name), start));
+        let mut children = vec![];
+        loop {
+            while matches!(self.eat(&op(Operator::Semicolon)), Ok(true)) {}
+            if matches!(self.peek(), Ok(None)) {
+                break;
+            }
+            children.push(self.recover(Self::parse_item));
+        }
+        self.errors
+            .sort_by_key(|error| error.span.map(|span| (span.file, span.start)));
+        self.node(
+            ExprKind::Statement(Statement::Module {
+                name,
+                inline: false,
+                children,
+            }),
+            start,
+        )
+    }
+
+    fn reset(&mut self) {
         self.lexer.reset();
-        let filename = self.lexer.file.as_ref();
-        let filename = OsString::from_str(filename).unwrap();
-        let filename = PathBuf::from(filename);
-        let filename = filename.file_stem().unwrap();
-        let filename: String = filename.to_owned().into_string().unwrap();
-        let mut module = Statement::Module {
-            name: Box::new(Expr::Identifier(filename.into())),
-            children: vec![],
+        self.saved_token = None;
+        self.peeked = None;
+        self.line = 1;
+        self.prev_span = Span::new(self.lexer.file_id, 0, 0);
+        self.maybe_insert_semicolon = false;
+        self.delimiters.clear();
+        self.prev_closed_block = false;
+        self.loop_labels.clear();
+        self.fallthroughs = None;
+        self.no_struct_literal = false;
+        self.condition_path = None;
+        self.warnings.clear();
+        self.errors.clear();
+        self.lexer_stuck = false;
+        self.next_id = self.first_id;
+    }
+
+    /// How many brackets are open before the next token.
+    fn depth(&self) -> usize {
+        match self.peeked {
+            Some(_) => self.peeked_depth,
+            None => self.delimiters.len(),
+        }
+    }
+
+    /// Reads the next token into `peeked`. Lexer errors are recorded and skipped.
+    fn fill(&mut self) -> Result<(), Error> {
+        while self.peeked.is_none() && !self.lexer_stuck {
+            self.peeked_depth = self.delimiters.len();
+            match self.get_token() {
+                Ok(token) => {
+                    self.peeked = token.map(|x| (x, self.span));
+                    break;
+                }
+                Err(error) => {
+                    self.lexer_stuck = error.span.is_none_or(|span| span.is_empty());
+                    self.errors.push(error);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn peek(&mut self) -> Result<Option<TokenKind>, Error> {
+        self.fill()?;
+        Ok(self.peeked.as_ref().map(|(x, _)| x.kind.clone()))
+    }
+
+    /// The span of the next token, or an empty span at the end of the file.
+    fn peek_span(&mut self) -> Result<Span, Error> {
+        self.fill()?;
+        Ok(match &self.peeked {
+            Some((_, span)) => *span,
+            None => Span::new(self.span.file, self.span.end, self.span.end),
+        })
+    }
+
+    fn bump(&mut self) -> Result<Option<Token>, Error> {
+        self.fill()?;
+        let Some((x, span)) = self.peeked.take() else {
+            return Ok(None);
+        };
+        self.prev_span = span;
+        self.prev_line = x.line;
+        self.prev_closed_block = x.kind == close(Parenthesis::Curly);
+        Ok(Some(x))
+    }
+
+    fn check(&mut self, kind: &TokenKind) -> Result<bool, Error> {
+        self.fill()?;
+        Ok(matches!(&self.peeked, Some((x, _)) if x.kind == *kind))
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> Result<bool, Error> {
+        if self.check(kind)? {
+            self.bump()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn expect(&mut self, kind: &TokenKind) -> Result<(), Error> {
+        if self.eat(kind)? {
+            return Ok(());
+        }
+        Err(self.unexpected(UNEXPECTED, &kind.to_string())?)
+    }
+
+    fn expect_ident(&mut self) -> Result<Box<str>, Error> {
+        if let Some(TokenKind::Identifier(name)) = self.peek()? {
+            self.bump()?;
+            return Ok(name);
+        }
+        Err(self.unexpected(EXPECTED_IDENTIFIER, &msg!("parser-identifier"))?)
+    }
+
+    /// Like [`Parser::expect_ident`], keeping where the name is.
+    fn expect_name(&mut self) -> Result<Ident, Error> {
+        let text = self.expect_ident()?;
+        Ok(Ident {
+            text,
+            span: self.prev_span,
+        })
+    }
+
+    /// Parses `item, item, ...` up to the `closing` delimiter, after the opening one was
+    /// consumed. Returns the items and whether the list ended with a trailing comma.
+    fn parse_comma_list<T>(
+        &mut self,
+        closing: Parenthesis,
+        mut parse: impl FnMut(&mut Self) -> Result<T, Error>,
+    ) -> Result<(Vec<T>, bool), Error> {
+        let mut items = vec![];
+        let mut trailing = false;
+        while !self.eat(&close(closing))? {
+            items.push(parse(self)?);
+            trailing = self.eat(&op(Operator::Comma))?;
+            if !trailing {
+                self.expect(&close(closing))?;
+                break;
+            }
+        }
+        Ok((items, trailing))
+    }
+
+    /// Items and statements end with a `;` (usually inserted at the end of the line),
+    /// the closing `}` of the enclosing block, the end of the file, or their own `}`.
+    fn expect_terminator(&mut self) -> Result<(), Error> {
+        if self.prev_closed_block
+            || self.eat(&op(Operator::Semicolon))?
+            || self.check(&close(Parenthesis::Curly))?
+            || self.peek()?.is_none()
+        {
+            return Ok(());
+        }
+        Err(self.unexpected(UNEXPECTED, &msg!("parser-statement-end"))?)
+    }
+
+    /// Wraps `kind` in a new node spanning from `start` to the last consumed token, or
+    /// an empty one at `start` if no token was consumed since.
+    fn node(&mut self, kind: ExprKind, start: Span) -> Expr {
+        let (id, span) = self.locate(start);
+        Expr { id, span, kind }
+    }
+
+    /// Like [`Parser::node`], for types.
+    fn type_node(&mut self, kind: TypeKind, start: Span) -> TypeExpr {
+        let (id, span) = self.locate(start);
+        TypeExpr { id, span, kind }
+    }
+
+    /// A new id, and the span from `start` to the last consumed token for a node.
+    fn locate(&mut self, start: Span) -> (NodeId, Span) {
+        let span = if self.prev_span.start < start.start {
+            Span::new(start.file, start.start, start.start)
+        } else {
+            start.to(self.prev_span)
         };
-        Expr::Statement(module)
+        self.next_id += 1;
+        (NodeId(self.next_id - 1), span)
+    }
+
+    /// Fails at the end of the file, which must not come before the `}` of a block.
+    fn expect_block_end(&mut self) -> Result<(), Error> {
+        match self.peek()? {
+            Some(_) => Ok(()),
+            None => Err(self.unexpected(UNEXPECTED, &close(Parenthesis::Curly).to_string())?),
+        }
+    }
+
+    /// Builds an "expected `expected` but found ..." error for the next token.
+    fn unexpected(&mut self, code: ErrorCode, expected: &str) -> Result<Error, Error> {
+        let span = self.peek_span()?;
+        Ok(match self.peek()? {
+            Some(found) => {
+                let line = self.peeked.as_ref().map_or(self.line, |(x, _)| x.line);
+                self.error(
+                    code,
+                    &msg!("parser-expected", expected = expected, found = found),
+                    line,
+                    span,
+                )
+            }
+            None => self.error(
+                UNEXPECTED_EOF,
+                &msg!("parser-expected-eof", expected = expected),
+                self.line,
+                span,
+            ),
+        })
+    }
+
+    fn error(&self, code: ErrorCode, message: &str, line: usize, span: Span) -> Error {
+        Error::new(code, message, &self.lexer.file, line).with_span(span)
+    }
+}
+
+/// Tokens after which a line break ends the statement, unless the next line can't start
+/// one (see [`starts_statement`]). This is the rule of [`Parser::get_token`] where the
+/// innermost bracket isn't a `(` or a `[`. A `>` closing generics ends a statement too,
+/// though the token alone doesn't tell it from a comparison.
+pub fn ends_statement(kind: &TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::Identifier(_)
+            | TokenKind::Keyword(
+                Keyword::Break
+                    | Keyword::Continue
+                    | Keyword::Fallthrough
+                    | Keyword::Return
+                    | Keyword::True
+                    | Keyword::False
+                    | Keyword::SelfType
+            )
+            | TokenKind::Literal(_, _)
+            | TokenKind::Operator(Operator::QuestionMark)
+            | TokenKind::Parenthesis { closing: true, .. }
+    )
+}
+
+/// Tokens that can't start a statement, so a line starting with one continues the
+/// previous line.
+fn continues_line(kind: &TokenKind) -> bool {
+    match kind {
+        TokenKind::Operator(x) => {
+            !matches!(x, Operator::Not | Operator::BitNot | Operator::Backslash)
+        }
+        TokenKind::Keyword(Keyword::Else | Keyword::Where) => true,
+        _ => false,
+    }
+}
+
+/// Whether a line starting with `kind` starts a new statement after a line that could end
+/// one. A `*` does, since it is usually a dereference.
+pub fn starts_statement(kind: &TokenKind) -> bool {
+    *kind == TokenKind::Operator(Operator::Star) || !continues_line(kind)
+}
+
+/// Operators that are both binary and prefix, so a line starting with one could be
+/// either a new statement or the rest of the previous one.
+fn ambiguous_continuation(kind: &TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::Operator(
+            Operator::Plus | Operator::Minus | Operator::Star | Operator::BitAnd | Operator::And
+        )
+    )
+}
+
+/// Keywords that start an item, which is also where recovery resumes after an error.
+fn starts_item(keyword: Keyword) -> bool {
+    matches!(
+        keyword,
+        Keyword::Pub
+            | Keyword::Fn
+            | Keyword::Struct
+            | Keyword::Enum
+            | Keyword::Union
+            | Keyword::Trait
+            | Keyword::Impl
+            | Keyword::Type
+            | Keyword::Module
+            | Keyword::Import
+            | Keyword::Extern
+            | Keyword::Static
+    )
+}
+
+fn op(op: Operator) -> TokenKind {
+    TokenKind::Operator(op)
+}
+
+fn kw(kw: Keyword) -> TokenKind {
+    TokenKind::Keyword(kw)
+}
+
+fn open(kind: Parenthesis) -> TokenKind {
+    TokenKind::Parenthesis {
+        closing: false,
+        kind,
+    }
+}
+
+fn close(kind: Parenthesis) -> TokenKind {
+    TokenKind::Parenthesis {
+        closing: true,
+        kind,
     }
 }
 
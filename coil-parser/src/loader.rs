@@ -0,0 +1,480 @@
+//! Loads a whole program from its entry file: the files of `module` declarations are
+//! parsed and put in place, then every `import` is resolved against the module tree.
+
+use std::{collections::BTreeMap, io, path::Path};
+
+use coil_error::{module_name, msg, Error, FileId, SourceMap, Span};
+use coil_lexer::Lexer;
+
+use crate::{
+    BinaryOperator, Expr, ExprKind, ImportTree, NodeId, Parser, Statement, AMBIGUOUS_MODULE_FILE,
+    DUPLICATE_NAME, IMPORT_CYCLE, MODULE_LOADED_TWICE, MODULE_NOT_FOUND, PRIVATE_IMPORT,
+    UNRESOLVED_IMPORT,
+};
+
+/// Identifies a module of a [`Program`]; the entry module is `ModuleId(0)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ModuleId(pub u32);
+
+#[derive(Debug, Clone)]
+pub struct Module {
+    pub name: Box<str>,
+    pub parent: Option<ModuleId>,
+    /// The file the module is written in, which is its parent's for inline modules.
+    pub file: FileId,
+    pub children: BTreeMap<Box<str>, ModuleId>,
+    /// The items declared in the module, and whether they are `pub`.
+    pub items: BTreeMap<Box<str>, bool>,
+    pub imports: Vec<Import>,
+}
+
+/// A name brought into scope by an `import`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Import {
+    pub name: Box<str>,
+    pub target: ImportTarget,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportTarget {
+    Module(ModuleId),
+    Item(ModuleId, Box<str>),
+}
+
+impl ImportTarget {
+    /// The module the imported name lives in, or the imported module itself.
+    pub fn module(&self) -> ModuleId {
+        match self {
+            Self::Module(id) | Self::Item(id, _) => *id,
+        }
+    }
+}
+
+/// Everything [`load`] found from an entry file.
+#[derive(Debug, Clone)]
+pub struct Program {
+    /// The entry module, where every `module name` declaration has the items of its file
+    /// as children.
+    pub root: Expr,
+    pub modules: Vec<Module>,
+    pub warnings: Vec<Error>,
+    /// Syntax errors of every file and errors of the module tree, in source order.
+    pub errors: Vec<Error>,
+}
+
+impl Program {
+    pub fn module(&self, id: ModuleId) -> &Module {
+        &self.modules[id.0 as usize]
+    }
+
+    /// The dotted path of a module from the entry module, such as `main.net.http`.
+    pub fn module_path(&self, id: ModuleId) -> String {
+        let module = self.module(id);
+        match module.parent {
+            Some(parent) => format!("{}.{}", self.module_path(parent), module.name),
+            None => module.name.to_string(),
+        }
+    }
+
+    /// Whether `id` is `ancestor` or nested inside it.
+    pub fn is_within(&self, id: ModuleId, ancestor: ModuleId) -> bool {
+        let mut current = Some(id);
+        while let Some(id) = current {
+            if id == ancestor {
+                return true;
+            }
+            current = self.module(id).parent;
+        }
+        false
+    }
+}
+
+/// Loads the program starting at `entry`.
+///
+/// `module name` in a file `dir/main.coil` or `dir/name/mod.coil` refers to
+/// `dir/name.coil` or `dir/name/mod.coil`; in any other file `dir/file.coil` it refers to
+/// the same files in `dir/file/`. Import paths start at the modules of the entry file,
+/// and may name a module, or an item that is `pub` or declared in an enclosing module.
+/// Modules can't import each other in a cycle.
+pub fn load(map: &mut SourceMap, entry: &Path) -> io::Result<Program> {
+    let file = map.load(entry)?;
+    let mut loader = Loader {
+        map,
+        program: Program {
+            root: ExprKind::unit().into(),
+            modules: vec![],
+            warnings: vec![],
+            errors: vec![],
+        },
+        imports: vec![],
+        next_id: NodeId::default(),
+    };
+    let mut root = loader.parse(file);
+    if let ExprKind::Statement(Statement::Module { children, .. }) = &mut root.kind {
+        let name: Box<str> = module_name(&loader.map.get(file).name).into();
+        let id = loader.add_module(name, None, file);
+        let dir = entry.parent().unwrap_or(Path::new("."));
+        loader.collect(id, children, dir);
+    }
+    loader.program.root = root;
+    loader.resolve_imports();
+    loader.check_cycles();
+    let mut program = loader.program;
+    program
+        .errors
+        .sort_by_key(|error| error.span.map(|span| (span.file, span.start)));
+    Ok(program)
+}
+
+struct Loader<'a> {
+    map: &'a mut SourceMap,
+    program: Program,
+    /// The `import`s of every module, resolved once the whole tree is loaded.
+    imports: Vec<(ModuleId, ImportTree)>,
+    next_id: NodeId,
+}
+
+impl Loader<'_> {
+    /// Parses a file into its `Statement::Module`, keeping its diagnostics.
+    fn parse(&mut self, file: FileId) -> Expr {
+        let lexer = Lexer::from_source(self.map, file);
+        let mut parser = Parser::new(lexer).with_first_id(self.next_id);
+        let module = parser.parse_recovering();
+        self.next_id = parser.next_id();
+        self.program.warnings.extend_from_slice(parser.warnings());
+        self.program.errors.extend_from_slice(parser.errors());
+        module
+    }
+
+    fn add_module(&mut self, name: Box<str>, parent: Option<ModuleId>, file: FileId) -> ModuleId {
+        let id = ModuleId(self.program.modules.len() as u32);
+        if let Some(parent) = parent {
+            let parent = &mut self.program.modules[parent.0 as usize];
+            parent.children.insert(name.clone(), id);
+        }
+        self.program.modules.push(Module {
+            name,
+            parent,
+            file,
+            children: BTreeMap::new(),
+            items: BTreeMap::new(),
+            imports: vec![],
+        });
+        id
+    }
+
+    /// Records the items of module `id`, whose own modules live in `dir`, and loads the
+    /// files of its `module` declarations.
+    fn collect(&mut self, id: ModuleId, items: &mut [Expr], dir: &Path) {
+        for item in items {
+            let ExprKind::Statement(statement) = &mut item.kind else {
+                continue;
+            };
+            match statement {
+                Statement::Module {
+                    name,
+                    inline,
+                    children,
+                } => {
+                    let span = name.span;
+                    let name = name_of(name);
+                    if self.module(id).children.contains_key(&name) {
+                        let message = msg!("parser-duplicate-module", name = name);
+                        self.error(DUPLICATE_NAME, &message, span);
+                        continue;
+                    }
+                    if *inline {
+                        let file = self.module(id).file;
+                        let child = self.add_module(name.clone(), Some(id), file);
+                        self.collect(child, children, &dir.join(&*name));
+                    } else if let Some(path) = self.find_module_file(&name, dir, span) {
+                        *children = self.load_module(name, id, &path, span);
+                    }
+                }
+                Statement::Fn { public, name, .. }
+                | Statement::Struct { public, name, .. }
+                | Statement::Enum { public, name, .. }
+                | Statement::Union { public, name, .. }
+                | Statement::Trait { public, name, .. }
+                | Statement::TypeAlias { public, name, .. }
+                | Statement::Static { public, name, .. } => {
+                    self.declare(id, name, *public);
+                }
+                Statement::Extern { items, .. } => {
+                    for item in items.iter() {
+                        if let ExprKind::Statement(Statement::Fn { public, name, .. }) = &item.kind
+                        {
+                            self.declare(id, name, *public);
+                        }
+                    }
+                }
+                Statement::Use { tree } => self.imports.push((id, tree.clone())),
+                _ => {}
+            }
+        }
+    }
+
+    fn declare(&mut self, id: ModuleId, name: &str, public: bool) {
+        let items = &mut self.program.modules[id.0 as usize].items;
+        // Overloads share a name, which is public if any of them is.
+        *items.entry(name.into()).or_default() |= public;
+    }
+
+    /// `dir/name.coil` or `dir/name/mod.coil`, whichever exists.
+    fn find_module_file(&mut self, name: &str, dir: &Path, span: Span) -> Option<Box<Path>> {
+        let file = dir.join(format!("{name}.coil"));
+        let dir_file = dir.join(name).join("mod.coil");
+        match (file.is_file(), dir_file.is_file()) {
+            (true, false) => Some(file.into()),
+            (false, true) => Some(dir_file.into()),
+            (true, true) => {
+                self.error(
+                    AMBIGUOUS_MODULE_FILE,
+                    &msg!(
+                        "parser-ambiguous-module-file",
+                        name = name,
+                        file = file.display(),
+                        dir_file = dir_file.display()
+                    ),
+                    span,
+                );
+                None
+            }
+            (false, false) => {
+                let error = Error::at(
+                    MODULE_NOT_FOUND,
+                    &msg!("parser-module-not-found", name = name),
+                    self.map,
+                    span,
+                )
+                .with_note(&msg!(
+                    "parser-note-module-files",
+                    file = file.display(),
+                    dir_file = dir_file.display()
+                ));
+                self.program.errors.push(error);
+                None
+            }
+        }
+    }
+
+    /// Loads the file of module `name` declared in `parent`, and returns its items.
+    fn load_module(
+        &mut self,
+        name: Box<str>,
+        parent: ModuleId,
+        path: &Path,
+        span: Span,
+    ) -> Vec<Expr> {
+        let loaded = self.map.files().count();
+        let file = match self.map.load(path) {
+            Ok(file) => file,
+            Err(error) => {
+                let message = msg!("parser-module-unreadable", name = name, error = error);
+                self.error(MODULE_NOT_FOUND, &message, span);
+                return vec![];
+            }
+        };
+        if (file.0 as usize) < loaded {
+            self.error(
+                MODULE_LOADED_TWICE,
+                &msg!("parser-module-loaded-twice", file = path.display()),
+                span,
+            );
+            return vec![];
+        }
+        let id = self.add_module(name, Some(parent), file);
+        let module = self.parse(file);
+        let ExprKind::Statement(Statement::Module { mut children, .. }) = module.kind else {
+            return vec![];
+        };
+        let dir = if path.file_name().is_some_and(|name| name == "mod.coil") {
+            path.parent().unwrap_or(Path::new(".")).to_path_buf()
+        } else {
+            path.with_extension("")
+        };
+        self.collect(id, &mut children, &dir);
+        children
+    }
+
+    fn resolve_imports(&mut self) {
+        for (id, tree) in std::mem::take(&mut self.imports) {
+            self.resolve_tree(id, &tree, &[]);
+        }
+    }
+
+    /// Resolves the paths of `tree`, which continue `prefix`, for an import in module `id`.
+    fn resolve_tree(&mut self, id: ModuleId, tree: &ImportTree, prefix: &[(Box<str>, Span)]) {
+        match tree {
+            ImportTree::Path { path, alias } => {
+                let mut segments = prefix.to_vec();
+                segments.extend(path_segments(path));
+                let Some(target) = self.resolve_path(id, &segments) else {
+                    return;
+                };
+                let (last, last_span) = segments.last().cloned().unwrap();
+                let name = alias.clone().unwrap_or(last);
+                let module = self.module(id);
+                if module.items.contains_key(&name)
+                    || module.children.contains_key(&name)
+                    || module.imports.iter().any(|import| import.name == name)
+                {
+                    let message = msg!("parser-duplicate-import", name = name);
+                    self.error(DUPLICATE_NAME, &message, last_span);
+                    return;
+                }
+                self.program.modules[id.0 as usize].imports.push(Import {
+                    name,
+                    target,
+                    span: path.span,
+                });
+            }
+            ImportTree::Group {
+                prefix: group,
+                items,
+            } => {
+                let mut segments = prefix.to_vec();
+                segments.extend(path_segments(group));
+                for item in items {
+                    self.resolve_tree(id, item, &segments);
+                }
+            }
+        }
+    }
+
+    /// Walks `segments` down from the entry module for an import in module `from`.
+    fn resolve_path(
+        &mut self,
+        from: ModuleId,
+        segments: &[(Box<str>, Span)],
+    ) -> Option<ImportTarget> {
+        let mut current = ModuleId(0);
+        let (last, modules) = segments.split_last()?;
+        for (name, span) in modules {
+            let Some(&child) = self.module(current).children.get(name) else {
+                let module = self.program.module_path(current);
+                let message = msg!("parser-unresolved-module", name = name, module = module);
+                self.error(UNRESOLVED_IMPORT, &message, *span);
+                return None;
+            };
+            current = child;
+        }
+        let (name, span) = last;
+        let module = self.module(current);
+        if let Some(&child) = module.children.get(name) {
+            return Some(ImportTarget::Module(child));
+        }
+        let module_path = self.program.module_path(current);
+        match module.items.get(name) {
+            Some(&public) => {
+                if !public && !self.program.is_within(from, current) {
+                    let error = Error::at(
+                        PRIVATE_IMPORT,
+                        &msg!("parser-private-import", name = name, module = module_path),
+                        self.map,
+                        *span,
+                    )
+                    .with_note(&msg!("parser-note-make-public"));
+                    self.program.errors.push(error);
+                    return None;
+                }
+                Some(ImportTarget::Item(current, name.clone()))
+            }
+            None => {
+                let message = msg!(
+                    "parser-unresolved-import",
+                    name = name,
+                    module = module_path
+                );
+                self.error(UNRESOLVED_IMPORT, &message, *span);
+                None
+            }
+        }
+    }
+
+    /// Reports every cycle of modules importing from each other once, at the import that
+    /// closes it.
+    fn check_cycles(&mut self) {
+        #[derive(Clone, Copy, PartialEq)]
+        enum State {
+            New,
+            Visiting,
+            Done,
+        }
+        fn visit(
+            loader: &mut Loader<'_>,
+            id: ModuleId,
+            states: &mut [State],
+            stack: &mut Vec<ModuleId>,
+        ) {
+            states[id.0 as usize] = State::Visiting;
+            stack.push(id);
+            let mut targets: Vec<(ModuleId, Span)> = vec![];
+            for import in &loader.module(id).imports {
+                let target = import.target.module();
+                if target != id && !targets.iter().any(|&(seen, _)| seen == target) {
+                    targets.push((target, import.span));
+                }
+            }
+            for (target, span) in targets {
+                match states[target.0 as usize] {
+                    State::New => visit(loader, target, states, stack),
+                    State::Visiting => {
+                        let start = stack.iter().position(|&x| x == target).unwrap();
+                        let cycle: Vec<_> = stack[start..]
+                            .iter()
+                            .chain([&target])
+                            .map(|&id| loader.program.module_path(id))
+                            .collect();
+                        let message = msg!("parser-import-cycle", cycle = cycle.join(" -> "));
+                        loader.error(IMPORT_CYCLE, &message, span);
+                    }
+                    State::Done => {}
+                }
+            }
+            stack.pop();
+            states[id.0 as usize] = State::Done;
+        }
+
+        let mut states = vec![State::New; self.program.modules.len()];
+        for id in 0..self.program.modules.len() {
+            if states[id] == State::New {
+                visit(self, ModuleId(id as u32), &mut states, &mut vec![]);
+            }
+        }
+    }
+
+    fn module(&self, id: ModuleId) -> &Module {
+        self.program.module(id)
+    }
+
+    fn error(&mut self, code: coil_error::ErrorCode, message: &str, span: Span) {
+        let error = Error::at(code, message, self.map, span);
+        self.program.errors.push(error);
+    }
+}
+
+fn name_of(name: &Expr) -> Box<str> {
+    match &name.kind {
+        ExprKind::Identifier(name) => name.clone(),
+        _ => "".into(),
+    }
+}
+
+/// The names of a dotted path and their spans.
+fn path_segments(path: &Expr) -> Vec<(Box<str>, Span)> {
+    match &path.kind {
+        ExprKind::Binary {
+            op: BinaryOperator::Dot,
+            left,
+            right,
+        } => {
+            let mut segments = path_segments(left);
+            segments.extend(path_segments(right));
+            segments
+        }
+        ExprKind::Identifier(name) => vec![(name.clone(), path.span)],
+        _ => vec![],
+    }
+}
@@ -0,0 +1,227 @@
+//! Operator metadata shared by the expression parser and anything printing expressions.
+//!
+//! From loosest to tightest binding:
+//!
+//! | Precedence   | Operators                                            | Associativity |
+//! |--------------|------------------------------------------------------|---------------|
+//! | `Assignment` | `=` `+=` `-=` `*=` `/=` `%=` `&&=` `\|\|=` `&=` `\|=` `^=` `<<=` `>>=` | right |
+//! | `Range`      | `..`                                                 | none          |
+//! | `Or`         | `\|\|`                                               | left          |
+//! | `And`        | `&&`                                                 | left          |
+//! | `Comparison` | `==` `!=` `<` `>` `<=` `>=`                          | none          |
+//! | `BitOr`      | `\|`                                                 | left          |
+//! | `BitXor`     | `^`                                                  | left          |
+//! | `BitAnd`     | `&`                                                  | left          |
+//! | `Shift`      | `<<` `>>`                                            | left          |
+//! | `Sum`        | `+` `-`                                              | left          |
+//! | `Product`    | `*` `/` `%`                                          | left          |
+//! | `Prefix`     | `-` `+` `!` `~` `&` `&&` `&mut` `*`                  |               |
+//! | `Postfix`    | `?`, calls, indexing, `.`                            | left          |
+//!
+//! Operators without associativity cannot be chained: `a < b < c` and `a..b..c` are errors.
+//! `,` only separates list elements and never takes part in expressions.
+use coil_lexer::Operator;
+
+use crate::{BinaryOperator, UnaryOperator};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Precedence {
+    Comma,
+    Assignment,
+    Range,
+    Or,
+    And,
+    Comparison,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Shift,
+    Sum,
+    Product,
+    Prefix,
+    Postfix,
+}
+
+impl Precedence {
+    /// The next tighter level; `Postfix` is the tightest.
+    pub fn next(self) -> Self {
+        use Precedence::*;
+        match self {
+            Comma => Assignment,
+            Assignment => Range,
+            Range => Or,
+            Or => And,
+            And => Comparison,
+            Comparison => BitOr,
+            BitOr => BitXor,
+            BitXor => BitAnd,
+            BitAnd => Shift,
+            Shift => Sum,
+            Sum => Product,
+            Product => Prefix,
+            Prefix | Postfix => Postfix,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Associativity {
+    Left,
+    Right,
+    None,
+}
+
+impl BinaryOperator {
+    pub fn from_operator(op: Operator) -> Option<Self> {
+        use BinaryOperator as B;
+        Some(match op {
+            Operator::Dot => B::Dot,
+            Operator::Comma => B::Comma,
+            Operator::DoubleDot => B::Range,
+            Operator::Plus => B::Add,
+            Operator::PlusAssign => B::AddAssign,
+            Operator::Minus => B::Sub,
+            Operator::MinusAssign => B::SubAssign,
+            Operator::Star => B::Mul,
+            Operator::StarAssign => B::MulAssign,
+            Operator::Slash => B::Div,
+            Operator::SlashAssign => B::DivAssign,
+            Operator::Percent => B::Mod,
+            Operator::PercentAssign => B::ModAssign,
+            Operator::Eq => B::Eq,
+            Operator::NotEq => B::NotEq,
+            Operator::Greater => B::Greater,
+            Operator::GreaterEq => B::GreaterEq,
+            Operator::Lesser => B::Lesser,
+            Operator::LesserEq => B::LesserEq,
+            Operator::And => B::And,
+            Operator::AndAssign => B::AndAssign,
+            Operator::Or => B::Or,
+            Operator::OrAssign => B::OrAssign,
+            Operator::BitAnd => B::BitAnd,
+            Operator::BitAndAssign => B::BitAndAssign,
+            Operator::BitOr => B::BitOr,
+            Operator::BitOrAssign => B::BitOrAssign,
+            Operator::BitXor => B::BitXor,
+            Operator::BitXorAssign => B::BitXorAssign,
+            Operator::BitShiftLeft => B::BitShiftLeft,
+            Operator::BitShiftLeftAssign => B::BitShiftLeftAssign,
+            Operator::BitShiftRight => B::BitShiftRight,
+            Operator::BitShiftRightAssign => B::BitShiftRightAssign,
+            Operator::Assign => B::Assign,
+            _ => return None,
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        use BinaryOperator as B;
+        match self {
+            B::Dot => ".",
+            B::Comma => ",",
+            B::Range => "..",
+            B::Add => "+",
+            B::AddAssign => "+=",
+            B::Sub => "-",
+            B::SubAssign => "-=",
+            B::Mul => "*",
+            B::MulAssign => "*=",
+            B::Div => "/",
+            B::DivAssign => "/=",
+            B::Mod => "%",
+            B::ModAssign => "%=",
+            B::Eq => "==",
+            B::NotEq => "!=",
+            B::Greater => ">",
+            B::GreaterEq => ">=",
+            B::Lesser => "<",
+            B::LesserEq => "<=",
+            B::And => "&&",
+            B::AndAssign => "&&=",
+            B::Or => "||",
+            B::OrAssign => "||=",
+            B::BitAnd => "&",
+            B::BitAndAssign => "&=",
+            B::BitOr => "|",
+            B::BitOrAssign => "|=",
+            B::BitXor => "^",
+            B::BitXorAssign => "^=",
+            B::BitShiftLeft => "<<",
+            B::BitShiftLeftAssign => "<<=",
+            B::BitShiftRight => ">>",
+            B::BitShiftRightAssign => ">>=",
+            B::Assign => "=",
+        }
+    }
+
+    pub fn precedence(&self) -> Precedence {
+        use BinaryOperator as B;
+        match self {
+            B::Comma => Precedence::Comma,
+            B::Assign
+            | B::AddAssign
+            | B::SubAssign
+            | B::MulAssign
+            | B::DivAssign
+            | B::ModAssign
+            | B::AndAssign
+            | B::OrAssign
+            | B::BitAndAssign
+            | B::BitOrAssign
+            | B::BitXorAssign
+            | B::BitShiftLeftAssign
+            | B::BitShiftRightAssign => Precedence::Assignment,
+            B::Range => Precedence::Range,
+            B::Or => Precedence::Or,
+            B::And => Precedence::And,
+            B::Eq | B::NotEq | B::Greater | B::GreaterEq | B::Lesser | B::LesserEq => {
+                Precedence::Comparison
+            }
+            B::BitOr => Precedence::BitOr,
+            B::BitXor => Precedence::BitXor,
+            B::BitAnd => Precedence::BitAnd,
+            B::BitShiftLeft | B::BitShiftRight => Precedence::Shift,
+            B::Add | B::Sub => Precedence::Sum,
+            B::Mul | B::Div | B::Mod => Precedence::Product,
+            B::Dot => Precedence::Postfix,
+        }
+    }
+
+    pub fn associativity(&self) -> Associativity {
+        match self.precedence() {
+            Precedence::Assignment => Associativity::Right,
+            Precedence::Range | Precedence::Comparison => Associativity::None,
+            _ => Associativity::Left,
+        }
+    }
+
+    pub fn is_assignment(&self) -> bool {
+        self.precedence() == Precedence::Assignment
+    }
+}
+
+impl UnaryOperator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UnaryOperator::Try => "?",
+            UnaryOperator::Not => "!",
+            UnaryOperator::BitNot => "~",
+            UnaryOperator::DoubleReference => "&&",
+            UnaryOperator::Reference => "&",
+            UnaryOperator::MutReference => "&mut ",
+            UnaryOperator::Dereference => "*",
+            UnaryOperator::Positive => "+",
+            UnaryOperator::Negative => "-",
+        }
+    }
+
+    pub fn precedence(&self) -> Precedence {
+        match self {
+            UnaryOperator::Try => Precedence::Postfix,
+            _ => Precedence::Prefix,
+        }
+    }
+
+    pub fn is_postfix(&self) -> bool {
+        self.precedence() == Precedence::Postfix
+    }
+}
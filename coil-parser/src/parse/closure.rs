@@ -0,0 +1,51 @@
+use coil_error::Error;
+use coil_lexer::{Operator, Parenthesis};
+
+use crate::{op, open, ClosureParam, Expr, ExprKind, Parser};
+
+impl Parser {
+    /// `\x, y -> body` takes its body as an expression. With the parameters in
+    /// parentheses, `\(x: Int) -> Int { ... }`, the arrow is followed by the return type
+    /// instead and the body is a block.
+    pub(crate) fn parse_closure(&mut self) -> Result<Expr, Error> {
+        let start = self.peek_span()?;
+        self.expect(&op(Operator::Backslash))?;
+        let (params, returns, body) = if self.eat(&open(Parenthesis::Normal))? {
+            let (params, _) = self
+                .nested(|p| p.parse_comma_list(Parenthesis::Normal, Self::parse_closure_param))?;
+            let returns = if self.eat(&op(Operator::Arrow))? {
+                Some(Box::new(self.parse_type()?))
+            } else {
+                None
+            };
+            (params, returns, self.parse_body(Self::parse_block)?)
+        } else {
+            let mut params = vec![];
+            while !self.eat(&op(Operator::Arrow))? {
+                params.push(self.parse_closure_param()?);
+                if !self.eat(&op(Operator::Comma))? {
+                    self.expect(&op(Operator::Arrow))?;
+                    break;
+                }
+            }
+            (params, None, self.parse_body(Self::parse_expr)?)
+        };
+        let kind = ExprKind::Closure {
+            params,
+            returns,
+            body: Box::new(body),
+        };
+        Ok(self.node(kind, start))
+    }
+
+    /// `name` or `name: Type`.
+    fn parse_closure_param(&mut self) -> Result<ClosureParam, Error> {
+        let name = self.expect_name()?;
+        let ty = if self.eat(&op(Operator::Colon))? {
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+        Ok(ClosureParam { name, ty })
+    }
+}
@@ -0,0 +1,227 @@
+use coil_error::{msg, Error, Span};
+use coil_lexer::{Keyword, Operator, Parenthesis, TokenKind};
+
+use crate::{
+    close, kw, op, open, parse::expr::trailing_path, Expr, ExprKind, MatchArm, Parser, Statement,
+    EXPECTED_LOOP, FALLTHROUGH_IN_LAST_ARM, FALLTHROUGH_OUTSIDE_MATCH, STRUCT_LITERAL_IN_CONDITION,
+    UNKNOWN_LOOP_LABEL,
+};
+
+impl Parser {
+    /// `if condition { ... }` or `unless condition { ... }`, optionally followed by
+    /// `else { ... }`, `else if ...` or `else unless ...`.
+    pub(crate) fn parse_if(&mut self) -> Result<Expr, Error> {
+        let start = self.peek_span()?;
+        let unless = !self.eat(&kw(Keyword::If))?;
+        if unless {
+            self.expect(&kw(Keyword::Unless))?;
+        }
+        let (condition, then) = self.parse_condition_and_body()?;
+        let otherwise = if self.eat(&kw(Keyword::Else))? {
+            Some(Box::new(match self.peek()? {
+                Some(TokenKind::Keyword(Keyword::If | Keyword::Unless)) => self.parse_if()?,
+                _ => self.parse_block()?,
+            }))
+        } else {
+            None
+        };
+        let kind = ExprKind::If {
+            unless,
+            condition,
+            then,
+            otherwise,
+        };
+        Ok(self.node(kind, start))
+    }
+
+    /// `match scrutinee { pattern => body, pattern if guard => body, ... }`. Arms are
+    /// separated by commas or line breaks.
+    pub(crate) fn parse_match(&mut self) -> Result<Expr, Error> {
+        let start = self.peek_span()?;
+        self.expect(&kw(Keyword::Match))?;
+        let scrutinee = Box::new(self.parse_condition()?);
+        // A `fallthrough` in a nested match belongs to the nested match's arm.
+        let outer = self.fallthroughs.take();
+        let arms = self.nested(Self::parse_match_arms);
+        self.fallthroughs = outer;
+        Ok(self.node(
+            ExprKind::Match {
+                scrutinee,
+                arms: arms?,
+            },
+            start,
+        ))
+    }
+
+    fn parse_match_arms(&mut self) -> Result<Vec<MatchArm>, Error> {
+        self.expect(&open(Parenthesis::Curly))?;
+        let mut arms = vec![];
+        let mut fallthroughs = vec![];
+        loop {
+            while self.eat(&op(Operator::Semicolon))? {}
+            if self.eat(&close(Parenthesis::Curly))? {
+                break;
+            }
+            self.expect_block_end()?;
+            let pattern = self.parse_pattern()?;
+            let guard = if self.eat(&kw(Keyword::If))? {
+                Some(Box::new(self.parse_expr()?))
+            } else {
+                None
+            };
+            self.expect(&op(Operator::Bolt))?;
+            self.fallthroughs = Some(vec![]);
+            let body = Box::new(self.parse_statement()?);
+            fallthroughs = self.fallthroughs.take().unwrap_or_default();
+            arms.push(MatchArm {
+                pattern,
+                guard,
+                body,
+            });
+            if !self.eat(&op(Operator::Comma))? {
+                self.expect_terminator()?;
+            }
+        }
+        if let Some(&(line, span)) = fallthroughs.first() {
+            return Err(self.error(
+                FALLTHROUGH_IN_LAST_ARM,
+                &msg!("parser-fallthrough-in-last-arm"),
+                line,
+                span,
+            ));
+        }
+        Ok(arms)
+    }
+
+    /// `fallthrough`, which is only allowed inside a match arm.
+    pub(crate) fn parse_fallthrough(&mut self) -> Result<Statement, Error> {
+        let span = self.peek_span()?;
+        self.expect(&kw(Keyword::Fallthrough))?;
+        let line = self.prev_line;
+        match &mut self.fallthroughs {
+            Some(fallthroughs) => fallthroughs.push((line, span)),
+            None => {
+                return Err(self.error(
+                    FALLTHROUGH_OUTSIDE_MATCH,
+                    &msg!("parser-fallthrough-outside-match"),
+                    line,
+                    span,
+                ))
+            }
+        }
+        Ok(Statement::Fallthrough)
+    }
+
+    /// `while`, `for` or `do` loops, after their label if they have one. The label is
+    /// known to `break` and `continue` inside the loop.
+    pub(crate) fn parse_loop(
+        &mut self,
+        label: Option<Box<str>>,
+        start: Span,
+    ) -> Result<Expr, Error> {
+        let Some(TokenKind::Keyword(keyword @ (Keyword::While | Keyword::For | Keyword::Do))) =
+            self.peek()?
+        else {
+            return Err(self.unexpected(EXPECTED_LOOP, &msg!("parser-loop"))?);
+        };
+        self.bump()?;
+        let depth = self.loop_labels.len();
+        self.loop_labels.extend(label.clone());
+        let kind = self.parse_loop_rest(keyword, label);
+        self.loop_labels.truncate(depth);
+        Ok(self.node(kind?, start))
+    }
+
+    fn parse_loop_rest(
+        &mut self,
+        keyword: Keyword,
+        label: Option<Box<str>>,
+    ) -> Result<ExprKind, Error> {
+        Ok(match keyword {
+            Keyword::While => {
+                let (condition, body) = self.parse_condition_and_body()?;
+                ExprKind::While {
+                    label,
+                    condition,
+                    body,
+                }
+            }
+            Keyword::For => {
+                let binding = self.expect_name()?;
+                self.expect(&kw(Keyword::In))?;
+                let (iterable, body) = self.parse_condition_and_body()?;
+                ExprKind::For {
+                    label,
+                    binding,
+                    iterable,
+                    body,
+                }
+            }
+            _ => {
+                let body = Box::new(self.parse_block()?);
+                self.expect(&kw(Keyword::While))?;
+                ExprKind::DoWhile {
+                    label,
+                    body,
+                    condition: Box::new(self.parse_expr()?),
+                }
+            }
+        })
+    }
+
+    /// The condition of an `if`, `while` or `for` and the block after it. A struct
+    /// literal ending the condition is cut short by the block, as in
+    /// `if p == Point { x: 1 } {}`, so `condition_path` is set for the block.
+    fn parse_condition_and_body(&mut self) -> Result<(Box<Expr>, Box<Expr>), Error> {
+        let condition = self.parse_condition()?;
+        if let Some(path) = trailing_path(&condition) {
+            if self.check(&open(Parenthesis::Curly))? {
+                self.condition_path = Some((path.clone(), self.prev_line));
+            }
+        }
+        let body = self.parse_block();
+        self.condition_path = None;
+        Ok((Box::new(condition), Box::new(body?)))
+    }
+
+    /// The error for a struct literal ending the condition before a block, seen from
+    /// the `name:` starting the block.
+    pub(crate) fn struct_literal_in_condition(&self, path: &Expr, line: usize) -> Error {
+        let span = Span::new(path.span.file, path.span.start, self.prev_span.end);
+        self.error(
+            STRUCT_LITERAL_IN_CONDITION,
+            &msg!("parser-struct-literal-in-condition", path = path),
+            line,
+            span,
+        )
+        .with_note(&msg!(
+            "parser-note-struct-literal-in-condition",
+            path = path
+        ))
+    }
+
+    /// The label after `break` or `continue`. Labels are plain names, so a name is only
+    /// taken as a label when a loop around it has that label; otherwise it is the value
+    /// of a `break`.
+    pub(crate) fn parse_loop_label(&mut self) -> Option<Box<str>> {
+        let Ok(Some(TokenKind::Identifier(name))) = self.peek() else {
+            return None;
+        };
+        if !self.loop_labels.contains(&name) {
+            return None;
+        }
+        let _ = self.bump();
+        Some(name)
+    }
+
+    pub(crate) fn unknown_label(&mut self, label: &str) -> Result<Error, Error> {
+        let span = self.peek_span()?;
+        let line = self.peeked.as_ref().map_or(self.line, |(x, _)| x.line);
+        Ok(self.error(
+            UNKNOWN_LOOP_LABEL,
+            &msg!("parser-unknown-loop-label", label = label),
+            line,
+            span,
+        ))
+    }
+}
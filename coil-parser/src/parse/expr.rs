@@ -0,0 +1,303 @@
+use coil_error::{msg, Error, Span};
+use coil_lexer::{Keyword, Literal, Operator, Parenthesis, TokenKind};
+
+use crate::{
+    close, kw, op, open, Associativity, BinaryOperator, Expr, ExprKind, Parser, Precedence,
+    UnaryOperator, CHAINED_OPERATOR, EXPECTED_EXPRESSION,
+};
+
+impl Parser {
+    pub fn parse_expr(&mut self) -> Result<Expr, Error> {
+        self.parse_binary(Precedence::Assignment)
+    }
+
+    /// Parses an expression that is followed by a block, like the condition of an `if`.
+    /// Struct literals are only allowed there inside brackets: in `if x == Point {`, the
+    /// `{` starts the body.
+    pub(crate) fn parse_condition(&mut self) -> Result<Expr, Error> {
+        let outer = std::mem::replace(&mut self.no_struct_literal, true);
+        let condition = self.parse_expr();
+        self.no_struct_literal = outer;
+        condition
+    }
+
+    /// Runs `parse` for the inside of brackets, where struct literals are allowed again.
+    pub(crate) fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let outer = std::mem::replace(&mut self.no_struct_literal, false);
+        let result = parse(self);
+        self.no_struct_literal = outer;
+        result
+    }
+
+    /// Parses operators binding at least as tightly as `min` using precedence climbing.
+    fn parse_binary(&mut self, min: Precedence) -> Result<Expr, Error> {
+        let mut left = self.parse_prefix()?;
+        let mut previous: Option<(BinaryOperator, Span)> = None;
+        loop {
+            let Some(TokenKind::Operator(token)) = self.peek()? else {
+                return Ok(left);
+            };
+            let Some(op) = BinaryOperator::from_operator(token) else {
+                return Ok(left);
+            };
+            let precedence = op.precedence();
+            if precedence < min || precedence == Precedence::Postfix || op == BinaryOperator::Comma
+            {
+                return Ok(left);
+            }
+            let span = self.peek_span()?;
+            if let Some((first, first_span)) = previous {
+                if first.precedence() == precedence && op.associativity() == Associativity::None {
+                    return Err(self.chained_error(first, op, first_span.to(span)));
+                }
+            }
+            self.bump()?;
+            let right = self.parse_binary(match op.associativity() {
+                Associativity::Right => precedence,
+                Associativity::Left | Associativity::None => precedence.next(),
+            })?;
+            let start = left.span;
+            let kind = ExprKind::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+            left = self.node(kind, start);
+            previous = Some((op, span));
+        }
+    }
+
+    fn chained_error(&self, first: BinaryOperator, second: BinaryOperator, span: Span) -> Error {
+        let error = self.error(
+            CHAINED_OPERATOR,
+            &msg!(
+                "parser-chained-operator",
+                first = first.as_str(),
+                second = second.as_str()
+            ),
+            self.line,
+            span,
+        );
+        if first.precedence() == Precedence::Comparison {
+            return error.with_note(&msg!("parser-note-chained-comparison"));
+        }
+        error.with_note(&msg!("parser-note-parenthesize"))
+    }
+
+    fn parse_prefix(&mut self) -> Result<Expr, Error> {
+        let start = self.peek_span()?;
+        let op = match self.peek()? {
+            Some(TokenKind::Operator(Operator::Minus)) => UnaryOperator::Negative,
+            Some(TokenKind::Operator(Operator::Plus)) => UnaryOperator::Positive,
+            Some(TokenKind::Operator(Operator::Not)) => UnaryOperator::Not,
+            Some(TokenKind::Operator(Operator::BitNot)) => UnaryOperator::BitNot,
+            Some(TokenKind::Operator(Operator::Star)) => UnaryOperator::Dereference,
+            Some(TokenKind::Operator(Operator::And)) => UnaryOperator::DoubleReference,
+            Some(TokenKind::Operator(Operator::BitAnd)) => {
+                self.bump()?;
+                let op = if self.eat(&kw(Keyword::Mut))? {
+                    UnaryOperator::MutReference
+                } else {
+                    UnaryOperator::Reference
+                };
+                let expr = Box::new(self.parse_prefix()?);
+                return Ok(self.node(ExprKind::Unary { op, expr }, start));
+            }
+            _ => return self.parse_postfix(),
+        };
+        self.bump()?;
+        let expr = Box::new(self.parse_prefix()?);
+        Ok(self.node(ExprKind::Unary { op, expr }, start))
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            let start = expr.span;
+            if self.check(&open(Parenthesis::Normal))? {
+                self.bump()?;
+                let (args, _) =
+                    self.nested(|p| p.parse_comma_list(Parenthesis::Normal, Self::parse_call_arg))?;
+                let kind = ExprKind::Call {
+                    callee: Box::new(expr),
+                    args,
+                };
+                expr = self.node(kind, start);
+            } else if self.eat(&open(Parenthesis::Square))? {
+                let index = self.nested(Self::parse_expr)?;
+                self.expect(&close(Parenthesis::Square))?;
+                let kind = ExprKind::Index {
+                    expr: Box::new(expr),
+                    index: Box::new(index),
+                };
+                expr = self.node(kind, start);
+            } else if self.eat(&op(Operator::QuestionMark))? {
+                let kind = ExprKind::Unary {
+                    op: UnaryOperator::Try,
+                    expr: Box::new(expr),
+                };
+                expr = self.node(kind, start);
+            } else if self.eat(&op(Operator::Dot))? {
+                // `<` is a comparison in expressions, so type arguments follow a `.`.
+                if self.check(&op(Operator::Lesser))? {
+                    let kind = ExprKind::Generic {
+                        path: Box::new(expr),
+                        args: self.parse_type_args()?,
+                    };
+                    expr = self.node(kind, start);
+                    continue;
+                }
+                let field_start = self.peek_span()?;
+                let field = match self.peek()? {
+                    Some(TokenKind::Literal(Literal::Integer { radix: 10, suffix: None }, n)) => {
+                        self.bump()?;
+                        ExprKind::Literal(Literal::Integer { radix: 10, suffix: None }, n)
+                    }
+                    _ => ExprKind::Identifier(self.expect_ident()?),
+                };
+                let field = self.node(field, field_start);
+                let kind = ExprKind::Binary {
+                    op: BinaryOperator::Dot,
+                    left: Box::new(expr),
+                    right: Box::new(field),
+                };
+                expr = self.node(kind, start);
+            } else if !self.no_struct_literal
+                && is_path(&expr)
+                && self.check(&open(Parenthesis::Curly))?
+            {
+                let fields = self.nested(Self::parse_field_values)?;
+                let kind = ExprKind::StructLiteral {
+                    path: Box::new(expr),
+                    fields,
+                };
+                expr = self.node(kind, start);
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    /// `{ name: value, name, ... }` of a struct literal.
+    fn parse_field_values(&mut self) -> Result<Vec<(Box<str>, Expr)>, Error> {
+        self.expect(&open(Parenthesis::Curly))?;
+        let mut fields = vec![];
+        loop {
+            while self.eat(&op(Operator::Semicolon))? {}
+            if self.eat(&close(Parenthesis::Curly))? {
+                return Ok(fields);
+            }
+            let start = self.peek_span()?;
+            let name = self.expect_ident()?;
+            let value = if self.eat(&op(Operator::Colon))? {
+                self.parse_expr()?
+            } else {
+                self.node(ExprKind::Identifier(name.clone()), start)
+            };
+            fields.push((name, value));
+            if !self.eat(&op(Operator::Comma))? {
+                self.expect_terminator()?;
+            }
+        }
+    }
+
+    /// `value` or `label: value`.
+    fn parse_call_arg(&mut self) -> Result<(Option<Box<str>>, Expr), Error> {
+        let value = self.parse_expr()?;
+        if let ExprKind::Identifier(label) = &value.kind {
+            if self.eat(&op(Operator::Colon))? {
+                return Ok((Some(label.clone()), self.parse_expr()?));
+            }
+        }
+        Ok((None, value))
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, Error> {
+        let start = self.peek_span()?;
+        let Some(kind) = self.peek()? else {
+            let error = self.unexpected(EXPECTED_EXPRESSION, &msg!("parser-expression"))?;
+            return self.missing_expression(error);
+        };
+        let kind = match kind {
+            TokenKind::Literal(literal, value) => {
+                self.bump()?;
+                ExprKind::Literal(literal, value)
+            }
+            TokenKind::Identifier(name) => {
+                self.bump()?;
+                ExprKind::Identifier(name)
+            }
+            TokenKind::Keyword(Keyword::True | Keyword::False) => {
+                self.bump()?;
+                ExprKind::Bool(kind == TokenKind::Keyword(Keyword::True))
+            }
+            TokenKind::Keyword(Keyword::SelfType) => {
+                self.bump()?;
+                ExprKind::Identifier("Self".into())
+            }
+            TokenKind::Parenthesis {
+                closing: false,
+                kind: Parenthesis::Normal,
+            } => {
+                self.bump()?;
+                let (mut exprs, trailing) =
+                    self.nested(|p| p.parse_comma_list(Parenthesis::Normal, Self::parse_expr))?;
+                if exprs.len() == 1 && !trailing {
+                    return Ok(exprs.remove(0));
+                }
+                ExprKind::Tuple(exprs)
+            }
+            TokenKind::Parenthesis {
+                closing: false,
+                kind: Parenthesis::Square,
+            } => {
+                self.bump()?;
+                let (exprs, _) =
+                    self.nested(|p| p.parse_comma_list(Parenthesis::Square, Self::parse_expr))?;
+                ExprKind::Array(exprs)
+            }
+            TokenKind::Parenthesis {
+                closing: false,
+                kind: Parenthesis::Curly,
+            } => return self.parse_block(),
+            TokenKind::Keyword(Keyword::If | Keyword::Unless) => return self.parse_if(),
+            TokenKind::Keyword(Keyword::Match) => return self.parse_match(),
+            TokenKind::Operator(Operator::Backslash) => return self.parse_closure(),
+            TokenKind::Keyword(Keyword::While | Keyword::For | Keyword::Do) => {
+                return self.parse_loop(None, start)
+            }
+            _ => {
+                let error = self.unexpected(EXPECTED_EXPRESSION, &msg!("parser-expression"))?;
+                return self.missing_expression(error);
+            }
+        };
+        Ok(self.node(kind, start))
+    }
+}
+
+/// Whether `expr` is a name or a dotted path, which a struct literal can start with.
+/// The path at the end of `expr`, where a struct literal would start, like the `Point`
+/// of `p == Point`.
+pub(crate) fn trailing_path(expr: &Expr) -> Option<&Expr> {
+    match &expr.kind {
+        _ if is_path(expr) => Some(expr),
+        ExprKind::Binary { right: expr, .. } | ExprKind::Unary { expr, .. } => trailing_path(expr),
+        _ => None,
+    }
+}
+
+fn is_path(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Identifier(_) => true,
+        ExprKind::Binary {
+            op: BinaryOperator::Dot,
+            left,
+            right,
+        } => is_path(left) && matches!(right.kind, ExprKind::Identifier(_)),
+        ExprKind::Generic { path, .. } => is_path(path),
+        _ => false,
+    }
+}
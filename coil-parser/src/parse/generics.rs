@@ -0,0 +1,100 @@
+use coil_error::{Error, Span};
+use coil_lexer::{Keyword, Operator, TokenKind};
+
+use crate::{kw, op, GenericParam, Generics, Parser, TypeExpr, WherePredicate, UNEXPECTED};
+
+impl Parser {
+    /// `<T, U: Bound + Bound>` after the name of an item, if there is one.
+    pub(crate) fn parse_generic_params(&mut self) -> Result<Generics, Error> {
+        let mut generics = Generics::default();
+        if !self.eat(&op(Operator::Lesser))? {
+            return Ok(generics);
+        }
+        generics.params = self.parse_angle_list(|p| {
+            let name = p.expect_name()?;
+            let bounds = if p.eat(&op(Operator::Colon))? {
+                p.parse_bounds()?
+            } else {
+                vec![]
+            };
+            Ok(GenericParam { name, bounds })
+        })?;
+        Ok(generics)
+    }
+
+    /// `where T: Bound, Vec<T>: Bound`, added to `generics`.
+    pub(crate) fn parse_where_clause(&mut self, generics: &mut Generics) -> Result<(), Error> {
+        if !self.eat(&kw(Keyword::Where))? {
+            return Ok(());
+        }
+        loop {
+            let ty = self.parse_type()?;
+            self.expect(&op(Operator::Colon))?;
+            let bounds = self.parse_bounds()?;
+            generics.where_clause.push(WherePredicate { ty, bounds });
+            if !self.eat(&op(Operator::Comma))? {
+                return Ok(());
+            }
+        }
+    }
+
+    /// `Bound + Bound`, which are trait types.
+    pub(crate) fn parse_bounds(&mut self) -> Result<Vec<TypeExpr>, Error> {
+        let mut bounds = vec![self.parse_type()?];
+        while self.eat(&op(Operator::Plus))? {
+            bounds.push(self.parse_type()?);
+        }
+        Ok(bounds)
+    }
+
+    /// `<Type, Type>`, whose `<` is the next token.
+    pub(crate) fn parse_type_args(&mut self) -> Result<Vec<TypeExpr>, Error> {
+        self.expect(&op(Operator::Lesser))?;
+        self.parse_angle_list(Self::parse_type)
+    }
+
+    /// Parses `item, item, ...` up to the closing `>`, after the `<` was consumed.
+    fn parse_angle_list<T>(
+        &mut self,
+        mut parse: impl FnMut(&mut Self) -> Result<T, Error>,
+    ) -> Result<Vec<T>, Error> {
+        let mut items = vec![];
+        while !self.eat_closing_angle()? {
+            items.push(parse(self)?);
+            if !self.eat(&op(Operator::Comma))? {
+                if !self.eat_closing_angle()? {
+                    return Err(self.unexpected(UNEXPECTED, &op(Operator::Greater).to_string())?);
+                }
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    /// Eats a `>` closing a list of generics. The lexer reads `Vec<Vec<Int>>` with a
+    /// `>>` at the end, so `>>`, `>=` and `>>=` are split and only their `>` is eaten.
+    fn eat_closing_angle(&mut self) -> Result<bool, Error> {
+        self.fill()?;
+        let Some((token, span)) = &mut self.peeked else {
+            return Ok(false);
+        };
+        let rest = match token.kind {
+            TokenKind::Operator(Operator::Greater) => {
+                self.bump()?;
+                // `>` doesn't end a statement in expressions, but it does end a type.
+                self.maybe_insert_semicolon = true;
+                return Ok(true);
+            }
+            TokenKind::Operator(Operator::BitShiftRight) => Operator::Greater,
+            TokenKind::Operator(Operator::GreaterEq) => Operator::Assign,
+            TokenKind::Operator(Operator::BitShiftRightAssign) => Operator::GreaterEq,
+            _ => return Ok(false),
+        };
+        token.kind = op(rest);
+        self.prev_span = Span::new(span.file, span.start, span.start + 1);
+        self.prev_line = token.line;
+        self.prev_closed_block = false;
+        span.start += 1;
+        Ok(true)
+    }
+}
@@ -0,0 +1,508 @@
+use coil_error::{msg, Error};
+use coil_lexer::{Keyword, Literal, Operator, Parenthesis, TokenKind};
+
+use crate::{
+    close, kw, op, open, ArgName, Expr, ExprKind, Field, Fields, Ident, ImportTree, Parser,
+    Signature, Statement, TypeKind, Variant, EXPECTED_ITEM, EXTERN_FN_BODY, MISSING_FN_BODY,
+    MISSING_STATIC_VALUE, PUB_NOT_ALLOWED,
+};
+
+/// Where an item is declared, which decides whether a function may or must have a body
+/// and whether a static needs a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FnContext {
+    Free,
+    Trait,
+    Extern,
+}
+
+impl Parser {
+    pub(crate) fn parse_item(&mut self) -> Result<Expr, Error> {
+        let pub_span = self.peek_span()?;
+        let public = self.eat(&kw(Keyword::Pub))?;
+        let Some(TokenKind::Keyword(keyword)) = self.peek()? else {
+            return Err(self.unexpected(EXPECTED_ITEM, &msg!("parser-item"))?);
+        };
+        let item = match keyword {
+            Keyword::Fn => self.parse_fn(public, FnContext::Free)?,
+            Keyword::Struct => self.parse_struct(public)?,
+            Keyword::Enum => self.parse_enum(public)?,
+            Keyword::Union => self.parse_union(public)?,
+            Keyword::Trait => self.parse_trait(public)?,
+            Keyword::Type => self.parse_type_alias(public)?,
+            Keyword::Static => self.parse_static(public, FnContext::Free)?,
+            Keyword::Module | Keyword::Import | Keyword::Impl | Keyword::Extern if public => {
+                return Err(self.error(
+                    PUB_NOT_ALLOWED,
+                    &msg!("parser-pub-not-allowed"),
+                    self.line,
+                    pub_span,
+                ));
+            }
+            Keyword::Module => self.parse_module()?,
+            Keyword::Import => self.parse_import()?,
+            Keyword::Impl => self.parse_impl()?,
+            Keyword::Extern => self.parse_extern()?,
+            _ => return Err(self.unexpected(EXPECTED_ITEM, &msg!("parser-item"))?),
+        };
+        Ok(self.node(ExprKind::Statement(item), pub_span))
+    }
+
+    /// Parses the items between `{` and `}`, for modules, traits, impls and extern blocks.
+    fn parse_item_block(
+        &mut self,
+        mut parse: impl FnMut(&mut Self) -> Result<Expr, Error>,
+    ) -> Result<Vec<Expr>, Error> {
+        self.expect(&open(Parenthesis::Curly))?;
+        let mut items = vec![];
+        loop {
+            while self.eat(&op(Operator::Semicolon))? {}
+            if self.eat(&close(Parenthesis::Curly))? {
+                return Ok(items);
+            }
+            self.expect_block_end()?;
+            items.push(self.recover(&mut parse));
+        }
+    }
+
+    fn parse_fn(&mut self, public: bool, context: FnContext) -> Result<Statement, Error> {
+        self.expect(&kw(Keyword::Fn))?;
+        let name = self.expect_name()?;
+        let mut generics = self.parse_generic_params()?;
+        self.expect(&open(Parenthesis::Normal))?;
+        let (named_args, _) = self.parse_comma_list(Parenthesis::Normal, |p| {
+            let name = p.parse_arg_name()?;
+            p.expect(&op(Operator::Colon))?;
+            Ok((name, p.parse_type()?))
+        })?;
+        let returns = if self.eat(&op(Operator::Arrow))? {
+            self.parse_type()?
+        } else {
+            let start = self.peek_span()?;
+            self.type_node(TypeKind::unit(), start)
+        };
+        self.parse_where_clause(&mut generics)?;
+        let body_span = self.peek_span()?;
+        let body = if self.check(&open(Parenthesis::Curly))? {
+            Some(Box::new(self.parse_body(Self::parse_block)?))
+        } else {
+            None
+        };
+        match (context, &body) {
+            (FnContext::Free, None) => {
+                return Err(self.error(
+                    MISSING_FN_BODY,
+                    &msg!("parser-missing-body", name = name),
+                    self.line,
+                    body_span,
+                ));
+            }
+            (FnContext::Extern, Some(_)) => {
+                return Err(self.error(
+                    EXTERN_FN_BODY,
+                    &msg!("parser-extern-body"),
+                    self.line,
+                    body_span,
+                ));
+            }
+            _ => {}
+        }
+        Ok(Statement::Fn {
+            public,
+            name,
+            generics,
+            signature: Signature {
+                returns: Box::new(returns),
+                named_args,
+            },
+            body,
+        })
+    }
+
+    /// Parses the body of a function or closure, which can't leave the loops and match
+    /// arms around it.
+    pub(crate) fn parse_body(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<Expr, Error>,
+    ) -> Result<Expr, Error> {
+        let loop_labels = std::mem::take(&mut self.loop_labels);
+        let fallthroughs = self.fallthroughs.take();
+        let body = parse(self);
+        self.loop_labels = loop_labels;
+        self.fallthroughs = fallthroughs;
+        body
+    }
+
+    /// An item of a trait or impl block: a method, an associated type or a static.
+    /// Traits may leave out method bodies, static values and the types themselves.
+    fn parse_associated_item(&mut self, context: FnContext) -> Result<Expr, Error> {
+        let start = self.peek_span()?;
+        let item = match self.peek()? {
+            Some(TokenKind::Keyword(Keyword::Type)) if context == FnContext::Trait => {
+                self.parse_associated_type()?
+            }
+            Some(TokenKind::Keyword(Keyword::Pub | Keyword::Type | Keyword::Static)) => {
+                let public = self.eat(&kw(Keyword::Pub))?;
+                match self.peek()? {
+                    Some(TokenKind::Keyword(Keyword::Type)) => self.parse_type_alias(public)?,
+                    Some(TokenKind::Keyword(Keyword::Static)) => {
+                        self.parse_static(public, context)?
+                    }
+                    _ => self.parse_fn(public, context)?,
+                }
+            }
+            _ => self.parse_fn(false, context)?,
+        };
+        Ok(self.node(ExprKind::Statement(item), start))
+    }
+
+    /// `type Name: Bound = Default` in a trait.
+    fn parse_associated_type(&mut self) -> Result<Statement, Error> {
+        self.expect(&kw(Keyword::Type))?;
+        let name = self.expect_name()?;
+        let bounds = if self.eat(&op(Operator::Colon))? {
+            self.parse_bounds()?
+        } else {
+            vec![]
+        };
+        let default = if self.eat(&op(Operator::Assign))? {
+            Some(Box::new(self.parse_type()?))
+        } else {
+            None
+        };
+        Ok(Statement::AssociatedType {
+            name,
+            bounds,
+            default,
+        })
+    }
+
+    /// A function inside an extern block, which may be `pub`.
+    fn parse_method(&mut self, context: FnContext) -> Result<Expr, Error> {
+        let start = self.peek_span()?;
+        let public = self.eat(&kw(Keyword::Pub))?;
+        let item = self.parse_fn(public, context)?;
+        Ok(self.node(ExprKind::Statement(item), start))
+    }
+
+    /// `name`, `label name` or `_ name`, as in `fn move(from src: Point, _ speed: Int)`.
+    fn parse_arg_name(&mut self) -> Result<ArgName, Error> {
+        let first = self.expect_name()?;
+        let Some(TokenKind::Identifier(_)) = self.peek()? else {
+            if &*first == "_" {
+                return Ok(ArgName::Unnamed(first));
+            }
+            return Ok(ArgName::Single(first));
+        };
+        let inner = self.expect_name()?;
+        if &*first == "_" {
+            return Ok(ArgName::Unnamed(inner));
+        }
+        Ok(ArgName::Assigned {
+            outer: first,
+            inner,
+        })
+    }
+
+    /// `{ name: T, pub name: T, ... }`
+    fn parse_named_fields(&mut self) -> Result<Vec<Field>, Error> {
+        self.expect(&open(Parenthesis::Curly))?;
+        let mut fields = vec![];
+        loop {
+            while self.eat(&op(Operator::Semicolon))? {}
+            if self.eat(&close(Parenthesis::Curly))? {
+                return Ok(fields);
+            }
+            let public = self.eat(&kw(Keyword::Pub))?;
+            let name = self.expect_name()?;
+            self.expect(&op(Operator::Colon))?;
+            fields.push(Field {
+                public,
+                name,
+                ty: self.parse_type()?,
+            });
+            if !self.eat(&op(Operator::Comma))? {
+                self.expect_terminator()?;
+            }
+        }
+    }
+
+    /// Named fields in braces, tuple fields in parentheses, or no fields.
+    fn parse_fields(&mut self) -> Result<Fields, Error> {
+        if self.check(&open(Parenthesis::Curly))? {
+            return Ok(Fields::Named(self.parse_named_fields()?));
+        }
+        if !self.eat(&open(Parenthesis::Normal))? {
+            return Ok(Fields::Unit);
+        }
+        let mut index = 0usize;
+        let (fields, _) = self.parse_comma_list(Parenthesis::Normal, |p| {
+            let public = p.eat(&kw(Keyword::Pub))?;
+            let ty = p.parse_type()?;
+            index += 1;
+            // A tuple field is named by its position, which is where its type is.
+            let name = Ident {
+                text: (index - 1).to_string().into(),
+                span: ty.span,
+            };
+            Ok(Field { public, name, ty })
+        })?;
+        Ok(Fields::Tuple(fields))
+    }
+
+    /// The `where` clause goes before named fields but after tuple fields.
+    fn parse_struct(&mut self, public: bool) -> Result<Statement, Error> {
+        self.expect(&kw(Keyword::Struct))?;
+        let name = self.expect_name()?;
+        let mut generics = self.parse_generic_params()?;
+        self.parse_where_clause(&mut generics)?;
+        let fields = self.parse_fields()?;
+        if let Fields::Tuple(_) = fields {
+            self.parse_where_clause(&mut generics)?;
+        }
+        Ok(Statement::Struct {
+            public,
+            name,
+            generics,
+            fields,
+        })
+    }
+
+    /// Unions only have named fields, which all share the same memory.
+    fn parse_union(&mut self, public: bool) -> Result<Statement, Error> {
+        self.expect(&kw(Keyword::Union))?;
+        let name = self.expect_name()?;
+        let mut generics = self.parse_generic_params()?;
+        self.parse_where_clause(&mut generics)?;
+        let fields = self.parse_named_fields()?;
+        Ok(Statement::Union {
+            public,
+            name,
+            generics,
+            fields,
+        })
+    }
+
+    fn parse_enum(&mut self, public: bool) -> Result<Statement, Error> {
+        self.expect(&kw(Keyword::Enum))?;
+        let name = self.expect_name()?;
+        let mut generics = self.parse_generic_params()?;
+        self.parse_where_clause(&mut generics)?;
+        self.expect(&open(Parenthesis::Curly))?;
+        let mut variants = vec![];
+        loop {
+            while self.eat(&op(Operator::Semicolon))? {}
+            if self.eat(&close(Parenthesis::Curly))? {
+                break;
+            }
+            let name = self.expect_name()?;
+            let fields = self.parse_fields()?;
+            let discriminant = if self.eat(&op(Operator::Assign))? {
+                Some(Box::new(self.parse_expr()?))
+            } else {
+                None
+            };
+            variants.push(Variant {
+                name,
+                fields,
+                discriminant,
+            });
+            if !self.eat(&op(Operator::Comma))? {
+                self.expect_terminator()?;
+            }
+        }
+        Ok(Statement::Enum {
+            public,
+            name,
+            generics,
+            variants,
+        })
+    }
+
+    /// `trait Name<T>: Supertrait + Supertrait where ... { ... }`
+    fn parse_trait(&mut self, public: bool) -> Result<Statement, Error> {
+        self.expect(&kw(Keyword::Trait))?;
+        let name = self.expect_name()?;
+        let mut generics = self.parse_generic_params()?;
+        let supertraits = if self.eat(&op(Operator::Colon))? {
+            self.parse_bounds()?
+        } else {
+            vec![]
+        };
+        self.parse_where_clause(&mut generics)?;
+        let items = self.parse_item_block(|p| p.parse_associated_item(FnContext::Trait))?;
+        Ok(Statement::Trait {
+            public,
+            name,
+            generics,
+            supertraits,
+            items,
+        })
+    }
+
+    /// `impl<T> Trait for Type where ... { ... }`, or `impl<T> Type { ... }`.
+    fn parse_impl(&mut self) -> Result<Statement, Error> {
+        self.expect(&kw(Keyword::Impl))?;
+        let mut generics = self.parse_generic_params()?;
+        let first = self.parse_type()?;
+        let (trait_, target) = if self.eat(&kw(Keyword::For))? {
+            (Some(Box::new(first)), self.parse_type()?)
+        } else {
+            (None, first)
+        };
+        self.parse_where_clause(&mut generics)?;
+        let items = self.parse_item_block(|p| p.parse_associated_item(FnContext::Free))?;
+        Ok(Statement::Impl {
+            generics,
+            trait_,
+            target: Box::new(target),
+            items,
+        })
+    }
+
+    fn parse_type_alias(&mut self, public: bool) -> Result<Statement, Error> {
+        self.expect(&kw(Keyword::Type))?;
+        let name = self.expect_name()?;
+        let generics = self.parse_generic_params()?;
+        self.expect(&op(Operator::Assign))?;
+        let ty = self.parse_type()?;
+        Ok(Statement::TypeAlias {
+            public,
+            name,
+            generics,
+            ty: Box::new(ty),
+        })
+    }
+
+    /// Statics in traits are associated constants, which may leave out the value.
+    fn parse_static(&mut self, public: bool, context: FnContext) -> Result<Statement, Error> {
+        self.expect(&kw(Keyword::Static))?;
+        let mutable = self.eat(&kw(Keyword::Mut))?;
+        let name = self.expect_name()?;
+        self.expect(&op(Operator::Colon))?;
+        let ty = self.parse_type()?;
+        let value = if self.eat(&op(Operator::Assign))? {
+            Some(Box::new(self.parse_expr()?))
+        } else if context == FnContext::Trait {
+            None
+        } else {
+            let span = self.peek_span()?;
+            return Err(self.error(
+                MISSING_STATIC_VALUE,
+                &msg!("parser-missing-static-value", name = name),
+                self.line,
+                span,
+            ));
+        };
+        Ok(Statement::Static {
+            public,
+            mutable,
+            name,
+            ty: Box::new(ty),
+            value,
+        })
+    }
+
+    /// `module name` declares a module stored in another file, `module name { ... }`
+    /// defines it inline.
+    fn parse_module(&mut self) -> Result<Statement, Error> {
+        self.expect(&kw(Keyword::Module))?;
+        let start = self.peek_span()?;
+        let name = ExprKind::Identifier(self.expect_ident()?);
+        let name = Box::new(self.node(name, start));
+        let inline = self.check(&open(Parenthesis::Curly))?;
+        let children = if inline {
+            self.parse_item_block(Self::parse_item)?
+        } else {
+            vec![]
+        };
+        Ok(Statement::Module {
+            name,
+            inline,
+            children,
+        })
+    }
+
+    fn parse_import(&mut self) -> Result<Statement, Error> {
+        self.expect(&kw(Keyword::Import))?;
+        Ok(Statement::Use {
+            tree: self.parse_import_tree()?,
+        })
+    }
+
+    /// `a.b`, `a.b as c` or `a.b.{tree, tree, ...}`.
+    fn parse_import_tree(&mut self) -> Result<ImportTree, Error> {
+        let start = self.peek_span()?;
+        let first = ExprKind::Identifier(self.expect_ident()?);
+        let mut path = self.node(first, start);
+        while self.eat(&op(Operator::Dot))? {
+            if self.eat(&open(Parenthesis::Curly))? {
+                let (items, _) =
+                    self.parse_comma_list(Parenthesis::Curly, Self::parse_import_tree)?;
+                return Ok(ImportTree::Group {
+                    prefix: Box::new(path),
+                    items,
+                });
+            }
+            path = self.parse_path_segment(path)?;
+        }
+        let alias = if self.eat(&kw(Keyword::As))? {
+            Some(self.expect_ident()?)
+        } else {
+            None
+        };
+        Ok(ImportTree::Path {
+            path: Box::new(path),
+            alias,
+        })
+    }
+
+    /// `extern fn ...` or `extern "abi" { fn ...; fn ... }`.
+    fn parse_extern(&mut self) -> Result<Statement, Error> {
+        self.expect(&kw(Keyword::Extern))?;
+        let abi = match self.peek()? {
+            Some(TokenKind::Literal(Literal::String, abi)) => {
+                self.bump()?;
+                Some(abi)
+            }
+            _ => None,
+        };
+        let items = if self.check(&open(Parenthesis::Curly))? {
+            self.parse_item_block(|p| p.parse_method(FnContext::Extern))?
+        } else {
+            vec![self.parse_method(FnContext::Extern)?]
+        };
+        Ok(Statement::Extern { abi, items })
+    }
+
+    /// A dotted path such as `std.io.File`.
+    pub(crate) fn parse_path(&mut self) -> Result<Expr, Error> {
+        let start = self.peek_span()?;
+        let first = ExprKind::Identifier(self.expect_ident()?);
+        let first = self.node(first, start);
+        self.parse_path_rest(first)
+    }
+
+    /// The `.segment`s of a path after its `first` segment.
+    pub(crate) fn parse_path_rest(&mut self, first: Expr) -> Result<Expr, Error> {
+        let mut path = first;
+        while self.eat(&op(Operator::Dot))? {
+            path = self.parse_path_segment(path)?;
+        }
+        Ok(path)
+    }
+
+    /// The segment after a `.`, which extends `path`.
+    fn parse_path_segment(&mut self, path: Expr) -> Result<Expr, Error> {
+        let path_start = path.span;
+        let start = self.peek_span()?;
+        let segment = ExprKind::Identifier(self.expect_ident()?);
+        let segment = self.node(segment, start);
+        let kind = ExprKind::Binary {
+            op: crate::BinaryOperator::Dot,
+            left: Box::new(path),
+            right: Box::new(segment),
+        };
+        Ok(self.node(kind, path_start))
+    }
+}
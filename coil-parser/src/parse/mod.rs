@@ -0,0 +1,12 @@
+//! The recursive-descent parser, split by grammar area. Every file adds an
+//! `impl Parser` block; the token plumbing lives in the crate root.
+
+mod closure;
+mod control;
+mod expr;
+mod generics;
+mod item;
+mod pattern;
+mod recover;
+mod stmt;
+mod types;
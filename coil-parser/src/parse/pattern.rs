@@ -0,0 +1,188 @@
+use coil_error::{msg, Error, Span};
+use coil_lexer::{Keyword, Literal, Operator, Parenthesis, TokenKind};
+
+use crate::{close, kw, op, open, ExprKind, Ident, Parser, Pattern, PatternKind, EXPECTED_PATTERN};
+
+/// The fields of a struct pattern, and whether it ends with `..`.
+type FieldPatterns = (Vec<(Box<str>, Pattern)>, bool);
+
+impl Parser {
+    /// Parses a pattern: one or more alternatives separated by `|`.
+    pub(crate) fn parse_pattern(&mut self) -> Result<Pattern, Error> {
+        let start = self.peek_span()?;
+        let mut alternatives = vec![self.parse_range_pattern()?];
+        while self.eat(&op(Operator::BitOr))? {
+            alternatives.push(self.parse_range_pattern()?);
+        }
+        if alternatives.len() == 1 {
+            return Ok(alternatives.remove(0));
+        }
+        Ok(self.pattern(PatternKind::Or(alternatives), start))
+    }
+
+    /// A pattern, or a range `start..end` between two literals.
+    fn parse_range_pattern(&mut self) -> Result<Pattern, Error> {
+        let start = self.peek_span()?;
+        let first = self.parse_primary_pattern()?;
+        if !matches!(first.kind, PatternKind::Literal(..)) || !self.eat(&op(Operator::DoubleDot))? {
+            return Ok(first);
+        }
+        let end_start = self.peek_span()?;
+        let Some(end) = self.parse_literal_pattern()? else {
+            return Err(self.unexpected(EXPECTED_PATTERN, &msg!("parser-pattern"))?);
+        };
+        let end = self.pattern(end, end_start);
+        let kind = PatternKind::Range {
+            start: Box::new(first),
+            end: Box::new(end),
+        };
+        Ok(self.pattern(kind, start))
+    }
+
+    fn parse_primary_pattern(&mut self) -> Result<Pattern, Error> {
+        let start = self.peek_span()?;
+        if let Some(literal) = self.parse_literal_pattern()? {
+            return Ok(self.pattern(literal, start));
+        }
+        let kind = match self.peek()? {
+            Some(TokenKind::Identifier(name)) if name.as_ref() == "_" => {
+                self.bump()?;
+                PatternKind::Wildcard
+            }
+            Some(TokenKind::Keyword(Keyword::Mut)) => {
+                self.bump()?;
+                PatternKind::Binding {
+                    mutable: true,
+                    name: self.expect_name()?,
+                }
+            }
+            Some(TokenKind::Keyword(Keyword::Is)) => {
+                self.bump()?;
+                PatternKind::Is {
+                    binding: None,
+                    ty: Box::new(self.parse_type()?),
+                }
+            }
+            Some(TokenKind::Keyword(Keyword::True | Keyword::False)) => {
+                let value = self.check(&kw(Keyword::True))?;
+                self.bump()?;
+                PatternKind::Bool(value)
+            }
+            Some(kind) if kind == open(Parenthesis::Normal) => {
+                self.bump()?;
+                let (mut patterns, trailing) =
+                    self.parse_comma_list(Parenthesis::Normal, Self::parse_pattern)?;
+                if patterns.len() == 1 && !trailing {
+                    return Ok(patterns.remove(0));
+                }
+                PatternKind::Tuple(patterns)
+            }
+            Some(TokenKind::Identifier(_)) => return self.parse_path_pattern(),
+            _ => return Err(self.unexpected(EXPECTED_PATTERN, &msg!("parser-pattern"))?),
+        };
+        Ok(self.pattern(kind, start))
+    }
+
+    /// A literal, or `-` followed by a number.
+    fn parse_literal_pattern(&mut self) -> Result<Option<PatternKind>, Error> {
+        match self.peek()? {
+            Some(TokenKind::Literal(literal, value)) => {
+                self.bump()?;
+                return Ok(Some(PatternKind::Literal(literal, value)));
+            }
+            Some(TokenKind::Operator(Operator::Minus)) => self.bump()?,
+            _ => return Ok(None),
+        };
+        match self.peek()? {
+            Some(TokenKind::Literal(literal, value)) if literal != Literal::String => {
+                self.bump()?;
+                Ok(Some(PatternKind::Literal(
+                    literal,
+                    format!("-{value}").into(),
+                )))
+            }
+            _ => Err(self.unexpected(EXPECTED_PATTERN, &msg!("parser-pattern"))?),
+        }
+    }
+
+    /// Patterns starting with a name: bindings, paths, variants, structs and `name is T`.
+    fn parse_path_pattern(&mut self) -> Result<Pattern, Error> {
+        let start = self.peek_span()?;
+        let path = self.parse_path()?;
+        let kind = match (self.peek()?, &path.kind) {
+            (Some(kind), _) if kind == open(Parenthesis::Normal) => {
+                self.bump()?;
+                let (args, _) = self.parse_comma_list(Parenthesis::Normal, Self::parse_pattern)?;
+                PatternKind::Variant {
+                    path: Box::new(path),
+                    args,
+                }
+            }
+            (Some(kind), _) if kind == open(Parenthesis::Curly) => {
+                let (fields, rest) = self.parse_field_patterns()?;
+                PatternKind::Struct {
+                    path: Box::new(path),
+                    fields,
+                    rest,
+                }
+            }
+            (Some(TokenKind::Keyword(Keyword::Is)), ExprKind::Identifier(name)) => {
+                let binding = Some(Ident {
+                    text: name.clone(),
+                    span: path.span,
+                });
+                self.bump()?;
+                PatternKind::Is {
+                    binding,
+                    ty: Box::new(self.parse_type()?),
+                }
+            }
+            (_, ExprKind::Identifier(name)) => PatternKind::Binding {
+                mutable: false,
+                name: Ident {
+                    text: name.clone(),
+                    span: path.span,
+                },
+            },
+            _ => PatternKind::Path(Box::new(path)),
+        };
+        Ok(self.pattern(kind, start))
+    }
+
+    /// `{ name, name: pattern, .. }`
+    fn parse_field_patterns(&mut self) -> Result<FieldPatterns, Error> {
+        self.expect(&open(Parenthesis::Curly))?;
+        let mut fields = vec![];
+        loop {
+            while self.eat(&op(Operator::Semicolon))? {}
+            if self.eat(&close(Parenthesis::Curly))? {
+                return Ok((fields, false));
+            }
+            if self.eat(&op(Operator::DoubleDot))? {
+                while self.eat(&op(Operator::Semicolon))? {}
+                self.expect(&close(Parenthesis::Curly))?;
+                return Ok((fields, true));
+            }
+            let start = self.peek_span()?;
+            let name = self.expect_name()?;
+            let pattern = if self.eat(&op(Operator::Colon))? {
+                self.parse_pattern()?
+            } else {
+                let binding = PatternKind::Binding {
+                    mutable: false,
+                    name: name.clone(),
+                };
+                self.pattern(binding, start)
+            };
+            fields.push((name.text, pattern));
+            if !self.eat(&op(Operator::Comma))? {
+                self.expect_terminator()?;
+            }
+        }
+    }
+
+    fn pattern(&mut self, kind: PatternKind, start: Span) -> Pattern {
+        let (id, span) = self.locate(start);
+        Pattern { id, span, kind }
+    }
+}
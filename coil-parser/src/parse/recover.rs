@@ -0,0 +1,75 @@
+use coil_error::Error;
+use coil_lexer::{Keyword, Operator, Parenthesis, TokenKind};
+
+use crate::{starts_item, Expr, ExprKind, Parser};
+
+impl Parser {
+    /// Parses a statement or item followed by its terminator. On a syntax error, the
+    /// error is recorded and the parser skips to the next synchronisation point. A
+    /// statement that is broken before its end becomes an [`ExprKind::Error`]; one that is
+    /// only missing its terminator is kept.
+    pub(crate) fn recover(&mut self, parse: impl FnOnce(&mut Self) -> Result<Expr, Error>) -> Expr {
+        let start = self.prev_span;
+        let errors = self.errors.len();
+        let first = self.peek_span().unwrap_or(start);
+        let depth = self.depth();
+        let (expr, error) = match parse(self) {
+            Ok(expr) => match self.expect_terminator() {
+                Ok(()) => return expr,
+                // Past a placeholder, the missing terminator is part of the same error.
+                Err(_) if self.errors.len() > errors => (Some(expr), None),
+                Err(error) => (Some(expr), Some(error)),
+            },
+            Err(error) => (None, Some(error)),
+        };
+        self.errors.extend(error);
+        // Always skip the offending token, so that the same error can't repeat.
+        if self.prev_span == start {
+            let _ = self.bump();
+        }
+        self.synchronize(depth);
+        expr.unwrap_or_else(|| self.node(ExprKind::Error, first))
+    }
+
+    /// Skips the rest of a broken statement that started with `depth` brackets open:
+    /// everything inside the brackets it opened, then up to and including a `;`, or up
+    /// to a `}` closing the enclosing block, a keyword starting an item or `let`.
+    fn synchronize(&mut self, depth: usize) {
+        while let Ok(Some(kind)) = self.peek() {
+            if self.depth() <= depth {
+                match kind {
+                    TokenKind::Operator(Operator::Semicolon) => {
+                        let _ = self.bump();
+                        return;
+                    }
+                    TokenKind::Parenthesis {
+                        closing: true,
+                        kind: Parenthesis::Curly,
+                    } => return,
+                    TokenKind::Keyword(keyword) if is_sync_keyword(keyword) => return,
+                    _ => {}
+                }
+            }
+            let _ = self.bump();
+        }
+    }
+
+    /// Records a missing expression and stands in for it, when the next token ends the
+    /// expression anyway so there is nothing to skip.
+    pub(crate) fn missing_expression(&mut self, error: Error) -> Result<Expr, Error> {
+        let start = self.peek_span()?;
+        match self.peek()? {
+            None
+            | Some(TokenKind::Operator(Operator::Semicolon | Operator::Comma))
+            | Some(TokenKind::Parenthesis { closing: true, .. }) => {}
+            Some(TokenKind::Keyword(keyword)) if is_sync_keyword(keyword) => {}
+            Some(_) => return Err(error),
+        }
+        self.errors.push(error);
+        Ok(self.node(ExprKind::Error, start))
+    }
+}
+
+fn is_sync_keyword(keyword: Keyword) -> bool {
+    keyword == Keyword::Let || starts_item(keyword)
+}
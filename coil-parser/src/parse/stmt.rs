@@ -0,0 +1,105 @@
+use coil_error::Error;
+use coil_lexer::{Keyword, Operator, Parenthesis, TokenKind};
+
+use crate::{close, kw, op, open, starts_item, Expr, ExprKind, Parser, Statement};
+
+impl Parser {
+    /// Parses `{ statement; statement; ... }`. The value of a block is its last statement.
+    pub(crate) fn parse_block(&mut self) -> Result<Expr, Error> {
+        self.nested(Self::parse_block_statements)
+    }
+
+    fn parse_block_statements(&mut self) -> Result<Expr, Error> {
+        let start = self.peek_span()?;
+        self.expect(&open(Parenthesis::Curly))?;
+        let mut statements = vec![];
+        loop {
+            while self.eat(&op(Operator::Semicolon))? {}
+            if self.eat(&close(Parenthesis::Curly))? {
+                return Ok(self.node(ExprKind::Block(statements), start));
+            }
+            self.expect_block_end()?;
+            statements.push(self.recover(Self::parse_statement));
+        }
+    }
+
+    pub fn parse_statement(&mut self) -> Result<Expr, Error> {
+        let condition_path = self.condition_path.take();
+        let start = self.peek_span()?;
+        let Some(TokenKind::Keyword(keyword)) = self.peek()? else {
+            let expr = self.parse_expr()?;
+            if let ExprKind::Identifier(label) = &expr.kind {
+                if self.eat(&op(Operator::Colon))? {
+                    let is_loop = matches!(
+                        self.peek()?,
+                        Some(TokenKind::Keyword(
+                            Keyword::While | Keyword::For | Keyword::Do
+                        ))
+                    );
+                    if let Some((path, line)) = condition_path.filter(|_| !is_loop) {
+                        return Err(self.struct_literal_in_condition(&path, line));
+                    }
+                    return self.parse_loop(Some(label.clone()), start);
+                }
+            }
+            return Ok(expr);
+        };
+        let statement = match keyword {
+            Keyword::Let => self.parse_let()?,
+            Keyword::Return => {
+                self.bump()?;
+                Statement::Return(self.parse_optional_expr()?.map(Box::new))
+            }
+            Keyword::Break => {
+                self.bump()?;
+                let label = self.parse_loop_label();
+                let value = self.parse_optional_expr()?.map(Box::new);
+                Statement::Break { label, value }
+            }
+            Keyword::Fallthrough => self.parse_fallthrough()?,
+            Keyword::Continue => {
+                self.bump()?;
+                let label = self.parse_loop_label();
+                if let Some(TokenKind::Identifier(label)) = self.peek()? {
+                    return Err(self.unknown_label(&label)?);
+                }
+                Statement::Continue { label }
+            }
+            keyword if starts_item(keyword) => return self.parse_item(),
+            _ => return self.parse_expr(),
+        };
+        Ok(self.node(ExprKind::Statement(statement), start))
+    }
+
+    fn parse_let(&mut self) -> Result<Statement, Error> {
+        self.expect(&kw(Keyword::Let))?;
+        let mutable = self.eat(&kw(Keyword::Mut))?;
+        let name = self.expect_name()?;
+        let ty = if self.eat(&op(Operator::Colon))? {
+            Some(Box::new(self.parse_type()?))
+        } else {
+            None
+        };
+        let value = if self.eat(&op(Operator::Assign))? {
+            Some(Box::new(self.parse_expr()?))
+        } else {
+            None
+        };
+        Ok(Statement::Let {
+            mutable,
+            name,
+            ty,
+            value,
+        })
+    }
+
+    /// Parses an expression unless the statement ends here, as in a bare `return`.
+    pub(crate) fn parse_optional_expr(&mut self) -> Result<Option<Expr>, Error> {
+        match self.peek()? {
+            None => Ok(None),
+            Some(TokenKind::Operator(Operator::Semicolon))
+            | Some(TokenKind::Parenthesis { closing: true, .. }) => Ok(None),
+            Some(_) => self.parse_expr().map(Some),
+        }
+    }
+}
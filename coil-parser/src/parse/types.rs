@@ -0,0 +1,133 @@
+use coil_error::{msg, Error};
+use coil_lexer::{Keyword, Operator, Parenthesis, TokenKind};
+
+use crate::{close, kw, op, open, Expr, ExprKind, Parser, TypeExpr, TypeKind, EXPECTED_TYPE};
+
+impl Parser {
+    /// A type followed by any number of `?` and `!Error` suffixes, which apply to
+    /// everything before them: `&Int?` is an optional reference.
+    pub(crate) fn parse_type(&mut self) -> Result<TypeExpr, Error> {
+        let start = self.peek_span()?;
+        let mut ty = self.parse_prefix_type()?;
+        loop {
+            let kind = if self.eat(&op(Operator::QuestionMark))? {
+                TypeKind::Optional(Box::new(ty))
+            } else if self.eat(&op(Operator::Not))? {
+                TypeKind::Result {
+                    ok: Box::new(ty),
+                    error: Box::new(self.parse_prefix_type()?),
+                }
+            } else {
+                return Ok(ty);
+            };
+            ty = self.type_node(kind, start);
+        }
+    }
+
+    /// References and pointers, whose `&` and `*` apply to the type right after them.
+    fn parse_prefix_type(&mut self) -> Result<TypeExpr, Error> {
+        let start = self.peek_span()?;
+        let kind = match self.peek()? {
+            Some(TokenKind::Operator(Operator::BitAnd)) => {
+                self.bump()?;
+                TypeKind::Reference {
+                    mutable: self.eat(&kw(Keyword::Mut))?,
+                    ty: Box::new(self.parse_prefix_type()?),
+                }
+            }
+            // The lexer reads `&&T` as `&&`, a reference to a reference.
+            Some(TokenKind::Operator(Operator::And)) => {
+                self.bump()?;
+                let inner = TypeKind::Reference {
+                    mutable: self.eat(&kw(Keyword::Mut))?,
+                    ty: Box::new(self.parse_prefix_type()?),
+                };
+                TypeKind::Reference {
+                    mutable: false,
+                    ty: Box::new(self.type_node(inner, start)),
+                }
+            }
+            Some(TokenKind::Operator(Operator::Star)) => {
+                self.bump()?;
+                TypeKind::Pointer {
+                    mutable: self.eat(&kw(Keyword::Mut))?,
+                    ty: Box::new(self.parse_prefix_type()?),
+                }
+            }
+            _ => return self.parse_primary_type(),
+        };
+        Ok(self.type_node(kind, start))
+    }
+
+    /// Paths with optional type arguments (`Map<K, Vec<V>>`), `Self`, tuples, arrays,
+    /// slices and function types.
+    fn parse_primary_type(&mut self) -> Result<TypeExpr, Error> {
+        let start = self.peek_span()?;
+        let kind = match self.peek()? {
+            Some(TokenKind::Identifier(_)) => {
+                let path = self.parse_path()?;
+                self.parse_type_path(path)?
+            }
+            Some(TokenKind::Keyword(Keyword::SelfType)) => {
+                self.bump()?;
+                let first = self.node(ExprKind::Identifier("Self".into()), start);
+                let path = self.parse_path_rest(first)?;
+                self.parse_type_path(path)?
+            }
+            Some(TokenKind::Keyword(Keyword::Fn)) => {
+                self.bump()?;
+                self.expect(&open(Parenthesis::Normal))?;
+                let (params, _) = self.parse_comma_list(Parenthesis::Normal, Self::parse_type)?;
+                let returns = if self.eat(&op(Operator::Arrow))? {
+                    self.parse_type()?
+                } else {
+                    let start = self.peek_span()?;
+                    self.type_node(TypeKind::unit(), start)
+                };
+                TypeKind::Fn {
+                    params,
+                    returns: Box::new(returns),
+                }
+            }
+            Some(kind) if kind == open(Parenthesis::Normal) => {
+                self.bump()?;
+                let (mut types, trailing) =
+                    self.parse_comma_list(Parenthesis::Normal, Self::parse_type)?;
+                if types.len() == 1 && !trailing {
+                    return Ok(types.remove(0));
+                }
+                TypeKind::Tuple(types)
+            }
+            Some(kind) if kind == open(Parenthesis::Square) => {
+                self.bump()?;
+                let ty = Box::new(self.parse_type()?);
+                let kind = if self.eat(&op(Operator::Semicolon))? {
+                    let len = self.nested(Self::parse_expr)?;
+                    TypeKind::Array {
+                        ty,
+                        len: Box::new(len),
+                    }
+                } else {
+                    TypeKind::Slice(ty)
+                };
+                self.expect(&close(Parenthesis::Square))?;
+                kind
+            }
+            _ => return Err(self.unexpected(EXPECTED_TYPE, &msg!("parser-type"))?),
+        };
+        Ok(self.type_node(kind, start))
+    }
+
+    /// A path type, with its type arguments if a `<` follows.
+    fn parse_type_path(&mut self, path: Expr) -> Result<TypeKind, Error> {
+        let args = if self.check(&op(Operator::Lesser))? {
+            self.parse_type_args()?
+        } else {
+            vec![]
+        };
+        Ok(TypeKind::Path {
+            path: Box::new(path),
+            args,
+        })
+    }
+}
@@ -0,0 +1,77 @@
+use coil_error::Span;
+use coil_lexer::Literal;
+
+use crate::{Expr, Ident, NodeId, TypeExpr};
+
+/// A pattern, as in the arms of a `match`. Like [`Expr`], patterns compare and hash by
+/// their contents only.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    pub id: NodeId,
+    pub span: Span,
+    pub kind: PatternKind,
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+impl std::hash::Hash for Pattern {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.kind.hash(state)
+    }
+}
+
+/// A pattern without a location, for trees built by hand.
+impl From<PatternKind> for Pattern {
+    fn from(kind: PatternKind) -> Self {
+        Self {
+            id: NodeId::default(),
+            span: Span::default(),
+            kind,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub enum PatternKind {
+    /// `_`
+    Wildcard,
+    /// `name` or `mut name`. A single name always binds; constants and unit variants
+    /// are matched with a path such as `Color.Red`.
+    Binding {
+        mutable: bool,
+        name: Ident,
+    },
+    /// A number, string or character, with a leading `-` for negative numbers.
+    Literal(Literal, Box<str>),
+    Bool(bool),
+    /// `start..end`
+    Range {
+        start: Box<Pattern>,
+        end: Box<Pattern>,
+    },
+    /// A dotted path to a constant or a unit variant, like `Color.Red`.
+    Path(Box<Expr>),
+    Tuple(Vec<Pattern>),
+    /// A tuple variant or tuple struct, like `Some(x)` or `Shape.Circle(radius)`.
+    Variant {
+        path: Box<Expr>,
+        args: Vec<Pattern>,
+    },
+    /// `Point { x, y: 0, .. }`; `x` alone is short for `x: x`.
+    Struct {
+        path: Box<Expr>,
+        fields: Vec<(Box<str>, Pattern)>,
+        rest: bool,
+    },
+    /// `a | b | c`
+    Or(Vec<Pattern>),
+    /// `is Type` or `name is Type`: a test of the value's type.
+    Is {
+        binding: Option<Ident>,
+        ty: Box<TypeExpr>,
+    },
+}
@@ -0,0 +1,890 @@
+//! Prints the AST back as Coil source, through the [`Display`](std::fmt::Display)
+//! implementations of [`Expr`], [`TypeExpr`] and [`Pattern`].
+//!
+//! Parentheses are only added where the [precedence table](crate::Precedence) or the
+//! grammar needs them, so `(a + b) * c` keeps its parentheses but `a + (b * c)` loses
+//! them. Parsing the printed source gives back an equal tree. Blocks are laid out over
+//! several lines, with four spaces of indentation; a module prints as the items of a
+//! file, one per line.
+
+use std::fmt;
+
+use coil_lexer::Literal;
+
+use crate::{
+    ArgName, Associativity, BinaryOperator, ClosureParam, Expr, ExprKind, Field, Fields, Generics,
+    ImportTree, MatchArm, Pattern, PatternKind, Precedence, Signature, Statement, TypeExpr,
+    TypeKind, Variant,
+};
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut printer = Printer::default();
+        match &self.kind {
+            ExprKind::Statement(Statement::Module {
+                inline: false,
+                children,
+                ..
+            }) => f.write_str(&printer.lines(children)),
+            _ => f.write_str(&printer.expr(self)),
+        }
+    }
+}
+
+impl fmt::Display for TypeExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&Printer::default().ty(self))
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&Printer::default().pattern(self))
+    }
+}
+
+#[derive(Default)]
+struct Printer {
+    indent: usize,
+    /// Whether a struct literal would be read as a name followed by a block here, as in
+    /// the condition of an `if`, mirroring `Parser::no_struct_literal`.
+    no_struct_literal: bool,
+}
+
+/// How tightly an expression binds when it is the operand of an operator.
+fn precedence(expr: &Expr) -> Precedence {
+    match &expr.kind {
+        ExprKind::Binary { op, .. } => op.precedence(),
+        ExprKind::Unary { op, .. } => op.precedence(),
+        // A closure without a block takes everything after its arrow as its body.
+        ExprKind::Closure { body, returns, .. } if !is_closed_closure(body, returns) => {
+            Precedence::Assignment
+        }
+        ExprKind::Statement(_) => Precedence::Comma,
+        _ => Precedence::Postfix,
+    }
+}
+
+/// Whether a closure is printed as `\(params) -> { ... }`, whose block ends it.
+fn is_closed_closure(body: &Expr, returns: &Option<Box<TypeExpr>>) -> bool {
+    returns.is_some() || matches!(body.kind, ExprKind::Block(_))
+}
+
+/// Whether a line starting with `text` would continue the line before it instead of
+/// starting a new statement, as `-x` does.
+fn continues_line(text: &str) -> bool {
+    text.starts_with(|c| "+-*/%&|^<>=.,:;?".contains(c))
+}
+
+impl Printer {
+    fn newline(&self) -> String {
+        self.newline_at(self.indent)
+    }
+
+    /// Prints `print` for the inside of brackets, where struct literals are allowed.
+    fn nested<T: ?Sized>(
+        &mut self,
+        value: &T,
+        print: impl FnOnce(&mut Self, &T) -> String,
+    ) -> String {
+        let outer = std::mem::replace(&mut self.no_struct_literal, false);
+        let text = print(self, value);
+        self.no_struct_literal = outer;
+        text
+    }
+
+    /// Prints an expression followed by a block, like the condition of an `if`.
+    fn condition(&mut self, expr: &Expr) -> String {
+        let outer = std::mem::replace(&mut self.no_struct_literal, true);
+        let text = self.expr(expr);
+        self.no_struct_literal = outer;
+        text
+    }
+
+    fn list<T>(&mut self, items: &[T], mut print: impl FnMut(&mut Self, &T) -> String) -> String {
+        let items: Vec<_> = items.iter().map(|item| print(self, item)).collect();
+        items.join(", ")
+    }
+
+    /// Statements or items on lines of their own. A `;` ends a line when the next one
+    /// starts with an operator, which would otherwise continue it.
+    fn lines(&mut self, items: &[Expr]) -> String {
+        let lines: Vec<_> = items.iter().map(|item| self.expr(item)).collect();
+        self.join_lines(lines, ";")
+    }
+
+    fn join_lines(&self, lines: Vec<String>, separator: &str) -> String {
+        let mut out = String::new();
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                if continues_line(line) {
+                    out.push_str(separator);
+                }
+                out.push_str(&self.newline());
+            }
+            out.push_str(line);
+        }
+        out
+    }
+
+    /// `{ ... }` around lines, indented one level further.
+    fn braced(&mut self, print: impl FnOnce(&mut Self) -> String) -> String {
+        let outer = std::mem::replace(&mut self.no_struct_literal, false);
+        self.indent += 1;
+        let inner = print(self);
+        self.indent -= 1;
+        self.no_struct_literal = outer;
+        if inner.is_empty() {
+            return "{}".into();
+        }
+        format!(
+            "{{{}{inner}{}}}",
+            self.newline_at(self.indent + 1),
+            self.newline()
+        )
+    }
+
+    /// A line break followed by `indent` levels of indentation.
+    fn newline_at(&self, indent: usize) -> String {
+        format!("\n{}", "    ".repeat(indent))
+    }
+
+    /// A block, on one line when it holds a single statement that fits on one.
+    fn block(&mut self, block: &Expr) -> String {
+        let ExprKind::Block(statements) = &block.kind else {
+            return self.braced(|p| p.expr(block));
+        };
+        if let [statement] = &statements[..] {
+            let text = self.nested(statement, Self::expr);
+            if !text.contains('\n') {
+                return format!("{{ {text} }}");
+            }
+        }
+        self.braced(|p| p.lines(statements))
+    }
+
+    /// `expr`, in parentheses if it binds more loosely than `min`.
+    fn operand(&mut self, expr: &Expr, min: Precedence) -> String {
+        if precedence(expr) < min {
+            return format!("({})", self.nested(expr, Self::expr));
+        }
+        self.expr(expr)
+    }
+
+    fn expr(&mut self, expr: &Expr) -> String {
+        match &expr.kind {
+            ExprKind::Statement(statement) => self.statement(statement),
+            ExprKind::Binary {
+                op: BinaryOperator::Dot,
+                left,
+                right,
+            } => {
+                let mut text = match &left.kind {
+                    // `1.x` would read as the number `1.`.
+                    ExprKind::Literal(Literal::Integer { .. } | Literal::Float { .. }, _) => {
+                        format!("({})", self.expr(left))
+                    }
+                    _ => self.operand(left, Precedence::Postfix),
+                };
+                // `x.0.1` would read as `x` and the number `0.1`.
+                if let ExprKind::Binary {
+                    op: BinaryOperator::Dot,
+                    right,
+                    ..
+                } = &left.kind
+                {
+                    if matches!(right.kind, ExprKind::Literal(..)) {
+                        text.push(' ');
+                    }
+                }
+                format!("{text}.{}", self.operand(right, Precedence::Postfix))
+            }
+            ExprKind::Binary { op, left, right } => {
+                let precedence = op.precedence();
+                let (left_min, right_min) = match op.associativity() {
+                    Associativity::Left => (precedence, precedence.next()),
+                    Associativity::Right => (precedence.next(), precedence),
+                    Associativity::None => (precedence.next(), precedence.next()),
+                };
+                let left = self.operand(left, left_min);
+                let right = self.operand(right, right_min);
+                match op {
+                    BinaryOperator::Range => format!("{left}..{right}"),
+                    BinaryOperator::Comma => format!("{left}, {right}"),
+                    _ => format!("{left} {} {right}", op.as_str()),
+                }
+            }
+            ExprKind::Unary { op, expr } if op.is_postfix() => {
+                format!("{}{}", self.operand(expr, Precedence::Postfix), op.as_str())
+            }
+            ExprKind::Unary { op, expr } => {
+                let operand = self.operand(expr, Precedence::Prefix);
+                // `& &x` isn't `&&x`.
+                let space = if op.as_str().ends_with('&') && operand.starts_with('&') {
+                    " "
+                } else {
+                    ""
+                };
+                format!("{}{space}{operand}", op.as_str())
+            }
+            ExprKind::Call { callee, args } => {
+                let callee = self.operand(callee, Precedence::Postfix);
+                let args = self.nested(args, |p, args| {
+                    p.list(args, |p, (label, arg)| match label {
+                        Some(label) => format!("{label}: {}", p.expr(arg)),
+                        None => p.expr(arg),
+                    })
+                });
+                format!("{callee}({args})")
+            }
+            ExprKind::Index { expr, index } => {
+                let expr = self.operand(expr, Precedence::Postfix);
+                format!("{expr}[{}]", self.nested(&**index, Self::expr))
+            }
+            ExprKind::Tuple(items) => {
+                let comma = if items.len() == 1 { "," } else { "" };
+                let items = self.nested(items, |p, items| p.list(items, Self::expr));
+                format!("({items}{comma})")
+            }
+            ExprKind::Array(items) => {
+                format!(
+                    "[{}]",
+                    self.nested(items, |p, items| p.list(items, Self::expr))
+                )
+            }
+            ExprKind::Generic { path, args } => {
+                let path = self.operand(path, Precedence::Postfix);
+                format!("{path}.<{}>", self.list(args, Self::ty))
+            }
+            ExprKind::StructLiteral { path, fields } => {
+                let path = self.operand(path, Precedence::Postfix);
+                let fields = self.nested(fields, |p, fields| {
+                    p.list(fields, |p, (name, value)| match &value.kind {
+                        ExprKind::Identifier(value) if value == name => name.to_string(),
+                        _ => format!("{name}: {}", p.expr(value)),
+                    })
+                });
+                let text = match fields.is_empty() {
+                    true => format!("{path} {{}}"),
+                    false => format!("{path} {{ {fields} }}"),
+                };
+                match self.no_struct_literal {
+                    true => format!("({text})"),
+                    false => text,
+                }
+            }
+            ExprKind::Block(_) => self.block(expr),
+            ExprKind::If {
+                unless,
+                condition,
+                then,
+                otherwise,
+            } => {
+                let keyword = if *unless { "unless" } else { "if" };
+                let mut text = format!(
+                    "{keyword} {} {}",
+                    self.condition(condition),
+                    self.block(then)
+                );
+                if let Some(otherwise) = otherwise {
+                    text.push_str(" else ");
+                    text.push_str(&self.expr(otherwise));
+                }
+                text
+            }
+            ExprKind::While {
+                label,
+                condition,
+                body,
+            } => format!(
+                "{}while {} {}",
+                loop_label(label),
+                self.condition(condition),
+                self.block(body)
+            ),
+            ExprKind::For {
+                label,
+                binding,
+                iterable,
+                body,
+            } => format!(
+                "{}for {binding} in {} {}",
+                loop_label(label),
+                self.condition(iterable),
+                self.block(body)
+            ),
+            ExprKind::DoWhile {
+                label,
+                body,
+                condition,
+            } => format!(
+                "{}do {} while {}",
+                loop_label(label),
+                self.block(body),
+                self.expr(condition)
+            ),
+            ExprKind::Match { scrutinee, arms } => {
+                let scrutinee = self.condition(scrutinee);
+                let arms = self.braced(|p| {
+                    let arms = arms.iter().map(|arm| p.arm(arm)).collect();
+                    p.join_lines(arms, ",")
+                });
+                format!("match {scrutinee} {arms}")
+            }
+            ExprKind::Closure {
+                params,
+                returns,
+                body,
+            } => self.closure(params, returns, body),
+            ExprKind::Literal(literal, text) => literal_text(literal, text),
+            ExprKind::Bool(value) => value.to_string(),
+            ExprKind::Identifier(name) => name.to_string(),
+            ExprKind::Error => "<error>".into(),
+        }
+    }
+
+    fn arm(&mut self, arm: &MatchArm) -> String {
+        let mut text = self.pattern(&arm.pattern);
+        if let Some(guard) = &arm.guard {
+            text.push_str(" if ");
+            text.push_str(&self.expr(guard));
+        }
+        format!("{text} => {}", self.expr(&arm.body))
+    }
+
+    /// `\x, y -> body`, or `\(x: Int) -> Int { ... }` when the body is a block.
+    fn closure(
+        &mut self,
+        params: &[ClosureParam],
+        returns: &Option<Box<TypeExpr>>,
+        body: &Expr,
+    ) -> String {
+        if is_closed_closure(body, returns) {
+            let params = self.list(params, |p, param| p.closure_param(param, false));
+            let returns = match returns {
+                Some(returns) => format!(" -> {}", self.ty(returns)),
+                None => String::new(),
+            };
+            return format!("\\({params}){returns} {}", self.block(body));
+        }
+        let params = self.list(params, |p, param| p.closure_param(param, true));
+        let space = if params.is_empty() { "" } else { " " };
+        format!("\\{params}{space}-> {}", self.expr(body))
+    }
+
+    /// `name` or `name: Type`. Before the arrow of a closure, a function type needs
+    /// parentheses, or the arrow would start its return type.
+    fn closure_param(&mut self, param: &ClosureParam, before_arrow: bool) -> String {
+        match &param.ty {
+            Some(ty) if before_arrow && ends_with_fn(ty, false) => {
+                format!("{}: ({})", param.name, self.ty(ty))
+            }
+            Some(ty) => format!("{}: {}", param.name, self.ty(ty)),
+            None => param.name.to_string(),
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) -> String {
+        match statement {
+            Statement::Module {
+                name,
+                inline,
+                children,
+            } => {
+                let name = self.expr(name);
+                // A module loaded from its own file prints with the items it was given.
+                if !inline && children.is_empty() {
+                    return format!("module {name}");
+                }
+                format!("module {name} {}", self.braced(|p| p.lines(children)))
+            }
+            Statement::Use { tree } => format!("import {}", self.import_tree(tree)),
+            Statement::Fn {
+                public,
+                name,
+                generics,
+                signature,
+                body,
+            } => {
+                let mut text = format!(
+                    "{}fn {name}{}{}",
+                    visibility(*public),
+                    self.generic_params(generics),
+                    self.signature(signature)
+                );
+                text.push_str(&self.where_clause(generics));
+                if let Some(body) = body {
+                    text.push(' ');
+                    text.push_str(&self.block(body));
+                }
+                text
+            }
+            Statement::Struct {
+                public,
+                name,
+                generics,
+                fields,
+            } => {
+                let mut text = format!(
+                    "{}struct {name}{}",
+                    visibility(*public),
+                    self.generic_params(generics)
+                );
+                // The `where` clause goes after tuple fields but before named fields.
+                if let Fields::Tuple(_) = fields {
+                    text.push_str(&self.fields(fields, false));
+                    text.push_str(&self.where_clause(generics));
+                } else {
+                    text.push_str(&self.where_clause(generics));
+                    text.push_str(&self.fields(fields, false));
+                }
+                text
+            }
+            Statement::Enum {
+                public,
+                name,
+                generics,
+                variants,
+            } => {
+                let variants = self.braced(|p| {
+                    let variants: Vec<_> = variants.iter().map(|v| p.variant(v)).collect();
+                    variants.join(&p.newline())
+                });
+                format!(
+                    "{}enum {name}{}{} {variants}",
+                    visibility(*public),
+                    self.generic_params(generics),
+                    self.where_clause(generics)
+                )
+            }
+            Statement::Union {
+                public,
+                name,
+                generics,
+                fields,
+            } => format!(
+                "{}union {name}{}{} {}",
+                visibility(*public),
+                self.generic_params(generics),
+                self.where_clause(generics),
+                self.named_fields(fields)
+            ),
+            Statement::Trait {
+                public,
+                name,
+                generics,
+                supertraits,
+                items,
+            } => {
+                let mut text = format!(
+                    "{}trait {name}{}",
+                    visibility(*public),
+                    self.generic_params(generics)
+                );
+                if !supertraits.is_empty() {
+                    text.push_str(": ");
+                    text.push_str(&self.bounds(supertraits));
+                }
+                text.push_str(&self.where_clause(generics));
+                format!("{text} {}", self.braced(|p| p.lines(items)))
+            }
+            Statement::Impl {
+                generics,
+                trait_,
+                target,
+                items,
+            } => {
+                let mut text = format!("impl{} ", self.generic_params(generics));
+                if let Some(trait_) = trait_ {
+                    text.push_str(&self.ty(trait_));
+                    text.push_str(" for ");
+                }
+                text.push_str(&self.ty(target));
+                text.push_str(&self.where_clause(generics));
+                format!("{text} {}", self.braced(|p| p.lines(items)))
+            }
+            Statement::TypeAlias {
+                public,
+                name,
+                generics,
+                ty,
+            } => format!(
+                "{}type {name}{} = {}",
+                visibility(*public),
+                self.generic_params(generics),
+                self.ty(ty)
+            ),
+            Statement::AssociatedType {
+                name,
+                bounds,
+                default,
+            } => {
+                let mut text = format!("type {name}");
+                if !bounds.is_empty() {
+                    text.push_str(": ");
+                    text.push_str(&self.bounds(bounds));
+                }
+                if let Some(default) = default {
+                    text.push_str(" = ");
+                    text.push_str(&self.ty(default));
+                }
+                text
+            }
+            Statement::Extern { abi, items } => {
+                let abi = match abi {
+                    Some(abi) => format!("{} ", literal_text(&Literal::String, abi)),
+                    None => String::new(),
+                };
+                format!("extern {abi}{}", self.braced(|p| p.lines(items)))
+            }
+            Statement::Static {
+                public,
+                mutable,
+                name,
+                ty,
+                value,
+            } => {
+                let mut text = format!(
+                    "{}static {}{name}: {}",
+                    visibility(*public),
+                    mutability(*mutable),
+                    self.ty(ty)
+                );
+                if let Some(value) = value {
+                    text.push_str(" = ");
+                    text.push_str(&self.expr(value));
+                }
+                text
+            }
+            Statement::Let {
+                mutable,
+                name,
+                ty,
+                value,
+            } => {
+                let mut text = format!("let {}{name}", mutability(*mutable));
+                if let Some(ty) = ty {
+                    text.push_str(": ");
+                    text.push_str(&self.ty(ty));
+                }
+                if let Some(value) = value {
+                    text.push_str(" = ");
+                    text.push_str(&self.expr(value));
+                }
+                text
+            }
+            Statement::Return(None) => "return".into(),
+            Statement::Return(Some(value)) => format!("return {}", self.expr(value)),
+            Statement::Break { label, value } => {
+                let mut text = String::from("break");
+                if let Some(label) = label {
+                    text.push(' ');
+                    text.push_str(label);
+                }
+                match value.as_deref() {
+                    // A name right after `break` could be read as a label.
+                    Some(
+                        value @ Expr {
+                            kind: ExprKind::Identifier(_),
+                            ..
+                        },
+                    ) if label.is_none() => text.push_str(&format!(" ({})", self.expr(value))),
+                    Some(value) => text.push_str(&format!(" {}", self.expr(value))),
+                    None => {}
+                }
+                text
+            }
+            Statement::Continue { label: None } => "continue".into(),
+            Statement::Continue { label: Some(label) } => format!("continue {label}"),
+            Statement::Fallthrough => "fallthrough".into(),
+        }
+    }
+
+    fn import_tree(&mut self, tree: &ImportTree) -> String {
+        match tree {
+            ImportTree::Path { path, alias: None } => self.expr(path),
+            ImportTree::Path {
+                path,
+                alias: Some(alias),
+            } => format!("{} as {alias}", self.expr(path)),
+            ImportTree::Group { prefix, items } => {
+                let items = self.list(items, Self::import_tree);
+                format!("{}.{{{items}}}", self.expr(prefix))
+            }
+        }
+    }
+
+    /// `(label name: Type, ...) -> Type`, without the arrow for functions returning `()`.
+    fn signature(&mut self, signature: &Signature) -> String {
+        let args = self.list(&signature.named_args, |p, (name, ty)| {
+            let name = match name {
+                ArgName::Unnamed(inner) if &**inner == "_" => "_".into(),
+                ArgName::Unnamed(inner) => format!("_ {inner}"),
+                ArgName::Single(name) => name.to_string(),
+                ArgName::Assigned { outer, inner } => format!("{outer} {inner}"),
+            };
+            format!("{name}: {}", p.ty(ty))
+        });
+        match &signature.returns.kind {
+            TypeKind::Tuple(types) if types.is_empty() => format!("({args})"),
+            _ => format!("({args}) -> {}", self.ty(&signature.returns)),
+        }
+    }
+
+    fn generic_params(&mut self, generics: &Generics) -> String {
+        if generics.params.is_empty() {
+            return String::new();
+        }
+        let params = self.list(&generics.params, |p, param| {
+            if param.bounds.is_empty() {
+                return param.name.to_string();
+            }
+            format!("{}: {}", param.name, p.bounds(&param.bounds))
+        });
+        format!("<{params}>")
+    }
+
+    /// ` where T: Bound, ...`, with its leading space.
+    fn where_clause(&mut self, generics: &Generics) -> String {
+        if generics.where_clause.is_empty() {
+            return String::new();
+        }
+        let predicates = self.list(&generics.where_clause, |p, predicate| {
+            format!("{}: {}", p.ty(&predicate.ty), p.bounds(&predicate.bounds))
+        });
+        format!(" where {predicates}")
+    }
+
+    fn bounds(&mut self, bounds: &[TypeExpr]) -> String {
+        let bounds: Vec<_> = bounds.iter().map(|bound| self.ty(bound)).collect();
+        bounds.join(" + ")
+    }
+
+    /// The fields of a struct or variant, with a space before named fields, which go on
+    /// lines of their own unless `inline`.
+    fn fields(&mut self, fields: &Fields, inline: bool) -> String {
+        match fields {
+            Fields::Named(fields) if inline && !fields.is_empty() => {
+                let fields = self.list(fields, Self::named_field);
+                format!(" {{ {fields} }}")
+            }
+            Fields::Named(fields) => format!(" {}", self.named_fields(fields)),
+            Fields::Tuple(fields) => format!("({})", self.list(fields, Self::field_type)),
+            Fields::Unit => String::new(),
+        }
+    }
+
+    fn named_fields(&mut self, fields: &[Field]) -> String {
+        self.braced(|p| {
+            let fields: Vec<_> = fields.iter().map(|field| p.named_field(field)).collect();
+            fields.join(&p.newline())
+        })
+    }
+
+    fn named_field(&mut self, field: &Field) -> String {
+        let ty = self.ty(&field.ty);
+        format!("{}{}: {ty}", visibility(field.public), field.name)
+    }
+
+    fn field_type(&mut self, field: &Field) -> String {
+        format!("{}{}", visibility(field.public), self.ty(&field.ty))
+    }
+
+    fn variant(&mut self, variant: &Variant) -> String {
+        let mut text = format!("{}{}", variant.name, self.fields(&variant.fields, true));
+        if let Some(discriminant) = &variant.discriminant {
+            text.push_str(" = ");
+            text.push_str(&self.expr(discriminant));
+        }
+        text
+    }
+
+    fn ty(&mut self, ty: &TypeExpr) -> String {
+        match &ty.kind {
+            TypeKind::Path { path, args } if args.is_empty() => self.expr(path),
+            TypeKind::Path { path, args } => {
+                format!("{}<{}>", self.expr(path), self.list(args, Self::ty))
+            }
+            TypeKind::Reference { mutable, ty } => {
+                format!("&{}{}", mutability(*mutable), self.prefix_operand(ty))
+            }
+            TypeKind::Pointer { mutable, ty } => {
+                format!("*{}{}", mutability(*mutable), self.prefix_operand(ty))
+            }
+            TypeKind::Array { ty, len } => {
+                let len = self.nested(&**len, Self::expr);
+                format!("[{}; {len}]", self.ty(ty))
+            }
+            TypeKind::Slice(ty) => format!("[{}]", self.ty(ty)),
+            TypeKind::Tuple(types) => {
+                let comma = if types.len() == 1 { "," } else { "" };
+                format!("({}{comma})", self.list(types, Self::ty))
+            }
+            TypeKind::Fn { params, returns } => {
+                let params = self.list(params, Self::ty);
+                match &returns.kind {
+                    TypeKind::Tuple(types) if types.is_empty() => format!("fn({params})"),
+                    _ => format!("fn({params}) -> {}", self.ty(returns)),
+                }
+            }
+            TypeKind::Optional(ty) => format!("{}?", self.suffix_operand(ty)),
+            TypeKind::Result { ok, error } => {
+                let ok = self.suffix_operand(ok);
+                format!("{ok}!{}", self.prefix_operand(error))
+            }
+        }
+    }
+
+    /// The type after `&`, `*` or `!`, which `?` and `!` can't follow unparenthesized, as
+    /// they would apply to the whole type: `&Int?` is an optional reference.
+    fn prefix_operand(&mut self, ty: &TypeExpr) -> String {
+        match ty.kind {
+            TypeKind::Optional(_) | TypeKind::Result { .. } => format!("({})", self.ty(ty)),
+            _ => self.ty(ty),
+        }
+    }
+
+    /// The type before `?` or `!`, which would become part of the return type of a
+    /// function type ending it.
+    fn suffix_operand(&mut self, ty: &TypeExpr) -> String {
+        match ends_with_fn(ty, true) {
+            true => format!("({})", self.ty(ty)),
+            false => self.ty(ty),
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) -> String {
+        match &pattern.kind {
+            PatternKind::Wildcard => "_".into(),
+            PatternKind::Binding { mutable, name } => format!("{}{name}", mutability(*mutable)),
+            PatternKind::Literal(literal, text) => literal_text(literal, text),
+            PatternKind::Bool(value) => value.to_string(),
+            PatternKind::Range { start, end } => {
+                format!("{}..{}", self.pattern(start), self.pattern(end))
+            }
+            PatternKind::Path(path) => self.expr(path),
+            PatternKind::Tuple(patterns) => {
+                let comma = if patterns.len() == 1 { "," } else { "" };
+                format!("({}{comma})", self.list(patterns, Self::pattern))
+            }
+            PatternKind::Variant { path, args } => {
+                format!("{}({})", self.expr(path), self.list(args, Self::pattern))
+            }
+            PatternKind::Struct { path, fields, rest } => {
+                let mut fields: Vec<_> = fields
+                    .iter()
+                    .map(|(name, pattern)| match &pattern.kind {
+                        PatternKind::Binding {
+                            mutable: false,
+                            name: binding,
+                        } if **binding == **name => name.to_string(),
+                        _ => format!("{name}: {}", self.pattern(pattern)),
+                    })
+                    .collect();
+                if *rest {
+                    fields.push("..".into());
+                }
+                match fields.is_empty() {
+                    true => format!("{} {{}}", self.expr(path)),
+                    false => format!("{} {{ {} }}", self.expr(path), fields.join(", ")),
+                }
+            }
+            PatternKind::Or(alternatives) => {
+                let alternatives: Vec<_> = alternatives
+                    .iter()
+                    .map(|alternative| match alternative.kind {
+                        PatternKind::Or(_) => format!("({})", self.pattern(alternative)),
+                        _ => self.pattern(alternative),
+                    })
+                    .collect();
+                alternatives.join(" | ")
+            }
+            PatternKind::Is { binding, ty } => match binding {
+                Some(binding) => format!("{binding} is {}", self.ty(ty)),
+                None => format!("is {}", self.ty(ty)),
+            },
+        }
+    }
+}
+
+/// Whether `ty` ends with a function type, whose return type would take in anything
+/// after it. With `returning`, only function types with an explicit return type count.
+fn ends_with_fn(ty: &TypeExpr, returning: bool) -> bool {
+    match &ty.kind {
+        TypeKind::Fn { returns, .. } => {
+            !returning || !matches!(&returns.kind, TypeKind::Tuple(types) if types.is_empty())
+        }
+        TypeKind::Reference { ty, .. } | TypeKind::Pointer { ty, .. } => {
+            ends_with_fn(ty, returning)
+        }
+        TypeKind::Result { error, .. } => ends_with_fn(error, returning),
+        _ => false,
+    }
+}
+
+fn visibility(public: bool) -> &'static str {
+    if public {
+        "pub "
+    } else {
+        ""
+    }
+}
+
+fn mutability(mutable: bool) -> &'static str {
+    if mutable {
+        "mut "
+    } else {
+        ""
+    }
+}
+
+fn loop_label(label: &Option<Box<str>>) -> String {
+    match label {
+        Some(label) => format!("{label}: "),
+        None => String::new(),
+    }
+}
+
+/// A literal as the lexer reads it: strings and chars quoted and escaped, and numbers
+/// with the prefix of their radix, after the `-` of negative numbers in patterns, and
+/// their suffix.
+fn literal_text(literal: &Literal, text: &str) -> String {
+    let (radix, suffix) = match literal {
+        Literal::String => return quote(text, '"'),
+        Literal::Char => return quote(text, '\''),
+        Literal::Integer { radix, suffix } | Literal::Float { radix, suffix } => (radix, suffix),
+    };
+    let (sign, digits) = match text.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", text),
+    };
+    let prefix = match radix {
+        16 => "0x",
+        8 => "0o",
+        2 => "0b",
+        _ => "",
+    };
+    let suffix = suffix.as_deref().unwrap_or_default();
+    format!("{sign}{prefix}{digits}{suffix}")
+}
+
+/// `text` between two `delimiter`s, escaping what the lexer would not read back as is.
+fn quote(text: &str, delimiter: char) -> String {
+    let mut out = String::from(delimiter);
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            c if c == delimiter => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 || c == '\x7f' => out.push_str(&format!("\\x{:02x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push(delimiter);
+    out
+}
@@ -0,0 +1,221 @@
+//! Typed views of [`SyntaxNode`]s. Each wrapper checks the kind of the node once, in
+//! [`AstNode::cast`], and then names its children; a child that is missing, as in broken
+//! code, comes back as `None`.
+
+use super::{SyntaxKind, SyntaxNode, SyntaxToken};
+use crate::{BinaryOperator, UnaryOperator};
+
+pub trait AstNode: Sized {
+    fn can_cast(kind: SyntaxKind) -> bool;
+
+    fn cast(node: SyntaxNode) -> Option<Self>;
+
+    fn syntax(&self) -> &SyntaxNode;
+}
+
+/// Defines wrappers for the nodes whose kinds `can_cast` accepts.
+macro_rules! ast_node {
+    ($($(#[$doc:meta])* $name:ident($can_cast:expr);)*) => {$(
+        $(#[$doc])*
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct $name(SyntaxNode);
+
+        impl AstNode for $name {
+            fn can_cast(kind: SyntaxKind) -> bool {
+                $can_cast(kind)
+            }
+
+            fn cast(node: SyntaxNode) -> Option<Self> {
+                Self::can_cast(node.kind()).then(|| Self(node))
+            }
+
+            fn syntax(&self) -> &SyntaxNode {
+                &self.0
+            }
+        }
+    )*};
+}
+
+ast_node! {
+    Module(|kind| kind == SyntaxKind::Module);
+    Fn(|kind| kind == SyntaxKind::Fn);
+    Struct(|kind| kind == SyntaxKind::Struct);
+    Let(|kind| kind == SyntaxKind::Let);
+    Return(|kind| kind == SyntaxKind::Return);
+    Block(|kind| kind == SyntaxKind::Block);
+    If(|kind| kind == SyntaxKind::If);
+    While(|kind| kind == SyntaxKind::While);
+    For(|kind| kind == SyntaxKind::For);
+    Binary(|kind| matches!(kind, SyntaxKind::Binary(_)));
+    Unary(|kind| matches!(kind, SyntaxKind::Unary(_)));
+    Call(|kind| kind == SyntaxKind::Call);
+    Identifier(|kind| kind == SyntaxKind::Identifier);
+    Literal(|kind| kind == SyntaxKind::Literal);
+    /// Any expression, statement or item.
+    Expr(SyntaxKind::is_expr);
+    /// Any type.
+    Type(SyntaxKind::is_type);
+}
+
+/// The children of `node` that are expressions.
+fn exprs(node: &SyntaxNode) -> impl Iterator<Item = Expr> + '_ {
+    node.children()
+        .filter(|child| child.kind().is_expr())
+        .map(Expr)
+}
+
+/// The first token of `node` itself, outside its children, that is an identifier.
+fn name(node: &SyntaxNode) -> Option<SyntaxToken> {
+    node.tokens()
+        .find(|token| token.kind() == SyntaxKind::IdentifierToken)
+}
+
+fn child<T: AstNode>(node: &SyntaxNode) -> Option<T> {
+    node.children().find_map(T::cast)
+}
+
+impl Expr {
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.kind()
+    }
+}
+
+impl Module {
+    pub fn items(&self) -> impl Iterator<Item = Expr> + '_ {
+        exprs(&self.0)
+    }
+}
+
+impl Fn {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        name(&self.0)
+    }
+
+    /// The types of the parameters and, last, of the return value if it is written.
+    pub fn types(&self) -> impl Iterator<Item = Type> + '_ {
+        self.0.children().filter_map(Type::cast)
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        child(&self.0)
+    }
+}
+
+impl Struct {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        name(&self.0)
+    }
+}
+
+impl Let {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        name(&self.0)
+    }
+
+    pub fn ty(&self) -> Option<Type> {
+        child(&self.0)
+    }
+
+    pub fn value(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+}
+
+impl Return {
+    pub fn value(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+}
+
+impl Block {
+    pub fn statements(&self) -> impl Iterator<Item = Expr> + '_ {
+        exprs(&self.0)
+    }
+}
+
+impl If {
+    pub fn condition(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+
+    pub fn then_branch(&self) -> Option<Block> {
+        exprs(&self.0).nth(1).and_then(|expr| Block::cast(expr.0))
+    }
+
+    /// The block or `if` after `else`.
+    pub fn else_branch(&self) -> Option<Expr> {
+        exprs(&self.0).nth(2)
+    }
+}
+
+impl While {
+    pub fn condition(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        exprs(&self.0).nth(1).and_then(|expr| Block::cast(expr.0))
+    }
+}
+
+impl For {
+    pub fn iterable(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        exprs(&self.0).nth(1).and_then(|expr| Block::cast(expr.0))
+    }
+}
+
+impl Binary {
+    pub fn op(&self) -> BinaryOperator {
+        match self.0.kind() {
+            SyntaxKind::Binary(op) => op,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn left(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+
+    pub fn right(&self) -> Option<Expr> {
+        exprs(&self.0).nth(1)
+    }
+}
+
+impl Unary {
+    pub fn op(&self) -> UnaryOperator {
+        match self.0.kind() {
+            SyntaxKind::Unary(op) => op,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn expr(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+}
+
+impl Call {
+    pub fn callee(&self) -> Option<Expr> {
+        exprs(&self.0).next()
+    }
+
+    pub fn args(&self) -> impl Iterator<Item = Expr> + '_ {
+        exprs(&self.0).skip(1)
+    }
+}
+
+impl Identifier {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        name(&self.0)
+    }
+}
+
+impl Literal {
+    pub fn token(&self) -> Option<SyntaxToken> {
+        self.0.tokens().next()
+    }
+}
@@ -0,0 +1,167 @@
+use std::sync::Arc;
+
+use coil_lexer::{Lexer, Literal, TokenKind};
+
+use super::{GreenElement, GreenNode, GreenToken, SyntaxKind};
+use crate::{walk, Expr, Pattern, TypeExpr, Visitor};
+
+/// Builds the green tree of `text`, which was parsed into `root`. Every node of the AST
+/// with a non-empty span becomes a node of the tree, holding the tokens of its span;
+/// whitespace and comments go to the deepest node around them, so they are never the
+/// first or last child of a node other than the root. The root covers all of `text`.
+pub(crate) fn build(name: &str, text: &str, root: &Expr) -> Arc<GreenNode> {
+    let mut nodes = Nodes(vec![]);
+    nodes.visit_expr(root);
+    let mut nodes = nodes.0.split_off(1);
+    // The visitor goes to the generics of an item first, so restore source order.
+    nodes.sort_by_key(|&(_, start, end)| (start, std::cmp::Reverse(end)));
+    let mut nodes = nodes.into_iter().peekable();
+    let mut stack = vec![Frame {
+        kind: SyntaxKind::of_expr(&root.kind),
+        end: text.len() as u32,
+        children: vec![],
+    }];
+    for (kind, start, end) in tokens(name, text) {
+        while let Some(&(kind, node_start, node_end)) = nodes.peek() {
+            if node_start > start {
+                break;
+            }
+            nodes.next();
+            close(&mut stack, node_start);
+            if node_start == start && node_end <= stack.last().unwrap().end {
+                stack.push(Frame {
+                    kind,
+                    end: node_end,
+                    children: vec![],
+                });
+            }
+        }
+        close(&mut stack, start);
+        let token = GreenToken::new(kind, &text[start as usize..end as usize]);
+        let top = stack.last_mut().unwrap();
+        top.children.push(GreenElement::Token(Arc::new(token)));
+    }
+    close(&mut stack, u32::MAX);
+    let root = stack.pop().unwrap();
+    Arc::new(GreenNode::new(root.kind, root.children))
+}
+
+struct Frame {
+    kind: SyntaxKind,
+    end: u32,
+    children: Vec<GreenElement>,
+}
+
+/// Finishes the nodes that end at or before `offset`, except for the root.
+fn close(stack: &mut Vec<Frame>, offset: u32) {
+    while stack.len() > 1 && stack.last().unwrap().end <= offset {
+        let frame = stack.pop().unwrap();
+        let node = GreenNode::new(frame.kind, frame.children);
+        let top = stack.last_mut().unwrap();
+        top.children.push(GreenElement::Node(Arc::new(node)));
+    }
+}
+
+/// The nodes of an AST with their spans, in the order the visitor finds them.
+struct Nodes(Vec<(SyntaxKind, u32, u32)>);
+
+impl Nodes {
+    fn push(&mut self, kind: SyntaxKind, start: u32, end: u32) {
+        if start < end || self.0.is_empty() {
+            self.0.push((kind, start, end));
+        }
+    }
+}
+
+impl Visitor<'_> for Nodes {
+    fn visit_expr(&mut self, expr: &Expr) {
+        self.push(
+            SyntaxKind::of_expr(&expr.kind),
+            expr.span.start,
+            expr.span.end,
+        );
+        walk::expr(self, expr);
+    }
+
+    fn visit_type(&mut self, ty: &TypeExpr) {
+        self.push(SyntaxKind::of_type(&ty.kind), ty.span.start, ty.span.end);
+        walk::ty(self, ty);
+    }
+
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        let kind = SyntaxKind::of_pattern(&pattern.kind);
+        self.push(kind, pattern.span.start, pattern.span.end);
+        walk::pattern(self, pattern);
+    }
+}
+
+/// Lexes `text` again, this time keeping everything: the tokens, the comments, and the
+/// text between them as whitespace or, where the lexer failed, [`SyntaxKind::Unknown`].
+fn tokens(name: &str, text: &str) -> Vec<(SyntaxKind, u32, u32)> {
+    let mut lexer = Lexer::new(name, text);
+    let mut tokens = vec![];
+    loop {
+        match lexer.next_token() {
+            Ok(Some(token)) => {
+                let span = lexer.span();
+                tokens.push((token_kind(&token.kind), span.start, span.end));
+            }
+            Ok(None) => break,
+            Err(error) if error.span.is_some_and(|span| !span.is_empty()) => {}
+            Err(_) => break,
+        }
+    }
+    tokens.extend(
+        lexer
+            .comments()
+            .iter()
+            .map(|comment| (SyntaxKind::Comment, comment.span.start, comment.span.end)),
+    );
+    tokens.sort_by_key(|&(_, start, _)| start);
+    let mut all = vec![];
+    let mut end = 0;
+    for token in tokens {
+        if token.1 < end {
+            continue;
+        }
+        gap(text, end, token.1, &mut all);
+        all.push(token);
+        end = token.2;
+    }
+    gap(text, end, text.len() as u32, &mut all);
+    all
+}
+
+/// Splits the text between two tokens into runs of whitespace and of anything else.
+fn gap(text: &str, start: u32, end: u32, tokens: &mut Vec<(SyntaxKind, u32, u32)>) {
+    let mut run_start = start;
+    let mut run_kind = None;
+    for (i, c) in text[start as usize..end as usize].char_indices() {
+        let kind = match c.is_whitespace() {
+            true => SyntaxKind::Whitespace,
+            false => SyntaxKind::Unknown,
+        };
+        let i = start + i as u32;
+        if run_kind.is_some_and(|run| run != kind) {
+            tokens.push((run_kind.unwrap(), run_start, i));
+            run_start = i;
+        }
+        run_kind = Some(kind);
+    }
+    if let Some(kind) = run_kind {
+        tokens.push((kind, run_start, end));
+    }
+}
+
+fn token_kind(kind: &TokenKind) -> SyntaxKind {
+    match kind {
+        TokenKind::Identifier(_) => SyntaxKind::IdentifierToken,
+        TokenKind::Keyword(keyword) => SyntaxKind::Keyword(*keyword),
+        TokenKind::Literal(Literal::String, _) => SyntaxKind::StringToken,
+        TokenKind::Literal(Literal::Char, _) => SyntaxKind::CharToken,
+        TokenKind::Literal(Literal::Integer { .. }, _) => SyntaxKind::IntegerToken,
+        TokenKind::Literal(Literal::Float { .. }, _) => SyntaxKind::FloatToken,
+        TokenKind::Operator(operator) => SyntaxKind::Operator(*operator),
+        &TokenKind::Parenthesis { closing, kind } => SyntaxKind::Parenthesis { closing, kind },
+    }
+}
@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use super::SyntaxKind;
+
+/// An immutable node of the tree, which knows its length but not where it is: the same
+/// green node can appear at several offsets, or in several versions of a tree after an
+/// edit.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GreenNode {
+    kind: SyntaxKind,
+    len: u32,
+    children: Box<[GreenElement]>,
+}
+
+/// A token with its text, which is trivia like whitespace and comments too.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GreenToken {
+    kind: SyntaxKind,
+    text: Box<str>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GreenElement {
+    Node(Arc<GreenNode>),
+    Token(Arc<GreenToken>),
+}
+
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> Self {
+        let len = children.iter().map(GreenElement::len).sum();
+        Self {
+            kind,
+            len,
+            children: children.into(),
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    /// The length of the text of the node in bytes.
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+
+    /// A copy of this node with its `index`th child replaced, sharing all the others.
+    pub fn replace_child(&self, index: usize, child: GreenElement) -> Self {
+        let mut children = self.children.to_vec();
+        children[index] = child;
+        Self::new(self.kind, children)
+    }
+
+    pub fn write_text(&self, out: &mut String) {
+        for child in self.children.iter() {
+            match child {
+                GreenElement::Node(node) => node.write_text(out),
+                GreenElement::Token(token) => out.push_str(&token.text),
+            }
+        }
+    }
+}
+
+impl GreenToken {
+    pub fn new(kind: SyntaxKind, text: &str) -> Self {
+        Self {
+            kind,
+            text: text.into(),
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn len(&self) -> u32 {
+        self.text.len() as u32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+}
+
+impl GreenElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            Self::Node(node) => node.kind(),
+            Self::Token(token) => token.kind(),
+        }
+    }
+
+    pub fn len(&self) -> u32 {
+        match self {
+            Self::Node(node) => node.len(),
+            Self::Token(token) => token.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
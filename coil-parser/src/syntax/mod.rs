@@ -0,0 +1,348 @@
+//! A lossless syntax tree for tools like editors, which need every byte of a file, trivia
+//! included, and cheap updates as it is typed into.
+//!
+//! The tree has two layers. Green nodes ([`GreenNode`]) are immutable, know only their
+//! kind, length and children, and are shared between versions of a tree. Red nodes
+//! ([`SyntaxNode`]) are built on the fly over them while walking the tree and add the
+//! parent and the offset. The typed wrappers of [`ast`] give names to the children.
+//!
+//! The nodes are the expressions, types and patterns of the AST, so the tree is built by
+//! parsing as usual and then lexing the file again. An edit inside a block only reparses
+//! that block when it can, sharing the rest of the tree (see [`SyntaxTree::edit`]).
+
+pub mod ast;
+mod build;
+mod green;
+mod red;
+
+use std::{ops::Range, sync::Arc};
+
+use coil_error::Error;
+use coil_lexer::{Keyword, Lexer, Operator, Parenthesis};
+pub use green::{GreenElement, GreenNode, GreenToken};
+pub use red::{SyntaxElement, SyntaxNode, SyntaxToken};
+
+use crate::{BinaryOperator, ExprKind, Parser, PatternKind, Statement, TypeKind, UnaryOperator};
+
+/// The kinds of the nodes and tokens of a [`SyntaxTree`]. Nodes are named like in
+/// [`Dump`](crate::Dump).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    Whitespace,
+    Comment,
+    /// Text the lexer couldn't make sense of.
+    Unknown,
+    IdentifierToken,
+    StringToken,
+    CharToken,
+    IntegerToken,
+    FloatToken,
+    Keyword(Keyword),
+    Operator(Operator),
+    Parenthesis {
+        closing: bool,
+        kind: Parenthesis,
+    },
+
+    Module,
+    Use,
+    Fn,
+    Struct,
+    Enum,
+    Union,
+    Trait,
+    Impl,
+    TypeAlias,
+    AssociatedType,
+    Extern,
+    Static,
+    Let,
+    Return,
+    Break,
+    Continue,
+    Fallthrough,
+    Binary(BinaryOperator),
+    Unary(UnaryOperator),
+    Call,
+    Index,
+    Tuple,
+    Array,
+    Generic,
+    StructLiteral,
+    Block,
+    If,
+    While,
+    For,
+    Match,
+    DoWhile,
+    Closure,
+    Literal,
+    Bool,
+    Identifier,
+    Error,
+
+    PathType,
+    ReferenceType,
+    PointerType,
+    ArrayType,
+    SliceType,
+    TupleType,
+    FnType,
+    OptionalType,
+    ResultType,
+
+    WildcardPattern,
+    BindingPattern,
+    LiteralPattern,
+    BoolPattern,
+    RangePattern,
+    PathPattern,
+    TuplePattern,
+    VariantPattern,
+    StructPattern,
+    OrPattern,
+    IsPattern,
+}
+
+impl SyntaxKind {
+    pub fn is_trivia(self) -> bool {
+        matches!(self, Self::Whitespace | Self::Comment)
+    }
+
+    pub fn is_token(self) -> bool {
+        matches!(
+            self,
+            Self::Whitespace
+                | Self::Comment
+                | Self::Unknown
+                | Self::IdentifierToken
+                | Self::StringToken
+                | Self::CharToken
+                | Self::IntegerToken
+                | Self::FloatToken
+                | Self::Keyword(_)
+                | Self::Operator(_)
+                | Self::Parenthesis { .. }
+        )
+    }
+
+    pub fn is_type(self) -> bool {
+        matches!(
+            self,
+            Self::PathType
+                | Self::ReferenceType
+                | Self::PointerType
+                | Self::ArrayType
+                | Self::SliceType
+                | Self::TupleType
+                | Self::FnType
+                | Self::OptionalType
+                | Self::ResultType
+        )
+    }
+
+    pub fn is_pattern(self) -> bool {
+        matches!(
+            self,
+            Self::WildcardPattern
+                | Self::BindingPattern
+                | Self::LiteralPattern
+                | Self::BoolPattern
+                | Self::RangePattern
+                | Self::PathPattern
+                | Self::TuplePattern
+                | Self::VariantPattern
+                | Self::StructPattern
+                | Self::OrPattern
+                | Self::IsPattern
+        )
+    }
+
+    /// Whether this is a node for an [`Expr`](crate::Expr), statements and items included.
+    pub fn is_expr(self) -> bool {
+        !self.is_token() && !self.is_type() && !self.is_pattern()
+    }
+
+    pub fn of_expr(kind: &ExprKind) -> Self {
+        match kind {
+            ExprKind::Statement(statement) => match statement {
+                Statement::Module { .. } => Self::Module,
+                Statement::Use { .. } => Self::Use,
+                Statement::Fn { .. } => Self::Fn,
+                Statement::Struct { .. } => Self::Struct,
+                Statement::Enum { .. } => Self::Enum,
+                Statement::Union { .. } => Self::Union,
+                Statement::Trait { .. } => Self::Trait,
+                Statement::Impl { .. } => Self::Impl,
+                Statement::TypeAlias { .. } => Self::TypeAlias,
+                Statement::AssociatedType { .. } => Self::AssociatedType,
+                Statement::Extern { .. } => Self::Extern,
+                Statement::Static { .. } => Self::Static,
+                Statement::Let { .. } => Self::Let,
+                Statement::Return(_) => Self::Return,
+                Statement::Break { .. } => Self::Break,
+                Statement::Continue { .. } => Self::Continue,
+                Statement::Fallthrough => Self::Fallthrough,
+            },
+            ExprKind::Binary { op, .. } => Self::Binary(*op),
+            ExprKind::Unary { op, .. } => Self::Unary(*op),
+            ExprKind::Call { .. } => Self::Call,
+            ExprKind::Index { .. } => Self::Index,
+            ExprKind::Tuple(_) => Self::Tuple,
+            ExprKind::Array(_) => Self::Array,
+            ExprKind::Generic { .. } => Self::Generic,
+            ExprKind::StructLiteral { .. } => Self::StructLiteral,
+            ExprKind::Block(_) => Self::Block,
+            ExprKind::If { .. } => Self::If,
+            ExprKind::While { .. } => Self::While,
+            ExprKind::For { .. } => Self::For,
+            ExprKind::Match { .. } => Self::Match,
+            ExprKind::DoWhile { .. } => Self::DoWhile,
+            ExprKind::Closure { .. } => Self::Closure,
+            ExprKind::Literal(..) => Self::Literal,
+            ExprKind::Bool(_) => Self::Bool,
+            ExprKind::Identifier(_) => Self::Identifier,
+            ExprKind::Error => Self::Error,
+        }
+    }
+
+    pub fn of_type(kind: &TypeKind) -> Self {
+        match kind {
+            TypeKind::Path { .. } => Self::PathType,
+            TypeKind::Reference { .. } => Self::ReferenceType,
+            TypeKind::Pointer { .. } => Self::PointerType,
+            TypeKind::Array { .. } => Self::ArrayType,
+            TypeKind::Slice(_) => Self::SliceType,
+            TypeKind::Tuple(_) => Self::TupleType,
+            TypeKind::Fn { .. } => Self::FnType,
+            TypeKind::Optional(_) => Self::OptionalType,
+            TypeKind::Result { .. } => Self::ResultType,
+        }
+    }
+
+    pub fn of_pattern(kind: &PatternKind) -> Self {
+        match kind {
+            PatternKind::Wildcard => Self::WildcardPattern,
+            PatternKind::Binding { .. } => Self::BindingPattern,
+            PatternKind::Literal(..) => Self::LiteralPattern,
+            PatternKind::Bool(_) => Self::BoolPattern,
+            PatternKind::Range { .. } => Self::RangePattern,
+            PatternKind::Path(_) => Self::PathPattern,
+            PatternKind::Tuple(_) => Self::TuplePattern,
+            PatternKind::Variant { .. } => Self::VariantPattern,
+            PatternKind::Struct { .. } => Self::StructPattern,
+            PatternKind::Or(_) => Self::OrPattern,
+            PatternKind::Is { .. } => Self::IsPattern,
+        }
+    }
+}
+
+/// The syntax tree of one file, with the syntax errors of its last full parse.
+#[derive(Debug, Clone)]
+pub struct SyntaxTree {
+    name: Box<str>,
+    green: Arc<GreenNode>,
+    errors: Vec<Error>,
+}
+
+impl SyntaxTree {
+    /// Parses `source`, recovering from syntax errors like [`Parser::parse_recovering`].
+    /// `name` is the file name used in errors.
+    pub fn parse(name: &str, source: &str) -> Self {
+        let mut parser = Parser::new(Lexer::new(name, source));
+        let module = parser.parse_recovering();
+        Self {
+            name: name.into(),
+            green: build::build(name, source, &module),
+            errors: std::mem::take(&mut parser.errors),
+        }
+    }
+
+    pub fn root(&self) -> SyntaxNode {
+        SyntaxNode::new_root(self.green.clone())
+    }
+
+    pub fn green(&self) -> &Arc<GreenNode> {
+        &self.green
+    }
+
+    pub fn text(&self) -> String {
+        self.root().text()
+    }
+
+    pub fn errors(&self) -> &[Error] {
+        &self.errors
+    }
+
+    /// The tree after replacing `range` of the text with `text`. If the edit is inside a
+    /// block of a file without errors, only the block is parsed again, provided that it
+    /// still parses on its own without errors. The rest of the new tree is then shared
+    /// with this one. Any other edit parses the whole file again.
+    ///
+    /// # Panics
+    ///
+    /// If `range` is not within the text or not on character boundaries.
+    pub fn edit(&self, range: Range<u32>, text: &str) -> Self {
+        if let Some(green) = self.reparse_block(&range, text) {
+            return Self {
+                green,
+                ..self.clone()
+            };
+        }
+        let mut source = self.text();
+        source.replace_range(range.start as usize..range.end as usize, text);
+        Self::parse(&self.name, &source)
+    }
+
+    fn reparse_block(&self, range: &Range<u32>, text: &str) -> Option<Arc<GreenNode>> {
+        if !self.errors.is_empty() {
+            return None;
+        }
+        let block = self
+            .root()
+            .covering_node(range.clone())
+            .ancestors()
+            .find(|node| {
+                let r = node.range();
+                node.kind() == SyntaxKind::Block && r.start < range.start && range.end < r.end
+            })?;
+        let start = block.range().start;
+        let mut source = block.text();
+        source.replace_range(
+            (range.start - start) as usize..(range.end - start) as usize,
+            text,
+        );
+        let mut parser = Parser::new(Lexer::new(&self.name, &source));
+        parser.loop_labels = loop_labels(&block);
+        let expr = parser.parse_block().ok()?;
+        if !matches!(parser.peek(), Ok(None)) {
+            return None;
+        }
+        if !parser.errors.is_empty() {
+            return None;
+        }
+        Some(block.replace_with(build::build(&self.name, &source, &expr)))
+    }
+}
+
+/// The labels of the loops around `node` that a `break` or `continue` in it can leave,
+/// which stops at the function or closure it is in.
+fn loop_labels(node: &SyntaxNode) -> Vec<Box<str>> {
+    let mut labels: Vec<Box<str>> = node
+        .ancestors()
+        .skip(1)
+        .take_while(|node| !matches!(node.kind(), SyntaxKind::Fn | SyntaxKind::Closure))
+        .filter(|node| {
+            matches!(
+                node.kind(),
+                SyntaxKind::While | SyntaxKind::For | SyntaxKind::DoWhile
+            )
+        })
+        .filter_map(|node| {
+            let first = node.tokens().next()?;
+            (first.kind() == SyntaxKind::IdentifierToken).then(|| first.text().into())
+        })
+        .collect();
+    labels.reverse();
+    labels
+}
@@ -0,0 +1,252 @@
+use std::{fmt, ops::Range, rc::Rc, sync::Arc};
+
+use super::{GreenElement, GreenNode, GreenToken, SyntaxKind};
+
+/// A green node at a position in a tree, with a link to its parent. Red nodes are made
+/// on the fly while walking down from the root, so they are cheap to create and drop.
+#[derive(Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+struct NodeData {
+    green: Arc<GreenNode>,
+    parent: Option<SyntaxNode>,
+    /// The position of the node among the children of its parent.
+    index: usize,
+    offset: u32,
+}
+
+#[derive(Clone)]
+pub struct SyntaxToken {
+    green: Arc<GreenToken>,
+    parent: SyntaxNode,
+    index: usize,
+    offset: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxNode {
+    pub fn new_root(green: Arc<GreenNode>) -> Self {
+        Self(Rc::new(NodeData {
+            green,
+            parent: None,
+            index: 0,
+            offset: 0,
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind()
+    }
+
+    pub fn green(&self) -> &Arc<GreenNode> {
+        &self.0.green
+    }
+
+    /// Where the node is in the text of the whole tree, in bytes.
+    pub fn range(&self) -> Range<u32> {
+        self.0.offset..self.0.offset + self.0.green.len()
+    }
+
+    /// The exact source text of the node, trivia included.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        self.0.green.write_text(&mut text);
+        text
+    }
+
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        self.0.parent.clone()
+    }
+
+    /// This node, its parent, and so on up to the root.
+    pub fn ancestors(&self) -> impl Iterator<Item = SyntaxNode> {
+        std::iter::successors(Some(self.clone()), SyntaxNode::parent)
+    }
+
+    pub fn children_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> + '_ {
+        let mut offset = self.0.offset;
+        self.0
+            .green
+            .children()
+            .iter()
+            .enumerate()
+            .map(move |(index, child)| {
+                let start = offset;
+                offset += child.len();
+                match child {
+                    GreenElement::Node(green) => SyntaxElement::Node(Self(Rc::new(NodeData {
+                        green: green.clone(),
+                        parent: Some(self.clone()),
+                        index,
+                        offset: start,
+                    }))),
+                    GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                        green: green.clone(),
+                        parent: self.clone(),
+                        index,
+                        offset: start,
+                    }),
+                }
+            })
+    }
+
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode> + '_ {
+        self.children_with_tokens()
+            .filter_map(SyntaxElement::into_node)
+    }
+
+    /// The tokens directly under this node, trivia left out.
+    pub fn tokens(&self) -> impl Iterator<Item = SyntaxToken> + '_ {
+        self.children_with_tokens()
+            .filter_map(SyntaxElement::into_token)
+            .filter(|token| !token.kind().is_trivia())
+    }
+
+    /// This node and every node below it, parents before their children.
+    pub fn descendants(&self) -> impl Iterator<Item = SyntaxNode> {
+        let mut stack = vec![self.clone()];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            let children: Vec<_> = node.children().collect();
+            stack.extend(children.into_iter().rev());
+            Some(node)
+        })
+    }
+
+    /// The token that `offset` is in, if the offset is inside the node.
+    pub fn token_at_offset(&self, offset: u32) -> Option<SyntaxToken> {
+        let mut node = self.clone();
+        loop {
+            let child = node
+                .children_with_tokens()
+                .find(|child| child.range().contains(&offset))?;
+            match child {
+                SyntaxElement::Node(child) => node = child,
+                SyntaxElement::Token(token) => return Some(token),
+            }
+        }
+    }
+
+    /// The deepest node whose text includes all of `range`.
+    pub fn covering_node(&self, range: Range<u32>) -> SyntaxNode {
+        let mut node = self.clone();
+        loop {
+            let child = node.children().find(|child| {
+                let r = child.range();
+                r.start <= range.start && range.end <= r.end
+            });
+            match child {
+                Some(child) => node = child,
+                None => return node,
+            }
+        }
+    }
+
+    /// The root of a new tree where this node is replaced by `green` and everything else
+    /// is shared with the old tree.
+    pub fn replace_with(&self, green: Arc<GreenNode>) -> Arc<GreenNode> {
+        match &self.0.parent {
+            None => green,
+            Some(parent) => {
+                let new_parent = parent
+                    .green()
+                    .replace_child(self.0.index, GreenElement::Node(green));
+                parent.replace_with(Arc::new(new_parent))
+            }
+        }
+    }
+}
+
+impl PartialEq for SyntaxNode {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0.green, &other.0.green) && self.0.offset == other.0.offset
+    }
+}
+
+impl fmt::Debug for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}@{:?}", self.kind(), self.range())
+    }
+}
+
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text())
+    }
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> SyntaxKind {
+        self.green.kind()
+    }
+
+    pub fn text(&self) -> &str {
+        self.green.text()
+    }
+
+    pub fn range(&self) -> Range<u32> {
+        self.offset..self.offset + self.green.len()
+    }
+
+    pub fn parent(&self) -> SyntaxNode {
+        self.parent.clone()
+    }
+
+    /// The position of the token among the children of its parent.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl PartialEq for SyntaxToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.green, &other.green) && self.offset == other.offset
+    }
+}
+
+impl fmt::Debug for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}@{:?} {:?}", self.kind(), self.range(), self.text())
+    }
+}
+
+impl fmt::Display for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.text())
+    }
+}
+
+impl SyntaxElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            Self::Node(node) => node.kind(),
+            Self::Token(token) => token.kind(),
+        }
+    }
+
+    pub fn range(&self) -> Range<u32> {
+        match self {
+            Self::Node(node) => node.range(),
+            Self::Token(token) => token.range(),
+        }
+    }
+
+    pub fn into_node(self) -> Option<SyntaxNode> {
+        match self {
+            Self::Node(node) => Some(node),
+            Self::Token(_) => None,
+        }
+    }
+
+    pub fn into_token(self) -> Option<SyntaxToken> {
+        match self {
+            Self::Node(_) => None,
+            Self::Token(token) => Some(token),
+        }
+    }
+}
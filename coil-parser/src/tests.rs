@@ -0,0 +1,2745 @@
+use std::sync::Arc;
+
+use coil_error::{Error, ErrorCode, SourceMap};
+use coil_lexer::{Keyword, Lexer, Literal, TokenKind};
+
+use crate::syntax::{ast, ast::AstNode, SyntaxKind, SyntaxTree};
+use crate::{
+    fold, load, walk, walk_mut, ArgName, Associativity, BinaryOperator, ClosureParam, Expr,
+    ExprKind, Field, Fields, Fold, GenericParam, Generics, Ident, ImportTarget, ImportTree,
+    LabelMismatch, MatchArm, ModuleId, Parser, Pattern, PatternKind, Program, Signature, Statement,
+    ToDump, TypeExpr, TypeKind, UnaryOperator, Variant, Visitor, VisitorMut, WherePredicate,
+    AMBIGUOUS_LINE_BREAK, AMBIGUOUS_MODULE_FILE, CHAINED_OPERATOR, DUPLICATE_NAME,
+    EXPECTED_EXPRESSION, EXPECTED_IDENTIFIER, EXPECTED_ITEM, EXPECTED_LOOP, EXPECTED_PATTERN,
+    EXPECTED_TYPE, EXTERN_FN_BODY, FALLTHROUGH_IN_LAST_ARM, FALLTHROUGH_OUTSIDE_MATCH,
+    IMPORT_CYCLE, MISSING_FN_BODY, MISSING_STATIC_VALUE, MODULE_NOT_FOUND, PRIVATE_IMPORT,
+    PUB_NOT_ALLOWED, STRUCT_LITERAL_IN_CONDITION, UNEXPECTED, UNEXPECTED_EOF, UNKNOWN_LOOP_LABEL,
+    UNRESOLVED_IMPORT,
+};
+
+fn quick_parse(source: &str) -> Result<Vec<Expr>, Error> {
+    let mut parser = Parser::new(Lexer::new("<inline>", source));
+    match parser.parse()? {
+        Expr {
+            kind: ExprKind::Statement(Statement::Module { children, .. }),
+            ..
+        } => Ok(children),
+        module => panic!("expected a module but got {module:?}"),
+    }
+}
+
+fn quick_expr(source: &str) -> Expr {
+    let mut parser = Parser::new(Lexer::new("<inline>", source));
+    parser
+        .parse_expr()
+        .expect("expected source to be fully parsed")
+}
+
+/// Renders the tokens the parser sees, inserted semicolons included, separated by spaces.
+fn quick_tokens(source: &str) -> (String, Vec<Error>) {
+    let mut parser = Parser::new(Lexer::new("<inline>", source));
+    let mut tokens = vec![];
+    while let Some(token) = parser
+        .get_token()
+        .expect("expected source to be fully lexed")
+    {
+        tokens.push(match token.kind {
+            TokenKind::Identifier(name) | TokenKind::Literal(_, name) => name.to_string(),
+            TokenKind::Keyword(x) => x.as_str().to_string(),
+            TokenKind::Operator(x) => x.as_str().to_string(),
+            TokenKind::Parenthesis { closing, kind } => kind.as_str(closing).to_string(),
+        });
+    }
+    (tokens.join(" "), parser.warnings)
+}
+
+fn node(kind: ExprKind) -> Expr {
+    kind.into()
+}
+
+fn ident(name: &str) -> Expr {
+    node(ExprKind::Identifier(name.into()))
+}
+
+fn int(value: &str) -> Expr {
+    node(ExprKind::Literal(
+        Literal::Integer { radix: 10, suffix: None },
+        value.into(),
+    ))
+}
+
+fn binary(op: BinaryOperator, left: Expr, right: Expr) -> Expr {
+    node(ExprKind::Binary {
+        op,
+        left: Box::new(left),
+        right: Box::new(right),
+    })
+}
+
+fn unary(op: UnaryOperator, expr: Expr) -> Expr {
+    node(ExprKind::Unary {
+        op,
+        expr: Box::new(expr),
+    })
+}
+
+fn call(callee: Expr, args: Vec<Expr>) -> Expr {
+    node(ExprKind::Call {
+        callee: Box::new(callee),
+        args: args.into_iter().map(|arg| (None, arg)).collect(),
+    })
+}
+
+fn statement(statement: Statement) -> Expr {
+    node(ExprKind::Statement(statement))
+}
+
+#[test]
+fn test_module_name() {
+    let mut parser = Parser::new(Lexer::new("src/dir/main.coil", ""));
+    let Expr {
+        kind: ExprKind::Statement(Statement::Module { name, children, .. }),
+        ..
+    } = parser.parse().unwrap()
+    else {
+        panic!("expected a module");
+    };
+    assert_eq!(*name, ident("main"));
+    assert!(children.is_empty());
+}
+
+#[test]
+fn test_expr_literals() {
+    assert_eq!(quick_expr("42"), int("42"));
+    assert_eq!(
+        quick_expr("\"hi\""),
+        node(ExprKind::Literal(Literal::String, "hi".into()))
+    );
+    assert_eq!(quick_expr("true"), node(ExprKind::Bool(true)));
+    assert_eq!(quick_expr("false"), node(ExprKind::Bool(false)));
+    assert_eq!(quick_expr("Self"), ident("Self"));
+    assert_eq!(quick_expr("()"), node(ExprKind::unit()));
+    assert_eq!(quick_expr("(1)"), int("1"));
+    assert_eq!(quick_expr("(1,)"), node(ExprKind::Tuple(vec![int("1")])));
+    assert_eq!(
+        quick_expr("(1, a)"),
+        node(ExprKind::Tuple(vec![int("1"), ident("a")]))
+    );
+    assert_eq!(
+        quick_expr("[1, 2, ]"),
+        node(ExprKind::Array(vec![int("1"), int("2")]))
+    );
+}
+
+#[test]
+fn test_expr_binary() {
+    use BinaryOperator::*;
+    assert_eq!(
+        quick_expr("1 + 2 * 3 - 4"),
+        binary(
+            Sub,
+            binary(Add, int("1"), binary(Mul, int("2"), int("3"))),
+            int("4")
+        )
+    );
+    assert_eq!(
+        quick_expr("a || b && c == d"),
+        binary(
+            Or,
+            ident("a"),
+            binary(And, ident("b"), binary(Eq, ident("c"), ident("d")))
+        )
+    );
+    assert_eq!(
+        quick_expr("a = b += c"),
+        binary(
+            Assign,
+            ident("a"),
+            binary(AddAssign, ident("b"), ident("c"))
+        )
+    );
+    assert_eq!(
+        quick_expr("(1 + 2) * 3"),
+        binary(Mul, binary(Add, int("1"), int("2")), int("3"))
+    );
+    assert_eq!(
+        quick_expr("a | b ^ c & d << 1"),
+        binary(
+            BitOr,
+            ident("a"),
+            binary(
+                BitXor,
+                ident("b"),
+                binary(
+                    BitAnd,
+                    ident("c"),
+                    binary(BitShiftLeft, ident("d"), int("1"))
+                )
+            )
+        )
+    );
+}
+
+#[test]
+fn test_expr_unary_postfix() {
+    assert_eq!(
+        quick_expr("-!~a"),
+        unary(
+            UnaryOperator::Negative,
+            unary(UnaryOperator::Not, unary(UnaryOperator::BitNot, ident("a")))
+        )
+    );
+    assert_eq!(
+        quick_expr("a.b(1, 2)[0].0"),
+        binary(
+            BinaryOperator::Dot,
+            node(ExprKind::Index {
+                expr: Box::new(call(
+                    binary(BinaryOperator::Dot, ident("a"), ident("b")),
+                    vec![int("1"), int("2")]
+                )),
+                index: Box::new(int("0")),
+            }),
+            int("0")
+        )
+    );
+    assert_eq!(quick_expr("f()"), call(ident("f"), vec![]));
+}
+
+#[test]
+fn test_fn() {
+    let items =
+        quick_parse("pub fn add(a: Int, b: Int) -> Int {\n    return a + b\n}\nfn main() {}")
+            .unwrap();
+    assert_eq!(
+        items,
+        [
+            statement(Statement::Fn {
+                public: true,
+                name: "add".into(),
+                generics: Generics::default(),
+                signature: crate::Signature {
+                    returns: Box::new(ty("Int")),
+                    named_args: vec![
+                        (ArgName::Single("a".into()), ty("Int")),
+                        (ArgName::Single("b".into()), ty("Int")),
+                    ],
+                },
+                body: Some(Box::new(node(ExprKind::Block(vec![statement(
+                    Statement::Return(Some(Box::new(binary(
+                        BinaryOperator::Add,
+                        ident("a"),
+                        ident("b")
+                    ))))
+                )])))),
+            }),
+            statement(Statement::Fn {
+                public: false,
+                name: "main".into(),
+                generics: Generics::default(),
+                signature: crate::Signature {
+                    returns: Box::new(type_node(TypeKind::unit())),
+                    named_args: vec![],
+                },
+                body: Some(Box::new(node(ExprKind::Block(vec![])))),
+            }),
+        ]
+    );
+}
+
+#[test]
+fn test_statements() {
+    let items = quick_parse("fn f() {\n    let mut a: Int = 1\n    let b\n    a += 2; f()\n    break\n    continue\n    return\n}").unwrap();
+    let [Expr {
+        kind: ExprKind::Statement(Statement::Fn {
+            body: Some(body), ..
+        }),
+        ..
+    }] = &items[..]
+    else {
+        panic!("expected a function but got {items:?}");
+    };
+    assert_eq!(
+        **body,
+        node(ExprKind::Block(vec![
+            statement(Statement::Let {
+                mutable: true,
+                name: "a".into(),
+                ty: Some(Box::new(ty("Int"))),
+                value: Some(Box::new(int("1"))),
+            }),
+            statement(Statement::Let {
+                mutable: false,
+                name: "b".into(),
+                ty: None,
+                value: None,
+            }),
+            binary(BinaryOperator::AddAssign, ident("a"), int("2")),
+            call(ident("f"), vec![]),
+            statement(Statement::Break {
+                label: None,
+                value: None,
+            }),
+            statement(Statement::Continue { label: None }),
+            statement(Statement::Return(None)),
+        ]))
+    );
+}
+
+fn field(public: bool, name: &str, ty: TypeExpr) -> Field {
+    Field {
+        public,
+        name: name.into(),
+        ty,
+    }
+}
+
+#[test]
+fn test_struct_union_enum() {
+    let fields = vec![
+        field(true, "x", ty("Int")),
+        field(false, "y", ty("math.Float")),
+    ];
+    let items = quick_parse(
+        "pub struct Point { pub x: Int, y: math.Float }\nunion Bits {\n    pub x: Int\n    y: math.Float\n}\nenum Color { Red, Green,\n    Blue\n}",
+    )
+    .unwrap();
+    let unit = |name: &str| Variant {
+        name: name.into(),
+        fields: Fields::Unit,
+        discriminant: None,
+    };
+    assert_eq!(
+        items,
+        [
+            statement(Statement::Struct {
+                public: true,
+                name: "Point".into(),
+                generics: Generics::default(),
+                fields: Fields::Named(fields.clone()),
+            }),
+            statement(Statement::Union {
+                public: false,
+                name: "Bits".into(),
+                generics: Generics::default(),
+                fields,
+            }),
+            statement(Statement::Enum {
+                public: false,
+                name: "Color".into(),
+                generics: Generics::default(),
+                variants: vec![unit("Red"), unit("Green"), unit("Blue")],
+            }),
+        ]
+    );
+}
+
+#[test]
+fn test_tuple_and_unit_structs() {
+    let items =
+        quick_parse("struct Pair(pub Int, (Int, Float),)\npub struct Marker\nstruct Empty {}")
+            .unwrap();
+    assert_eq!(
+        items,
+        [
+            statement(Statement::Struct {
+                public: false,
+                name: "Pair".into(),
+                generics: Generics::default(),
+                fields: Fields::Tuple(vec![
+                    field(true, "0", ty("Int")),
+                    field(
+                        false,
+                        "1",
+                        type_node(TypeKind::Tuple(vec![ty("Int"), ty("Float")]))
+                    ),
+                ]),
+            }),
+            statement(Statement::Struct {
+                public: true,
+                name: "Marker".into(),
+                generics: Generics::default(),
+                fields: Fields::Unit,
+            }),
+            statement(Statement::Struct {
+                public: false,
+                name: "Empty".into(),
+                generics: Generics::default(),
+                fields: Fields::Named(vec![]),
+            }),
+        ]
+    );
+    let error = quick_parse("union U(Int)").unwrap_err();
+    assert_eq!(error.code, UNEXPECTED);
+}
+
+#[test]
+fn test_enum_variants() {
+    let items = quick_parse(
+        "enum Shape {\n    Circle(Float)\n    Rect { pub w: Float, h: Float }\n    Empty = 1 << 4,\n    Point(Int) = -1\n}",
+    )
+    .unwrap();
+    assert_eq!(
+        items,
+        [statement(Statement::Enum {
+            public: false,
+            name: "Shape".into(),
+            generics: Generics::default(),
+            variants: vec![
+                Variant {
+                    name: "Circle".into(),
+                    fields: Fields::Tuple(vec![field(false, "0", ty("Float"))]),
+                    discriminant: None,
+                },
+                Variant {
+                    name: "Rect".into(),
+                    fields: Fields::Named(vec![
+                        field(true, "w", ty("Float")),
+                        field(false, "h", ty("Float")),
+                    ]),
+                    discriminant: None,
+                },
+                Variant {
+                    name: "Empty".into(),
+                    fields: Fields::Unit,
+                    discriminant: Some(Box::new(binary(
+                        BinaryOperator::BitShiftLeft,
+                        int("1"),
+                        int("4")
+                    ))),
+                },
+                Variant {
+                    name: "Point".into(),
+                    fields: Fields::Tuple(vec![field(false, "0", ty("Int"))]),
+                    discriminant: Some(Box::new(unary(UnaryOperator::Negative, int("1")))),
+                },
+            ],
+        })]
+    );
+    let error = quick_parse("enum E { A B }").unwrap_err();
+    assert_eq!(error.code, UNEXPECTED);
+}
+
+#[test]
+fn test_struct_literals() {
+    let point = |fields: Vec<(&str, Expr)>| {
+        node(ExprKind::StructLiteral {
+            path: Box::new(ident("Point")),
+            fields: fields
+                .into_iter()
+                .map(|(name, value)| (name.into(), value))
+                .collect(),
+        })
+    };
+    assert_eq!(
+        quick_expr("Point { x: 1, y }"),
+        point(vec![("x", int("1")), ("y", ident("y"))])
+    );
+    assert_eq!(
+        quick_expr("geo.Point {\n    x: a + 1\n    y: f(Point {})\n}.x"),
+        binary(
+            BinaryOperator::Dot,
+            node(ExprKind::StructLiteral {
+                path: Box::new(binary(BinaryOperator::Dot, ident("geo"), ident("Point"))),
+                fields: vec![
+                    (
+                        "x".into(),
+                        binary(BinaryOperator::Add, ident("a"), int("1"))
+                    ),
+                    ("y".into(), call(ident("f"), vec![point(vec![])])),
+                ],
+            }),
+            ident("x")
+        )
+    );
+
+    // After `if`, `while`, `for` and `match`, a `{` starts the body...
+    assert_eq!(
+        quick_expr("if a == Point { b }"),
+        node(ExprKind::If {
+            unless: false,
+            condition: Box::new(binary(BinaryOperator::Eq, ident("a"), ident("Point"))),
+            then: Box::new(block(vec![ident("b")])),
+            otherwise: None,
+        })
+    );
+    assert_eq!(
+        quick_expr("while p { }"),
+        node(ExprKind::While {
+            label: None,
+            condition: Box::new(ident("p")),
+            body: Box::new(block(vec![])),
+        })
+    );
+    // ...unless the literal is in brackets, and struct literals are fine in the body.
+    assert_eq!(
+        quick_expr("if a == (Point {}) { Point {} }"),
+        node(ExprKind::If {
+            unless: false,
+            condition: Box::new(binary(BinaryOperator::Eq, ident("a"), point(vec![]))),
+            then: Box::new(block(vec![point(vec![])])),
+            otherwise: None,
+        })
+    );
+    assert_eq!(
+        quick_expr("for p in f(Point {}) { }"),
+        node(ExprKind::For {
+            label: None,
+            binding: "p".into(),
+            iterable: Box::new(call(ident("f"), vec![point(vec![])])),
+            body: Box::new(block(vec![])),
+        })
+    );
+    assert!(matches!(
+        quick_expr("match p { _ => Point { x } }").kind,
+        ExprKind::Match { .. }
+    ));
+    let error = quick_parse("fn f() { Point { 1 } }").unwrap_err();
+    assert_eq!(error.code, EXPECTED_IDENTIFIER);
+}
+
+#[test]
+fn test_struct_literal_in_condition() {
+    // The block after the condition starts at the `{` of the literal, so `x:` would be
+    // read as a loop label.
+    let error = quick_parse("fn f() {\n    if p == Point { x: 1 } {}\n}").unwrap_err();
+    assert_eq!((error.code, error.line), (STRUCT_LITERAL_IN_CONDITION, 2));
+    assert_eq!(
+        error.message.as_ref(),
+        "a struct literal of 'Point' cannot be written directly in a condition"
+    );
+    assert_eq!(
+        &error.notes,
+        &["wrap it in parentheses, as in '(Point { ... })', since a '{' after the condition starts the block".into()]
+    );
+    assert_eq!(error.span.map(|s| (s.start, s.end)), Some((21, 31)));
+    for source in [
+        "fn f() { while !geo.Point { x: 1, y: 2 } {} }",
+        "fn f() { for p in ps == Point { x: 1 } {} }",
+    ] {
+        let error = quick_parse(source).unwrap_err();
+        assert_eq!(error.code, STRUCT_LITERAL_IN_CONDITION, "{source}");
+    }
+    // A loop label starting the body is still fine, and so is a label elsewhere.
+    assert!(quick_parse("fn f() { if a == B { l: while c {} } }").is_ok());
+    let error = quick_parse("fn f() { if a == B { c }\n x: 1 }").unwrap_err();
+    assert_eq!(error.code, EXPECTED_LOOP);
+}
+
+#[test]
+fn test_type_alias() {
+    assert_eq!(
+        quick_parse("pub type Pair = (Int, Int)\ntype Id = std.Int").unwrap(),
+        [
+            statement(Statement::TypeAlias {
+                public: true,
+                name: "Pair".into(),
+                generics: Generics::default(),
+                ty: Box::new(type_node(TypeKind::Tuple(vec![ty("Int"), ty("Int")]))),
+            }),
+            statement(Statement::TypeAlias {
+                public: false,
+                name: "Id".into(),
+                generics: Generics::default(),
+                ty: Box::new(ty("std.Int")),
+            }),
+        ]
+    );
+}
+
+#[test]
+fn test_trait_impl() {
+    let items = quick_parse(
+        "trait Shape {\n    fn area(self: Self) -> Float\n    fn name() -> String { return \"shape\" }\n}\nimpl Shape for Square {\n    fn area(self: Self) -> Float { self.side * self.side }\n}\nimpl Square {}",
+    )
+    .unwrap();
+    let [Expr {
+        kind:
+            ExprKind::Statement(Statement::Trait {
+                name,
+                items: methods,
+                ..
+            }),
+        ..
+    }, Expr {
+        kind:
+            ExprKind::Statement(Statement::Impl {
+                trait_: Some(trait_),
+                target,
+                items: impl_items,
+                ..
+            }),
+        ..
+    }, Expr {
+        kind:
+            ExprKind::Statement(Statement::Impl {
+                trait_: None,
+                target: inherent,
+                items: inherent_items,
+                ..
+            }),
+        ..
+    }] = &items[..]
+    else {
+        panic!("unexpected items: {items:?}");
+    };
+    assert_eq!(&**name, "Shape");
+    assert!(matches!(
+        &methods[..],
+        [
+            Expr {
+                kind: ExprKind::Statement(Statement::Fn { body: None, .. }),
+                ..
+            },
+            Expr {
+                kind: ExprKind::Statement(Statement::Fn { body: Some(_), .. }),
+                ..
+            },
+        ]
+    ));
+    assert_eq!(**trait_, ty("Shape"));
+    assert_eq!(**target, ty("Square"));
+    assert_eq!(impl_items.len(), 1);
+    assert_eq!(**inherent, ty("Square"));
+    assert!(inherent_items.is_empty());
+}
+
+fn type_node(kind: TypeKind) -> TypeExpr {
+    kind.into()
+}
+
+/// A path type such as `Int` or `std.Int`, without type arguments.
+fn ty(path: &str) -> TypeExpr {
+    generic_ty(path, vec![])
+}
+
+fn generic_ty(path: &str, args: Vec<TypeExpr>) -> TypeExpr {
+    let mut segments = path.split('.').map(ident);
+    let first = segments.next().unwrap();
+    let path = segments.fold(first, |path, segment| {
+        binary(BinaryOperator::Dot, path, segment)
+    });
+    type_node(TypeKind::Path {
+        path: Box::new(path),
+        args,
+    })
+}
+
+fn generic(path: Expr, args: Vec<TypeExpr>) -> Expr {
+    node(ExprKind::Generic {
+        path: Box::new(path),
+        args,
+    })
+}
+
+#[test]
+fn test_generic_items() {
+    let items = quick_parse(
+        "fn map<T, U: Show + Eq>(x: T) -> Vec<U>\nwhere T: Into<U>, Vec<T>: Show {}\nstruct Pair<A, B>(A, B) where A: Eq\nenum Option<T> { Some(T), None }",
+    )
+    .unwrap();
+    let [Expr {
+        kind:
+            ExprKind::Statement(Statement::Fn {
+                generics,
+                signature,
+                ..
+            }),
+        ..
+    }, Expr {
+        kind:
+            ExprKind::Statement(Statement::Struct {
+                generics: pair_generics,
+                ..
+            }),
+        ..
+    }, Expr {
+        kind:
+            ExprKind::Statement(Statement::Enum {
+                generics: option_generics,
+                ..
+            }),
+        ..
+    }] = &items[..]
+    else {
+        panic!("unexpected items: {items:?}");
+    };
+    let param = |name: &str, bounds: Vec<TypeExpr>| GenericParam {
+        name: name.into(),
+        bounds,
+    };
+    assert_eq!(
+        *generics,
+        Generics {
+            params: vec![param("T", vec![]), param("U", vec![ty("Show"), ty("Eq")]),],
+            where_clause: vec![
+                WherePredicate {
+                    ty: ty("T"),
+                    bounds: vec![generic_ty("Into", vec![ty("U")])],
+                },
+                WherePredicate {
+                    ty: generic_ty("Vec", vec![ty("T")]),
+                    bounds: vec![ty("Show")],
+                },
+            ],
+        }
+    );
+    assert_eq!(*signature.returns, generic_ty("Vec", vec![ty("U")]));
+    assert_eq!(
+        pair_generics.params,
+        [param("A", vec![]), param("B", vec![])]
+    );
+    assert_eq!(pair_generics.where_clause.len(), 1);
+    assert_eq!(option_generics.params, [param("T", vec![])]);
+}
+
+/// Parses `source` as the type of a type alias.
+fn quick_type(source: &str) -> TypeExpr {
+    let items = quick_parse(&format!("type T = {source}")).unwrap();
+    let [Expr {
+        kind: ExprKind::Statement(Statement::TypeAlias { ty, .. }),
+        ..
+    }] = &items[..]
+    else {
+        panic!("expected a type alias but got {items:?}");
+    };
+    (**ty).clone()
+}
+
+#[test]
+fn test_types() {
+    let reference = |mutable, ty: TypeExpr| {
+        type_node(TypeKind::Reference {
+            mutable,
+            ty: Box::new(ty),
+        })
+    };
+    let optional = |ty: TypeExpr| type_node(TypeKind::Optional(Box::new(ty)));
+    assert_eq!(quick_type("&Int"), reference(false, ty("Int")));
+    assert_eq!(
+        quick_type("&&mut Int"),
+        reference(false, reference(true, ty("Int")))
+    );
+    assert_eq!(
+        quick_type("*mut Self.Item"),
+        type_node(TypeKind::Pointer {
+            mutable: true,
+            ty: Box::new(ty("Self.Item")),
+        })
+    );
+    assert_eq!(
+        quick_type("[[Int; 4]]"),
+        type_node(TypeKind::Slice(Box::new(type_node(TypeKind::Array {
+            ty: Box::new(ty("Int")),
+            len: Box::new(int("4")),
+        }))))
+    );
+    assert_eq!(
+        quick_type("fn(Int, &Str) -> Bool"),
+        type_node(TypeKind::Fn {
+            params: vec![ty("Int"), reference(false, ty("Str"))],
+            returns: Box::new(ty("Bool")),
+        })
+    );
+    assert_eq!(
+        quick_type("fn()"),
+        type_node(TypeKind::Fn {
+            params: vec![],
+            returns: Box::new(type_node(TypeKind::unit())),
+        })
+    );
+    // Suffixes apply to everything before them
+    assert_eq!(quick_type("&Int?"), optional(reference(false, ty("Int"))));
+    assert_eq!(
+        quick_type("Vec<Int>!io.Error?"),
+        optional(type_node(TypeKind::Result {
+            ok: Box::new(generic_ty("Vec", vec![ty("Int")])),
+            error: Box::new(ty("io.Error")),
+        }))
+    );
+    assert_eq!(
+        quick_type("(Int, (Int)?)"),
+        type_node(TypeKind::Tuple(vec![ty("Int"), optional(ty("Int"))]))
+    );
+
+    // Types in closures, patterns and statics
+    let [statement] = &quick_body("fn f() { \\x: &[Int] -> x }").unwrap()[..] else {
+        panic!("expected one statement");
+    };
+    let ExprKind::Closure { params, .. } = &statement.kind else {
+        panic!("expected a closure but got {statement:?}");
+    };
+    assert_eq!(
+        params[0].ty,
+        Some(reference(
+            false,
+            type_node(TypeKind::Slice(Box::new(ty("Int"))))
+        ))
+    );
+    assert_eq!(
+        quick_patterns("is Int? => a").unwrap(),
+        [pattern(PatternKind::Is {
+            binding: None,
+            ty: Box::new(optional(ty("Int"))),
+        })]
+    );
+
+    let error = quick_parse("type T = [Int; 4").unwrap_err();
+    assert_eq!(error.code, UNEXPECTED_EOF);
+    let error = quick_parse("static X: + = 1").unwrap_err();
+    assert_eq!(error.code, EXPECTED_TYPE);
+}
+
+#[test]
+fn test_nested_generics() {
+    let vec = |arg| generic_ty("Vec", vec![arg]);
+    let [statement] = &quick_body("fn f() { let x: Map<Int, Vec<Vec<Int>>> = y }").unwrap()[..]
+    else {
+        panic!("expected one statement");
+    };
+    let ExprKind::Statement(Statement::Let { ty: Some(map), .. }) = &statement.kind else {
+        panic!("expected a let but got {statement:?}");
+    };
+    assert_eq!(
+        **map,
+        generic_ty("Map", vec![ty("Int"), vec(vec(ty("Int")))])
+    );
+    // `>>=` is split into `>` and `=`, and the type still ends the line.
+    let body =
+        quick_body("fn f() {\n    let x: Vec<Vec<Int>>= y\n    let z: Vec<Int>\n    z\n}").unwrap();
+    assert_eq!(body.len(), 3);
+    assert_eq!(
+        quick_expr("a < b"),
+        binary(BinaryOperator::Lesser, ident("a"), ident("b"))
+    );
+    assert_eq!(
+        quick_expr("a.size.<Int>() >> 2"),
+        binary(
+            BinaryOperator::BitShiftRight,
+            call(
+                generic(
+                    binary(BinaryOperator::Dot, ident("a"), ident("size")),
+                    vec![ty("Int")]
+                ),
+                vec![]
+            ),
+            int("2")
+        )
+    );
+}
+
+#[test]
+fn test_associated_items() {
+    let items = quick_parse(
+        "trait Iterator<T>: Clone + Show {\n    type Item: Show = Int\n    static EMPTY: Self\n    fn next(self: Self) -> Item\n}\nimpl<T: Eq> Iterator<T> for Range<T> where T: Show {\n    type Item = T\n    static EMPTY: Self = Range.<T> { start: 0, end: 0 }\n    pub fn next(self: Self) -> Item { self.start }\n}",
+    )
+    .unwrap();
+    let [Expr {
+        kind:
+            ExprKind::Statement(Statement::Trait {
+                generics,
+                supertraits,
+                items: trait_items,
+                ..
+            }),
+        ..
+    }, Expr {
+        kind:
+            ExprKind::Statement(Statement::Impl {
+                generics: impl_generics,
+                trait_: Some(trait_),
+                target,
+                items: impl_items,
+            }),
+        ..
+    }] = &items[..]
+    else {
+        panic!("unexpected items: {items:?}");
+    };
+    assert_eq!(generics.params.len(), 1);
+    assert_eq!(*supertraits, [ty("Clone"), ty("Show")]);
+    assert_eq!(
+        trait_items[0],
+        statement(Statement::AssociatedType {
+            name: "Item".into(),
+            bounds: vec![ty("Show")],
+            default: Some(Box::new(ty("Int"))),
+        })
+    );
+    assert!(matches!(
+        &trait_items[1..],
+        [
+            Expr {
+                kind: ExprKind::Statement(Statement::Static { value: None, .. }),
+                ..
+            },
+            Expr {
+                kind: ExprKind::Statement(Statement::Fn { body: None, .. }),
+                ..
+            },
+        ]
+    ));
+    assert_eq!(impl_generics.params[0].bounds, [ty("Eq")]);
+    assert_eq!(impl_generics.where_clause.len(), 1);
+    assert_eq!(**trait_, generic_ty("Iterator", vec![ty("T")]));
+    assert_eq!(**target, generic_ty("Range", vec![ty("T")]));
+    assert!(matches!(
+        &impl_items[..],
+        [
+            Expr {
+                kind: ExprKind::Statement(Statement::TypeAlias { .. }),
+                ..
+            },
+            Expr {
+                kind: ExprKind::Statement(Statement::Static { value: Some(_), .. }),
+                ..
+            },
+            Expr {
+                kind: ExprKind::Statement(Statement::Fn { public: true, .. }),
+                ..
+            },
+        ]
+    ));
+
+    let error = quick_parse("static COUNT: Int").unwrap_err();
+    assert_eq!(error.code, MISSING_STATIC_VALUE);
+    let error = quick_parse("impl Show for Int { static NAME: String }").unwrap_err();
+    assert_eq!(error.code, MISSING_STATIC_VALUE);
+    let error = quick_parse("fn f<T(x: T) {}").unwrap_err();
+    assert_eq!(error.code, UNEXPECTED);
+}
+
+#[test]
+fn test_other_items() {
+    let items = quick_parse(
+        "type Pair = (Int, Float)\nmodule math\nmodule inline { fn f() {} }\nimport std.io\nextern fn puts(s: String) -> Int\nextern \"C\" {\n    fn abs(x: Int) -> Int\n}\nstatic mut COUNT: Int = 0",
+    )
+    .unwrap();
+    assert_eq!(
+        items[0],
+        statement(Statement::TypeAlias {
+            public: false,
+            name: "Pair".into(),
+            generics: Generics::default(),
+            ty: Box::new(type_node(TypeKind::Tuple(vec![ty("Int"), ty("Float")]))),
+        })
+    );
+    assert_eq!(
+        items[1],
+        statement(Statement::Module {
+            name: Box::new(ident("math")),
+            inline: false,
+            children: vec![],
+        })
+    );
+    assert!(
+        matches!(&items[2], Expr { kind: ExprKind::Statement(Statement::Module { inline: true, children, .. }), .. } if children.len() == 1)
+    );
+    assert_eq!(
+        items[3],
+        statement(Statement::Use {
+            tree: ImportTree::Path {
+                path: Box::new(binary(BinaryOperator::Dot, ident("std"), ident("io"))),
+                alias: None,
+            },
+        })
+    );
+    assert!(
+        matches!(&items[4], Expr { kind: ExprKind::Statement(Statement::Extern { abi: None, items }), .. } if items.len() == 1)
+    );
+    assert!(
+        matches!(&items[5], Expr { kind: ExprKind::Statement(Statement::Extern { abi: Some(abi), items }), .. } if abi.as_ref() == "C" && items.len() == 1)
+    );
+    assert_eq!(
+        items[6],
+        statement(Statement::Static {
+            public: false,
+            mutable: true,
+            name: "COUNT".into(),
+            ty: Box::new(ty("Int")),
+            value: Some(Box::new(int("0"))),
+        })
+    );
+}
+
+#[test]
+fn test_errors() {
+    let err = quick_parse("let a = 1").expect_err("expected to get an error");
+    assert_eq!(err.code, EXPECTED_ITEM);
+    assert_eq!(err.message.as_ref(), "expected an item but found 'let'");
+    assert_eq!(err.file.as_ref(), "<inline>");
+    assert_eq!(err.line, 1);
+
+    let err = quick_parse("fn f() {\n    1 +\n}").expect_err("expected to get an error");
+    assert_eq!(err.code, EXPECTED_EXPRESSION);
+    assert_eq!(err.message.as_ref(), "expected an expression but found '}'");
+    assert_eq!(err.line, 3);
+
+    let err = quick_parse("fn (").expect_err("expected to get an error");
+    assert_eq!(err.code, EXPECTED_IDENTIFIER);
+    assert_eq!(err.message.as_ref(), "expected an identifier but found '('");
+
+    let err = quick_parse("struct A { x: 1 }").expect_err("expected to get an error");
+    assert_eq!(err.code, EXPECTED_TYPE);
+    assert_eq!(err.message.as_ref(), "expected a type but found number '1'");
+
+    let err = quick_parse("fn f(a: Int").expect_err("expected to get an error");
+    assert_eq!(err.code, UNEXPECTED_EOF);
+    assert_eq!(err.message.as_ref(), "expected ')' but found end of file");
+
+    let err = quick_parse("fn f() { a b }").expect_err("expected to get an error");
+    assert_eq!(err.code, UNEXPECTED);
+    assert_eq!(
+        err.message.as_ref(),
+        "expected ';' or a new line but found identifier 'b'"
+    );
+
+    let err = quick_parse("fn f() -> Int").expect_err("expected to get an error");
+    assert_eq!(err.code, MISSING_FN_BODY);
+    assert_eq!(err.message.as_ref(), "function 'f' has no body");
+
+    let err = quick_parse("extern fn f() {}").expect_err("expected to get an error");
+    assert_eq!(err.code, EXTERN_FN_BODY);
+
+    let err = quick_parse("pub impl A {}").expect_err("expected to get an error");
+    assert_eq!(err.code, PUB_NOT_ALLOWED);
+    assert_eq!(err.message.as_ref(), "'pub' is not allowed here");
+}
+
+#[test]
+fn test_expr_precedence_table() {
+    use BinaryOperator::*;
+    assert!(Assign.precedence() < Range.precedence());
+    assert!(Range.precedence() < Or.precedence());
+    assert!(Lesser.precedence() < BitOr.precedence());
+    assert!(Mul.precedence() < UnaryOperator::Negative.precedence());
+    assert!(UnaryOperator::Negative.precedence() < UnaryOperator::Try.precedence());
+    assert_eq!(BitShiftLeftAssign.associativity(), Associativity::Right);
+    assert_eq!(Eq.associativity(), Associativity::None);
+    assert_eq!(Range.associativity(), Associativity::None);
+    assert_eq!(Sub.associativity(), Associativity::Left);
+}
+
+#[test]
+fn test_expr_references_and_try() {
+    use UnaryOperator::*;
+    assert_eq!(
+        quick_expr("&a + *b"),
+        binary(
+            BinaryOperator::Add,
+            unary(Reference, ident("a")),
+            unary(Dereference, ident("b"))
+        )
+    );
+    assert_eq!(
+        quick_expr("&&a & &mut b"),
+        binary(
+            BinaryOperator::BitAnd,
+            unary(DoubleReference, ident("a")),
+            unary(MutReference, ident("b"))
+        )
+    );
+    assert_eq!(
+        quick_expr("-f()?.x?"),
+        unary(
+            Negative,
+            unary(
+                Try,
+                binary(
+                    BinaryOperator::Dot,
+                    unary(Try, call(ident("f"), vec![])),
+                    ident("x")
+                )
+            )
+        )
+    );
+    assert_eq!(
+        quick_expr("a * *b"),
+        binary(
+            BinaryOperator::Mul,
+            ident("a"),
+            unary(Dereference, ident("b"))
+        )
+    );
+}
+
+#[test]
+fn test_expr_ranges_and_assignment() {
+    use BinaryOperator::*;
+    assert_eq!(
+        quick_expr("x = 0..n + 1"),
+        binary(
+            Assign,
+            ident("x"),
+            binary(Range, int("0"), binary(Add, ident("n"), int("1")))
+        )
+    );
+    assert_eq!(
+        quick_expr("a..b || c"),
+        binary(Range, ident("a"), binary(Or, ident("b"), ident("c")))
+    );
+    assert_eq!(
+        quick_expr("a - b - c"),
+        binary(Sub, binary(Sub, ident("a"), ident("b")), ident("c"))
+    );
+    assert_eq!(
+        quick_expr("(a < b) == c"),
+        binary(Eq, binary(Lesser, ident("a"), ident("b")), ident("c"))
+    );
+    assert_eq!(
+        quick_expr("a < b && b < c"),
+        binary(
+            And,
+            binary(Lesser, ident("a"), ident("b")),
+            binary(Lesser, ident("b"), ident("c"))
+        )
+    );
+}
+
+#[test]
+fn test_expr_chained_errors() {
+    let mut parser = Parser::new(Lexer::new("<inline>", "a < b < c"));
+    let err = parser.parse_expr().expect_err("expected to get an error");
+    assert_eq!(err.code, CHAINED_OPERATOR);
+    assert_eq!(err.message.as_ref(), "'<' and '<' cannot be chained");
+    assert_eq!(
+        &err.notes,
+        &["use '&&' to combine comparisons, as in 'a < b && b < c'".into()]
+    );
+    assert_eq!(err.span.map(|s| (s.start, s.end)), Some((2, 7)));
+
+    let mut parser = Parser::new(Lexer::new("<inline>", "a == b != c"));
+    let err = parser.parse_expr().expect_err("expected to get an error");
+    assert_eq!(err.code, CHAINED_OPERATOR);
+
+    let mut parser = Parser::new(Lexer::new("<inline>", "0..1..2"));
+    let err = parser.parse_expr().expect_err("expected to get an error");
+    assert_eq!(err.code, CHAINED_OPERATOR);
+    assert_eq!(err.message.as_ref(), "'..' and '..' cannot be chained");
+    assert_eq!(
+        &err.notes,
+        &["add parentheses to make the order explicit".into()]
+    );
+}
+
+fn signature_of(item: &Expr) -> &Signature {
+    match item {
+        Expr {
+            kind: ExprKind::Statement(Statement::Fn { signature, .. }),
+            ..
+        } => signature,
+        _ => panic!("expected a function but got {item:?}"),
+    }
+}
+
+#[test]
+fn test_fn_labels() {
+    let items = quick_parse(
+        "fn move(from src: Point, to dst: Point, _ speed: Int, by_: Int, _: Bool) -> Bool {}",
+    )
+    .unwrap();
+    let args: Vec<_> = signature_of(&items[0])
+        .named_args
+        .iter()
+        .map(|(name, _)| (name.label(), &**name.binding()))
+        .collect();
+    assert_eq!(
+        args,
+        [
+            (Some("from"), "src"),
+            (Some("to"), "dst"),
+            (None, "speed"),
+            (Some("by_"), "by_"),
+            (None, "_"),
+        ]
+    );
+    assert!(matches!(
+        &signature_of(&items[0]).named_args[0].0,
+        ArgName::Assigned { outer, inner } if &**outer == "from" && &**inner == "src"
+    ));
+}
+
+#[test]
+fn test_call_labels() {
+    assert_eq!(
+        quick_expr("move(from: a, to: b.c, 1)"),
+        node(ExprKind::Call {
+            callee: Box::new(ident("move")),
+            args: vec![
+                (Some("from".into()), ident("a")),
+                (
+                    Some("to".into()),
+                    binary(BinaryOperator::Dot, ident("b"), ident("c"))
+                ),
+                (None, int("1")),
+            ],
+        })
+    );
+}
+
+#[test]
+fn test_overload_identity() {
+    let items = quick_parse(
+        "fn f(from a: Int) {}\nfn f(from b: Float) {}\nfn f(to a: Int) {}\nfn f(_ a: Int) {}",
+    )
+    .unwrap();
+    let [a, b, c, d] = &items[..] else {
+        panic!("expected four functions");
+    };
+    assert!(signature_of(a).same_overload(signature_of(b)));
+    assert!(!signature_of(a).same_overload(signature_of(c)));
+    assert!(!signature_of(d).same_overload(signature_of(d)));
+    assert_eq!(
+        signature_of(a).match_labels(&[Some("to")]),
+        Err(LabelMismatch::Wrong {
+            index: 0,
+            found: "to".into(),
+            expected: "from".into()
+        })
+    );
+}
+
+#[test]
+fn test_semicolon_insertion() {
+    let corpus = [
+        // Lines ending in something that can end a statement
+        ("a\nb", "a ; b"),
+        (
+            "1\n\"s\"\ntrue\nfalse\nSelf\nb",
+            "1 ; s ; true ; false ; Self ; b",
+        ),
+        ("f()\nv[0]\n{}\nb", "f ( ) ; v [ 0 ] ; { } ; b"),
+        ("a?\nb", "a ? ; b"),
+        (
+            "break\ncontinue\nfallthrough\nreturn\nb",
+            "break ; continue ; fallthrough ; return ; b",
+        ),
+        ("a;\nb", "a ; b"),
+        ("a\n\n\nb", "a ; b"),
+        // Lines ending in something that can't
+        ("a +\nb", "a + b"),
+        ("a =\nb", "a = b"),
+        ("f(a,\nb)", "f ( a , b )"),
+        ("let\na", "let a"),
+        ("fn f() {\na\n}", "fn f ( ) { a ; }"),
+        // Inside `(` and `[`
+        ("f(\na\n)", "f ( a )"),
+        ("[\na\nb\n]", "[ a b ]"),
+        ("(a\n(b))", "( a ( b ) )"),
+        ("f({\na\nb\n})", "f ( { a ; b ; } )"),
+        ("[a\n{\nb\n}\n]\nc", "[ a { b ; } ] ; c"),
+        // Next lines continuing the previous one
+        ("a\n.b\n.c()", "a . b . c ( )"),
+        ("}\n.b", "} . b"),
+        ("a\n?", "a ?"),
+        ("a\n== b\n/ c", "a == b / c"),
+        ("a\n|| b\n|= c", "a || b |= c"),
+        ("a\n= b", "a = b"),
+        ("a\n..b", "a .. b"),
+        ("a\n: b", "a : b"),
+        ("a\n-> b", "a -> b"),
+        ("}\nelse {}", "} else { }"),
+        // Next lines starting with a prefix-only token
+        ("a\n!b", "a ; ! b"),
+        ("a\n~b", "a ; ~ b"),
+        ("a\n\\x", "a ; \\ x"),
+        ("a\n(b)", "a ; ( b )"),
+        ("a\n[b]", "a ; [ b ]"),
+        ("a\n{}", "a ; { }"),
+        ("}\nfn", "} ; fn"),
+    ];
+    for (source, expected) in corpus {
+        let (tokens, warnings) = quick_tokens(source);
+        assert_eq!(tokens, expected, "for {source:?}");
+        assert!(warnings.is_empty(), "for {source:?}");
+    }
+}
+
+#[test]
+fn test_semicolon_insertion_ambiguous() {
+    for operator in ["-", "+", "&", "&&"] {
+        let source = format!("a\n{operator}b");
+        let (tokens, warnings) = quick_tokens(&source);
+        assert_eq!(tokens, format!("a {operator} b"), "for {source:?}");
+        assert_eq!(warnings.len(), 1, "for {source:?}");
+        assert_eq!(warnings[0].code, AMBIGUOUS_LINE_BREAK);
+        assert_eq!(warnings[0].line, 2);
+        assert!(warnings[0].is_warning());
+        assert_eq!(warnings[0].notes.len(), 1);
+    }
+
+    // No ambiguity when the previous line can't end a statement, or inside `(`
+    for source in ["a;\n-b", "a =\n-b", "(a\n-b)"] {
+        let (_, warnings) = quick_tokens(source);
+        assert!(warnings.is_empty(), "for {source:?}");
+    }
+    assert_eq!(quick_tokens("a;\n-b").0, "a ; - b");
+
+    // A `*` starts a new statement instead, still with the warning.
+    let (tokens, warnings) = quick_tokens("a = b\n*r = x");
+    assert_eq!(tokens, "a = b ; * r = x");
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].code, AMBIGUOUS_LINE_BREAK);
+    assert_eq!(warnings[0].line, 2);
+    assert_eq!(
+        &*warnings[0].message,
+        "line starting with '*' starts a new statement"
+    );
+    for (source, expected) in [
+        ("a;\n*r", "a ; * r"),
+        ("a *\nb", "a * b"),
+        ("(a\n*b)", "( a * b )"),
+    ] {
+        let (tokens, warnings) = quick_tokens(source);
+        assert_eq!(tokens, expected, "for {source:?}");
+        assert!(warnings.is_empty(), "for {source:?}");
+    }
+}
+
+#[test]
+fn test_multiline_expressions() {
+    assert_eq!(
+        quick_parse("fn f() {\n    a\n        .b()\n        + c\n    d\n}").unwrap()[0],
+        statement(Statement::Fn {
+            public: false,
+            name: "f".into(),
+            generics: Generics::default(),
+            signature: Signature {
+                returns: Box::new(type_node(TypeKind::unit())),
+                named_args: vec![],
+            },
+            body: Some(Box::new(node(ExprKind::Block(vec![
+                binary(
+                    BinaryOperator::Add,
+                    call(binary(BinaryOperator::Dot, ident("a"), ident("b")), vec![]),
+                    ident("c"),
+                ),
+                ident("d"),
+            ])))),
+        })
+    );
+}
+
+#[test]
+fn test_recovery() {
+    let source = "fn f() {\n    let a =\n    let b = 1 2\n    g(,)\n    h(a b)\n    a +\n}\nstruct S { x: }\nfn g() { return 1 }\n$\nfn h( -> { }\nlet x = 1\n}\nfn i() {}";
+    let mut parser = Parser::new(Lexer::new("<inline>", source));
+    let module = parser.parse_recovering();
+    let errors: Vec<_> = parser.errors().iter().map(|e| (e.code, e.line)).collect();
+    assert_eq!(
+        errors,
+        [
+            (EXPECTED_EXPRESSION, 3),
+            (UNEXPECTED, 3),
+            (EXPECTED_EXPRESSION, 4),
+            (UNEXPECTED, 5),
+            (EXPECTED_EXPRESSION, 7),
+            (EXPECTED_TYPE, 8),
+            (ErrorCode::lexer(2), 10),
+            (EXPECTED_IDENTIFIER, 11),
+        ]
+    );
+    let Expr {
+        kind: ExprKind::Statement(Statement::Module { children, .. }),
+        ..
+    } = module
+    else {
+        panic!("expected a module but got {module:?}");
+    };
+    let empty_fn = |name: &str, body| {
+        statement(Statement::Fn {
+            public: false,
+            name: name.into(),
+            generics: Generics::default(),
+            signature: Signature {
+                returns: Box::new(type_node(TypeKind::unit())),
+                named_args: vec![],
+            },
+            body: Some(Box::new(node(ExprKind::Block(body)))),
+        })
+    };
+    let let_ = |name: &str, value| {
+        statement(Statement::Let {
+            mutable: false,
+            name: name.into(),
+            ty: None,
+            value: Some(Box::new(value)),
+        })
+    };
+    assert_eq!(
+        children,
+        [
+            empty_fn(
+                "f",
+                vec![
+                    let_("a", node(ExprKind::Error)),
+                    let_("b", int("1")),
+                    call(ident("g"), vec![node(ExprKind::Error)]),
+                    node(ExprKind::Error),
+                    binary(BinaryOperator::Add, ident("a"), node(ExprKind::Error)),
+                ]
+            ),
+            node(ExprKind::Error),
+            empty_fn(
+                "g",
+                vec![statement(Statement::Return(Some(Box::new(int("1")))))]
+            ),
+            node(ExprKind::Error),
+            empty_fn("i", vec![]),
+        ]
+    );
+}
+
+#[test]
+fn test_recovery_cascades() {
+    // A stray `}` and a top-level statement are one error each
+    let mut parser = Parser::new(Lexer::new("<inline>", "}\nlet x = f(1,\n2)\nfn f() {}"));
+    parser.parse_recovering();
+    let errors: Vec<_> = parser.errors().iter().map(|e| (e.code, e.line)).collect();
+    assert_eq!(errors, [(EXPECTED_ITEM, 1), (EXPECTED_ITEM, 2)]);
+
+    // A call with a broken argument isn't checked against the labels
+    let mut parser = Parser::new(Lexer::new("<inline>", "fn f(a: Int) {}\nfn g() { f(a: ) }"));
+    parser.parse_recovering();
+    let errors: Vec<_> = parser.errors().iter().map(|e| e.code).collect();
+    assert_eq!(errors, [EXPECTED_EXPRESSION]);
+
+    // `parse` fails with the first error in the file
+    let error = quick_parse("fn f() { g(, }\nfn g() {\n1 +\n}").unwrap_err();
+    assert_eq!((error.code, error.line), (EXPECTED_EXPRESSION, 1));
+
+    // A block left open at the end of the file
+    let error = quick_parse("fn f() {\n    g(1\n").unwrap_err();
+    assert_eq!(error.code, UNEXPECTED_EOF);
+
+    // An unfinished string ends the file
+    let mut parser = Parser::new(Lexer::new("<inline>", "fn f() {}\nlet s = \"abc"));
+    parser.parse_recovering();
+    assert_eq!(parser.errors().len(), 2);
+}
+
+#[test]
+fn test_spans() {
+    let mut map = SourceMap::new();
+    let source = "pub fn add(a: Int) -> Int {\n    return -a + b * f(c)[0].x\n}\nstruct P { x: std.Int }\nfn g() { h(,) }\n";
+    let file = map.add("<inline>", source);
+    let mut parser = Parser::new(Lexer::from_source(&map, file));
+    let module = parser.parse_recovering();
+    assert_eq!(map.text(module.span), source.trim_end());
+    let ExprKind::Statement(Statement::Module { children, .. }) = &module.kind else {
+        panic!("expected a module but got {module:?}");
+    };
+    let [add, point, g] = &children[..] else {
+        panic!("unexpected items: {children:?}");
+    };
+    assert_eq!(
+        map.text(add.span),
+        "pub fn add(a: Int) -> Int {\n    return -a + b * f(c)[0].x\n}"
+    );
+    assert_eq!(map.text(point.span), "struct P { x: std.Int }");
+
+    let ExprKind::Statement(Statement::Fn {
+        signature,
+        body: Some(body),
+        ..
+    }) = &add.kind
+    else {
+        panic!("expected a function but got {add:?}");
+    };
+    assert_eq!(map.text(signature.named_args[0].1.span), "Int");
+    assert_eq!(map.text(signature.returns.span), "Int");
+    assert_eq!(map.text(body.span), "{\n    return -a + b * f(c)[0].x\n}");
+    let ExprKind::Block(statements) = &body.kind else {
+        panic!("expected a block but got {body:?}");
+    };
+    assert_eq!(map.text(statements[0].span), "return -a + b * f(c)[0].x");
+    let ExprKind::Statement(Statement::Return(Some(value))) = &statements[0].kind else {
+        panic!("expected a return but got {:?}", statements[0]);
+    };
+    let ExprKind::Binary { left, right, .. } = &value.kind else {
+        panic!("expected a binary expression but got {value:?}");
+    };
+    assert_eq!(map.text(value.span), "-a + b * f(c)[0].x");
+    assert_eq!(map.text(left.span), "-a");
+    assert_eq!(map.text(right.span), "b * f(c)[0].x");
+    let ExprKind::Binary { right: field, .. } = &right.kind else {
+        panic!("expected a binary expression but got {right:?}");
+    };
+    let ExprKind::Binary {
+        left: index,
+        right: x,
+        ..
+    } = &field.kind
+    else {
+        panic!("expected a field access but got {field:?}");
+    };
+    assert_eq!(map.text(field.span), "f(c)[0].x");
+    assert_eq!(map.text(index.span), "f(c)[0]");
+    assert_eq!(map.text(x.span), "x");
+    let ExprKind::Index { expr: call, .. } = &index.kind else {
+        panic!("expected an index but got {index:?}");
+    };
+    assert_eq!(map.text(call.span), "f(c)");
+
+    // A missing return type is an empty span where it would be written
+    let ExprKind::Statement(Statement::Fn {
+        signature,
+        body: Some(body),
+        ..
+    }) = &g.kind
+    else {
+        panic!("expected a function but got {g:?}");
+    };
+    assert!(signature.returns.span.is_empty());
+    assert_eq!(map.location(signature.returns.span).column, 8);
+    // ...and so is a missing expression
+    let ExprKind::Block(statements) = &body.kind else {
+        panic!("expected a block but got {body:?}");
+    };
+    let ExprKind::Call { args, .. } = &statements[0].kind else {
+        panic!("expected a call but got {:?}", statements[0]);
+    };
+    assert_eq!(args[0].1.kind, ExprKind::Error);
+    assert!(args[0].1.span.is_empty());
+
+    // Every node has its own id
+    let mut ids = vec![
+        module.id, add.id, point.id, g.id, body.id, value.id, left.id, right.id, field.id,
+        index.id, x.id, call.id,
+    ];
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), 12);
+}
+
+/// Collects the names a tree declares, in order.
+#[derive(Default)]
+struct Declared(Vec<Ident>);
+
+impl Visitor<'_> for Declared {
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::For { binding, .. } => self.0.push(binding.clone()),
+            ExprKind::Closure { params, .. } => {
+                self.0.extend(params.iter().map(|param| param.name.clone()))
+            }
+            _ => {}
+        }
+        walk::expr(self, expr);
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Fn {
+                name,
+                generics,
+                signature,
+                ..
+            } => {
+                self.0.push(name.clone());
+                self.0
+                    .extend(generics.params.iter().map(|param| param.name.clone()));
+                for (arg, _) in &signature.named_args {
+                    match arg {
+                        ArgName::Unnamed(name) | ArgName::Single(name) => self.0.push(name.clone()),
+                        ArgName::Assigned { outer, inner } => {
+                            self.0.extend([outer.clone(), inner.clone()])
+                        }
+                    }
+                }
+            }
+            Statement::Struct { name, fields, .. } => {
+                self.0.push(name.clone());
+                if let Fields::Named(fields) | Fields::Tuple(fields) = fields {
+                    self.0.extend(fields.iter().map(|field| field.name.clone()));
+                }
+            }
+            Statement::Enum { name, variants, .. } => {
+                self.0.push(name.clone());
+                self.0
+                    .extend(variants.iter().map(|variant| variant.name.clone()));
+            }
+            Statement::Let { name, .. } => self.0.push(name.clone()),
+            _ => {}
+        }
+        walk::statement(self, statement);
+    }
+
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        if let PatternKind::Binding { name, .. }
+        | PatternKind::Is {
+            binding: Some(name),
+            ..
+        } = &pattern.kind
+        {
+            self.0.push(name.clone());
+        }
+        walk::pattern(self, pattern);
+    }
+}
+
+#[test]
+fn test_name_spans() {
+    let mut map = SourceMap::new();
+    // The names also appear in the comment, before where they are declared.
+    let source = "// f T x y z w v i k n P E A B\nfn f<T>(from x: T, _ y: Int, z: Int) {\n    let w = \\v -> v\n    for i in w {}\n    match z { mut k => k, n is Int => n }\n}\nstruct P { x: Int }\nstruct Q(Int)\nenum E { A, B(Int) }\n";
+    let file = map.add("<inline>", source);
+    let module = Parser::new(Lexer::from_source(&map, file)).parse().unwrap();
+    let mut names = Declared::default();
+    names.visit_expr(&module);
+    let texts: Vec<_> = names.0.iter().map(|name| &*name.text).collect();
+    assert_eq!(
+        texts,
+        [
+            "f", "T", "from", "x", "y", "z", "w", "v", "i", "k", "n", "P", "x", "Q", "0", "E", "A",
+            "B"
+        ]
+    );
+    let comment = source.find('\n').unwrap() as u32;
+    for name in &names.0 {
+        assert!(name.span.start > comment, "{name} at {:?}", name.span);
+        if &**name == "0" {
+            // A tuple field is named by its position, and located by its type.
+            assert_eq!(map.text(name.span), "Int");
+        } else {
+            assert_eq!(map.text(name.span), &*name.text);
+        }
+    }
+}
+
+fn block(statements: Vec<Expr>) -> Expr {
+    node(ExprKind::Block(statements))
+}
+
+/// The statements of the body of the only function in `source`.
+fn quick_body(source: &str) -> Result<Vec<Expr>, Error> {
+    let items = quick_parse(source)?;
+    let [Expr {
+        kind: ExprKind::Statement(Statement::Fn {
+            body: Some(body), ..
+        }),
+        ..
+    }] = &items[..]
+    else {
+        panic!("expected a function but got {items:?}");
+    };
+    let ExprKind::Block(statements) = &body.kind else {
+        panic!("expected a block but got {body:?}");
+    };
+    Ok(statements.clone())
+}
+
+#[test]
+fn test_if() {
+    assert_eq!(
+        quick_expr("if a { 1 }"),
+        node(ExprKind::If {
+            unless: false,
+            condition: Box::new(ident("a")),
+            then: Box::new(block(vec![int("1")])),
+            otherwise: None,
+        })
+    );
+    assert_eq!(
+        quick_expr("unless a == b { 1 } else unless c { 2 } else if d { 3 } else { 4 }"),
+        node(ExprKind::If {
+            unless: true,
+            condition: Box::new(binary(BinaryOperator::Eq, ident("a"), ident("b"))),
+            then: Box::new(block(vec![int("1")])),
+            otherwise: Some(Box::new(node(ExprKind::If {
+                unless: true,
+                condition: Box::new(ident("c")),
+                then: Box::new(block(vec![int("2")])),
+                otherwise: Some(Box::new(node(ExprKind::If {
+                    unless: false,
+                    condition: Box::new(ident("d")),
+                    then: Box::new(block(vec![int("3")])),
+                    otherwise: Some(Box::new(block(vec![int("4")]))),
+                }))),
+            }))),
+        })
+    );
+    // `if` is an expression, and `else` may start the next line
+    assert_eq!(
+        quick_body("fn f() {\n    x = if a {\n        1\n    }\n    else { {} }\n    g()\n}")
+            .unwrap(),
+        [
+            binary(
+                BinaryOperator::Assign,
+                ident("x"),
+                node(ExprKind::If {
+                    unless: false,
+                    condition: Box::new(ident("a")),
+                    then: Box::new(block(vec![int("1")])),
+                    otherwise: Some(Box::new(block(vec![block(vec![])]))),
+                })
+            ),
+            call(ident("g"), vec![]),
+        ]
+    );
+    let error = quick_parse("fn f() { if a { 1 } else 2 }").unwrap_err();
+    assert_eq!(error.code, UNEXPECTED);
+}
+
+#[test]
+fn test_loops() {
+    assert_eq!(
+        quick_expr("while i < 10 { i += 1 }"),
+        node(ExprKind::While {
+            label: None,
+            condition: Box::new(binary(BinaryOperator::Lesser, ident("i"), int("10"))),
+            body: Box::new(block(vec![binary(
+                BinaryOperator::AddAssign,
+                ident("i"),
+                int("1")
+            )])),
+        })
+    );
+    assert_eq!(
+        quick_expr("for i in 0..n + 1 { f(i) }"),
+        node(ExprKind::For {
+            label: None,
+            binding: "i".into(),
+            iterable: Box::new(binary(
+                BinaryOperator::Range,
+                int("0"),
+                binary(BinaryOperator::Add, ident("n"), int("1"))
+            )),
+            body: Box::new(block(vec![call(ident("f"), vec![ident("i")])])),
+        })
+    );
+    assert_eq!(
+        quick_body("fn f() {\n    do {\n        i += 1\n    } while i < 10\n    i\n}").unwrap(),
+        [
+            node(ExprKind::DoWhile {
+                label: None,
+                body: Box::new(block(vec![binary(
+                    BinaryOperator::AddAssign,
+                    ident("i"),
+                    int("1")
+                )])),
+                condition: Box::new(binary(BinaryOperator::Lesser, ident("i"), int("10"))),
+            }),
+            ident("i"),
+        ]
+    );
+    let error = quick_parse("fn f() { for 1 in xs {} }").unwrap_err();
+    assert_eq!(error.code, EXPECTED_IDENTIFIER);
+    let error = quick_parse("fn f() { do {} }").unwrap_err();
+    assert_eq!(error.code, UNEXPECTED);
+}
+
+#[test]
+fn test_loop_labels() {
+    let statements = quick_body(
+        "fn f() {\n    outer: for x in xs {\n        inner: while true {\n            break outer x\n            continue inner\n            break x\n            continue\n        }\n        break outer\n    }\n}",
+    )
+    .unwrap();
+    let break_ = |label: Option<&str>, value: Option<Expr>| {
+        statement(Statement::Break {
+            label: label.map(Into::into),
+            value: value.map(Box::new),
+        })
+    };
+    assert_eq!(
+        statements,
+        [node(ExprKind::For {
+            label: Some("outer".into()),
+            binding: "x".into(),
+            iterable: Box::new(ident("xs")),
+            body: Box::new(block(vec![
+                node(ExprKind::While {
+                    label: Some("inner".into()),
+                    condition: Box::new(node(ExprKind::Bool(true))),
+                    body: Box::new(block(vec![
+                        break_(Some("outer"), Some(ident("x"))),
+                        statement(Statement::Continue {
+                            label: Some("inner".into()),
+                        }),
+                        // `x` isn't a label, so it is the value
+                        break_(None, Some(ident("x"))),
+                        statement(Statement::Continue { label: None }),
+                    ])),
+                }),
+                break_(Some("outer"), None),
+            ])),
+        })]
+    );
+
+    // Labels are only known inside their loop, and not inside nested functions
+    let error = quick_parse("fn f() {\n    a: while true {}\n    continue a\n}").unwrap_err();
+    assert_eq!((error.code, error.line), (UNKNOWN_LOOP_LABEL, 3));
+    let error =
+        quick_parse("fn f() {\n    a: while true {\n        fn g() { continue a }\n    }\n}")
+            .unwrap_err();
+    assert_eq!((error.code, error.line), (UNKNOWN_LOOP_LABEL, 3));
+    let error = quick_parse("fn f() { a: b }").unwrap_err();
+    assert_eq!(error.code, EXPECTED_LOOP);
+}
+
+fn closure(params: &[(&str, Option<TypeExpr>)], returns: Option<TypeExpr>, body: Expr) -> Expr {
+    node(ExprKind::Closure {
+        params: params
+            .iter()
+            .map(|(name, ty)| ClosureParam {
+                name: (*name).into(),
+                ty: ty.clone(),
+            })
+            .collect(),
+        returns: returns.map(Box::new),
+        body: Box::new(body),
+    })
+}
+
+#[test]
+fn test_closures() {
+    use BinaryOperator::*;
+    assert_eq!(
+        quick_expr("\\x, y -> x + y"),
+        closure(
+            &[("x", None), ("y", None)],
+            None,
+            binary(Add, ident("x"), ident("y"))
+        )
+    );
+    assert_eq!(quick_expr("\\-> 1"), closure(&[], None, int("1")));
+    assert_eq!(
+        quick_expr("map(\\x: Int -> x * 2, list)"),
+        call(
+            ident("map"),
+            vec![
+                closure(
+                    &[("x", Some(ty("Int")))],
+                    None,
+                    binary(Mul, ident("x"), int("2"))
+                ),
+                ident("list"),
+            ]
+        )
+    );
+    assert_eq!(
+        quick_expr("\\(x: Int, y) -> Int { x }"),
+        closure(
+            &[("x", Some(ty("Int"))), ("y", None)],
+            Some(ty("Int")),
+            block(vec![ident("x")])
+        )
+    );
+    assert_eq!(
+        quick_expr("\\() { 1 }"),
+        closure(&[], None, block(vec![int("1")]))
+    );
+    // The body goes on until the end of the line, or the next line continues it
+    let body = quick_body("fn f() {\n    let g = \\x ->\n        x + 1\n    g\n}").unwrap();
+    assert_eq!(body.len(), 2);
+
+    let error = quick_parse("fn f() { \\(x) -> x + 1 }").unwrap_err();
+    assert_eq!(error.code, UNEXPECTED);
+    let error =
+        quick_parse("fn f() {\n    a: while true {\n        \\ -> { continue a }\n    }\n}")
+            .unwrap_err();
+    assert_eq!((error.code, error.line), (UNKNOWN_LOOP_LABEL, 3));
+}
+
+fn pattern(kind: PatternKind) -> Pattern {
+    kind.into()
+}
+
+fn binding(name: &str) -> Pattern {
+    pattern(PatternKind::Binding {
+        mutable: false,
+        name: name.into(),
+    })
+}
+
+fn int_pattern(value: &str) -> Pattern {
+    pattern(PatternKind::Literal(
+        Literal::Integer { radix: 10, suffix: None },
+        value.into(),
+    ))
+}
+
+/// The pattern of every arm of `match x { ... }`.
+fn quick_patterns(arms: &str) -> Result<Vec<Pattern>, Error> {
+    let mut parser = Parser::new(Lexer::new("<inline>", &format!("match x {{ {arms} }}")));
+    let expr = parser.parse_expr()?;
+    let ExprKind::Match { arms, .. } = expr.kind else {
+        panic!("expected a match but got {expr:?}");
+    };
+    Ok(arms.into_iter().map(|arm| arm.pattern).collect())
+}
+
+#[test]
+fn test_match() {
+    assert_eq!(
+        quick_expr("match a + 1 {\n    0 => b\n    n if n > 9 => { c }, _ => d,\n}"),
+        node(ExprKind::Match {
+            scrutinee: Box::new(binary(BinaryOperator::Add, ident("a"), int("1"))),
+            arms: vec![
+                MatchArm {
+                    pattern: int_pattern("0"),
+                    guard: None,
+                    body: Box::new(ident("b")),
+                },
+                MatchArm {
+                    pattern: binding("n"),
+                    guard: Some(Box::new(binary(
+                        BinaryOperator::Greater,
+                        ident("n"),
+                        int("9")
+                    ))),
+                    body: Box::new(block(vec![ident("c")])),
+                },
+                MatchArm {
+                    pattern: pattern(PatternKind::Wildcard),
+                    guard: None,
+                    body: Box::new(ident("d")),
+                },
+            ],
+        })
+    );
+    let error = quick_parse("fn f() { match x { 1 b } }").unwrap_err();
+    assert_eq!(error.code, UNEXPECTED);
+}
+
+#[test]
+fn test_patterns() {
+    let path = |names: &[&str]| {
+        names[1..].iter().fold(ident(names[0]), |path, name| {
+            binary(BinaryOperator::Dot, path, ident(name))
+        })
+    };
+    assert_eq!(
+        quick_patterns(
+            "1..5 => a, -3 => a, \"s\" => a, true => a, mut n => a, Color.Red => a, (a, _,) => a, (b) => a"
+        )
+        .unwrap(),
+        [
+            pattern(PatternKind::Range {
+                start: Box::new(int_pattern("1")),
+                end: Box::new(int_pattern("5")),
+            }),
+            int_pattern("-3"),
+            pattern(PatternKind::Literal(Literal::String, "s".into())),
+            pattern(PatternKind::Bool(true)),
+            pattern(PatternKind::Binding {
+                mutable: true,
+                name: "n".into(),
+            }),
+            pattern(PatternKind::Path(Box::new(path(&["Color", "Red"])))),
+            pattern(PatternKind::Tuple(vec![
+                binding("a"),
+                pattern(PatternKind::Wildcard)
+            ])),
+            binding("b"),
+        ]
+    );
+    assert_eq!(
+        quick_patterns(
+            "Some(x) | None => a\nShape.Circle(r, _) => a\nPoint { x, y: 0 | 1, .. } => a\nPoint {\n    x: -1..1\n    y\n} => a"
+        )
+        .unwrap(),
+        [
+            pattern(PatternKind::Or(vec![
+                pattern(PatternKind::Variant {
+                    path: Box::new(ident("Some")),
+                    args: vec![binding("x")],
+                }),
+                binding("None"),
+            ])),
+            pattern(PatternKind::Variant {
+                path: Box::new(path(&["Shape", "Circle"])),
+                args: vec![binding("r"), pattern(PatternKind::Wildcard)],
+            }),
+            pattern(PatternKind::Struct {
+                path: Box::new(ident("Point")),
+                fields: vec![
+                    ("x".into(), binding("x")),
+                    (
+                        "y".into(),
+                        pattern(PatternKind::Or(vec![int_pattern("0"), int_pattern("1")])),
+                    ),
+                ],
+                rest: true,
+            }),
+            pattern(PatternKind::Struct {
+                path: Box::new(ident("Point")),
+                fields: vec![
+                    (
+                        "x".into(),
+                        pattern(PatternKind::Range {
+                            start: Box::new(int_pattern("-1")),
+                            end: Box::new(int_pattern("1")),
+                        }),
+                    ),
+                    ("y".into(), binding("y")),
+                ],
+                rest: false,
+            }),
+        ]
+    );
+    assert_eq!(
+        quick_patterns("is Int => a, n is std.Float => a").unwrap(),
+        [
+            pattern(PatternKind::Is {
+                binding: None,
+                ty: Box::new(ty("Int")),
+            }),
+            pattern(PatternKind::Is {
+                binding: Some("n".into()),
+                ty: Box::new(ty("std.Float")),
+            }),
+        ]
+    );
+    for arms in ["+ => a", "- x => a", "1.. => a", "Point { .., x } => a"] {
+        let error = quick_patterns(arms).unwrap_err();
+        assert!(
+            [EXPECTED_PATTERN, UNEXPECTED].contains(&error.code),
+            "for {arms:?}: {error:?}"
+        );
+    }
+}
+
+#[test]
+fn test_fallthrough() {
+    let statements = quick_body(
+        "fn f() {\n    match x {\n        1 => fallthrough\n        2 => {\n            g()\n            fallthrough\n        }\n        _ => {}\n    }\n}",
+    )
+    .unwrap();
+    let [Expr {
+        kind: ExprKind::Match { arms, .. },
+        ..
+    }] = &statements[..]
+    else {
+        panic!("expected a match but got {statements:?}");
+    };
+    assert_eq!(*arms[0].body, statement(Statement::Fallthrough));
+    assert_eq!(
+        *arms[1].body,
+        block(vec![
+            call(ident("g"), vec![]),
+            statement(Statement::Fallthrough)
+        ])
+    );
+
+    let error = quick_parse("fn f() {\n    fallthrough\n}").unwrap_err();
+    assert_eq!((error.code, error.line), (FALLTHROUGH_OUTSIDE_MATCH, 2));
+    let error =
+        quick_parse("fn f() {\n    match x {\n        1 => {}\n        _ => fallthrough\n    }\n}")
+            .unwrap_err();
+    assert_eq!((error.code, error.line), (FALLTHROUGH_IN_LAST_ARM, 4));
+    // The arms of a nested match are separate from the outer one's
+    let error = quick_parse(
+        "fn f() {\n    match x {\n        1 => match y {\n            _ => fallthrough\n        }\n        _ => {}\n    }\n}",
+    )
+    .unwrap_err();
+    assert_eq!((error.code, error.line), (FALLTHROUGH_IN_LAST_ARM, 4));
+    quick_parse("fn f() {\n    match x {\n        1 => {\n            match y { _ => {} }\n            fallthrough\n        }\n        _ => {}\n    }\n}")
+        .unwrap();
+}
+
+#[test]
+fn test_import_trees() {
+    let items = quick_parse("import a.b.{c, d as e, f.{g}}\nimport a as b").unwrap();
+    let path = |names: &[&str], alias: Option<&str>| {
+        let mut names = names.iter().map(|name| ident(name));
+        let first = names.next().unwrap();
+        ImportTree::Path {
+            path: Box::new(names.fold(first, |path, name| binary(BinaryOperator::Dot, path, name))),
+            alias: alias.map(Into::into),
+        }
+    };
+    assert_eq!(
+        items,
+        [
+            statement(Statement::Use {
+                tree: ImportTree::Group {
+                    prefix: Box::new(binary(BinaryOperator::Dot, ident("a"), ident("b"))),
+                    items: vec![
+                        path(&["c"], None),
+                        path(&["d"], Some("e")),
+                        ImportTree::Group {
+                            prefix: Box::new(ident("f")),
+                            items: vec![path(&["g"], None)],
+                        },
+                    ],
+                },
+            }),
+            statement(Statement::Use {
+                tree: path(&["a"], Some("b")),
+            }),
+        ]
+    );
+    let error = quick_parse("import a.{b").unwrap_err();
+    assert_eq!(error.code, UNEXPECTED_EOF);
+}
+
+/// Writes `files` to a fresh directory named after `test` and loads the first one.
+fn quick_load(test: &str, files: &[(&str, &str)]) -> Program {
+    let dir = std::env::temp_dir().join(format!("coil-{test}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    for (path, source) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, source).unwrap();
+    }
+    let mut map = SourceMap::new();
+    let program = load(&mut map, &dir.join(files[0].0)).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    program
+}
+
+#[test]
+fn test_load_modules() {
+    let program = quick_load(
+        "modules",
+        &[
+            (
+                "main.coil",
+                "module util\nmodule net\nmodule shapes { pub struct Square {} }\nimport util.{helper, Config as Cfg}\nimport net.http.get\nimport net.http as web\nfn main() {}",
+            ),
+            ("util.coil", "pub fn helper() {}\npub struct Config {}"),
+            ("net/mod.coil", "module http"),
+            ("net/http.coil", "pub fn get() {}\nfn secret() {}\nimport util.helper"),
+            ("net/http/inner.coil", "fn unused() {}"),
+        ],
+    );
+    assert!(program.errors.is_empty(), "{:?}", program.errors);
+    let paths: Vec<_> = (0..program.modules.len())
+        .map(|id| program.module_path(ModuleId(id as u32)))
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            "main",
+            "main.util",
+            "main.net",
+            "main.net.http",
+            "main.shapes"
+        ]
+    );
+    let http = program.module(ModuleId(3));
+    assert_eq!(
+        http.items.iter().collect::<Vec<_>>(),
+        [(&"get".into(), &true), (&"secret".into(), &false)]
+    );
+
+    let root = program.module(ModuleId(0));
+    let imports: Vec<_> = root
+        .imports
+        .iter()
+        .map(|import| (import.name.as_ref(), import.target.clone()))
+        .collect();
+    assert_eq!(
+        imports,
+        [
+            ("helper", ImportTarget::Item(ModuleId(1), "helper".into())),
+            ("Cfg", ImportTarget::Item(ModuleId(1), "Config".into())),
+            ("get", ImportTarget::Item(ModuleId(3), "get".into())),
+            ("web", ImportTarget::Module(ModuleId(3))),
+        ]
+    );
+
+    // The items of each file take the place of its declaration, with distinct ids
+    let ExprKind::Statement(Statement::Module { children, .. }) = &program.root.kind else {
+        panic!("expected a module but got {:?}", program.root);
+    };
+    let Expr {
+        id: util_id,
+        kind: ExprKind::Statement(Statement::Module { children: util, .. }),
+        ..
+    } = &children[0]
+    else {
+        panic!("expected a module but got {:?}", children[0]);
+    };
+    assert_eq!(util.len(), 2);
+    assert_ne!(util[0].id, *util_id);
+    assert!(util[0].id > program.root.id);
+}
+
+#[test]
+fn test_load_errors() {
+    let program = quick_load(
+        "errors",
+        &[
+            (
+                "main.coil",
+                "module a\nmodule b\nmodule missing\nmodule both\nmodule a {}\nimport a.secret\nimport a.nothing\nimport c.x\nimport b.g\nimport a.f as g\nfn f( {}",
+            ),
+            ("a.coil", "fn secret() {}\npub fn f() {}\nimport b.g"),
+            ("b.coil", "pub fn g() {}\nimport a.f"),
+            ("both.coil", ""),
+            ("both/mod.coil", ""),
+        ],
+    );
+    let errors: Vec<_> = program
+        .errors
+        .iter()
+        .map(|error| (error.code, error.line))
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (MODULE_NOT_FOUND, 3),
+            (AMBIGUOUS_MODULE_FILE, 4),
+            (DUPLICATE_NAME, 5),
+            (PRIVATE_IMPORT, 6),
+            (UNRESOLVED_IMPORT, 7),
+            (UNRESOLVED_IMPORT, 8),
+            (DUPLICATE_NAME, 10),
+            (EXPECTED_IDENTIFIER, 11),
+            (IMPORT_CYCLE, 3),
+        ]
+    );
+    let cycle = program.errors.last().unwrap();
+    assert!(cycle.message.contains("main.b -> main.a -> main.b"));
+    assert!(cycle.file.ends_with("a.coil"));
+}
+
+/// Collects identifiers and the paths of types in the order a [`Visitor`] meets them.
+#[derive(Default)]
+struct Names {
+    idents: Vec<String>,
+    types: usize,
+    patterns: usize,
+}
+
+impl Visitor<'_> for Names {
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let ExprKind::Identifier(name) = &expr.kind {
+            self.idents.push(name.to_string());
+        }
+        walk::expr(self, expr)
+    }
+
+    fn visit_type(&mut self, ty: &TypeExpr) {
+        self.types += 1;
+        walk::ty(self, ty)
+    }
+
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        self.patterns += 1;
+        walk::pattern(self, pattern)
+    }
+}
+
+const VISITED: &str = "
+struct Pair<T: Show> { a: T, b: [Int; size] }
+fn f(x: Int) -> Str where T: Eq {
+    let y: Vec<Bool> = g(x)
+    match y {
+        Some(z) if z => h(\\w -> w)
+        Point { a: (b, c), .. } => c
+    }
+}
+";
+
+#[test]
+fn test_visitor() {
+    let mut names = Names::default();
+    for item in quick_parse(VISITED).unwrap() {
+        names.visit_expr(&item);
+    }
+    assert_eq!(
+        names.idents,
+        [
+            "Show", "T", "Int", "size", "T", "Eq", "Int", "Str", "Vec", "Bool", "g", "x", "y",
+            "Some", "z", "h", "w", "Point", "c"
+        ]
+    );
+    // `Show`, `T`, `[Int; size]` and `Int` in the struct, then `T`, `Eq`, `Int`, `Str`,
+    // `Vec<Bool>` and `Bool` in the function.
+    assert_eq!(names.types, 10);
+    // `Some(z)`, `z`, `Point { .. }`, `(b, c)`, `b` and `c`.
+    assert_eq!(names.patterns, 6);
+}
+
+#[test]
+fn test_visitor_mut() {
+    struct Rename;
+    impl VisitorMut<'_> for Rename {
+        fn visit_expr(&mut self, expr: &mut Expr) {
+            if let ExprKind::Identifier(name) = &mut expr.kind {
+                if &**name == "x" {
+                    *name = "renamed".into();
+                }
+            }
+            walk_mut::expr(self, expr)
+        }
+    }
+    let mut items = quick_parse("fn f(x: Int) -> Int { let y = x * [x][0]; y + x }").unwrap();
+    for item in &mut items {
+        Rename.visit_expr(item);
+    }
+    assert_eq!(
+        items,
+        quick_parse("fn f(x: Int) -> Int { let y = renamed * [renamed][0]; y + renamed }").unwrap()
+    );
+}
+
+#[test]
+fn test_fold() {
+    /// Folds `a + 0` into `a`, innermost first.
+    struct AddZero;
+    impl Fold for AddZero {
+        fn fold_expr(&mut self, expr: Expr) -> Expr {
+            let expr = fold::expr(self, expr);
+            match expr.kind {
+                ExprKind::Binary {
+                    op: BinaryOperator::Add,
+                    left,
+                    right,
+                } if *right == int("0") => *left,
+                kind => Expr { kind, ..expr },
+            }
+        }
+    }
+    let source = "fn f(a: Int) -> Int { let b = (a + 0) + 0; g(b + 0, [a + 1]) }";
+    let items = quick_parse(source).unwrap();
+
+    struct Identity;
+    impl Fold for Identity {}
+    let same: Vec<_> = items
+        .iter()
+        .cloned()
+        .map(|item| Identity.fold_expr(item))
+        .collect();
+    assert_eq!(same, items);
+
+    let folded: Vec<_> = items
+        .into_iter()
+        .map(|item| AddZero.fold_expr(item))
+        .collect();
+    assert_eq!(
+        folded,
+        quick_parse("fn f(a: Int) -> Int { let b = a; g(b, [a + 1]) }").unwrap()
+    );
+}
+
+#[test]
+fn test_dump_sexp() {
+    assert_eq!(
+        quick_expr("-a + f(x: 1)").to_dump().to_sexp(None),
+        "(Binary\n  :op \"Add\"\n  :left (Unary :op \"Negative\" :expr (Identifier :name \"a\"))\n  \
+         :right (Call\n    :callee (Identifier :name \"f\")\n    \
+         :args [(Arg :label \"x\" :value (Literal :kind (Integer :radix 10) :text \"1\"))]))\n"
+    );
+    assert_eq!(
+        quick_parse("static x: &Int? = nil")
+            .unwrap()
+            .to_dump()
+            .to_sexp(None),
+        "[(Static\n    :public false\n    :mutable false\n    :name \"x\"\n    \
+         :ty (OptionalType\n      :ty (ReferenceType\n        :mutable false\n        \
+         :ty (PathType :path (Identifier :name \"Int\") :args [])))\n    \
+         :value (Identifier :name \"nil\"))]\n"
+    );
+}
+
+#[test]
+fn test_dump_json_and_tree() {
+    let mut map = SourceMap::new();
+    let file = map.add("<inline>", "match x {\n    (a, _) => \"a\\n\"\n}");
+    let expr = Parser::new(Lexer::from_source(&map, file))
+        .parse_expr()
+        .unwrap();
+    let dump = expr.to_dump();
+    assert_eq!(
+        dump.to_json(Some(&map)),
+        r#"{
+  "node": "Match",
+  "span": "<inline>:1:1-3:2",
+  "scrutinee": {
+    "node": "Identifier",
+    "span": "<inline>:1:7-1:8",
+    "name": "x"
+  },
+  "arms": [
+    {
+      "node": "MatchArm",
+      "pattern": {
+        "node": "TuplePattern",
+        "span": "<inline>:2:5-2:11",
+        "patterns": [
+          {
+            "node": "BindingPattern",
+            "span": "<inline>:2:6-2:7",
+            "mutable": false,
+            "name": "a"
+          },
+          {
+            "node": "WildcardPattern",
+            "span": "<inline>:2:9-2:10"
+          }
+        ]
+      },
+      "guard": null,
+      "body": {
+        "node": "Literal",
+        "span": "<inline>:2:15-2:20",
+        "kind": {
+          "node": "String"
+        },
+        "text": "a\n"
+      }
+    }
+  ]
+}
+"#
+    );
+    assert_eq!(
+        dump.to_tree(Some(&map)),
+        r#"Match @ <inline>:1:1-3:2
+  scrutinee: Identifier @ 1:7-1:8
+    name: "x"
+  arms:
+    - MatchArm
+      pattern: TuplePattern @ 2:5-2:11
+        patterns:
+          - BindingPattern @ 2:6-2:7
+            mutable: false
+            name: "a"
+          - WildcardPattern @ 2:9-2:10
+      guard: nil
+      body: Literal @ 2:15-2:20
+        kind: String
+        text: "a\n"
+"#
+    );
+    // Without spans, only the structure is left, so differently laid out code matches.
+    let same = quick_expr("match x { (a, _) => \"a\\n\" }").to_dump();
+    assert_eq!(same.to_tree(None), dump.to_tree(None));
+}
+
+/// Prints the module parsed from `source` and checks that the printed source parses to
+/// the same tree and prints the same again.
+fn round_trip(source: &str) -> String {
+    let module = Parser::new(Lexer::new("<inline>", source))
+        .parse()
+        .expect("expected source to parse");
+    let printed = module.to_string();
+    let reparsed = Parser::new(Lexer::new("<inline>", &printed))
+        .parse()
+        .unwrap_or_else(|error| panic!("expected to parse\n{printed}\nbut got {error:?}"));
+    assert_eq!(reparsed, module, "printed as\n{printed}");
+    assert_eq!(reparsed.to_string(), printed);
+    printed
+}
+
+const PRINTED: &str = r#"import std.io.{File, Read as R}
+pub struct Pair<T: Show + Eq> {
+    pub a: T
+    b: [Int; 4]
+}
+struct Wrapper<T>(pub T, (Int,)) where T: Eq
+struct Marker
+union Bits {
+    i: Int
+}
+enum Shape {
+    Circle(Float)
+    Rect { w: Float, h: Float }
+    Empty = 0x1F
+}
+pub trait Show: Eq {
+    type Item: Show = Int
+    static NAME: Str
+    fn show(this: &Self, to out: &mut Str) -> Bool
+}
+impl<T> Show for Pair<T> where T: Show {
+    type Item = T
+    fn show(this: &Self, to out: &mut Str) -> Bool { true }
+}
+extern "C" {
+    pub fn puts(s: *Char) -> Int
+}
+module inner {
+    static mut COUNT: Int? = 0
+}
+fn f(x: Int, f: fn(Int) -> Int!Str) -> &[Int] {
+    let mut y: Vec<Bool> = g(x, -x, !done, &mut y, & &x)
+    y = a.b.0 .1 + (1).c
+    let z = (a + b) * c - (d - e) / -(f + g)?
+    a = b = c..d
+    let h = \w, v: (fn()) -> w + v
+    let k = \(w: Int) -> Int {
+        w
+        w
+    }
+    outer: for i in 0..10 {
+        while (P { x }) == p { break outer i }
+        do { continue outer } while i < 10 && i > 0
+    }
+    if a { 1 } else if b { -2 } else { "q\"\n" };
+    *p = 1;
+    -x
+    match y {
+        Some(z) | None if z => h(w: 1)?,
+        -1..1 => {
+            break (i)
+            g()
+            fallthrough
+        },
+        -5 => Point { x: 1, y }
+        Point { a: (b, _), .. } => c
+        p is Int => c.<Int>[0]
+        _ => return
+    }
+    unless x { return x }
+}"#;
+
+#[test]
+fn test_pretty_round_trip() {
+    assert_eq!(round_trip(PRINTED), PRINTED);
+    for source in [VISITED, "fn f() {\n    a\n        .b()\n        + c\n    d\n}"] {
+        round_trip(source);
+    }
+    assert_eq!(
+        round_trip("extern fn f(_ s: Int, _: Bool, from to: Int)"),
+        "extern {\n    fn f(_ s: Int, _: Bool, from to: Int)\n}"
+    );
+}
+
+#[test]
+fn test_pretty_parentheses() {
+    let (a, b, c) = (ident("a"), ident("b"), ident("c"));
+    let sum = binary(BinaryOperator::Add, a.clone(), b.clone());
+    let product = binary(BinaryOperator::Mul, sum.clone(), c.clone());
+    assert_eq!(product.to_string(), "(a + b) * c");
+    let product = binary(BinaryOperator::Mul, a.clone(), b.clone());
+    assert_eq!(binary(BinaryOperator::Add, product, c.clone()).to_string(), "a * b + c");
+    let right = binary(BinaryOperator::Sub, b.clone(), c.clone());
+    let left = binary(BinaryOperator::Sub, a.clone(), b.clone());
+    assert_eq!(binary(BinaryOperator::Sub, a.clone(), right).to_string(), "a - (b - c)");
+    assert_eq!(binary(BinaryOperator::Sub, left, c.clone()).to_string(), "a - b - c");
+    let assign = binary(BinaryOperator::Assign, a.clone(), b.clone());
+    assert_eq!(
+        binary(BinaryOperator::Assign, assign, c.clone()).to_string(),
+        "(a = b) = c"
+    );
+    let compare = binary(BinaryOperator::Lesser, a.clone(), b.clone());
+    assert_eq!(
+        binary(BinaryOperator::Lesser, compare, c.clone()).to_string(),
+        "(a < b) < c"
+    );
+    let negated = unary(UnaryOperator::Negative, sum.clone());
+    assert_eq!(negated.to_string(), "-(a + b)");
+    let tried = unary(UnaryOperator::Try, unary(UnaryOperator::Negative, a.clone()));
+    assert_eq!(tried.to_string(), "(-a)?");
+    let field = binary(BinaryOperator::Dot, sum, ident("len"));
+    assert_eq!(field.to_string(), "(a + b).len");
+
+    let slice = type_node(TypeKind::Reference {
+        mutable: false,
+        ty: Box::new(type_node(TypeKind::Slice(Box::new(ty("Int"))))),
+    });
+    assert_eq!(slice.to_string(), "&[Int]");
+    let optional = type_node(TypeKind::Optional(Box::new(ty("Int"))));
+    let reference = type_node(TypeKind::Reference {
+        mutable: true,
+        ty: Box::new(optional),
+    });
+    assert_eq!(reference.to_string(), "&mut (Int?)");
+    let function = type_node(TypeKind::Fn {
+        params: vec![ty("Int")],
+        returns: Box::new(ty("Int")),
+    });
+    let optional = type_node(TypeKind::Optional(Box::new(function)));
+    assert_eq!(optional.to_string(), "(fn(Int) -> Int)?");
+}
+
+const SYNTAX: &str = "\
+// Sums what it is given.
+fn sum(xs: &[Int]) -> Int {
+    let mut total = 0 /* so far */
+    outer: for x in xs {
+        if x < 0 { break outer }
+        total += x
+    }
+    return total
+}
+
+struct Point { x: Int, y: Int }
+";
+
+/// Checks that every node of `tree` reproduces exactly its part of the text.
+fn check_syntax_text(tree: &SyntaxTree) {
+    let text = tree.text();
+    for node in tree.root().descendants() {
+        let range = node.range();
+        assert_eq!(node.text(), &text[range.start as usize..range.end as usize]);
+    }
+}
+
+#[test]
+fn test_syntax_tree_is_lossless() {
+    let tree = SyntaxTree::parse("<inline>", SYNTAX);
+    assert!(tree.errors().is_empty());
+    assert_eq!(tree.text(), SYNTAX);
+    assert_eq!(tree.root().range(), 0..SYNTAX.len() as u32);
+    check_syntax_text(&tree);
+
+    let broken = "fn f( {\n    let = 1 @ 2\n}\n\"unfinished";
+    let tree = SyntaxTree::parse("<inline>", broken);
+    assert!(!tree.errors().is_empty());
+    assert_eq!(tree.text(), broken);
+    check_syntax_text(&tree);
+}
+
+#[test]
+fn test_syntax_tree_navigation() {
+    let tree = SyntaxTree::parse("<inline>", SYNTAX);
+    let root = tree.root();
+    assert_eq!(root.kind(), SyntaxKind::Module);
+    let offset = SYNTAX.find("break").unwrap() as u32;
+    let token = root.token_at_offset(offset).unwrap();
+    assert_eq!(token.kind(), SyntaxKind::Keyword(Keyword::Break));
+    assert_eq!(token.text(), "break");
+    let kinds: Vec<_> = token.parent().ancestors().map(|node| node.kind()).collect();
+    assert_eq!(
+        kinds,
+        [
+            SyntaxKind::Break,
+            SyntaxKind::Block,
+            SyntaxKind::If,
+            SyntaxKind::Block,
+            SyntaxKind::For,
+            SyntaxKind::Block,
+            SyntaxKind::Fn,
+            SyntaxKind::Module,
+        ]
+    );
+    let comment = root.token_at_offset(0).unwrap();
+    assert_eq!(comment.kind(), SyntaxKind::Comment);
+    assert_eq!(comment.parent(), root);
+    let offset = SYNTAX.find("/*").unwrap() as u32;
+    let comment = root.token_at_offset(offset).unwrap();
+    assert_eq!(comment.text(), "/* so far */");
+    assert_eq!(comment.parent().kind(), SyntaxKind::Block);
+}
+
+#[test]
+fn test_syntax_tree_accessors() {
+    let tree = SyntaxTree::parse("<inline>", SYNTAX);
+    let module = ast::Module::cast(tree.root()).unwrap();
+    let items: Vec<_> = module.items().collect();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[1].kind(), SyntaxKind::Struct);
+    let function = ast::Fn::cast(items[0].syntax().clone()).unwrap();
+    assert!(ast::Struct::cast(items[0].syntax().clone()).is_none());
+    assert_eq!(function.name().unwrap().text(), "sum");
+    let types: Vec<_> = function.types().map(|ty| ty.syntax().text()).collect();
+    assert_eq!(types, ["&[Int]", "Int"]);
+    let body = function.body().unwrap();
+    let statements: Vec<_> = body.statements().collect();
+    assert_eq!(statements.len(), 3);
+    let mut_total = ast::Let::cast(statements[0].syntax().clone()).unwrap();
+    assert_eq!(mut_total.name().unwrap().text(), "total");
+    assert!(mut_total.ty().is_none());
+    assert_eq!(mut_total.value().unwrap().syntax().text(), "0");
+    let for_loop = ast::For::cast(statements[1].syntax().clone()).unwrap();
+    assert_eq!(for_loop.iterable().unwrap().syntax().text(), "xs");
+    let loop_body: Vec<_> = for_loop.body().unwrap().statements().collect();
+    let condition = ast::If::cast(loop_body[0].syntax().clone()).unwrap();
+    let comparison = ast::Binary::cast(condition.condition().unwrap().syntax().clone()).unwrap();
+    assert_eq!(comparison.op(), BinaryOperator::Lesser);
+    assert_eq!(comparison.left().unwrap().syntax().text(), "x");
+    assert_eq!(comparison.right().unwrap().syntax().text(), "0");
+    assert!(condition.else_branch().is_none());
+    let add = ast::Binary::cast(loop_body[1].syntax().clone()).unwrap();
+    assert_eq!(add.op(), BinaryOperator::AddAssign);
+    let returned = ast::Return::cast(statements[2].syntax().clone()).unwrap();
+    assert_eq!(returned.value().unwrap().syntax().text(), "total");
+}
+
+/// Edits `source` both ways and checks that the trees agree, returning the edited tree.
+fn edit_syntax(source: &str, from: &str, to: &str) -> (SyntaxTree, SyntaxTree) {
+    let old = SyntaxTree::parse("<inline>", source);
+    let start = source.find(from).unwrap() as u32;
+    let edited = old.edit(start..start + from.len() as u32, to);
+    let expected = SyntaxTree::parse("<inline>", &source.replacen(from, to, 1));
+    assert_eq!(edited.text(), expected.text());
+    assert_eq!(edited.green(), expected.green());
+    assert_eq!(edited.errors().len(), expected.errors().len());
+    check_syntax_text(&edited);
+    (old, edited)
+}
+
+/// The items of the root of `tree` whose green nodes are the same as in `other`.
+fn shared_items(tree: &SyntaxTree, other: &SyntaxTree) -> Vec<bool> {
+    tree.root()
+        .children()
+        .zip(other.root().children())
+        .map(|(a, b)| Arc::ptr_eq(a.green(), b.green()))
+        .collect()
+}
+
+#[test]
+fn test_syntax_tree_incremental_edit() {
+    // Inside the `if` block, which is reparsed on its own: the struct is untouched.
+    let (old, edited) = edit_syntax(SYNTAX, "break outer", "break outer total");
+    assert_eq!(shared_items(&old, &edited), [false, true]);
+    let lets = |tree: &SyntaxTree| {
+        let root = tree.root();
+        let lets = root
+            .descendants()
+            .filter(|node| node.kind() == SyntaxKind::Let);
+        lets.map(|node| node.green().clone()).collect::<Vec<_>>()
+    };
+    for (a, b) in lets(&old).iter().zip(lets(&edited).iter()) {
+        assert!(Arc::ptr_eq(a, b));
+    }
+    // Without the loop label known, `outer` would be the value of the `break`.
+    let breaks: Vec<_> = edited
+        .root()
+        .descendants()
+        .filter(|node| node.kind() == SyntaxKind::Break)
+        .collect();
+    assert_eq!(breaks[0].children().count(), 1);
+
+    // Line breaks inside the block change where statements end.
+    edit_syntax(SYNTAX, "total += x", "total +=\n x\n total");
+    // Unbalancing the block, or an edit between items, parses the whole file again.
+    let (old, edited) = edit_syntax(SYNTAX, "total += x", "total += x }");
+    assert!(!edited.errors().is_empty());
+    assert_eq!(shared_items(&old, &edited), [false, false]);
+    let (old, edited) = edit_syntax(SYNTAX, "\n\nstruct", "\nstruct");
+    assert_eq!(shared_items(&old, &edited), [false, false]);
+    // A function nested in the block is reparsed with it.
+    let (old, edited) = edit_syntax(
+        SYNTAX,
+        "return total",
+        "fn sum(ys xs: Int) -> Int { xs }\n    return sum(ys: total)",
+    );
+    assert_eq!(shared_items(&old, &edited), [false, true]);
+    let (_, edited) = edit_syntax(SYNTAX, "/* so far */", "/* unfinished");
+    assert!(!edited.errors().is_empty());
+}
+
+#[test]
+fn test_comments() {
+    let corpus = [
+        // Comments are skipped like whitespace
+        ("f(a, /* b */ c)", "f ( a , c )"),
+        ("a /* /* nested */ */ b", "a b"),
+        ("// only a comment", ""),
+        // A line break inside or after a comment still ends the statement
+        ("a // c\nb", "a ; b"),
+        ("a /* c */\nb", "a ; b"),
+        ("a /* c\n */ b", "a ; b"),
+        // and still doesn't when the line can't end there or the next one continues it
+        ("a + // c\nb", "a + b"),
+        ("a\n// c\n.b", "a . b"),
+    ];
+    for (source, expected) in corpus {
+        let (tokens, warnings) = quick_tokens(source);
+        assert_eq!(tokens, expected, "for {source:?}");
+        assert!(warnings.is_empty(), "for {source:?}");
+    }
+
+    let commented = "// A function.\nfn f(/* none */) {\n    /* first */ a // then\n    b\n}\n";
+    assert_eq!(
+        quick_parse(commented).unwrap(),
+        quick_parse("fn f() {\n    a\n    b\n}\n").unwrap()
+    );
+}
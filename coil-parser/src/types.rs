@@ -0,0 +1,74 @@
+use coil_error::Span;
+
+use crate::{Expr, NodeId};
+
+/// A type as written in the source, wherever a type can appear. Like [`Expr`], types
+/// compare and hash by their contents only.
+#[derive(Debug, Clone)]
+pub struct TypeExpr {
+    pub id: NodeId,
+    pub span: Span,
+    pub kind: TypeKind,
+}
+
+impl PartialEq for TypeExpr {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+impl std::hash::Hash for TypeExpr {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.kind.hash(state)
+    }
+}
+
+/// A type without a location, for trees built by hand.
+impl From<TypeKind> for TypeExpr {
+    fn from(kind: TypeKind) -> Self {
+        Self {
+            id: NodeId::default(),
+            span: Span::default(),
+            kind,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub enum TypeKind {
+    /// `Int`, `std.io.File` or `Self.Item`, applied to type arguments as in
+    /// `Map<K, V>` when `args` isn't empty.
+    Path {
+        path: Box<Expr>,
+        args: Vec<TypeExpr>,
+    },
+    /// `&T` or `&mut T`.
+    Reference { mutable: bool, ty: Box<TypeExpr> },
+    /// `*T` or `*mut T`, a raw pointer.
+    Pointer { mutable: bool, ty: Box<TypeExpr> },
+    /// `[T; N]`
+    Array { ty: Box<TypeExpr>, len: Box<Expr> },
+    /// `[T]`
+    Slice(Box<TypeExpr>),
+    /// `(A, B)`; `()` is the unit type.
+    Tuple(Vec<TypeExpr>),
+    /// `fn(A, B) -> C`; without an arrow the function returns `()`.
+    Fn {
+        params: Vec<TypeExpr>,
+        returns: Box<TypeExpr>,
+    },
+    /// `T?`, short for an optional `T`.
+    Optional(Box<TypeExpr>),
+    /// `T!E`, short for either a `T` or an error `E`.
+    Result {
+        ok: Box<TypeExpr>,
+        error: Box<TypeExpr>,
+    },
+}
+
+impl TypeKind {
+    /// The unit type, `()`.
+    pub fn unit() -> Self {
+        TypeKind::Tuple(Vec::new())
+    }
+}
@@ -0,0 +1,573 @@
+use crate::{
+    ClosureParam, Expr, ExprKind, Field, Fields, GenericParam, Generics, ImportTree, MatchArm,
+    Pattern, PatternKind, Signature, Statement, TypeExpr, TypeKind, Variant, WherePredicate,
+};
+
+/// Defines a visitor trait and the module of its walk functions. [`Visitor`] and
+/// [`VisitorMut`] come from the same definition, which differs only in the `mut` of the
+/// references, so that both always cover the same nodes.
+macro_rules! make_visitor {
+    ($(#[$doc:meta])* $Visitor:ident, $walk:ident, $($mut:ident)?) => {
+        $(#[$doc])*
+        pub trait $Visitor<'ast> {
+            fn visit_expr(&mut self, expr: &'ast $($mut)? Expr) {
+                $walk::expr(self, expr)
+            }
+
+            fn visit_statement(&mut self, statement: &'ast $($mut)? Statement) {
+                $walk::statement(self, statement)
+            }
+
+            fn visit_type(&mut self, ty: &'ast $($mut)? TypeExpr) {
+                $walk::ty(self, ty)
+            }
+
+            fn visit_pattern(&mut self, pattern: &'ast $($mut)? Pattern) {
+                $walk::pattern(self, pattern)
+            }
+        }
+
+        #[doc = concat!("The default traversals of [`", stringify!($Visitor), "`], which visit ")]
+        /// every child of a node in source order, except that the generics of an item come
+        /// first, `where` clause included. A visitor overriding a method calls the function
+        /// of the same name here to go on into the children.
+        pub mod $walk {
+            use super::*;
+
+            pub fn expr<'ast, V: $Visitor<'ast> + ?Sized>(v: &mut V, expr: &'ast $($mut)? Expr) {
+                match &$($mut)? expr.kind {
+                    ExprKind::Statement(statement) => v.visit_statement(statement),
+                    ExprKind::Binary { op: _, left, right } => {
+                        v.visit_expr(left);
+                        v.visit_expr(right);
+                    }
+                    ExprKind::Unary { op: _, expr } => v.visit_expr(expr),
+                    ExprKind::Call { callee, args } => {
+                        v.visit_expr(callee);
+                        for (_, arg) in args {
+                            v.visit_expr(arg);
+                        }
+                    }
+                    ExprKind::Index { expr, index } => {
+                        v.visit_expr(expr);
+                        v.visit_expr(index);
+                    }
+                    ExprKind::Tuple(items) | ExprKind::Array(items) | ExprKind::Block(items) => {
+                        for item in items {
+                            v.visit_expr(item);
+                        }
+                    }
+                    ExprKind::Generic { path, args } => {
+                        v.visit_expr(path);
+                        for arg in args {
+                            v.visit_type(arg);
+                        }
+                    }
+                    ExprKind::StructLiteral { path, fields } => {
+                        v.visit_expr(path);
+                        for (_, value) in fields {
+                            v.visit_expr(value);
+                        }
+                    }
+                    ExprKind::If {
+                        unless: _,
+                        condition,
+                        then,
+                        otherwise,
+                    } => {
+                        v.visit_expr(condition);
+                        v.visit_expr(then);
+                        if let Some(otherwise) = otherwise {
+                            v.visit_expr(otherwise);
+                        }
+                    }
+                    ExprKind::While {
+                        label: _,
+                        condition,
+                        body,
+                    } => {
+                        v.visit_expr(condition);
+                        v.visit_expr(body);
+                    }
+                    ExprKind::For {
+                        label: _,
+                        binding: _,
+                        iterable,
+                        body,
+                    } => {
+                        v.visit_expr(iterable);
+                        v.visit_expr(body);
+                    }
+                    ExprKind::Match { scrutinee, arms } => {
+                        v.visit_expr(scrutinee);
+                        for arm in arms {
+                            match_arm(v, arm);
+                        }
+                    }
+                    ExprKind::DoWhile {
+                        label: _,
+                        body,
+                        condition,
+                    } => {
+                        v.visit_expr(body);
+                        v.visit_expr(condition);
+                    }
+                    ExprKind::Closure {
+                        params,
+                        returns,
+                        body,
+                    } => {
+                        for param in params {
+                            closure_param(v, param);
+                        }
+                        if let Some(returns) = returns {
+                            v.visit_type(returns);
+                        }
+                        v.visit_expr(body);
+                    }
+                    ExprKind::Literal(_, _)
+                    | ExprKind::Bool(_)
+                    | ExprKind::Identifier(_)
+                    | ExprKind::Error => {}
+                }
+            }
+
+            pub fn statement<'ast, V: $Visitor<'ast> + ?Sized>(
+                v: &mut V,
+                statement: &'ast $($mut)? Statement,
+            ) {
+                match statement {
+                    Statement::Module {
+                        name,
+                        inline: _,
+                        children,
+                    } => {
+                        v.visit_expr(name);
+                        for child in children {
+                            v.visit_expr(child);
+                        }
+                    }
+                    Statement::Use { tree } => import_tree(v, tree),
+                    Statement::Fn {
+                        public: _,
+                        name: _,
+                        generics: params,
+                        signature: sig,
+                        body,
+                    } => {
+                        generics(v, params);
+                        signature(v, sig);
+                        if let Some(body) = body {
+                            v.visit_expr(body);
+                        }
+                    }
+                    Statement::Struct {
+                        public: _,
+                        name: _,
+                        generics: params,
+                        fields: items,
+                    } => {
+                        generics(v, params);
+                        fields(v, items);
+                    }
+                    Statement::Enum {
+                        public: _,
+                        name: _,
+                        generics: params,
+                        variants,
+                    } => {
+                        generics(v, params);
+                        for item in variants {
+                            variant(v, item);
+                        }
+                    }
+                    Statement::Union {
+                        public: _,
+                        name: _,
+                        generics: params,
+                        fields: items,
+                    } => {
+                        generics(v, params);
+                        for item in items {
+                            field(v, item);
+                        }
+                    }
+                    Statement::Trait {
+                        public: _,
+                        name: _,
+                        generics: params,
+                        supertraits,
+                        items,
+                    } => {
+                        generics(v, params);
+                        for supertrait in supertraits {
+                            v.visit_type(supertrait);
+                        }
+                        for item in items {
+                            v.visit_expr(item);
+                        }
+                    }
+                    Statement::Impl {
+                        generics: params,
+                        trait_,
+                        target,
+                        items,
+                    } => {
+                        generics(v, params);
+                        if let Some(trait_) = trait_ {
+                            v.visit_type(trait_);
+                        }
+                        v.visit_type(target);
+                        for item in items {
+                            v.visit_expr(item);
+                        }
+                    }
+                    Statement::TypeAlias {
+                        public: _,
+                        name: _,
+                        generics: params,
+                        ty,
+                    } => {
+                        generics(v, params);
+                        v.visit_type(ty);
+                    }
+                    Statement::AssociatedType {
+                        name: _,
+                        bounds,
+                        default,
+                    } => {
+                        for bound in bounds {
+                            v.visit_type(bound);
+                        }
+                        if let Some(default) = default {
+                            v.visit_type(default);
+                        }
+                    }
+                    Statement::Extern { abi: _, items } => {
+                        for item in items {
+                            v.visit_expr(item);
+                        }
+                    }
+                    Statement::Static {
+                        public: _,
+                        mutable: _,
+                        name: _,
+                        ty,
+                        value,
+                    } => {
+                        v.visit_type(ty);
+                        if let Some(value) = value {
+                            v.visit_expr(value);
+                        }
+                    }
+                    Statement::Let {
+                        mutable: _,
+                        name: _,
+                        ty,
+                        value,
+                    } => {
+                        if let Some(ty) = ty {
+                            v.visit_type(ty);
+                        }
+                        if let Some(value) = value {
+                            v.visit_expr(value);
+                        }
+                    }
+                    Statement::Return(value) | Statement::Break { label: _, value } => {
+                        if let Some(value) = value {
+                            v.visit_expr(value);
+                        }
+                    }
+                    Statement::Continue { label: _ } | Statement::Fallthrough => {}
+                }
+            }
+
+            pub fn ty<'ast, V: $Visitor<'ast> + ?Sized>(v: &mut V, ty: &'ast $($mut)? TypeExpr) {
+                match &$($mut)? ty.kind {
+                    TypeKind::Path { path, args } => {
+                        v.visit_expr(path);
+                        for arg in args {
+                            v.visit_type(arg);
+                        }
+                    }
+                    TypeKind::Reference { mutable: _, ty }
+                    | TypeKind::Pointer { mutable: _, ty }
+                    | TypeKind::Slice(ty)
+                    | TypeKind::Optional(ty) => v.visit_type(ty),
+                    TypeKind::Array { ty, len } => {
+                        v.visit_type(ty);
+                        v.visit_expr(len);
+                    }
+                    TypeKind::Tuple(types) => {
+                        for ty in types {
+                            v.visit_type(ty);
+                        }
+                    }
+                    TypeKind::Fn { params, returns } => {
+                        for param in params {
+                            v.visit_type(param);
+                        }
+                        v.visit_type(returns);
+                    }
+                    TypeKind::Result { ok, error } => {
+                        v.visit_type(ok);
+                        v.visit_type(error);
+                    }
+                }
+            }
+
+            pub fn pattern<'ast, V: $Visitor<'ast> + ?Sized>(
+                v: &mut V,
+                pattern: &'ast $($mut)? Pattern,
+            ) {
+                match &$($mut)? pattern.kind {
+                    PatternKind::Range { start, end } => {
+                        v.visit_pattern(start);
+                        v.visit_pattern(end);
+                    }
+                    PatternKind::Path(path) => v.visit_expr(path),
+                    PatternKind::Tuple(patterns) | PatternKind::Or(patterns) => {
+                        for pattern in patterns {
+                            v.visit_pattern(pattern);
+                        }
+                    }
+                    PatternKind::Variant { path, args } => {
+                        v.visit_expr(path);
+                        for arg in args {
+                            v.visit_pattern(arg);
+                        }
+                    }
+                    PatternKind::Struct {
+                        path,
+                        fields,
+                        rest: _,
+                    } => {
+                        v.visit_expr(path);
+                        for (_, pattern) in fields {
+                            v.visit_pattern(pattern);
+                        }
+                    }
+                    PatternKind::Is { binding: _, ty } => v.visit_type(ty),
+                    PatternKind::Wildcard
+                    | PatternKind::Binding {
+                        mutable: _,
+                        name: _,
+                    }
+                    | PatternKind::Literal(_, _)
+                    | PatternKind::Bool(_) => {}
+                }
+            }
+
+            pub fn match_arm<'ast, V: $Visitor<'ast> + ?Sized>(
+                v: &mut V,
+                arm: &'ast $($mut)? MatchArm,
+            ) {
+                let MatchArm {
+                    pattern,
+                    guard,
+                    body,
+                } = arm;
+                v.visit_pattern(pattern);
+                if let Some(guard) = guard {
+                    v.visit_expr(guard);
+                }
+                v.visit_expr(body);
+            }
+
+            pub fn closure_param<'ast, V: $Visitor<'ast> + ?Sized>(
+                v: &mut V,
+                param: &'ast $($mut)? ClosureParam,
+            ) {
+                let ClosureParam { name: _, ty } = param;
+                if let Some(ty) = ty {
+                    v.visit_type(ty);
+                }
+            }
+
+            /// The bounds of the parameters, then the `where` clause.
+            pub fn generics<'ast, V: $Visitor<'ast> + ?Sized>(
+                v: &mut V,
+                generics: &'ast $($mut)? Generics,
+            ) {
+                let Generics {
+                    params,
+                    where_clause,
+                } = generics;
+                for GenericParam { name: _, bounds } in params {
+                    for bound in bounds {
+                        v.visit_type(bound);
+                    }
+                }
+                for WherePredicate { ty, bounds } in where_clause {
+                    v.visit_type(ty);
+                    for bound in bounds {
+                        v.visit_type(bound);
+                    }
+                }
+            }
+
+            /// The argument types, then the return type.
+            pub fn signature<'ast, V: $Visitor<'ast> + ?Sized>(
+                v: &mut V,
+                signature: &'ast $($mut)? Signature,
+            ) {
+                let Signature {
+                    returns,
+                    named_args,
+                } = signature;
+                for (_, ty) in named_args {
+                    v.visit_type(ty);
+                }
+                v.visit_type(returns);
+            }
+
+            pub fn fields<'ast, V: $Visitor<'ast> + ?Sized>(
+                v: &mut V,
+                fields: &'ast $($mut)? Fields,
+            ) {
+                match fields {
+                    Fields::Named(items) | Fields::Tuple(items) => {
+                        for item in items {
+                            field(v, item);
+                        }
+                    }
+                    Fields::Unit => {}
+                }
+            }
+
+            pub fn field<'ast, V: $Visitor<'ast> + ?Sized>(v: &mut V, field: &'ast $($mut)? Field) {
+                let Field {
+                    public: _,
+                    name: _,
+                    ty,
+                } = field;
+                v.visit_type(ty);
+            }
+
+            pub fn variant<'ast, V: $Visitor<'ast> + ?Sized>(
+                v: &mut V,
+                variant: &'ast $($mut)? Variant,
+            ) {
+                let Variant {
+                    name: _,
+                    fields: items,
+                    discriminant,
+                } = variant;
+                fields(v, items);
+                if let Some(discriminant) = discriminant {
+                    v.visit_expr(discriminant);
+                }
+            }
+
+            /// The paths of an `import`, prefixes of groups included.
+            pub fn import_tree<'ast, V: $Visitor<'ast> + ?Sized>(
+                v: &mut V,
+                tree: &'ast $($mut)? ImportTree,
+            ) {
+                match tree {
+                    ImportTree::Path { path, alias: _ } => v.visit_expr(path),
+                    ImportTree::Group { prefix, items } => {
+                        v.visit_expr(prefix);
+                        for item in items {
+                            import_tree(v, item);
+                        }
+                    }
+                }
+            }
+        }
+    };
+}
+
+make_visitor! {
+    /// Walks the AST by shared reference. Every method defaults to visiting the children
+    /// of its node through [`walk`], so a visitor only overrides the nodes it cares about.
+    /// The nodes live for `'ast`, so a visitor can keep references to them.
+    Visitor, walk,
+}
+
+make_visitor! {
+    /// Walks the AST by mutable reference, to change nodes in place. Every method defaults
+    /// to visiting the children of its node through [`walk_mut`].
+    VisitorMut, walk_mut, mut
+}
+
+/// Rebuilds the AST: each method takes a node and returns the node that replaces it. The
+/// defaults fold the children of the node through [`fold`] and keep the node itself, so
+/// folding with nothing overridden gives back the same tree.
+pub trait Fold {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        fold::expr(self, expr)
+    }
+
+    fn fold_statement(&mut self, statement: Statement) -> Statement {
+        fold::statement(self, statement)
+    }
+
+    fn fold_type(&mut self, ty: TypeExpr) -> TypeExpr {
+        fold::ty(self, ty)
+    }
+
+    fn fold_pattern(&mut self, pattern: Pattern) -> Pattern {
+        fold::pattern(self, pattern)
+    }
+}
+
+/// The default folds of [`Fold`], which fold every child of a node in source order and
+/// return the node with its new children.
+pub mod fold {
+    use super::*;
+
+    pub fn expr<F: Fold + ?Sized>(f: &mut F, mut expr: Expr) -> Expr {
+        walk_mut::expr(&mut Folder(f), &mut expr);
+        expr
+    }
+
+    pub fn statement<F: Fold + ?Sized>(f: &mut F, mut statement: Statement) -> Statement {
+        walk_mut::statement(&mut Folder(f), &mut statement);
+        statement
+    }
+
+    pub fn ty<F: Fold + ?Sized>(f: &mut F, mut ty: TypeExpr) -> TypeExpr {
+        walk_mut::ty(&mut Folder(f), &mut ty);
+        ty
+    }
+
+    pub fn pattern<F: Fold + ?Sized>(f: &mut F, mut pattern: Pattern) -> Pattern {
+        walk_mut::pattern(&mut Folder(f), &mut pattern);
+        pattern
+    }
+}
+
+/// Folds the children of a node in place, which keeps [`Fold`] on the same traversal as
+/// [`VisitorMut`]. Each child is briefly swapped for a placeholder while it is folded.
+struct Folder<'a, F: ?Sized>(&'a mut F);
+
+impl<F: Fold + ?Sized> VisitorMut<'_> for Folder<'_, F> {
+    fn visit_expr(&mut self, expr: &mut Expr) {
+        let folded = self
+            .0
+            .fold_expr(std::mem::replace(expr, ExprKind::Error.into()));
+        *expr = folded;
+    }
+
+    fn visit_statement(&mut self, statement: &mut Statement) {
+        let folded = self
+            .0
+            .fold_statement(std::mem::replace(statement, Statement::Fallthrough));
+        *statement = folded;
+    }
+
+    fn visit_type(&mut self, ty: &mut TypeExpr) {
+        let folded = self
+            .0
+            .fold_type(std::mem::replace(ty, TypeKind::unit().into()));
+        *ty = folded;
+    }
+
+    fn visit_pattern(&mut self, pattern: &mut Pattern) {
+        let folded = self
+            .0
+            .fold_pattern(std::mem::replace(pattern, PatternKind::Wildcard.into()));
+        *pattern = folded;
+    }
+}
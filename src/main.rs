@@ -1,10 +1,18 @@
-use clap::Parser;
-use std::{convert::identity, fs, path::PathBuf, str::FromStr};
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
+use coil_error::{msg, Lang, SourceMap};
+use coil_fmt::Config;
+use coil_parser::{DumpFormat, ToDump};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
 enum Step {
     Lexing,
     Parsing,
+    Analysis,
     Codegen,
     Linking,
     #[default]
@@ -19,6 +27,7 @@ impl FromStr for Step {
         match s {
             "l" | "lex" | "lexing" | "lexer" => Ok(Lexing),
             "p" | "parse" | "parsing" | "parser" => Ok(Parsing),
+            "a" | "analyse" | "analysis" | "check" => Ok(Analysis),
             "c" | "codegen" | "codegenerator" => Ok(Codegen),
             "L" | "link" | "linking" | "linker" => Ok(Linking),
             "f" | "end" | "finish" | "finishing" => Ok(Finishing),
@@ -27,33 +36,203 @@ impl FromStr for Step {
     }
 }
 
-impl ToString for Step {
-    fn to_string(&self) -> String {
-        format!("{self:?}")
+impl std::fmt::Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Step::Lexing => "lex",
+            Step::Parsing => "parse",
+            Step::Analysis => "analysis",
+            Step::Codegen => "codegen",
+            Step::Linking => "link",
+            Step::Finishing => "finish",
+        })
+    }
+}
+
+/// What to print at the step where the compiler halts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Emit {
+    /// The parsed module, `ast=json`, `ast=sexp` or `ast=tree`.
+    Ast(DumpFormat),
+}
+
+impl FromStr for Emit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let format = match s {
+            "ast" | "ast=tree" => DumpFormat::Tree,
+            "ast=json" => DumpFormat::Json,
+            "ast=sexp" => DumpFormat::Sexp,
+            _ => return Err(format!("invalid value: {s}")),
+        };
+        Ok(Emit::Ast(format))
+    }
+}
+
+impl Emit {
+    /// The only step whose output this can print.
+    fn step(self) -> Step {
+        match self {
+            Emit::Ast(_) => Step::Parsing,
+        }
     }
 }
 
 #[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
     /// The source file path
-    source: PathBuf,
+    #[arg(required = true)]
+    source: Option<PathBuf>,
     #[arg(short, long)]
     /// The output file path
     output: Option<PathBuf>,
     /// The step at which to halt
     #[arg(short, long, default_value_t = Step::Finishing)]
     until: Step,
+    /// The language of diagnostics (defaults to `LANG`)
+    #[arg(long, global = true)]
+    lang: Option<Lang>,
+    /// What to print when halting: `ast=json`, `ast=sexp` or `ast=tree`, which need
+    /// `--until parse`
+    #[arg(long)]
+    emit: Option<Emit>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Format source files in place, with the settings of the closest `coil-fmt.toml`
+    Fmt {
+        /// Only list the files that aren't formatted, and fail if there are any
+        #[arg(long)]
+        check: bool,
+        /// The files and directories to format (defaults to the current directory)
+        paths: Vec<PathBuf>,
+    },
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
-    let source = args.source.canonicalize()?;
-    let output = args.output.map_or_else(|| source.with_extension(std::env::consts::EXE_EXTENSION), identity);
-    let lexer_source = fs::read_to_string(&source)?;
-    let lx = coil_lexer::Lexer::new(&source.into_os_string().into_string().unwrap(), &lexer_source);
-    let tokens: Vec<_> = lx.flatten().collect();
-    for token in tokens.iter() {
-        println!("{token:?}");
+    coil_error::set_lang(args.lang.unwrap_or_else(Lang::from_env));
+    if let Some(Command::Fmt { check, paths }) = args.command {
+        return fmt(check, paths);
+    }
+    if let Some(emit) = args.emit.filter(|emit| emit.step() != args.until) {
+        let message = msg!("cli-emit-conflict", step = emit.step(), until = args.until);
+        Args::command()
+            .error(ErrorKind::ArgumentConflict, message)
+            .exit();
+    }
+    let source = args.source.expect("required by clap").canonicalize()?;
+    let _output = args
+        .output
+        .unwrap_or_else(|| source.with_extension(std::env::consts::EXE_EXTENSION));
+    let mut map = SourceMap::new();
+    if args.until == Step::Lexing {
+        let file = map.load(&source)?;
+        for token in coil_lexer::Lexer::from_source(&map, file) {
+            match token {
+                Ok(token) => println!("{token:?}"),
+                Err(e) => {
+                    eprint!("{}", e.display(&map));
+                    std::process::exit(1);
+                }
+            }
+        }
+        return Ok(());
+    }
+    let program = coil_parser::load(&mut map, &source)?;
+    for diagnostic in program.warnings.iter().chain(&program.errors) {
+        eprint!("{}", diagnostic.display(&map));
+    }
+    if !program.errors.is_empty() {
+        std::process::exit(1);
+    }
+    if args.until == Step::Parsing {
+        let Emit::Ast(format) = args.emit.unwrap_or(Emit::Ast(DumpFormat::Tree));
+        print!("{}", program.root.to_dump().render(format, Some(&map)));
+        return Ok(());
+    }
+    let resolution = coil_analysis::resolve(&program, &map);
+    let typing = coil_analysis::check(&program, &map, &resolution);
+    let warnings = resolution.warnings.iter().chain(&typing.warnings);
+    let errors = resolution.errors.iter().chain(&typing.errors);
+    for diagnostic in warnings.chain(errors) {
+        eprint!("{}", diagnostic.display(&map));
+    }
+    if !resolution.errors.is_empty() || !typing.errors.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn fmt(check: bool, paths: Vec<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let paths = if paths.is_empty() {
+        vec![PathBuf::from(".")]
+    } else {
+        paths
+    };
+    let mut files = vec![];
+    for path in paths {
+        if path.is_dir() {
+            collect_sources(&path, &mut files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    let mut map = SourceMap::new();
+    let mut failed = false;
+    for path in files {
+        let file = map.load(&path)?;
+        // The parent of a bare file name is "".
+        let dir = match path.parent() {
+            Some(dir) if dir != Path::new("") => dir,
+            _ => Path::new("."),
+        };
+        let config = Config::find(&dir.canonicalize()?)?;
+        let formatted = match coil_fmt::format(&map, file, &config) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                for error in errors {
+                    eprint!("{}", error.display(&map));
+                }
+                failed = true;
+                continue;
+            }
+        };
+        if *formatted == *map.get(file).source {
+            continue;
+        }
+        if check {
+            println!("{}", path.display());
+            failed = true;
+        } else {
+            std::fs::write(&path, formatted)?;
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Collects the `.coil` files under `dir`, skipping hidden files and directories.
+fn collect_sources(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = std::fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            collect_sources(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "coil") {
+            files.push(path);
+        }
     }
     Ok(())
 }
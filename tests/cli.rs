@@ -0,0 +1,42 @@
+use std::{fs, path::PathBuf, process::Command};
+
+/// A fresh directory for a test to write files in.
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("coil-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_fmt_bare_file_name() {
+    let dir = scratch_dir("fmt-bare");
+    fs::write(dir.join("foo.coil"), "fn  f( ) { a }\n").unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_coil-lang"))
+        .args(["fmt", "foo.coil"])
+        .current_dir(&dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(
+        fs::read_to_string(dir.join("foo.coil")).unwrap(),
+        "fn f() {\n    a\n}\n"
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_emit_conflict_is_translated() {
+    let output = Command::new(env!("CARGO_BIN_EXE_coil-lang"))
+        .args(["--lang", "tr", "--emit", "ast", "foo.coil"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(
+            "`--emit` için `--until parse` gerekiyor ama derleyici `finish` adımında duruyor"
+        ),
+        "{stderr}"
+    );
+}